@@ -0,0 +1,399 @@
+//! Action definitions loaded from `actions.actions_dir`
+//!
+//! Each `.yaml`, `.yml` or `.json` file in the directory defines one action
+//! the client knows how to run:
+//!
+//! ```yaml
+//! id: disk.cleanup
+//! description: Remove rotated logs older than a number of days
+//! risk_level: Standard
+//! command: [find, "{path}", -name, "*.gz", -mtime, "+{days}", -delete]
+//! parameters:
+//!   path: { type: path }
+//!   days: { type: integer, required: false, default: "7" }
+//! ```
+//!
+//! Parameters of an incoming command are checked against the definition
+//! before anything runs: unknown parameters, missing required ones and values
+//! of the wrong type are all rejected.
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::mcp::PermissionLevel;
+
+/// Type of an action parameter's value
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    /// Any text without control characters that does not start with `-`
+    String,
+    /// A whole number
+    Integer,
+    /// `true` or `false`
+    Boolean,
+    /// An absolute path without `..` components
+    Path,
+    /// One of `values`
+    Enum,
+}
+
+/// Schema of one action parameter
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParameterSpec {
+    /// Type the value must have
+    #[serde(rename = "type")]
+    pub param_type: ParameterType,
+    /// Whether a command must provide the parameter (unless it has a default)
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Value used when a command leaves the parameter out
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Accepted values of an `enum` parameter
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Parameters are required unless stated otherwise
+fn default_required() -> bool {
+    true
+}
+
+impl ParameterSpec {
+    /// Describe why `value` does not fit the parameter, if it does not
+    fn check(&self, value: &str) -> Option<String> {
+        let valid = match self.param_type {
+            // A leading `-` would be read as an option by the command
+            ParameterType::String => !value.chars().any(char::is_control) && !value.starts_with('-'),
+            ParameterType::Integer => value.parse::<i64>().is_ok(),
+            ParameterType::Boolean => value == "true" || value == "false",
+            ParameterType::Path => {
+                let path = Path::new(value);
+                path.is_absolute() && !path.components().any(|c| c == std::path::Component::ParentDir)
+            },
+            ParameterType::Enum => self.values.iter().any(|allowed| allowed == value),
+        };
+
+        (!valid).then(|| match self.param_type {
+            ParameterType::Enum => format!("'{}' is not one of {}", value, self.values.join(", ")),
+            ParameterType::Path => format!("'{}' is not an absolute path without '..'", value),
+            ParameterType::String if value.starts_with('-') => format!("'{}' must not start with '-'", value),
+            _ => format!("'{}' is not a valid {}", value, format!("{:?}", self.param_type).to_lowercase()),
+        })
+    }
+}
+
+/// An action the client can run
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionDefinition {
+    /// Action identifier matched against incoming commands
+    pub id: String,
+    /// Human-readable description, shown to operators asked to approve the action
+    #[serde(default)]
+    pub description: String,
+    /// Risk of running the action; `HighRisk` actions always need confirmation
+    pub risk_level: PermissionLevel,
+    /// Program and arguments, with `{name}` replaced by parameter values
+    pub command: Vec<String>,
+    /// Parameters the action accepts
+    #[serde(default)]
+    pub parameters: HashMap<String, ParameterSpec>,
+}
+
+impl ActionDefinition {
+    /// Check a command's parameters, returning them with defaults filled in
+    ///
+    /// Every problem is reported in one error.
+    pub fn validate(&self, params: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let mut errors = Vec::new();
+
+        let mut unknown: Vec<&String> = params.keys().filter(|name| !self.parameters.contains_key(*name)).collect();
+        unknown.sort();
+        errors.extend(unknown.into_iter().map(|name| format!("unknown parameter '{}'", name)));
+
+        let mut names: Vec<&String> = self.parameters.keys().collect();
+        names.sort();
+
+        let mut validated = HashMap::new();
+        for name in names {
+            let spec = &self.parameters[name];
+            match params.get(name).or(spec.default.as_ref()) {
+                Some(value) => {
+                    if let Some(problem) = spec.check(value) {
+                        errors.push(format!("parameter '{}': {}", name, problem));
+                    }
+                    validated.insert(name.clone(), value.clone());
+                },
+                None if spec.required => errors.push(format!("missing parameter '{}'", name)),
+                None => {},
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(validated)
+        } else {
+            Err(anyhow!("Invalid parameters for {}: {}", self.id, errors.join("; ")))
+        }
+    }
+
+    /// Program and arguments with validated parameters substituted
+    ///
+    /// Each template element stays one argument, so values are never split
+    /// or interpreted by a shell. Placeholders of absent optional parameters
+    /// become empty. Each template is read once from left to right, so a
+    /// value containing `{name}` is inserted as is rather than expanded again.
+    pub fn render(&self, params: &HashMap<String, String>) -> Vec<String> {
+        self.command.iter().map(|template| self.render_argument(template, params)).collect()
+    }
+
+    /// Substitute the placeholders of one template element
+    fn render_argument(&self, template: &str, params: &HashMap<String, String>) -> String {
+        let mut argument = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            argument.push_str(&rest[..open]);
+            let placeholder = rest[open + 1..].find('}').map(|close| &rest[open + 1..open + 1 + close]);
+            match placeholder.filter(|name| self.parameters.contains_key(*name)) {
+                Some(name) => {
+                    argument.push_str(params.get(name).map_or("", String::as_str));
+                    rest = &rest[open + name.len() + 2..];
+                },
+                // Braces that are not a declared placeholder are kept as they are
+                None => {
+                    argument.push('{');
+                    rest = &rest[open + 1..];
+                },
+            }
+        }
+
+        argument.push_str(rest);
+        argument
+    }
+
+    /// Reject definitions that could never run
+    fn check(&self) -> Result<()> {
+        if self.command.is_empty() {
+            return Err(anyhow!("command must not be empty"));
+        }
+
+        for (name, spec) in &self.parameters {
+            if spec.param_type == ParameterType::Enum && spec.values.is_empty() {
+                return Err(anyhow!("enum parameter '{}' has no values", name));
+            }
+            if let Some(problem) = spec.default.as_ref().and_then(|default| spec.check(default)) {
+                return Err(anyhow!("default of parameter '{}': {}", name, problem));
+            }
+        }
+
+        let placeholder = regex::Regex::new(r"\{([A-Za-z0-9_.-]+)\}")?;
+        for captures in self.command.iter().flat_map(|argument| placeholder.captures_iter(argument)) {
+            if !self.parameters.contains_key(&captures[1]) {
+                return Err(anyhow!("command uses undeclared parameter '{}'", &captures[1]));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Action definitions by identifier
+#[derive(Default)]
+pub struct ActionRegistry {
+    actions: HashMap<String, ActionDefinition>,
+}
+
+impl ActionRegistry {
+    /// Load every action definition in `dir`
+    ///
+    /// Files without a `.yaml`, `.yml` or `.json` extension are ignored. A
+    /// definition that does not parse or could never run fails the load.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read actions directory {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.sort();
+
+        let mut definitions = Vec::new();
+        for path in paths {
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if !matches!(extension, Some("yaml" | "yml" | "json")) || !path.is_file() {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read action definition {}", path.display()))?;
+            // JSON is a subset of YAML
+            let definition: ActionDefinition = serde_yaml::from_str(&content)
+                .with_context(|| format!("Invalid action definition {}", path.display()))?;
+            definitions.push(definition);
+        }
+
+        let registry = Self::from_definitions(definitions)?;
+        tracing::info!("Loaded {} action definitions from {}", registry.actions.len(), dir.display());
+        Ok(registry)
+    }
+
+    /// Build a registry from definitions, rejecting duplicates and definitions that could never run
+    pub fn from_definitions(definitions: impl IntoIterator<Item = ActionDefinition>) -> Result<Self> {
+        let mut actions = HashMap::new();
+        for definition in definitions {
+            definition.check().with_context(|| format!("Invalid action {}", definition.id))?;
+            if actions.contains_key(&definition.id) {
+                return Err(anyhow!("Action {} is defined more than once", definition.id));
+            }
+            actions.insert(definition.id.clone(), definition);
+        }
+
+        Ok(Self { actions })
+    }
+
+    /// Definition of an action, if the client knows it
+    pub fn get(&self, action_id: &str) -> Option<&ActionDefinition> {
+        self.actions.get(action_id)
+    }
+
+    /// Identifiers of every defined action
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn sample_registry() -> Result<ActionRegistry> {
+        let dir = tempdir()?;
+        std::fs::write(dir.path().join("disk_cleanup.yaml"), r#"
+            id: disk.cleanup
+            description: Remove rotated logs
+            risk_level: Standard
+            command: [find, "{path}", -name, "*.gz", -mtime, "+{days}", -delete]
+            parameters:
+              path: { type: path }
+              days: { type: integer, required: false, default: "7" }
+        "#)?;
+        std::fs::write(dir.path().join("service_restart.json"), r#"{
+            "id": "service.restart",
+            "risk_level": "HighRisk",
+            "command": ["systemctl", "{mode}", "{unit}"],
+            "parameters": {
+                "unit": { "type": "string" },
+                "mode": { "type": "enum", "values": ["restart", "reload"], "default": "restart" },
+                "dry_run": { "type": "boolean", "required": false }
+            }
+        }"#)?;
+        std::fs::write(dir.path().join("README.md"), "not an action")?;
+
+        ActionRegistry::load(dir.path())
+    }
+
+    #[test]
+    fn test_load_actions_dir() -> Result<()> {
+        let registry = sample_registry()?;
+
+        let cleanup = registry.get("disk.cleanup").expect("disk.cleanup is loaded");
+        assert_eq!(cleanup.risk_level, PermissionLevel::Standard);
+        let restart = registry.get("service.restart").expect("service.restart is loaded");
+        assert_eq!(restart.risk_level, PermissionLevel::HighRisk);
+        assert!(registry.get("README").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_valid_parameters_are_completed_and_rendered() -> Result<()> {
+        let registry = sample_registry()?;
+
+        let cleanup = registry.get("disk.cleanup").unwrap();
+        let validated = cleanup.validate(&params(&[("path", "/var/log/nginx")]))?;
+        assert_eq!(validated, params(&[("path", "/var/log/nginx"), ("days", "7")]));
+        assert_eq!(
+            cleanup.render(&validated),
+            ["find", "/var/log/nginx", "-name", "*.gz", "-mtime", "+7", "-delete"],
+        );
+
+        let restart = registry.get("service.restart").unwrap();
+        let validated = restart.validate(&params(&[("unit", "nginx; rm -rf /"), ("mode", "reload"), ("dry_run", "true")]))?;
+        assert_eq!(restart.render(&validated), ["systemctl", "reload", "nginx; rm -rf /"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_parameters_are_rejected() -> Result<()> {
+        let registry = sample_registry()?;
+
+        let error = registry.get("disk.cleanup").unwrap()
+            .validate(&params(&[("path", "/var/log/../../etc"), ("days", "seven"), ("recursive", "yes")]))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid parameters for disk.cleanup: unknown parameter 'recursive'; \
+             parameter 'days': 'seven' is not a valid integer; \
+             parameter 'path': '/var/log/../../etc' is not an absolute path without '..'",
+        );
+
+        let error = registry.get("service.restart").unwrap()
+            .validate(&params(&[("mode", "stop"), ("dry_run", "1")]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("parameter 'dry_run': '1' is not a valid boolean"));
+        assert!(error.contains("parameter 'mode': 'stop' is not one of restart, reload"));
+        assert!(error.contains("missing parameter 'unit'"));
+
+        // A unit that would be read as an option of systemctl
+        let error = registry.get("service.restart").unwrap()
+            .validate(&params(&[("unit", "--all"), ("mode", "restart")]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("parameter 'unit': '--all' must not start with '-'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_substitutes_each_placeholder_once() -> Result<()> {
+        let definition: ActionDefinition = serde_yaml::from_str(r#"
+            id: copy
+            risk_level: Standard
+            command: [cp, "{source}", "{target}", "{}", "{source}.{target}"]
+            parameters:
+              source: { type: string }
+              target: { type: string }
+        "#)?;
+
+        // Values naming another placeholder are inserted literally, whatever the hash order
+        let rendered = definition.render(&params(&[("source", "{target}"), ("target", "{source}")]));
+        assert_eq!(rendered, ["cp", "{target}", "{source}", "{}", "{target}.{source}"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_definitions_that_cannot_run() -> Result<()> {
+        let definition = |yaml: &str| -> Result<ActionDefinition> { Ok(serde_yaml::from_str(yaml)?) };
+
+        let undeclared = definition("{ id: a, risk_level: ReadOnly, command: [cat, '{file}'] }")?;
+        let error = ActionRegistry::from_definitions([undeclared]).err().unwrap();
+        assert!(format!("{:#}", error).contains("command uses undeclared parameter 'file'"));
+
+        let duplicate = definition("{ id: a, risk_level: ReadOnly, command: [uptime] }")?;
+        let error = ActionRegistry::from_definitions([duplicate.clone(), duplicate]).err().unwrap();
+        assert_eq!(error.to_string(), "Action a is defined more than once");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! Operator approval of actions held for confirmation
+//!
+//! When `actions.require_confirmation` is set, actions needing confirmation
+//! wait for an operator's decision through the channel configured in
+//! `actions.approval`:
+//!
+//! ```yaml
+//! actions:
+//!   approval:
+//!     channel: webhook  # or tty
+//!     webhook_url: https://approvals.example.com/lognarrator
+//! ```
+//!
+//! The `tty` channel prompts on the client's terminal, for interactive use.
+//! The `webhook` channel posts an [`ApprovalRequest`] to the webhook and
+//! waits for its answer, `{"approved": true}` or
+//! `{"approved": false, "reason": "..."}`. Either way, no answer within
+//! `actions.execution_timeout` leaves the action unrun.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+use tokio::sync::Mutex;
+
+use crate::config::{ApprovalChannelKind, McpConfig};
+
+/// Action awaiting an operator's decision
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    /// Client that would run the action
+    pub client_id: String,
+    /// MCP message recommending the action
+    pub message_id: String,
+    /// Narrative of the issue the action addresses
+    pub narrative: String,
+    /// Action identifier
+    pub action_id: String,
+    /// Human-readable description of the action, as the server recommends it
+    pub description: String,
+    /// What the action does according to its local definition; empty if the
+    /// definition has no description
+    pub defined_as: String,
+    /// Parameters the action would run with
+    pub parameters: HashMap<String, String>,
+}
+
+/// Operator's decision on an action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    /// The action may run
+    Approved,
+    /// The action must not run, and why
+    Denied(String),
+}
+
+/// Asks an operator to approve actions
+///
+/// Implementations wait until the operator decides; callers bound the wait.
+#[async_trait]
+pub trait ApprovalChannel: Send + Sync {
+    /// Request a decision on `request`
+    async fn request(&self, request: &ApprovalRequest) -> Result<Approval>;
+}
+
+/// Create the approval channel configured in `config`
+pub fn from_config(config: &McpConfig) -> Result<Box<dyn ApprovalChannel>> {
+    let approval = &config.actions.approval;
+    Ok(match approval.channel {
+        ApprovalChannelKind::Tty => Box::new(TtyApproval::new()),
+        ApprovalChannelKind::Webhook => {
+            let url = approval.webhook_url.as_deref()
+                .ok_or_else(|| anyhow!("actions.approval.webhook_url is required for the webhook channel"))?;
+            Box::new(WebhookApproval::new(url, config.security.verify_certs)?)
+        },
+    })
+}
+
+/// Prompts on the terminal, approving when the operator types the action ID
+///
+/// Typing the action ID rather than "y" keeps an answer typed after a
+/// request timed out from approving a different action.
+pub struct TtyApproval {
+    lines: Mutex<Lines<BufReader<Stdin>>>,
+}
+
+impl TtyApproval {
+    /// Create a channel reading answers from standard input
+    pub fn new() -> Self {
+        if !std::io::stdin().is_terminal() {
+            tracing::warn!("Standard input is not a terminal; actions needing confirmation will be denied");
+        }
+        Self { lines: Mutex::new(BufReader::new(tokio::io::stdin()).lines()) }
+    }
+}
+
+impl Default for TtyApproval {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ApprovalChannel for TtyApproval {
+    async fn request(&self, request: &ApprovalRequest) -> Result<Approval> {
+        // One prompt at a time, so answers go to the action they were typed for
+        let mut lines = self.lines.lock().await;
+
+        let mut parameters: Vec<String> = request.parameters.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        parameters.sort();
+        let mut stderr = std::io::stderr();
+        writeln!(
+            stderr,
+            "\n{}\nAction {} requests approval: {}",
+            request.narrative, request.action_id, request.description,
+        )?;
+        if !request.defined_as.is_empty() {
+            writeln!(stderr, "  defined as: {}", request.defined_as)?;
+        }
+        write!(stderr, "  parameters: {}\nType {} to approve, anything else to deny: ", parameters.join(" "), request.action_id)?;
+        stderr.flush()?;
+
+        // `next_line` is cancel-safe, so a timed-out prompt loses no input
+        Ok(match lines.next_line().await? {
+            Some(answer) if answer.trim() == request.action_id => Approval::Approved,
+            Some(_) => Approval::Denied("Denied by operator".to_string()),
+            None => Approval::Denied("No operator input available".to_string()),
+        })
+    }
+}
+
+/// Decision returned by an approval webhook
+#[derive(Debug, Deserialize)]
+struct WebhookDecision {
+    approved: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Posts requests to an approval webhook, which answers once an operator decides
+pub struct WebhookApproval {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookApproval {
+    /// Create a channel posting to `url`
+    pub fn new(url: &str, verify_certs: bool) -> Result<Self> {
+        let http_client = reqwest::Client::builder().danger_accept_invalid_certs(!verify_certs).build()?;
+        Ok(Self { url: url.to_string(), http_client })
+    }
+}
+
+#[async_trait]
+impl ApprovalChannel for WebhookApproval {
+    async fn request(&self, request: &ApprovalRequest) -> Result<Approval> {
+        let response = self.http_client.post(&self.url)
+            .json(request)
+            .send()
+            .await
+            .context("Failed to reach approval webhook")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Approval webhook returned {}", response.status()));
+        }
+
+        let decision: WebhookDecision = response.json().await.context("Invalid approval webhook response")?;
+        Ok(if decision.approved {
+            Approval::Approved
+        } else {
+            Approval::Denied(decision.reason.unwrap_or_else(|| "Denied by approval webhook".to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ApprovalRequest {
+        ApprovalRequest {
+            client_id: "test-client".to_string(),
+            message_id: "msg-1".to_string(),
+            narrative: "Disk filling up".to_string(),
+            action_id: "disk.cleanup".to_string(),
+            description: "Remove rotated logs".to_string(),
+            defined_as: "Remove rotated logs older than a number of days".to_string(),
+            parameters: [("path".to_string(), "/var/log".to_string())].into_iter().collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_decisions() -> Result<()> {
+        let mut server = mockito::Server::new_async().await;
+        let approve = server.mock("POST", "/approve")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "client_id": "test-client",
+                "action_id": "disk.cleanup",
+                "defined_as": "Remove rotated logs older than a number of days",
+                "parameters": { "path": "/var/log" },
+            })))
+            .with_body(r#"{"approved": true}"#)
+            .create_async()
+            .await;
+        server.mock("POST", "/deny")
+            .with_body(r#"{"approved": false, "reason": "Not during business hours"}"#)
+            .create_async()
+            .await;
+        server.mock("POST", "/broken").with_status(500).create_async().await;
+
+        let channel = |path: &str| WebhookApproval::new(&format!("{}{}", server.url(), path), true);
+        assert_eq!(channel("/approve")?.request(&request()).await?, Approval::Approved);
+        approve.assert_async().await;
+        assert_eq!(
+            channel("/deny")?.request(&request()).await?,
+            Approval::Denied("Not during business hours".to_string()),
+        );
+        assert!(channel("/broken")?.request(&request()).await.is_err());
+
+        Ok(())
+    }
+}
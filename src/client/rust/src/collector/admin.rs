@@ -0,0 +1,120 @@
+//! Admin command socket of a running collector
+//!
+//! With an `admin` section the collector listens on a Unix socket. A client
+//! writes one command per connection, ended by a newline, and reads back one
+//! line of JSON before the collector closes the connection:
+//!
+//! - `pipeline`: the [`PipelineTopology`](crate::collector::pipeline::PipelineTopology)
+//!   as loaded, reflecting any reload
+//! - `health`: the [`SourceHealth`](crate::collector::health::SourceHealth) of
+//!   every source, as a list
+//!
+//! A command the collector does not know is answered with `{"error": "..."}`.
+//! `log_collector --admin <COMMAND>` sends a command and prints the answer.
+
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use tokio::task::JoinHandle;
+
+use crate::collector::config::AdminConfig;
+use crate::collector::pipeline::TopologyHandle;
+
+/// Longest command line read from a client
+const MAX_COMMAND_BYTES: u64 = 1024;
+
+/// Answer `command` from the running pipeline, as one line of JSON
+async fn answer(command: &str, topology: &TopologyHandle) -> String {
+    match command {
+        "pipeline" => serde_json::to_string(&topology.snapshot().await).unwrap_or_else(|e| error(&e.to_string())),
+        "health" => serde_json::to_string(&topology.source_health()).unwrap_or_else(|e| error(&e.to_string())),
+        other => error(&format!("unknown command '{}'", other)),
+    }
+}
+
+/// An error answer
+fn error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Listen on `config.socket_path`, answering commands about the pipeline behind `topology`
+///
+/// A socket file left behind by a collector that is no longer running is
+/// replaced; a socket another collector still answers on is not.
+#[cfg(unix)]
+pub async fn start_admin_server(config: &AdminConfig, topology: TopologyHandle) -> Result<JoinHandle<()>> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    let path = Path::new(&config.socket_path);
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(anyhow!("Admin socket path {} exists and is not a socket", path.display()));
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(anyhow!("Admin socket {} is in use by another process", path.display()));
+        }
+        std::fs::remove_file(path)
+            .map_err(|e| anyhow!("Failed to remove stale admin socket {}: {}", path.display(), e))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .map_err(|e| anyhow!("Failed to bind admin socket {}: {}", path.display(), e))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| anyhow!("Failed to restrict admin socket {}: {}", path.display(), e))?;
+
+    tracing::info!("Serving admin commands on {}", path.display());
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Failed to accept admin connection: {}", e);
+                    continue;
+                },
+            };
+
+            let topology = topology.clone();
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.into_split();
+                let mut command = String::new();
+                if let Err(e) = BufReader::new(reader.take(MAX_COMMAND_BYTES)).read_line(&mut command).await {
+                    tracing::debug!("Admin connection closed before sending a command: {}", e);
+                    return;
+                }
+
+                let mut response = answer(command.trim(), &topology).await;
+                response.push('\n');
+                if let Err(e) = writer.write_all(response.as_bytes()).await {
+                    tracing::debug!("Failed to answer admin command: {}", e);
+                }
+            });
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+pub async fn start_admin_server(config: &AdminConfig, _topology: TopologyHandle) -> Result<JoinHandle<()>> {
+    Err(anyhow!("Admin socket {} requires a Unix system", config.socket_path))
+}
+
+/// Send `command` to the collector listening on `socket_path` and return its answer
+#[cfg(unix)]
+pub async fn send_command(socket_path: &Path, command: &str) -> Result<String> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::UnixStream::connect(socket_path)
+        .await
+        .map_err(|e| anyhow!("Failed to connect to admin socket {}: {}", socket_path.display(), e))?;
+    stream.write_all(format!("{}\n", command).as_bytes()).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(response.trim_end().to_string())
+}
+
+#[cfg(not(unix))]
+pub async fn send_command(socket_path: &Path, _command: &str) -> Result<String> {
+    Err(anyhow!("Admin socket {} requires a Unix system", socket_path.display()))
+}
@@ -0,0 +1,104 @@
+//! Exponential backoff and random jitter for retries and timers
+//!
+//! Each retry waits twice as long as the one before, up to a ceiling, and the
+//! wait is spread randomly by a fraction of itself so that many clients
+//! failing at once do not all retry at the same moment. Periodic timers use
+//! the same jitter so collectors sharing an interval do not fire together.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Delays between successive attempts at a failing operation
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    jitter: f64,
+}
+
+impl Backoff {
+    /// Start at `base`, double per attempt up to `max`, and spread each delay by ±`jitter`
+    pub fn new(base: Duration, max: Duration, jitter: f64) -> Self {
+        Self {
+            base,
+            max: max.max(base),
+            jitter: jitter.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Delay before retry `attempt` (0 for the first retry), without jitter
+    pub fn nominal(&self, attempt: u32) -> Duration {
+        self.base.saturating_mul(1u32 << attempt.min(31)).min(self.max)
+    }
+
+    /// Delay before retry `attempt`, with jitter applied and capped at the maximum
+    pub fn delay(&self, attempt: u32) -> Duration {
+        jitter(self.nominal(attempt), self.jitter).min(self.max)
+    }
+
+    /// Delay before retry `attempt` when the server asked for `retry_after`
+    ///
+    /// The server's wait is honoured when it is longer than the backoff, but
+    /// never beyond the maximum, so a huge `Retry-After` cannot stall the caller.
+    pub fn delay_after(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self.delay(attempt);
+        retry_after.map_or(backoff, |retry_after| retry_after.max(backoff).min(self.max))
+    }
+}
+
+/// Lengthen or shorten `duration` by a random amount of up to `fraction` of it
+pub fn jitter(duration: Duration, fraction: f64) -> Duration {
+    spread(duration, fraction, random_unit())
+}
+
+/// Scale `duration` by `1 + fraction * (2 * unit - 1)` for `unit` in [0, 1)
+fn spread(duration: Duration, fraction: f64, unit: f64) -> Duration {
+    let factor = 1.0 + fraction.clamp(0.0, 1.0) * (2.0 * unit - 1.0);
+    duration.mul_f64(factor.max(0.0))
+}
+
+/// A random number in [0, 1)
+///
+/// Every `RandomState` is keyed differently, so hashing nothing with a fresh
+/// one is a dependency-free source of randomness good enough for jitter.
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_double_up_to_the_maximum() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000), 0.0);
+
+        let delays: Vec<_> = (0..6).map(|attempt| backoff.delay(attempt)).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis));
+        assert_eq!(backoff.nominal(u32::MAX), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_retry_after_is_capped_at_the_maximum() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000), 0.0);
+
+        assert_eq!(backoff.delay_after(0, None), Duration::from_millis(100));
+        assert_eq!(backoff.delay_after(0, Some(Duration::ZERO)), Duration::from_millis(100));
+        assert_eq!(backoff.delay_after(0, Some(Duration::from_millis(500))), Duration::from_millis(500));
+        assert_eq!(backoff.delay_after(0, Some(Duration::from_secs(86_400))), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_the_band() {
+        let second = Duration::from_secs(1);
+        assert_eq!(spread(second, 0.2, 0.0), Duration::from_millis(800));
+        assert_eq!(spread(second, 0.2, 0.5), second);
+        assert_eq!(spread(second, 0.0, 0.9), second);
+
+        let delays: Vec<_> = (0..200).map(|_| jitter(second, 0.2)).collect();
+        assert!(delays.iter().all(|delay| *delay >= Duration::from_millis(800) && *delay <= Duration::from_millis(1200)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]), "jitter should vary the delay");
+    }
+}
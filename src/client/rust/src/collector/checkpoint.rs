@@ -0,0 +1,115 @@
+//! Durable positions for sources
+//!
+//! Sources that resume after a restart (file offsets, journal cursors, HTTP
+//! poll cursors) keep their position through a [`Checkpointer`] handed to
+//! them at creation, rather than opening a database themselves. Keys are
+//! namespaced by the source, e.g. `file_offset:{source}:{path}`.
+
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::db::Database;
+
+/// Store of source positions, keyed by string
+pub trait Checkpointer: Send + Sync {
+    /// Look up the value stored under `key`
+    fn load(&self, key: &str) -> Result<Option<String>>;
+    /// Store `value` under `key`, replacing any earlier value
+    fn store(&self, key: &str, value: &str) -> Result<()>;
+}
+
+/// Checkpointer handle shared between sources
+pub type SharedCheckpointer = Arc<dyn Checkpointer>;
+
+/// Checkpointer backed by the `metadata` table of a SQLite database
+pub struct SqliteCheckpointer {
+    db: Mutex<Database>,
+}
+
+impl SqliteCheckpointer {
+    /// Open, creating if needed, the database at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let db = Database::open(path).map_err(|e| anyhow!("Failed to open checkpoint database {:?}: {}", path, e))?;
+
+        Ok(Self { db: Mutex::new(db) })
+    }
+
+    fn db(&self) -> std::sync::MutexGuard<'_, Database> {
+        self.db.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Checkpointer for SqliteCheckpointer {
+    fn load(&self, key: &str) -> Result<Option<String>> {
+        self.db().get_metadata(key)
+    }
+
+    fn store(&self, key: &str, value: &str) -> Result<()> {
+        self.db().set_metadata(key, value)
+    }
+}
+
+/// Checkpointer holding positions in memory, for tests
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryCheckpointer {
+    values: Mutex<std::collections::HashMap<String, String>>,
+}
+
+#[cfg(test)]
+impl MemoryCheckpointer {
+    fn values(&self) -> std::sync::MutexGuard<'_, std::collections::HashMap<String, String>> {
+        self.values.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+impl Checkpointer for MemoryCheckpointer {
+    fn load(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.values().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, value: &str) -> Result<()> {
+        self.values().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sqlite_checkpoints_survive_reopening() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("checkpoints.db");
+
+        let checkpoints = SqliteCheckpointer::open(&path)?;
+        assert_eq!(checkpoints.load("file_offset:app:/var/log/app.log")?, None);
+        checkpoints.store("file_offset:app:/var/log/app.log", "10")?;
+        checkpoints.store("file_offset:app:/var/log/app.log", "42")?;
+        checkpoints.store("journald_cursor:journal", "s=abc")?;
+        drop(checkpoints);
+
+        let reopened = SqliteCheckpointer::open(&path)?;
+        assert_eq!(reopened.load("file_offset:app:/var/log/app.log")?, Some("42".to_string()));
+        assert_eq!(reopened.load("journald_cursor:journal")?, Some("s=abc".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_checkpoints_replace_values() -> Result<()> {
+        let checkpoints: SharedCheckpointer = Arc::new(MemoryCheckpointer::default());
+
+        assert_eq!(checkpoints.load("cursor")?, None);
+        checkpoints.store("cursor", "page-1")?;
+        checkpoints.store("cursor", "page-2")?;
+        assert_eq!(checkpoints.load("cursor")?, Some("page-2".to_string()));
+
+        Ok(())
+    }
+}
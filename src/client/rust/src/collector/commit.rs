@@ -0,0 +1,170 @@
+//! Coordinated commit of logs across exporters
+//!
+//! With a `commit` block configured, logs stored by the `store` database
+//! exporter are only marked exported once every exporter listed in `require`
+//! has confirmed them too. Each batch sent to the exporters gets one ticket,
+//! and each exporter receives an [`Ack`] for it: the store reports the row ids
+//! it wrote, the others confirm once the logs are durably handled. The rows
+//! are marked when the last of them reports in. An ack dropped without being
+//! confirmed leaves the rows unmarked, so a failure never counts as delivery.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::collector::config::CommitConfig;
+
+/// Marks stored rows as exported once their ticket completes
+pub type MarkExported = Box<dyn FnOnce(&[i64]) -> Result<()> + Send>;
+
+/// Tracks which exporters still have to confirm each batch
+pub struct CommitCoordinator {
+    store: String,
+    require: Vec<String>,
+}
+
+impl CommitCoordinator {
+    /// Create a coordinator from configuration
+    pub fn new(config: &CommitConfig) -> Self {
+        Self {
+            store: config.store.clone(),
+            require: config.require.clone(),
+        }
+    }
+
+    /// Start tracking a batch sent to `exporters`, each of which gets its [`Ticket::ack`]
+    ///
+    /// Returns `None` when the store is not among them, as there are no rows to mark.
+    pub fn ticket<'a>(&self, exporters: impl IntoIterator<Item = &'a str>) -> Option<Ticket> {
+        let exporters: Vec<&str> = exporters.into_iter().collect();
+        if !exporters.contains(&self.store.as_str()) {
+            return None;
+        }
+
+        let mut remaining: HashSet<String> = self.require
+            .iter()
+            .filter(|name| exporters.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        remaining.insert(self.store.clone());
+
+        Some(Ticket {
+            state: Arc::new(Mutex::new(TicketState { remaining, stored: None })),
+        })
+    }
+}
+
+/// Completion state of one batch
+struct TicketState {
+    /// Exporters that have not confirmed yet, the store included
+    remaining: HashSet<String>,
+    /// Rows the store wrote, and how to mark them
+    stored: Option<(Vec<i64>, MarkExported)>,
+}
+
+/// One batch tracked by a [`CommitCoordinator`]
+pub struct Ticket {
+    state: Arc<Mutex<TicketState>>,
+}
+
+impl Ticket {
+    /// The ack `exporter` confirms this batch through
+    pub fn ack(&self, exporter: &str) -> Ack {
+        Ack {
+            exporter: exporter.to_string(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// One exporter's share of a batch's commit
+pub struct Ack {
+    exporter: String,
+    state: Arc<Mutex<TicketState>>,
+}
+
+impl Ack {
+    /// Confirm the batch as durably handled by this exporter
+    pub fn confirm(self) {
+        self.complete(None);
+    }
+
+    /// Confirm the batch as stored in rows `ids`, which `mark` flags as exported once the batch completes
+    pub fn stored(self, ids: Vec<i64>, mark: MarkExported) {
+        self.complete(Some((ids, mark)));
+    }
+
+    fn complete(self, stored: Option<(Vec<i64>, MarkExported)>) {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if stored.is_some() {
+            state.stored = stored;
+        }
+        state.remaining.remove(&self.exporter);
+
+        if state.remaining.is_empty() {
+            if let Some((ids, mark)) = state.stored.take() {
+                if let Err(e) = mark(&ids) {
+                    tracing::error!("Failed to mark {} committed logs as exported: {}", ids.len(), e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinator(require: &[&str]) -> CommitCoordinator {
+        CommitCoordinator::new(&CommitConfig {
+            store: "database".to_string(),
+            require: require.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    fn recorder() -> (Arc<Mutex<Vec<i64>>>, impl Fn() -> MarkExported) {
+        let marked = Arc::new(Mutex::new(Vec::new()));
+        let recorded = marked.clone();
+        let mark = move || -> MarkExported {
+            let recorded = recorded.clone();
+            Box::new(move |ids: &[i64]| {
+                recorded.lock().unwrap().extend_from_slice(ids);
+                Ok(())
+            })
+        };
+        (marked, mark)
+    }
+
+    #[test]
+    fn test_ticket_completes_once_every_exporter_confirms() {
+        let (marked, mark) = recorder();
+        let coordinator = coordinator(&["cloud"]);
+
+        // Confirmations may arrive in either order
+        let ticket = coordinator.ticket(["database", "cloud", "console"]).unwrap();
+        ticket.ack("database").stored(vec![1, 2], mark());
+        ticket.ack("console").confirm();
+        assert!(marked.lock().unwrap().is_empty());
+        ticket.ack("cloud").confirm();
+        assert_eq!(*marked.lock().unwrap(), vec![1, 2]);
+
+        let ticket = coordinator.ticket(["database", "cloud"]).unwrap();
+        ticket.ack("cloud").confirm();
+        ticket.ack("database").stored(vec![3], mark());
+        assert_eq!(*marked.lock().unwrap(), vec![1, 2, 3]);
+
+        // A dropped ack is a failure: the rows stay unmarked
+        let ticket = coordinator.ticket(["database", "cloud"]).unwrap();
+        ticket.ack("database").stored(vec![4], mark());
+        drop(ticket.ack("cloud"));
+        assert_eq!(*marked.lock().unwrap(), vec![1, 2, 3]);
+
+        // A batch routed away from a required exporter only waits for the store
+        let ticket = coordinator.ticket(["database"]).unwrap();
+        ticket.ack("database").stored(vec![5], mark());
+        assert_eq!(*marked.lock().unwrap(), vec![1, 2, 3, 5]);
+
+        // Batches that skip the store have nothing to commit
+        assert!(coordinator.ticket(["cloud"]).is_none());
+    }
+}
@@ -1,10 +1,16 @@
 //! Configuration handling for the log collector module
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
 use std::path::Path;
 
+use crate::collector::docker;
+use crate::collector::elasticsearch;
+use crate::collector::severity::{Severity, CANONICAL_LEVELS};
+
 /// Main configuration structure for the log collector
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CollectorConfig {
@@ -14,6 +20,849 @@ pub struct CollectorConfig {
     pub processors: Vec<ProcessorConfig>,
     /// Exporters configuration (where to send logs)
     pub exporters: Vec<ExporterConfig>,
+    /// Named processor/exporter chains that sources can bind to with `pipeline`
+    #[serde(default)]
+    pub pipelines: HashMap<String, PipelineConfig>,
+    /// Alerting on sustained pipeline backpressure
+    #[serde(default)]
+    pub queue_alert: Option<QueueAlertConfig>,
+    /// What happens to new logs while a pipeline's queue is full
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    /// Routing of the collector's own error logs into the pipeline
+    #[serde(default)]
+    pub internal_logs: Option<InternalLogsConfig>,
+    /// Seconds without an event after which a source is reported as stale
+    #[serde(default = "default_source_staleness")]
+    pub source_staleness_seconds: u64,
+    /// Seconds `stop` waits for queued logs to drain through processors and exporters
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout_seconds: u64,
+    /// Calls each exporter may have in flight at once
+    #[serde(default = "default_max_concurrent_exports")]
+    pub max_concurrent_exports: usize,
+    /// Named retry and circuit-breaker policies that exporters opt into with `resilience`
+    #[serde(default)]
+    pub resilience: HashMap<String, ResilienceConfig>,
+    /// Connections each listening source serves at once; further clients wait to be accepted
+    #[serde(default = "default_max_source_connections")]
+    pub max_source_connections: usize,
+    /// What to do with sources that cannot run on this operating system
+    #[serde(default)]
+    pub unsupported_sources: UnsupportedSourcePolicy,
+    /// What to do when more than one file source includes the same file
+    #[serde(default)]
+    pub overlapping_files: OverlappingFilesPolicy,
+    /// Only mark stored logs exported once other exporters confirmed them too
+    #[serde(default)]
+    pub commit: Option<CommitConfig>,
+    /// Prometheus endpoint exposing the collector's own metrics
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+    /// Liveness and readiness endpoints for orchestrators such as Kubernetes
+    #[serde(default)]
+    pub probes: Option<ProbesConfig>,
+    /// Unix socket answering operators' commands about the running collector
+    #[serde(default)]
+    pub admin: Option<AdminConfig>,
+}
+
+/// Pipeline fed by sources neither bound to nor listed by a named pipeline
+///
+/// Unless `pipelines` declares it, it runs every processor and exports to
+/// every exporter.
+pub const DEFAULT_PIPELINE: &str = "default";
+
+/// Largest accepted `send_batch_size` for the batch processor
+const MAX_BATCH_SIZE: u64 = 100_000;
+
+/// Largest accepted `max_line_bytes` for sources
+const MAX_LINE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Longest accepted batching or windowing interval, in seconds
+const MAX_INTERVAL_SECONDS: u64 = 3600;
+
+impl CollectorConfig {
+    /// Reject settings that deserialize fine but make the pipeline degenerate
+    ///
+    /// All problems are reported together rather than one per load attempt.
+    pub fn validate(&self) -> Result<()> {
+        report_problems(self.problems())
+    }
+
+    /// [`validate`](Self::validate), and also check what starting the collector needs
+    ///
+    /// Sources and exporters must not be empty, and the key, certificate and
+    /// database files the configuration references must be in place. Every
+    /// problem is listed in one error.
+    pub fn validate_for_startup(&self) -> Result<()> {
+        let mut errors = self.problems();
+        errors.extend(self.startup_problems());
+        report_problems(errors)
+    }
+
+    /// Settings that cannot work, whatever host the collector runs on
+    fn problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        check_unique(&mut errors, "source", self.sources.iter().map(SourceConfig::name));
+        check_unique(&mut errors, "processor", self.processors.iter().map(ProcessorConfig::name));
+        check_unique(&mut errors, "exporter", self.exporters.iter().map(ExporterConfig::name));
+        self.check_listeners(&mut errors);
+
+        for source in &self.sources {
+            match source {
+                SourceConfig::File { name, poll_interval_ms, multiline, max_line_bytes, .. } => {
+                    let what = format!("source '{}'", name);
+                    check_range(&mut errors, &what, "poll_interval_ms", *poll_interval_ms, 10, MAX_INTERVAL_SECONDS * 1000);
+                    check_range(&mut errors, &what, "max_line_bytes", *max_line_bytes as u64, 16, MAX_LINE_BYTES);
+
+                    if let Some(multiline) = multiline {
+                        check_range(&mut errors, &what, "multiline.max_lines", multiline.max_lines as u64, 1, MAX_BATCH_SIZE);
+                        check_range(
+                            &mut errors,
+                            &what,
+                            "multiline.flush_timeout_ms",
+                            multiline.flush_timeout_ms,
+                            1,
+                            MAX_INTERVAL_SECONDS * 1000,
+                        );
+                    }
+                },
+                SourceConfig::Docker { name, refresh_interval_seconds, .. } => {
+                    let what = format!("source '{}'", name);
+                    check_range(&mut errors, &what, "refresh_interval_seconds", *refresh_interval_seconds, 1, MAX_INTERVAL_SECONDS);
+                },
+                SourceConfig::HttpPoll {
+                    name,
+                    auth,
+                    poll_interval_seconds,
+                    records_pointer,
+                    message_pointer,
+                    timestamp_pointer,
+                    cursor,
+                    ..
+                } => {
+                    let what = format!("source '{}'", name);
+                    check_range(&mut errors, &what, "poll_interval_seconds", *poll_interval_seconds, 1, MAX_INTERVAL_SECONDS);
+                    if let Some(auth) = auth {
+                        let basic = auth.username.is_some() || auth.password.is_some();
+                        let incomplete = auth.username.is_none() || auth.password.is_none();
+                        if basic == auth.bearer_token.is_some() || (basic && incomplete) {
+                            errors.push(format!("{}: auth needs either username and password, or bearer_token", what));
+                        }
+                    }
+                    let pointers = [
+                        ("records_pointer", Some(records_pointer)),
+                        ("message_pointer", Some(message_pointer)),
+                        ("timestamp_pointer", timestamp_pointer.as_ref()),
+                        ("cursor.pointer", cursor.as_ref().map(|cursor| &cursor.pointer)),
+                    ];
+                    for (field, pointer) in pointers {
+                        if let Some(pointer) = pointer.filter(|pointer| !pointer.is_empty() && !pointer.starts_with('/')) {
+                            errors.push(format!("{}: {} '{}' must be empty or start with '/'", what, field, pointer));
+                        }
+                    }
+                },
+                SourceConfig::Otlp { name, server, protocol, tls, .. } => {
+                    let what = format!("source '{}'", name);
+                    check_range(&mut errors, &what, "server.max_connections", server.max_connections as u64, 1, u64::MAX);
+                    check_range(&mut errors, &what, "server.idle_timeout_seconds", server.idle_timeout_seconds, 1, MAX_INTERVAL_SECONDS);
+                    if let Some(tls) = tls {
+                        if *protocol != OtlpProtocol::Http {
+                            errors.push(format!("{}: tls requires protocol: http", what));
+                        }
+                        check_client_cert(&mut errors, &what, tls);
+                    }
+                },
+                SourceConfig::Syslog { name, protocol, tls: Some(tls), .. } => {
+                    let what = format!("source '{}'", name);
+                    if *protocol != SyslogProtocol::Tcp {
+                        errors.push(format!("{}: tls requires protocol: tcp", what));
+                    }
+                    check_client_cert(&mut errors, &what, tls);
+                },
+                _ => {},
+            }
+        }
+
+        let overlaps = self.overlapping_file_sources();
+        match self.overlapping_files {
+            OverlappingFilesPolicy::Warn => {
+                for overlap in overlaps {
+                    tracing::warn!("{}; its lines will be collected once per source", overlap);
+                }
+            },
+            OverlappingFilesPolicy::Error => {
+                for overlap in overlaps {
+                    errors.push(format!("{} (set overlapping_files: dedupe to read it once)", overlap));
+                }
+            },
+            OverlappingFilesPolicy::Dedupe => {},
+        }
+
+        for processor in &self.processors {
+            match processor {
+                ProcessorConfig::Batch { name, timeout, send_batch_size, timeout_jitter } => {
+                    let what = format!("processor '{}'", name);
+                    check_range(&mut errors, &what, "timeout", *timeout, 1, MAX_INTERVAL_SECONDS);
+                    check_range(&mut errors, &what, "send_batch_size", *send_batch_size as u64, 1, MAX_BATCH_SIZE);
+                    check_fraction(&mut errors, &what, "timeout_jitter", *timeout_jitter);
+                },
+                ProcessorConfig::Coalesce { name, window_seconds }
+                | ProcessorConfig::RateAnomaly { name, window_seconds, .. }
+                | ProcessorConfig::Quota { name, window_seconds, .. } => {
+                    let what = format!("processor '{}'", name);
+                    check_range(&mut errors, &what, "window_seconds", *window_seconds, 1, MAX_INTERVAL_SECONDS);
+                },
+                ProcessorConfig::Filter { name, logs, min_severity, .. } => {
+                    let what = format!("processor '{}'", name);
+                    if let Some(min_severity) = min_severity.as_deref().filter(|level| Severity::parse(level).is_none()) {
+                        errors.push(format!(
+                            "{}: min_severity '{}' is not a known level (use one of {})",
+                            what,
+                            min_severity,
+                            CANONICAL_LEVELS.join(", "),
+                        ));
+                    }
+                    for matcher in [&logs.include, &logs.exclude].into_iter().flatten().flat_map(MatchRule::conditions) {
+                        if matcher.match_type == MatchType::Regexp {
+                            for pattern in matcher.regexp.iter().flatten() {
+                                check_regex(&mut errors, &what, pattern);
+                            }
+                        }
+                        let attribute = matcher.field.strip_prefix(ATTRIBUTE_FIELD_PREFIX);
+                        if !matches!(matcher.field.as_str(), "message" | "level") && attribute.is_none_or(str::is_empty) {
+                            errors.push(format!(
+                                "{}: field must be message, level or attributes.<key> (got '{}')",
+                                what,
+                                matcher.field,
+                            ));
+                        }
+                    }
+                },
+                ProcessorConfig::Transform { name, transforms } => {
+                    let what = format!("processor '{}'", name);
+                    for pattern in transforms.iter().filter_map(|transform| transform.parameters.get("pattern")) {
+                        check_regex(&mut errors, &what, pattern);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        for exporter in &self.exporters {
+            match exporter {
+                ExporterConfig::LogNarrator {
+                    name,
+                    max_in_flight,
+                    flush_interval_seconds,
+                    flush_jitter,
+                    failover,
+                    tls,
+                    retry,
+                    dead_letter,
+                    encryption,
+                    allow_plaintext,
+                    ..
+                } => {
+                    let what = format!("exporter '{}'", name);
+                    check_range(&mut errors, &what, "max_in_flight", *max_in_flight as u64, 1, u64::MAX);
+                    if tls.client_cert_path.is_some() != tls.client_key_path.is_some() {
+                        errors.push(format!("{}: tls.client_cert_path and tls.client_key_path must be set together", what));
+                    }
+                    match encryption {
+                        Some(encryption) => check_encryption(&mut errors, &what, encryption),
+                        None if !allow_plaintext => errors.push(plaintext_refused(&what)),
+                        None => {},
+                    }
+                    check_range(&mut errors, &what, "flush_interval_seconds", *flush_interval_seconds, 1, MAX_INTERVAL_SECONDS);
+                    check_fraction(&mut errors, &what, "flush_jitter", *flush_jitter);
+                    check_range(
+                        &mut errors,
+                        &what,
+                        "failover.failover_after_failures",
+                        u64::from(failover.failover_after_failures),
+                        1,
+                        u64::MAX,
+                    );
+                    check_range(
+                        &mut errors,
+                        &what,
+                        "failover.failback_interval_seconds",
+                        failover.failback_interval_seconds,
+                        1,
+                        MAX_INTERVAL_SECONDS,
+                    );
+                    check_retry(&mut errors, &what, retry);
+                    check_dead_letter(&mut errors, &what, dead_letter);
+                },
+                ExporterConfig::Elasticsearch {
+                    name,
+                    nodes,
+                    index,
+                    auth,
+                    batch_size,
+                    flush_interval_seconds,
+                    flush_jitter,
+                    retry,
+                    ..
+                } => {
+                    let what = format!("exporter '{}'", name);
+                    if nodes.is_empty() {
+                        errors.push(format!("{}: nodes must not be empty", what));
+                    }
+                    if let Err(e) = elasticsearch::check_index_pattern(index) {
+                        errors.push(format!("{}: index {}", what, e));
+                    }
+                    if let Some(auth) = auth {
+                        let basic = auth.username.is_some() || auth.password.is_some();
+                        let incomplete = auth.username.is_none() || auth.password.is_none();
+                        if basic == auth.api_key.is_some() || (basic && incomplete) {
+                            errors.push(format!("{}: auth needs either username and password, or api_key", what));
+                        }
+                    }
+                    check_range(&mut errors, &what, "batch_size", *batch_size as u64, 1, MAX_BATCH_SIZE);
+                    check_range(&mut errors, &what, "flush_interval_seconds", *flush_interval_seconds, 1, MAX_INTERVAL_SECONDS);
+                    check_fraction(&mut errors, &what, "flush_jitter", *flush_jitter);
+                    check_retry(&mut errors, &what, retry);
+                },
+                ExporterConfig::LocalCache { name, drain: Some(drain), .. } => {
+                    let what = format!("exporter '{}'", name);
+                    if drain.exporter == *name {
+                        errors.push(format!("{}: drain.exporter must name another exporter", what));
+                    } else if !self.exporters.iter().any(|config| config.name() == drain.exporter) {
+                        errors.push(format!("{}: unknown drain.exporter '{}'", what, drain.exporter));
+                    }
+                    check_range(&mut errors, &what, "drain.interval_seconds", drain.interval_seconds, 1, MAX_INTERVAL_SECONDS);
+                },
+                ExporterConfig::Database { name, dead_letter, .. } => {
+                    let what = format!("exporter '{}'", name);
+                    check_dead_letter(&mut errors, &what, dead_letter);
+                },
+                _ => {},
+            }
+        }
+
+        if let Some(commit) = &self.commit {
+            match self.exporters.iter().find(|config| config.name() == commit.store) {
+                Some(ExporterConfig::Database { .. }) => {},
+                Some(_) => errors.push(format!("commit: store '{}' is not a database exporter", commit.store)),
+                None => errors.push(format!("commit: unknown exporter '{}'", commit.store)),
+            }
+            for name in &commit.require {
+                match self.exporters.iter().find(|config| config.name() == name) {
+                    Some(ExporterConfig::Elasticsearch { .. }) => {
+                        errors.push(format!("commit: exporter '{}' cannot confirm delivery", name));
+                    },
+                    Some(_) => {},
+                    None => errors.push(format!("commit: unknown exporter '{}'", name)),
+                }
+            }
+        }
+
+        if let Some(telemetry) = &self.telemetry {
+            if telemetry.listen_address.parse::<std::net::SocketAddr>().is_err() {
+                errors.push(format!("telemetry: listen_address '{}' is not an address and port", telemetry.listen_address));
+            }
+            if !telemetry.path.starts_with('/') {
+                errors.push(format!("telemetry: path '{}' must start with '/'", telemetry.path));
+            }
+        }
+
+        check_range(&mut errors, "collector", "max_concurrent_exports", self.max_concurrent_exports as u64, 1, u64::MAX);
+
+        let mut policy_names: Vec<&String> = self.resilience.keys().collect();
+        policy_names.sort();
+        for policy_name in policy_names {
+            let policy = &self.resilience[policy_name];
+            let what = format!("resilience '{}'", policy_name);
+            check_retry(&mut errors, &what, &policy.retry);
+            check_range(&mut errors, &what, "failure_threshold", u64::from(policy.failure_threshold), 1, u64::MAX);
+            check_range(&mut errors, &what, "cooldown_seconds", policy.cooldown_seconds, 1, MAX_INTERVAL_SECONDS);
+            check_dead_letter(&mut errors, &what, &policy.dead_letter);
+        }
+        for exporter in &self.exporters {
+            if let Some(policy_name) = exporter.resilience() {
+                if !self.resilience.contains_key(policy_name) {
+                    errors.push(format!("exporter '{}': unknown resilience policy '{}'", exporter.name(), policy_name));
+                }
+            }
+        }
+        check_range(&mut errors, "collector", "max_source_connections", self.max_source_connections as u64, 1, u64::MAX);
+
+        if let Some(probes) = &self.probes {
+            if probes.listen_address.parse::<std::net::SocketAddr>().is_err() {
+                errors.push(format!("probes: listen_address '{}' is not an address and port", probes.listen_address));
+            }
+            check_range(&mut errors, "probes", "max_flush_age_seconds", probes.max_flush_age_seconds, 1, u64::MAX);
+        }
+
+        let mut pipeline_names: Vec<&String> = self.pipelines.keys().collect();
+        pipeline_names.sort();
+        for pipeline_name in pipeline_names {
+            let pipeline = &self.pipelines[pipeline_name];
+            let what = format!("pipeline '{}'", pipeline_name);
+
+            for source in &pipeline.sources {
+                if !self.sources.iter().any(|config| config.name() == source) {
+                    errors.push(format!("{}: unknown source '{}'", what, source));
+                }
+            }
+            for processor in &pipeline.processors {
+                if !self.processors.iter().any(|config| config.name() == processor) {
+                    errors.push(format!("{}: unknown processor '{}'", what, processor));
+                }
+            }
+            for exporter in &pipeline.exporters {
+                if !self.exporters.iter().any(|config| config.name() == exporter) {
+                    errors.push(format!("{}: unknown exporter '{}'", what, exporter));
+                }
+            }
+            if pipeline.exporters.is_empty() {
+                errors.push(format!("{}: exporters must not be empty", what));
+            }
+        }
+
+        for source in &self.sources {
+            if let Some(pipeline) = source.pipeline() {
+                if pipeline != DEFAULT_PIPELINE && !self.pipelines.contains_key(pipeline) {
+                    errors.push(format!("source '{}': unknown pipeline '{}'", source.name(), pipeline));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Settings the host must satisfy before the pipeline can start
+    fn startup_problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.sources.is_empty() {
+            errors.push("sources must not be empty".to_string());
+        }
+        if self.exporters.is_empty() {
+            errors.push("exporters must not be empty".to_string());
+        }
+
+        for source in &self.sources {
+            if let SourceConfig::Syslog { name, tls: Some(tls), .. } | SourceConfig::Otlp { name, tls: Some(tls), .. } = source {
+                let what = format!("source '{}'", name);
+                check_file(&mut errors, &what, "tls.cert_path", &tls.cert_path);
+                check_file(&mut errors, &what, "tls.key_path", &tls.key_path);
+                if let Some(client_ca_path) = &tls.client_ca_path {
+                    check_file(&mut errors, &what, "tls.client_ca_path", client_ca_path);
+                }
+            }
+        }
+
+        for exporter in &self.exporters {
+            match exporter {
+                ExporterConfig::LogNarrator { name, key_path, signing_key_path, encryption, tls, .. } => {
+                    let what = format!("exporter '{}'", name);
+                    check_file(&mut errors, &what, "key_path", key_path);
+                    for (field, path) in [
+                        ("signing_key_path", signing_key_path),
+                        ("tls.ca_cert_path", &tls.ca_cert_path),
+                        ("tls.client_cert_path", &tls.client_cert_path),
+                        ("tls.client_key_path", &tls.client_key_path),
+                    ] {
+                        if let Some(path) = path {
+                            check_file(&mut errors, &what, field, path);
+                        }
+                    }
+                    if let Some(server_public_key_path) = encryption.as_ref().and_then(|encryption| encryption.server_public_key_path.as_ref()) {
+                        check_file(&mut errors, &what, "encryption.server_public_key_path", server_public_key_path);
+                    }
+                },
+                ExporterConfig::Database { name, path, .. } => {
+                    // SQLite creates the file, but not its directory
+                    let directory = Path::new(path).parent().filter(|directory| !directory.as_os_str().is_empty());
+                    if directory.is_some_and(|directory| !directory.is_dir()) {
+                        errors.push(format!("exporter '{}': directory of path '{}' does not exist", name, path));
+                    } else if Path::new(path).exists() {
+                        if let Err(e) = OpenOptions::new().read(true).write(true).open(path) {
+                            errors.push(format!("exporter '{}': path '{}' is not writable: {}", name, path, e));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        errors
+    }
+
+    /// Record an error for each pair of network sources bound to the same port
+    ///
+    /// OTLP listens over TCP and syslog over its `protocol`, so a TCP and a UDP
+    /// listener may share a port. Interfaces conflict when they are equal or
+    /// either is a wildcard address.
+    fn check_listeners(&self, errors: &mut Vec<String>) {
+        let listeners: Vec<(&String, &String, u16, SyslogProtocol)> = self.sources
+            .iter()
+            .filter_map(|source| match source {
+                SourceConfig::Otlp { name, interface, port, .. } => Some((name, interface, *port, SyslogProtocol::Tcp)),
+                SourceConfig::Syslog { name, interface, port, protocol, .. } => Some((name, interface, *port, *protocol)),
+                SourceConfig::Tcp { name, interface, port, .. } => Some((name, interface, *port, SyslogProtocol::Tcp)),
+                SourceConfig::Udp { name, interface, port, .. } => Some((name, interface, *port, SyslogProtocol::Udp)),
+                _ => None,
+            })
+            .collect();
+
+        let transport = |protocol: &SyslogProtocol| match protocol {
+            SyslogProtocol::Tcp => "tcp",
+            SyslogProtocol::Udp => "udp",
+        };
+        let wildcard = |interface: &str| interface.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+        for (index, (first, first_interface, port, protocol)) in listeners.iter().enumerate() {
+            for (second, second_interface, other_port, other_protocol) in &listeners[index + 1..] {
+                let shared = first_interface == second_interface || wildcard(first_interface) || wildcard(second_interface);
+                if port == other_port && protocol == other_protocol && shared {
+                    errors.push(format!(
+                        "sources '{}' and '{}' both listen on {} port {}",
+                        first,
+                        second,
+                        transport(protocol),
+                        port,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Pipelines `source` feeds, in delivery order
+    ///
+    /// That is the pipeline it is bound to with `pipeline`, then every
+    /// pipeline listing it in `sources`. A source neither bound nor listed
+    /// feeds the default pipeline.
+    pub fn source_pipelines<'a>(&'a self, source: &'a SourceConfig) -> Vec<&'a str> {
+        let mut listing: Vec<&str> = self.pipelines
+            .iter()
+            .filter(|(_, pipeline)| pipeline.sources.iter().any(|name| name == source.name()))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        listing.sort();
+
+        let mut names: Vec<&str> = source.pipeline().into_iter().collect();
+        for name in listing {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            names.push(DEFAULT_PIPELINE);
+        }
+        names
+    }
+
+    /// Describe each pair of file sources whose include patterns can match the same file
+    ///
+    /// Patterns overlap when they are equal, when one matches the other as a
+    /// path, or when both currently match an existing file. Exclude patterns
+    /// are not taken into account.
+    pub fn overlapping_file_sources(&self) -> Vec<String> {
+        let files: Vec<(&String, &Vec<String>)> = self.sources
+            .iter()
+            .filter_map(|source| match source {
+                SourceConfig::File { name, include, .. } => Some((name, include)),
+                _ => None,
+            })
+            .collect();
+
+        let mut overlaps = Vec::new();
+        for (index, (first, first_include)) in files.iter().enumerate() {
+            for (second, second_include) in &files[index + 1..] {
+                let shared = first_include
+                    .iter()
+                    .find_map(|a| second_include.iter().find_map(|b| shared_path(a, b)));
+                if let Some(shared) = shared {
+                    overlaps.push(format!("sources '{}' and '{}' both include {}", first, second, shared));
+                }
+            }
+        }
+
+        overlaps
+    }
+}
+
+/// A path both include patterns can match, if there is one
+fn shared_path(a: &str, b: &str) -> Option<String> {
+    let options = glob::MatchOptions { require_literal_separator: true, ..glob::MatchOptions::new() };
+    let matches = |pattern: &str, path: &str| {
+        glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches_with(path, options))
+    };
+    if a == b || matches(a, b) {
+        return Some(b.to_string());
+    }
+    if matches(b, a) {
+        return Some(a.to_string());
+    }
+
+    // Two distinct globs: compare the files they match right now
+    let existing = |pattern: &str| -> Vec<std::path::PathBuf> {
+        glob::glob(pattern).map(|paths| paths.flatten().collect()).unwrap_or_default()
+    };
+    let b_files = existing(b);
+    existing(a)
+        .into_iter()
+        .find(|path| b_files.contains(path))
+        .map(|path| path.display().to_string())
+}
+
+/// Turn the collected problems into a single error listing all of them
+fn report_problems(errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid collector configuration:\n  {}", errors.join("\n  ")))
+    }
+}
+
+/// Record an error for each name used more than once
+fn check_unique<'a>(errors: &mut Vec<String>, kind: &str, names: impl Iterator<Item = &'a str>) {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for name in names {
+        if !seen.insert(name) && reported.insert(name) {
+            errors.push(format!("{} name '{}' is used more than once", kind, name));
+        }
+    }
+}
+
+/// Record an error if `pattern` is not a valid regular expression
+fn check_regex(errors: &mut Vec<String>, what: &str, pattern: &str) {
+    if let Err(e) = Regex::new(pattern) {
+        // The parser's message spans several lines; keep its last one
+        let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+        errors.push(format!("{}: invalid regex '{}': {}", what, pattern, reason));
+    }
+}
+
+/// Record an error if a referenced file does not exist or cannot be read
+fn check_file(errors: &mut Vec<String>, what: &str, field: &str, path: &str) {
+    if !Path::new(path).is_file() {
+        errors.push(format!("{}: {} '{}' does not exist", what, field, path));
+    } else if let Err(e) = File::open(path) {
+        errors.push(format!("{}: {} '{}' is not readable: {}", what, field, path, e));
+    }
+}
+
+/// Record an error when `value` falls outside `min..=max`
+fn check_range(errors: &mut Vec<String>, what: &str, field: &str, value: u64, min: u64, max: u64) {
+    if value < min {
+        errors.push(format!("{}: {} must be at least {} (got {})", what, field, min, value));
+    } else if value > max {
+        errors.push(format!("{}: {} must be at most {} (got {})", what, field, max, value));
+    }
+}
+
+/// Record an error if a jitter fraction is outside 0 to 1
+fn check_fraction(errors: &mut Vec<String>, what: &str, field: &str, value: f64) {
+    if !(0.0..=1.0).contains(&value) {
+        errors.push(format!("{}: {} must be between 0 and 1 (got {})", what, field, value));
+    }
+}
+
+/// Record an error for each retry setting that cannot work
+fn check_retry(errors: &mut Vec<String>, what: &str, retry: &RetryConfig) {
+    check_range(errors, what, "retry.base_delay_ms", retry.base_delay_ms, 1, MAX_INTERVAL_SECONDS * 1000);
+    check_range(
+        errors,
+        what,
+        "retry.max_delay_ms",
+        retry.max_delay_ms,
+        retry.base_delay_ms,
+        MAX_INTERVAL_SECONDS * 1000,
+    );
+    check_range(errors, what, "retry.max_retries", u64::from(retry.max_retries), 0, 100);
+    check_fraction(errors, what, "retry.jitter", retry.jitter);
+}
+
+/// Record an error for each dead-letter size limit that cannot work
+fn check_dead_letter(errors: &mut Vec<String>, what: &str, dead_letter: &DeadLetterConfig) {
+    check_range(errors, what, "dead_letter.max_file_mb", dead_letter.max_file_mb, 1, u64::MAX);
+    check_range(
+        errors,
+        what,
+        "dead_letter.max_total_mb",
+        dead_letter.max_total_mb,
+        dead_letter.max_file_mb,
+        u64::MAX,
+    );
+}
+
+/// Record an error for a client certificate requirement no CA can satisfy
+fn check_client_cert(errors: &mut Vec<String>, what: &str, tls: &TlsConfig) {
+    if tls.require_client_cert && tls.client_ca_path.is_none() {
+        errors.push(format!("{}: tls.require_client_cert needs tls.client_ca_path", what));
+    }
+}
+
+/// Why a LogNarrator exporter without `encryption` is refused
+pub(crate) fn plaintext_refused(what: &str) -> String {
+    format!("{}: uploads would be readable plaintext; configure encryption, or set allow_plaintext: true", what)
+}
+
+/// Record an error for encryption settings missing what their scheme needs
+fn check_encryption(errors: &mut Vec<String>, what: &str, encryption: &EncryptionConfig) {
+    match encryption.scheme {
+        EncryptionScheme::Sodium => {
+            if encryption.server_public_key_path.is_none() {
+                errors.push(format!("{}: encryption.server_public_key_path is required by the sodium scheme", what));
+            }
+        },
+        EncryptionScheme::Age => {
+            if !cfg!(feature = "age") {
+                errors.push(format!("{}: the age encryption scheme requires a build with the `age` feature", what));
+            }
+            if encryption.recipients.is_empty() {
+                errors.push(format!("{}: encryption.recipients must not be empty with the age scheme", what));
+            }
+            for recipient in encryption.recipients.iter().filter(|recipient| !recipient.starts_with("age1")) {
+                errors.push(format!("{}: '{}' is not an age X25519 recipient (age1...)", what, recipient));
+            }
+        },
+    }
+}
+
+/// Handling of configured sources the current operating system cannot run
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsupportedSourcePolicy {
+    /// Log a warning and run without the source
+    #[default]
+    Skip,
+    /// Refuse to start
+    Error,
+}
+
+/// Handling of logs arriving while a pipeline's queue is full
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Make sources wait for room, pushing back on whatever feeds them
+    #[default]
+    Block,
+    /// Discard the arriving log
+    DropNewest,
+    /// Discard the longest-queued log to make room for the arriving one
+    DropOldest,
+}
+
+/// Handling of a file included by more than one file source
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlappingFilesPolicy {
+    /// Log a warning and read the file in every source that includes it
+    #[default]
+    Warn,
+    /// Refuse to start
+    Error,
+    /// Read the file only in the first source to open it
+    Dedupe,
+}
+
+/// Transport an OTLP source receives exports over
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    /// OTLP/HTTP: protobuf or JSON posted to `/v1/logs` (conventionally port 4318)
+    #[default]
+    Http,
+    /// OTLP/gRPC: the `LogsService/Export` RPC (conventionally port 4317)
+    Grpc,
+}
+
+/// Timestamp given to an OTLP record sent without `time_unix_nano`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingTimestamp {
+    /// The record's `observed_time_unix_nano`, or the ingestion time when that is unset too
+    #[default]
+    Observed,
+    /// The time the receiver decoded the record
+    Ingestion,
+}
+
+/// Coordinated commit of logs stored in the local database
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CommitConfig {
+    /// Database exporter whose stored logs are marked exported
+    pub store: String,
+    /// Exporters that must also confirm a log before it is marked; empty marks it once stored
+    #[serde(default)]
+    pub require: Vec<String>,
+}
+
+/// Prometheus metrics endpoint
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TelemetryConfig {
+    /// Address and port the endpoint listens on
+    #[serde(default = "default_telemetry_address")]
+    pub listen_address: String,
+    /// Path the metrics are served at
+    #[serde(default = "default_metrics_path")]
+    pub path: String,
+}
+
+/// Admin command socket
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AdminConfig {
+    /// Path of the Unix socket, created readable and writable by the owner only
+    #[serde(default = "default_admin_socket")]
+    pub socket_path: String,
+}
+
+/// Liveness (`/healthz`) and readiness (`/readyz`) endpoints
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProbesConfig {
+    /// Address and port the endpoints listen on
+    #[serde(default = "default_probes_address")]
+    pub listen_address: String,
+    /// Seconds an exporter flushing on a timer may go without a successful flush and still be ready
+    #[serde(default = "default_max_flush_age")]
+    pub max_flush_age_seconds: u64,
+}
+
+/// Configuration for the queue-full alerting hook
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct QueueAlertConfig {
+    /// Fill ratio (0.0-1.0) at which a queue counts as saturated
+    #[serde(default = "default_saturation_ratio")]
+    pub saturation_ratio: f64,
+    /// How long a queue must stay saturated before the alert fires (in seconds)
+    #[serde(default = "default_sustain_seconds")]
+    pub sustain_seconds: u64,
+}
+
+/// Configuration for feeding the collector's own logs into the pipeline
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InternalLogsConfig {
+    /// Sustained number of internal entries admitted per second
+    #[serde(default = "default_internal_rate")]
+    pub rate_per_second: u32,
+    /// Number of internal entries admitted in a single burst
+    #[serde(default = "default_internal_burst")]
+    pub burst: u32,
+}
+
+/// A named chain of processors and exporters
+///
+/// Processors run in the listed order. Each pipeline gets its own processor
+/// instances, so stateful processors such as `batch` are not shared between
+/// pipelines; exporters are shared.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PipelineConfig {
+    /// Names of sources feeding this pipeline besides those bound to it with `pipeline`
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Names of the processors to run, in order
+    #[serde(default)]
+    pub processors: Vec<String>,
+    /// Names of the exporters to deliver to
+    pub exporters: Vec<String>,
 }
 
 /// Configuration for log sources
@@ -24,16 +873,36 @@ pub enum SourceConfig {
     File {
         /// Unique name for the source
         name: String,
-        /// List of file paths to include
+        /// File paths or glob patterns to include; files may appear after startup
         include: Vec<String>,
         /// Optional regex pattern to exclude files
         exclude_filename_pattern: Option<String>,
         /// Where to start reading (beginning or end of file)
         #[serde(default = "default_start_at")]
         start_at: StartAt,
+        /// Record delimiter; the default `\n` also splits `\r\n` line endings
+        #[serde(default = "default_delimiter")]
+        delimiter: String,
+        /// How often files are checked for new data, rotation and new matches (in milliseconds)
+        #[serde(default = "default_file_poll_interval")]
+        poll_interval_ms: u64,
+        /// SQLite database recording read offsets, so a restart resumes where it left off
+        #[serde(default)]
+        offsets_path: Option<String>,
+        /// Joining of multi-line entries such as stack traces
+        #[serde(default)]
+        multiline: Option<MultilineConfig>,
+        /// Longest record kept whole; longer records are never buffered in full
+        #[serde(default = "default_max_line_bytes")]
+        max_line_bytes: usize,
+        /// What to do with records longer than `max_line_bytes`
+        #[serde(default)]
+        long_lines: LongLineAction,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
-    /// Journald log source (Linux only)
-    #[cfg(target_os = "linux")]
+    /// Journald log source (only runs on Linux builds with the `journald` feature; see `unsupported_sources`)
     Journald {
         /// Unique name for the source
         name: String,
@@ -41,6 +910,12 @@ pub enum SourceConfig {
         directory: Option<String>,
         /// List of systemd units to collect logs from
         units: Vec<String>,
+        /// SQLite file remembering the journal cursor across restarts
+        #[serde(default)]
+        cursor_path: Option<String>,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
     /// Docker container logs
     Docker {
@@ -51,6 +926,15 @@ pub enum SourceConfig {
         /// Whether to collect logs from all containers
         #[serde(default)]
         all_containers: bool,
+        /// Path of the Docker daemon socket, e.g. `$XDG_RUNTIME_DIR/docker.sock` for rootless Docker
+        #[serde(default = "default_docker_socket")]
+        socket_path: String,
+        /// How often running containers are re-listed to pick up started ones (in seconds)
+        #[serde(default = "default_docker_refresh_interval")]
+        refresh_interval_seconds: u64,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
     /// OpenTelemetry Protocol HTTP receiver
     Otlp {
@@ -61,230 +945,2503 @@ pub enum SourceConfig {
         /// Interface to bind to
         #[serde(default = "default_interface")]
         interface: String,
+        /// Transport to receive exports over
+        #[serde(default)]
+        protocol: OtlpProtocol,
+        /// Retry binding the port instead of failing startup immediately
+        #[serde(default)]
+        bind_retry: Option<BindRetryConfig>,
+        /// HTTP protocol versions, keep-alive and connection limits (`http` only)
+        #[serde(default)]
+        server: HttpServerConfig,
+        /// Serve HTTPS with these certificates (`http` only)
+        #[serde(default)]
+        tls: Option<TlsConfig>,
+        /// Timestamp given to records sent without one
+        #[serde(default)]
+        missing_timestamp: MissingTimestamp,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
-}
-
-/// Configuration for log processors
-#[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(tag = "processor_type", rename_all = "lowercase")]
-pub enum ProcessorConfig {
-    /// Resource processor adds metadata to logs
-    Resource {
-        /// Unique name for the processor
+    /// Syslog receiver for RFC 3164 and RFC 5424 messages
+    Syslog {
+        /// Unique name for the source
         name: String,
-        /// Attributes to add to logs
-        attributes: Vec<AttributeAction>,
+        /// Port to listen on
+        port: u16,
+        /// Interface to bind to
+        #[serde(default = "default_interface")]
+        interface: String,
+        /// Transport to receive messages over
+        #[serde(default)]
+        protocol: SyslogProtocol,
+        /// Accept TLS connections (TCP only)
+        #[serde(default)]
+        tls: Option<TlsConfig>,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
-    /// Filter processor includes or excludes logs based on patterns
-    Filter {
-        /// Unique name for the processor
+    /// Raw text records streamed over TCP connections
+    Tcp {
+        /// Unique name for the source
         name: String,
-        /// Filter configuration
-        logs: FilterConfig,
+        /// Port to listen on
+        port: u16,
+        /// Interface to bind to
+        #[serde(default = "default_interface")]
+        interface: String,
+        /// How records are delimited on the stream
+        #[serde(default)]
+        framing: Framing,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
-    /// Batch processor groups logs for efficient transmission
-    Batch {
-        /// Unique name for the processor
+    /// Raw text records sent in UDP datagrams
+    Udp {
+        /// Unique name for the source
         name: String,
-        /// Timeout before sending a batch (in seconds)
-        timeout: u64,
-        /// Maximum batch size
-        send_batch_size: usize,
+        /// Port to listen on
+        port: u16,
+        /// Interface to bind to
+        #[serde(default = "default_interface")]
+        interface: String,
+        /// How records are delimited within a datagram
+        #[serde(default)]
+        framing: Framing,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
-    /// Transform processor modifies log content
-    Transform {
-        /// Unique name for the processor
+    /// Records polled from a REST API
+    HttpPoll {
+        /// Unique name for the source
         name: String,
-        /// List of transformations to apply
-        transforms: Vec<TransformAction>,
+        /// URL returning a page of records as JSON
+        url: String,
+        /// HTTP method of each request
+        #[serde(default)]
+        method: HttpMethod,
+        /// Extra request headers
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Basic-auth or bearer-token credentials
+        #[serde(default)]
+        auth: Option<HttpPollAuth>,
+        /// Seconds between polls once every page has been read
+        #[serde(default = "default_http_poll_interval")]
+        poll_interval_seconds: u64,
+        /// JSON Pointer to the array of records in a response; empty when the response is the array
+        #[serde(default)]
+        records_pointer: String,
+        /// JSON Pointer to the message within a record
+        #[serde(default = "default_message_pointer")]
+        message_pointer: String,
+        /// JSON Pointer to the timestamp within a record; entries without one are stamped on receipt
+        #[serde(default)]
+        timestamp_pointer: Option<String>,
+        /// Pagination by a cursor the API hands back with each page
+        #[serde(default)]
+        cursor: Option<HttpCursorConfig>,
+        /// SQLite file remembering the cursor and latest timestamp across restarts
+        #[serde(default)]
+        state_path: Option<String>,
+        /// Named pipeline the source feeds; unbound sources feed the default pipeline
+        #[serde(default)]
+        pipeline: Option<String>,
     },
 }
 
-/// Configuration for log exporters
+/// TLS settings of a TCP listener
 #[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(tag = "exporter_type", rename_all = "lowercase")]
-pub enum ExporterConfig {
-    /// LogNarrator cloud service exporter
-    LogNarrator {
-        /// Unique name for the exporter
-        name: String,
-        /// API endpoint URL
-        endpoint: String,
-        /// Client identifier
-        client_id: String,
-        /// Path to private key for authentication
-        key_path: String,
-    },
-    /// Local file cache exporter
-    LocalCache {
-        /// Unique name for the exporter
-        name: String,
-        /// Directory path for the cache
-        directory: String,
-        /// Maximum cache size in MB
-        max_size_mb: u64,
-    },
+pub struct TlsConfig {
+    /// PEM certificate chain presented to clients
+    pub cert_path: String,
+    /// PEM private key of the certificate
+    pub key_path: String,
+    /// PEM CA certificates; when set, clients may authenticate with a certificate they signed
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// Refuse clients that do not present a certificate signed by `client_ca_path` (mutual TLS)
+    #[serde(default)]
+    pub require_client_cert: bool,
+    /// Whether plaintext connections are still accepted on the same port
+    #[serde(default)]
+    pub mode: TlsMode,
 }
 
-/// Position to start reading logs from
-#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// Handling of plaintext connections on a TLS-enabled listener
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub enum StartAt {
-    /// Start from the beginning of the file
-    Beginning,
-    /// Start from the end of the file
-    End,
+pub enum TlsMode {
+    /// Refuse connections that do not open with a TLS handshake
+    #[default]
+    Required,
+    /// Serve TLS and plaintext clients on the same port
+    Optional,
 }
 
-/// Default value for start_at
-fn default_start_at() -> StartAt {
-    StartAt::End
+/// Transport for the syslog source
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    /// One message per datagram
+    #[default]
+    Udp,
+    /// Octet-counted or newline-delimited messages on a stream (RFC 6587)
+    Tcp,
 }
 
-/// Default interface to bind to
-fn default_interface() -> String {
-    "0.0.0.0".to_string()
+/// HTTP method used by the HTTP polling source
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpMethod {
+    /// Fetch pages with GET
+    #[default]
+    Get,
+    /// Fetch pages with an empty POST
+    Post,
 }
 
-/// Action to perform on an attribute
+/// Credentials for a polled API: basic auth or a bearer token
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct AttributeAction {
-    /// Action type
-    pub action: ActionType,
-    /// Attribute key
-    pub key: String,
-    /// Attribute value (supports environment variable interpolation)
-    pub value: String,
+pub struct HttpPollAuth {
+    /// Basic-auth user name
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Basic-auth password
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Token sent as `Authorization: Bearer <bearer_token>`
+    #[serde(default)]
+    pub bearer_token: Option<String>,
 }
 
-/// Type of action to perform on an attribute
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+/// Cursor pagination of a polled API
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HttpCursorConfig {
+    /// Query parameter the cursor is sent in
+    pub param: String,
+    /// JSON Pointer to the next page's cursor in a response; missing or null on the last page
+    pub pointer: String,
+}
+
+/// Record delimiting for the TCP and UDP sources
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Framing {
+    /// One record per line
+    #[default]
+    Newline,
+    /// Each record preceded by its length as a 4-byte big-endian integer
+    LengthPrefixed,
+}
+
+/// Handling of records longer than a source's `max_line_bytes`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub enum ActionType {
-    /// Insert a new attribute
-    Insert,
-    /// Update an existing attribute
-    Update,
-    /// Insert if not exists, otherwise update
-    Upsert,
-    /// Delete an attribute
-    Delete,
+pub enum LongLineAction {
+    /// Keep the first `max_line_bytes` and skip the rest, tagging the entry `record.truncated`
+    #[default]
+    Truncate,
+    /// Emit every `max_line_bytes` as its own entry, tagged `record.split`
+    Split,
 }
 
-/// Filter configuration
+/// Store-and-forward of local cache files to another exporter
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct FilterConfig {
-    /// Patterns to include
-    pub include: Option<MatchConfig>,
-    /// Patterns to exclude
-    pub exclude: Option<MatchConfig>,
+pub struct CacheDrainConfig {
+    /// Name of the exporter cached logs are forwarded to
+    pub exporter: String,
+    /// Seconds between passes over the cache directory
+    #[serde(default = "default_cache_drain_interval")]
+    pub interval_seconds: u64,
 }
 
-/// Match configuration for filters
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct MatchConfig {
-    /// Type of matching to perform
-    pub match_type: MatchType,
-    /// List of exact match strings (used if match_type is exact)
-    pub exact: Option<Vec<String>>,
-    /// List of regular expressions (used if match_type is regexp)
-    pub regexp: Option<Vec<String>>,
+/// Compression applied to local cache files
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheCompression {
+    /// Plain JSONL files
+    #[default]
+    None,
+    /// Write each file through a streaming gzip encoder (`.jsonl.gz`)
+    Gzip,
 }
 
-/// Type of matching to perform
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+/// Compression applied to LogNarrator upload bodies
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub enum MatchType {
-    /// Exact string matching
-    Exact,
-    /// Regular expression matching
-    Regexp,
+pub enum BatchCompression {
+    /// Send the JSON batch as is; the ingest API does not decode `Content-Encoding` yet
+    #[default]
+    None,
+    /// Gzip the batch (`Content-Encoding: gzip`)
+    Gzip,
+    /// Zstandard-compress the batch (`Content-Encoding: zstd`)
+    Zstd,
 }
 
-/// Transform action to apply to logs
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct TransformAction {
-    /// Type of transformation
-    pub transform_type: TransformType,
-    /// Field to transform
-    pub field: String,
-    /// Parameters for the transformation
-    #[serde(default)]
-    pub parameters: HashMap<String, String>,
+impl BatchCompression {
+    /// Value of the `Content-Encoding` header for compressed bodies
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            BatchCompression::None => None,
+            BatchCompression::Gzip => Some("gzip"),
+            BatchCompression::Zstd => Some("zstd"),
+        }
+    }
 }
 
-/// Type of transformation to apply
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+/// How dotted attribute keys are laid out in exported JSON
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub enum TransformType {
-    /// Mask sensitive information
-    Mask,
-    /// Extract fields using a regular expression
-    Extract,
-    /// Convert field to a different format
-    Convert,
-    /// Rename a field
-    Rename,
+pub enum AttributeShape {
+    /// One object keyed by the full dotted names (`{"http.method": "GET"}`)
+    #[default]
+    Flat,
+    /// Objects nested at each dot (`{"http": {"method": "GET"}}`)
+    Nested,
 }
 
-/// Load collector configuration from a file
-pub fn load_config<P: AsRef<Path>>(path: P) -> Result<CollectorConfig> {
-    let content = std::fs::read_to_string(path)?;
-    let config: CollectorConfig = serde_yaml::from_str(&content)?;
-    Ok(config)
+/// Payload schema version of LogNarrator uploads
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaVersion {
+    /// Records timestamped in milliseconds, as every server accepts
+    #[default]
+    V1,
+    /// Records timestamped in nanoseconds, with the version declared in the batch
+    V2,
+    /// Ask each endpoint which versions it accepts, falling back to v1
+    Negotiate,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
-    use tempfile::tempdir;
+impl SchemaVersion {
+    /// The pinned version number, or `None` when negotiated
+    pub fn pinned(self) -> Option<u32> {
+        match self {
+            SchemaVersion::V1 => Some(1),
+            SchemaVersion::V2 => Some(2),
+            SchemaVersion::Negotiate => None,
+        }
+    }
+}
 
-    #[test]
-    fn test_load_valid_config() -> Result<()> {
-        let dir = tempdir()?;
-        let config_path = dir.path().join("collector.yaml");
+/// Joining of multi-line entries in file sources
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MultilineConfig {
+    /// Regex matching the first line of an entry; other lines continue the previous entry
+    pub start_pattern: String,
+    /// Maximum number of lines joined into one entry
+    #[serde(default = "default_multiline_max_lines")]
+    pub max_lines: usize,
+    /// Time after the last line before a pending entry is emitted (in milliseconds)
+    #[serde(default = "default_multiline_flush_timeout")]
+    pub flush_timeout_ms: u64,
+}
 
-        let mut file = File::create(&config_path)?;
-        write!(file, r#"
-            sources:
-              - source_type: file
-                name: system-logs
-                include:
-                  - /var/log/syslog
-                  - /var/log/messages
-                exclude_filename_pattern: '.*\.gz$'
-                start_at: end
-            processors:
-              - processor_type: filter
-                name: error-filter
-                logs:
-                  include:
-                    match_type: regexp
-                    regexp:
-                      - '.*error.*'
-                      - '.*warning.*'
-            exporters:
-              - exporter_type: lognarrator
-                name: cloud-export
-                endpoint: https://api.lognarrator.com
-                client_id: test-client
-                key_path: /app/config/private.key
-        "#)?;
+/// Retry with exponential backoff for network sources whose port is briefly unavailable
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BindRetryConfig {
+    /// Total number of bind attempts, including the first
+    #[serde(default = "default_bind_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the second attempt, doubled after each failure (in milliseconds)
+    #[serde(default = "default_bind_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound on the delay between attempts (in milliseconds)
+    #[serde(default = "default_bind_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
 
-        let config = load_config(config_path)?;
+/// Connection handling of an HTTP receiver
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HttpServerConfig {
+    /// Keep HTTP/1.1 connections open between requests
+    #[serde(default = "default_true")]
+    pub keep_alive: bool,
+    /// Also accept HTTP/2 (prior knowledge, without TLS) on the same port
+    #[serde(default = "default_true")]
+    pub http2: bool,
+    /// Connections served at once; further clients wait to be accepted
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+    /// Time without traffic after which a connection is closed (in seconds)
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout_seconds: u64,
+}
 
-        assert_eq!(config.sources.len(), 1);
-        assert_eq!(config.processors.len(), 1);
-        assert_eq!(config.exporters.len(), 1);
+impl Default for HttpServerConfig {
+    fn default() -> Self {
+        Self {
+            keep_alive: true,
+            http2: true,
+            max_connections: default_max_connections(),
+            idle_timeout_seconds: default_idle_timeout(),
+        }
+    }
+}
 
-        if let SourceConfig::File { name, include, .. } = &config.sources[0] {
-            assert_eq!(name, "system-logs");
-            assert_eq!(include.len(), 2);
-        } else {
-            panic!("Expected File source");
+impl SourceConfig {
+    /// Unique name of the source
+    pub fn name(&self) -> &str {
+        match self {
+            SourceConfig::File { name, .. }
+            | SourceConfig::Journald { name, .. }
+            | SourceConfig::Docker { name, .. }
+            | SourceConfig::Otlp { name, .. }
+            | SourceConfig::Syslog { name, .. }
+            | SourceConfig::Tcp { name, .. }
+            | SourceConfig::Udp { name, .. }
+            | SourceConfig::HttpPoll { name, .. } => name,
+        }
+    }
+
+    /// Named pipeline the source is bound to, if any
+    pub fn pipeline(&self) -> Option<&str> {
+        match self {
+            SourceConfig::File { pipeline, .. }
+            | SourceConfig::Journald { pipeline, .. }
+            | SourceConfig::Docker { pipeline, .. }
+            | SourceConfig::Otlp { pipeline, .. }
+            | SourceConfig::Syslog { pipeline, .. }
+            | SourceConfig::Tcp { pipeline, .. }
+            | SourceConfig::Udp { pipeline, .. }
+            | SourceConfig::HttpPoll { pipeline, .. } => pipeline.as_deref(),
+        }
+    }
+
+    /// SQLite database the source keeps its position in, if it resumes across restarts
+    pub fn checkpoint_path(&self) -> Option<&str> {
+        match self {
+            SourceConfig::File { offsets_path: path, .. }
+            | SourceConfig::Journald { cursor_path: path, .. }
+            | SourceConfig::HttpPoll { state_path: path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether this kind of source can run on the current operating system
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, SourceConfig::Journald { .. }) || cfg!(all(target_os = "linux", feature = "journald"))
+    }
+}
+
+/// Configuration for log processors
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "processor_type", rename_all = "lowercase")]
+pub enum ProcessorConfig {
+    /// Resource processor adds metadata to logs
+    Resource {
+        /// Unique name for the processor
+        name: String,
+        /// Attributes to add to logs
+        attributes: Vec<AttributeAction>,
+    },
+    /// Filter processor includes or excludes logs based on patterns
+    Filter {
+        /// Unique name for the processor
+        name: String,
+        /// Filter configuration
+        #[serde(default)]
+        logs: FilterConfig,
+        /// Drop logs whose level is less severe than this one, e.g. `WARN`
+        #[serde(default)]
+        min_severity: Option<String>,
+        /// What happens to logs without a recognized level when `min_severity` is set
+        #[serde(default)]
+        unknown_severity: UnknownSeverityAction,
+    },
+    /// Batch processor groups logs for efficient transmission
+    Batch {
+        /// Unique name for the processor
+        name: String,
+        /// Timeout before sending a batch (in seconds)
+        timeout: u64,
+        /// Maximum batch size
+        send_batch_size: usize,
+        /// Fraction by which each batch's timeout is randomly lengthened or shortened (0 to 1)
+        #[serde(default)]
+        timeout_jitter: f64,
+    },
+    /// Transform processor modifies log content
+    Transform {
+        /// Unique name for the processor
+        name: String,
+        /// List of transformations to apply
+        transforms: Vec<TransformAction>,
+    },
+    /// Coalesce processor collapses bursts of identical multi-line messages
+    Coalesce {
+        /// Unique name for the processor
+        name: String,
+        /// Window in seconds during which identical messages are merged
+        #[serde(default = "default_coalesce_window")]
+        window_seconds: u64,
+    },
+    /// Level prefix processor lifts a leading `[ERROR]` or `WARN:` token into the level
+    LevelPrefix {
+        /// Unique name for the processor
+        name: String,
+        /// Level tokens to recognize (case-insensitive)
+        #[serde(default = "default_level_tokens")]
+        tokens: Vec<String>,
+        /// Whether to remove the token from the message once extracted
+        #[serde(default)]
+        strip: bool,
+    },
+    /// Require attributes processor enforces attributes mandated by a data contract
+    RequireAttributes {
+        /// Unique name for the processor
+        name: String,
+        /// Attribute keys every log must carry
+        required: Vec<String>,
+        /// What to do with logs missing a required attribute
+        on_missing: MissingAttributeAction,
+        /// Values used to fill missing attributes when `on_missing` is `default`
+        #[serde(default)]
+        defaults: HashMap<String, String>,
+    },
+    /// Partition processor derives `year`/`month`/`day`/`hour` attributes from the timestamp
+    Partition {
+        /// Unique name for the processor
+        name: String,
+        /// IANA timezone the partition fields are computed in
+        #[serde(default = "default_timezone")]
+        timezone: String,
+    },
+    /// Language parser processor understands common application logger formats
+    LanguageParser {
+        /// Unique name for the processor
+        name: String,
+        /// Formats to try, in order; the first that matches wins
+        #[serde(default = "default_language_formats")]
+        formats: Vec<LanguageFormat>,
+    },
+    /// Binary filter processor drops or tags lines that look like binary garbage
+    BinaryFilter {
+        /// Unique name for the processor
+        name: String,
+        /// Minimum ratio (0.0-1.0) of printable characters for a line to count as text
+        #[serde(default = "default_min_printable_ratio")]
+        min_printable_ratio: f64,
+        /// What to do with lines below the threshold
+        #[serde(default)]
+        action: BinaryAction,
+    },
+    /// Rate anomaly processor flags sudden changes in a source's event rate
+    RateAnomaly {
+        /// Unique name for the processor
+        name: String,
+        /// Length of each rate measurement window (in seconds)
+        #[serde(default = "default_anomaly_window")]
+        window_seconds: u64,
+        /// Standard deviations from the smoothed rate that count as an anomaly
+        #[serde(default = "default_anomaly_sensitivity")]
+        sensitivity: f64,
+    },
+    /// Normalize level processor maps level spellings such as `Warning` or `W` to canonical names
+    #[serde(alias = "normalizeseverity")]
+    NormalizeLevel {
+        /// Unique name for the processor
+        name: String,
+        /// Extra spellings mapped to a canonical level (TRACE, DEBUG, INFO, WARN, ERROR, FATAL)
+        #[serde(default)]
+        aliases: HashMap<String, String>,
+    },
+    /// Source template processor composes `source` from attributes, e.g. `{tenant}/{service}/{component}`
+    SourceTemplate {
+        /// Unique name for the processor
+        name: String,
+        /// Template whose `{key}` placeholders are replaced with attribute values
+        template: String,
+        /// Source used when a referenced attribute is missing; the original source is kept if unset
+        #[serde(default)]
+        fallback: Option<String>,
+    },
+    /// Bucket processor maps a numeric attribute to a range label, e.g. latency to `10-100ms`
+    Bucket {
+        /// Unique name for the processor
+        name: String,
+        /// Attribute holding the number
+        attribute: String,
+        /// Attribute receiving the label (defaults to `<attribute>.bucket`)
+        #[serde(default)]
+        target: Option<String>,
+        /// Ascending bucket boundaries; each bucket includes its lower bound
+        boundaries: Vec<f64>,
+        /// Unit appended to labels, such as `ms`
+        #[serde(default)]
+        unit: String,
+    },
+    /// Sample processor drops entries beyond a fixed ratio or rate, e.g. from a noisy source
+    Sample {
+        /// Unique name for the processor
+        name: String,
+        /// Keep one entry in every `one_in`
+        #[serde(default)]
+        one_in: Option<u64>,
+        /// Keep at most this many entries per second
+        #[serde(default)]
+        rate_per_second: Option<u32>,
+        /// Entries admitted at once above `rate_per_second` (defaults to `rate_per_second`)
+        #[serde(default)]
+        burst: Option<u32>,
+        /// Sample each `source`, or each value of the named attribute, separately
+        #[serde(default)]
+        key: Option<String>,
+    },
+    /// Expand list processor turns bracketed lists such as `tags=[a,b,c]` into attributes
+    ExpandList {
+        /// Unique name for the processor
+        name: String,
+        /// Attribute holding a bracketed list; unset scans the message for `key=[...]` pairs
+        #[serde(default)]
+        attribute: Option<String>,
+        /// How the expanded list is stored
+        #[serde(default)]
+        format: ListFormat,
+    },
+    /// JSON parse processor lifts the fields of a JSON log line into attributes
+    JsonParse {
+        /// Unique name for the processor
+        name: String,
+        /// Field holding the JSON: `message`, or an attribute name
+        #[serde(default = "default_json_field")]
+        field: String,
+        /// Key whose value replaces the level, with nested keys written `outer.inner`
+        #[serde(default)]
+        level_key: Option<String>,
+        /// Key whose value (RFC 3339, or Unix seconds or milliseconds) replaces the timestamp
+        #[serde(default)]
+        timestamp_key: Option<String>,
+        /// Key whose value replaces the message; unset keeps the original message
+        #[serde(default)]
+        message_key: Option<String>,
+    },
+    /// Strip ANSI processor removes terminal color and cursor escape sequences
+    StripAnsi {
+        /// Unique name for the processor
+        name: String,
+        /// Also strip escapes from attribute values
+        #[serde(default)]
+        attributes: bool,
+    },
+    /// Quota processor sheds a tenant's entries once it has used up its budget
+    Quota {
+        /// Unique name for the processor
+        name: String,
+        /// Attribute naming the tenant of each entry; entries without it are not metered
+        #[serde(default = "default_tenant_attribute")]
+        attribute: String,
+        /// Length of the rolling window budgets apply to (in seconds)
+        #[serde(default = "default_quota_window")]
+        window_seconds: u64,
+        /// Budget of tenants not listed in `tenants`
+        #[serde(default)]
+        default: QuotaBudget,
+        /// Budgets of individual tenants, by tenant ID
+        #[serde(default)]
+        tenants: HashMap<String, QuotaBudget>,
+    },
+}
+
+/// What a tenant may send per quota window; an unset limit is unbounded
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaBudget {
+    /// Most entries admitted per window
+    #[serde(default)]
+    pub max_entries: Option<u64>,
+    /// Most bytes of message and attributes admitted per window
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+impl ProcessorConfig {
+    /// Unique name of the processor
+    pub fn name(&self) -> &str {
+        match self {
+            ProcessorConfig::Resource { name, .. }
+            | ProcessorConfig::Filter { name, .. }
+            | ProcessorConfig::Batch { name, .. }
+            | ProcessorConfig::Transform { name, .. }
+            | ProcessorConfig::Coalesce { name, .. }
+            | ProcessorConfig::LevelPrefix { name, .. }
+            | ProcessorConfig::RequireAttributes { name, .. }
+            | ProcessorConfig::Partition { name, .. }
+            | ProcessorConfig::LanguageParser { name, .. }
+            | ProcessorConfig::BinaryFilter { name, .. }
+            | ProcessorConfig::RateAnomaly { name, .. }
+            | ProcessorConfig::NormalizeLevel { name, .. }
+            | ProcessorConfig::SourceTemplate { name, .. }
+            | ProcessorConfig::Bucket { name, .. }
+            | ProcessorConfig::Sample { name, .. }
+            | ProcessorConfig::ExpandList { name, .. }
+            | ProcessorConfig::JsonParse { name, .. }
+            | ProcessorConfig::StripAnsi { name, .. }
+            | ProcessorConfig::Quota { name, .. } => name,
         }
+    }
+}
+
+/// Configuration for log exporters
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "exporter_type", rename_all = "lowercase")]
+#[allow(clippy::large_enum_variant)] // A handful are read at startup; boxing would only complicate matching
+pub enum ExporterConfig {
+    /// LogNarrator cloud service exporter
+    LogNarrator {
+        /// Unique name for the exporter
+        name: String,
+        /// API endpoint URL (shorthand for a single-entry `endpoints` list)
+        #[serde(default)]
+        endpoint: Option<String>,
+        /// API endpoint URLs in failover order; the first is the primary
+        #[serde(default)]
+        endpoints: Vec<String>,
+        /// Client identifier
+        client_id: String,
+        /// Path to private key for authentication
+        key_path: String,
+        /// Ed25519 key (see `--generate-keys --key-type signing`) signing each
+        /// batch; batches carry no `signature` without one
+        #[serde(default)]
+        signing_key_path: Option<String>,
+        /// Maximum age in seconds of a failed log still worth retrying
+        #[serde(default)]
+        max_retry_age_seconds: Option<u64>,
+        /// Maximum number of batches uploaded concurrently
+        #[serde(default = "default_max_in_flight")]
+        max_in_flight: usize,
+        /// How often buffered logs are uploaded even if the buffer is not full (in seconds)
+        #[serde(default = "default_flush_interval")]
+        flush_interval_seconds: u64,
+        /// Fraction by which each flush interval is randomly lengthened or shortened (0 to 1)
+        #[serde(default = "default_flush_jitter")]
+        flush_jitter: f64,
+        /// Compression of each uploaded batch
+        #[serde(default)]
+        compression: BatchCompression,
+        /// Shape of each record's attributes in the uploaded JSON
+        #[serde(default)]
+        attribute_shape: AttributeShape,
+        /// Payload schema version of uploads, or `negotiate` to ask each endpoint
+        #[serde(default)]
+        schema_version: SchemaVersion,
+        /// Encrypt each batch to the server's public key (sent as plain JSON without it)
+        #[serde(default)]
+        encryption: Option<EncryptionConfig>,
+        /// Upload readable JSON when `encryption` is not configured, instead of refusing to start
+        #[serde(default)]
+        allow_plaintext: bool,
+        /// Delivery guarantee for batches that fail to upload
+        #[serde(default)]
+        delivery: DeliveryMode,
+        /// When to move between `endpoints`
+        #[serde(default)]
+        failover: FailoverConfig,
+        /// Server verification, custom CA and client certificate of HTTPS endpoints
+        #[serde(default)]
+        tls: ClientTlsConfig,
+        /// Backoff between attempts at uploading a failed batch
+        #[serde(default)]
+        retry: RetryConfig,
+        /// JSONL file receiving batches that could not be delivered
+        /// (without one they are dropped)
+        #[serde(default)]
+        dead_letter_path: Option<String>,
+        /// Rotation and size budget of the dead-letter file
+        #[serde(default)]
+        dead_letter: DeadLetterConfig,
+        /// Name of a `resilience` policy retrying and circuit-breaking this exporter's calls
+        #[serde(default)]
+        resilience: Option<String>,
+    },
+    /// Local file cache exporter
+    LocalCache {
+        /// Unique name for the exporter
+        name: String,
+        /// Directory path for the cache
+        directory: String,
+        /// Cap on the total size of the cache files in MB; the oldest are evicted past it
+        max_size_mb: u64,
+        /// On-disk format version to write; lower it to stay readable by older collectors
+        #[serde(default = "default_cache_format_version")]
+        format_version: u32,
+        /// Compress cache files as they are written
+        #[serde(default)]
+        compression: CacheCompression,
+        /// Forward completed cache files to another exporter, deleting them once delivered
+        #[serde(default)]
+        drain: Option<CacheDrainConfig>,
+        /// Name of a `resilience` policy retrying and circuit-breaking this exporter's calls
+        #[serde(default)]
+        resilience: Option<String>,
+    },
+    /// Local SQLite database exporter
+    Database {
+        /// Unique name for the exporter
+        name: String,
+        /// Path to the SQLite database file
+        path: String,
+        /// JSONL file receiving logs that could not be written
+        /// (defaults to `<path>.deadletter.jsonl`)
+        #[serde(default)]
+        dead_letter_path: Option<String>,
+        /// Rotation and size budget of the dead-letter file
+        #[serde(default)]
+        dead_letter: DeadLetterConfig,
+        /// Name of a `resilience` policy retrying and circuit-breaking this exporter's calls
+        #[serde(default)]
+        resilience: Option<String>,
+    },
+    /// Console exporter printing each log to stdout, for trying out configurations
+    Stdout {
+        /// Unique name for the exporter
+        name: String,
+        /// How each log is printed
+        #[serde(default)]
+        format: StdoutFormat,
+        /// How newlines inside a message are written by the line-based formats
+        #[serde(default)]
+        newlines: MessageNewlines,
+        /// Name of a `resilience` policy retrying and circuit-breaking this exporter's calls
+        #[serde(default)]
+        resilience: Option<String>,
+    },
+    /// Elasticsearch or OpenSearch `_bulk` exporter
+    Elasticsearch {
+        /// Unique name for the exporter
+        name: String,
+        /// Base URLs of the cluster's nodes, tried in turn when one is unreachable
+        nodes: Vec<String>,
+        /// Index name; strftime fields (`logs-%Y.%m.%d`) are filled from each log's timestamp
+        #[serde(default = "default_elasticsearch_index")]
+        index: String,
+        /// Basic-auth or API-key credentials
+        #[serde(default)]
+        auth: Option<ElasticsearchAuth>,
+        /// Number of buffered logs that triggers a bulk request
+        #[serde(default = "default_elasticsearch_batch_size")]
+        batch_size: usize,
+        /// How often buffered logs are sent even if the buffer is not full (in seconds)
+        #[serde(default = "default_flush_interval")]
+        flush_interval_seconds: u64,
+        /// Fraction by which each flush interval is randomly lengthened or shortened (0 to 1)
+        #[serde(default = "default_flush_jitter")]
+        flush_jitter: f64,
+        /// Backoff between attempts at sending failed documents
+        #[serde(default)]
+        retry: RetryConfig,
+        /// Name of a `resilience` policy retrying and circuit-breaking this exporter's calls
+        #[serde(default)]
+        resilience: Option<String>,
+    },
+}
+
+impl ExporterConfig {
+    /// Unique name of the exporter
+    pub fn name(&self) -> &str {
+        match self {
+            ExporterConfig::LogNarrator { name, .. }
+            | ExporterConfig::LocalCache { name, .. }
+            | ExporterConfig::Database { name, .. }
+            | ExporterConfig::Stdout { name, .. }
+            | ExporterConfig::Elasticsearch { name, .. } => name,
+        }
+    }
+
+    /// Name of the resilience policy wrapping the exporter, if any
+    pub fn resilience(&self) -> Option<&str> {
+        match self {
+            ExporterConfig::LogNarrator { resilience, .. }
+            | ExporterConfig::LocalCache { resilience, .. }
+            | ExporterConfig::Database { resilience, .. }
+            | ExporterConfig::Stdout { resilience, .. }
+            | ExporterConfig::Elasticsearch { resilience, .. } => resilience.as_deref(),
+        }
+    }
+}
+
+/// Action taken on a line that looks like binary data
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryAction {
+    /// Drop the log
+    #[default]
+    Drop,
+    /// Keep the log and mark it with a `binary_content` attribute
+    Tag,
+}
+
+/// What a severity filter does with a log whose level it cannot place
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownSeverityAction {
+    /// Keep the log
+    #[default]
+    Keep,
+    /// Drop the log
+    Drop,
+}
+
+/// How the expand list processor stores a list
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ListFormat {
+    /// One attribute holding a JSON array, e.g. `tags: ["a","b","c"]`
+    #[default]
+    Json,
+    /// One attribute per item, e.g. `tags.0: a`, `tags.1: b`
+    Indexed,
+}
+
+/// Application logger output formats recognized by the language parser
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageFormat {
+    /// Go `slog` / `logrus` JSON lines (`time`, `level`, `msg`)
+    GoJson,
+    /// Rust `tracing_subscriber::fmt` default output
+    RustTracing,
+    /// Rust `env_logger` default output
+    EnvLogger,
+    /// Python `logging` default and `asctime - name - levelname - message` output
+    Python,
+}
+
+/// Failover behaviour across an exporter's ordered endpoints
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FailoverConfig {
+    /// Consecutive failed uploads before moving to the next endpoint
+    #[serde(default = "default_failover_after_failures")]
+    pub failover_after_failures: u32,
+    /// How often the primary is retried while failed over (in seconds)
+    #[serde(default = "default_failback_interval")]
+    pub failback_interval_seconds: u64,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            failover_after_failures: default_failover_after_failures(),
+            failback_interval_seconds: default_failback_interval(),
+        }
+    }
+}
+
+/// TLS settings of an exporter's HTTPS connections
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ClientTlsConfig {
+    /// Verify the server's certificate; turn off only for self-hosted development servers
+    #[serde(default = "default_true")]
+    pub verify_certs: bool,
+    /// PEM CA certificates trusted in addition to the system roots
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// PEM certificate presented to the server for mutual TLS
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+    /// PEM private key of `client_cert_path`
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+}
+
+impl Default for ClientTlsConfig {
+    fn default() -> Self {
+        Self {
+            verify_certs: true,
+            ca_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+        }
+    }
+}
+
+/// End-to-end encryption of LogNarrator uploads
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EncryptionConfig {
+    /// How batches are encrypted
+    #[serde(default)]
+    pub scheme: EncryptionScheme,
+    /// Path to the LogNarrator server's X25519 public key (32 raw bytes); required by `sodium`
+    #[serde(default)]
+    pub server_public_key_path: Option<String>,
+    /// `age` X25519 recipients (`age1...`) each batch is encrypted to; required by `age`
+    #[serde(default)]
+    pub recipients: Vec<String>,
+}
+
+/// Encryption backend of LogNarrator uploads
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionScheme {
+    /// libsodium `crypto_box` from the client key to the server's public key
+    #[default]
+    Sodium,
+    /// `age` file encrypted to each of `recipients` (needs a build with the `age` feature)
+    Age,
+}
+
+/// Line format of the stdout exporter
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StdoutFormat {
+    /// One JSON object per line
+    #[default]
+    Json,
+    /// `key=value` pairs, quoted where needed
+    Logfmt,
+    /// Human-readable lines, with levels colored on a terminal
+    Pretty,
+}
+
+/// How a line-based exporter writes newlines embedded in a message
+///
+/// A multiline message, such as a joined stack trace, would otherwise spill
+/// over several output lines.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageNewlines {
+    /// Write the message as it is
+    #[default]
+    Keep,
+    /// Write each newline as a literal `\n` (and carriage return as `\r`)
+    Escape,
+    /// Write each line break as a single space
+    Space,
+}
+
+/// Credentials for an Elasticsearch cluster: basic auth or an API key
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ElasticsearchAuth {
+    /// Basic-auth user name
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Basic-auth password
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Base64-encoded API key, sent as `Authorization: ApiKey <api_key>`
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Exponential backoff between attempts at uploading a failed batch
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RetryConfig {
+    /// Delay before the first retry; each further retry doubles it (in milliseconds)
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+    /// Upper bound on the delay between attempts (in milliseconds)
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// Retries after the first attempt before the batch counts as failed
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Fraction by which each delay is randomly lengthened or shortened (0 to 1)
+    #[serde(default = "default_retry_jitter")]
+    pub jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            base_delay_ms: default_retry_base_delay_ms(),
+            max_delay_ms: default_retry_max_delay_ms(),
+            max_retries: default_max_retries(),
+            jitter: default_retry_jitter(),
+        }
+    }
+}
+
+/// Retry and circuit-breaker policy shared by the exporters that name it
+///
+/// The breaker opens after `failure_threshold` consecutive calls fail even
+/// after their retries. While open, exports are short-circuited to the
+/// dead-letter file and flushes are skipped; once `cooldown_seconds` pass,
+/// one call is let through to probe the exporter and closes the breaker if
+/// it succeeds.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ResilienceConfig {
+    /// Backoff between attempts at a failed export or flush
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Consecutive failed calls that open the breaker
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Time an open breaker short-circuits calls before probing again (in seconds)
+    #[serde(default = "default_breaker_cooldown")]
+    pub cooldown_seconds: u64,
+    /// JSONL file receiving logs that were short-circuited or failed all their retries
+    /// (without one they are reported as failed)
+    #[serde(default)]
+    pub dead_letter_path: Option<String>,
+    /// Rotation and size budget of the dead-letter file
+    #[serde(default)]
+    pub dead_letter: DeadLetterConfig,
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryConfig::default(),
+            failure_threshold: default_breaker_failure_threshold(),
+            cooldown_seconds: default_breaker_cooldown(),
+            dead_letter_path: None,
+            dead_letter: DeadLetterConfig::default(),
+        }
+    }
+}
+
+/// Size limits of a dead-letter file
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DeadLetterConfig {
+    /// Size at which the live file is rotated (in MB)
+    #[serde(default = "default_dead_letter_file_mb")]
+    pub max_file_mb: u64,
+    /// Total size of the live and rotated files; the oldest are deleted beyond it (in MB)
+    #[serde(default = "default_dead_letter_total_mb")]
+    pub max_total_mb: u64,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            max_file_mb: default_dead_letter_file_mb(),
+            max_total_mb: default_dead_letter_total_mb(),
+        }
+    }
+}
+
+/// Delivery guarantee offered by an exporter
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Never retry; a failed batch is dropped
+    AtMostOnce,
+    /// Retry failed batches, tagging uploads with an idempotency key
+    #[default]
+    AtLeastOnce,
+}
+
+/// Position to start reading logs from
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StartAt {
+    /// Start from the beginning of the file
+    Beginning,
+    /// Start from the end of the file
+    End,
+}
+
+/// Default value for start_at
+fn default_start_at() -> StartAt {
+    StartAt::End
+}
+
+/// Default record delimiter for file sources
+fn default_delimiter() -> String {
+    "\n".to_string()
+}
+
+/// Default interval between file polls, in milliseconds
+fn default_file_poll_interval() -> u64 {
+    250
+}
+
+/// Default longest record kept whole, in bytes
+fn default_max_line_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Default maximum lines in a joined multi-line entry
+fn default_multiline_max_lines() -> usize {
+    500
+}
+
+/// Default wait for further lines of a multi-line entry, in milliseconds
+fn default_multiline_flush_timeout() -> u64 {
+    1000
+}
+
+/// Default Docker daemon socket
+fn default_docker_socket() -> String {
+    docker::DEFAULT_SOCKET_PATH.to_string()
+}
+
+/// Default interval between polls of an HTTP source, in seconds
+fn default_http_poll_interval() -> u64 {
+    60
+}
+
+/// Default JSON Pointer to a polled record's message
+fn default_message_pointer() -> String {
+    "/message".to_string()
+}
+
+/// Default interval between cache drain passes, in seconds
+fn default_cache_drain_interval() -> u64 {
+    30
+}
+
+/// Default interval between Docker container listings
+fn default_docker_refresh_interval() -> u64 {
+    10
+}
+
+/// Default interface to bind to
+fn default_interface() -> String {
+    "0.0.0.0".to_string()
+}
+
+/// Default for settings that are on unless disabled
+fn default_true() -> bool {
+    true
+}
+
+/// Default number of connections an HTTP receiver serves at once
+fn default_max_connections() -> usize {
+    256
+}
+
+/// Default idle time before an HTTP receiver closes a connection, in seconds
+fn default_idle_timeout() -> u64 {
+    60
+}
+
+/// Default number of bind attempts
+fn default_bind_attempts() -> u32 {
+    5
+}
+
+/// Default initial delay between bind attempts, in milliseconds
+fn default_bind_backoff_ms() -> u64 {
+    500
+}
+
+/// Default maximum delay between bind attempts, in milliseconds
+fn default_bind_max_backoff_ms() -> u64 {
+    10_000
+}
+
+/// Default fill ratio considered saturated
+fn default_saturation_ratio() -> f64 {
+    0.9
+}
+
+/// Default duration of saturation before alerting, in seconds
+fn default_sustain_seconds() -> u64 {
+    30
+}
+
+/// Default staleness threshold for source health, in seconds
+fn default_source_staleness() -> u64 {
+    300
+}
+
+/// Default time allowed for draining the pipeline on shutdown, in seconds
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+/// Default number of concurrent calls into one exporter
+fn default_max_concurrent_exports() -> usize {
+    4
+}
+
+/// Default number of connections one listening source serves at once
+fn default_max_source_connections() -> usize {
+    256
+}
+
+/// Default sustained rate of internal entries per second
+fn default_internal_rate() -> u32 {
+    5
+}
+
+/// Default burst of internal entries
+fn default_internal_burst() -> u32 {
+    20
+}
+
+/// Default coalescing window in seconds
+fn default_coalesce_window() -> u64 {
+    10
+}
+
+/// Default Elasticsearch index, one per day
+fn default_elasticsearch_index() -> String {
+    "logs-%Y.%m.%d".to_string()
+}
+
+/// Default number of documents per Elasticsearch bulk request
+fn default_elasticsearch_batch_size() -> usize {
+    500
+}
+
+/// Default number of concurrent batch uploads per exporter
+fn default_max_in_flight() -> usize {
+    4
+}
+
+/// Default consecutive failures before failing over
+fn default_failover_after_failures() -> u32 {
+    3
+}
+
+/// Default interval between primary probes while failed over, in seconds
+fn default_failback_interval() -> u64 {
+    30
+}
+
+/// Default interval between timed LogNarrator flushes, in seconds
+fn default_flush_interval() -> u64 {
+    10
+}
+
+/// Default fraction of random spread applied to flush intervals
+fn default_flush_jitter() -> f64 {
+    0.1
+}
+
+/// Default delay before the first upload retry, in milliseconds
+fn default_retry_base_delay_ms() -> u64 {
+    500
+}
+
+/// Default upper bound on the delay between upload retries, in milliseconds
+fn default_retry_max_delay_ms() -> u64 {
+    30_000
+}
+
+/// Default number of upload retries after the first attempt
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// Default fraction of random spread applied to retry delays
+fn default_retry_jitter() -> f64 {
+    0.2
+}
+
+/// Default size at which a dead-letter file is rotated, in MB
+fn default_dead_letter_file_mb() -> u64 {
+    10
+}
+
+/// Default total size of dead-letter files, in MB
+fn default_dead_letter_total_mb() -> u64 {
+    100
+}
+
+/// Default number of consecutive failed calls that open a circuit breaker
+fn default_breaker_failure_threshold() -> u32 {
+    5
+}
+
+/// Default time an open circuit breaker waits before probing, in seconds
+fn default_breaker_cooldown() -> u64 {
+    30
+}
+
+/// Default on-disk cache format version
+fn default_cache_format_version() -> u32 {
+    2
+}
+
+/// Default timezone for partition fields
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// Default printable ratio below which a line counts as binary
+fn default_min_printable_ratio() -> f64 {
+    0.8
+}
+
+/// Default rate measurement window in seconds
+fn default_anomaly_window() -> u64 {
+    60
+}
+
+/// Default anomaly threshold in standard deviations
+fn default_anomaly_sensitivity() -> f64 {
+    3.0
+}
+
+/// Default formats tried by the language parser
+fn default_language_formats() -> Vec<LanguageFormat> {
+    vec![
+        LanguageFormat::GoJson,
+        LanguageFormat::RustTracing,
+        LanguageFormat::EnvLogger,
+        LanguageFormat::Python,
+    ]
+}
+
+/// Default level tokens recognized by the level prefix processor
+fn default_level_tokens() -> Vec<String> {
+    ["TRACE", "DEBUG", "INFO", "NOTICE", "WARN", "WARNING", "ERROR", "ERR", "CRITICAL", "FATAL"]
+        .iter()
+        .map(|token| token.to_string())
+        .collect()
+}
+
+/// Default field parsed by the JSON parse processor
+fn default_json_field() -> String {
+    "message".to_string()
+}
+
+/// Default attribute naming the tenant of an entry
+fn default_tenant_attribute() -> String {
+    "tenant_id".to_string()
+}
+
+/// Default quota window in seconds
+fn default_quota_window() -> u64 {
+    60
+}
+
+/// Default address of the metrics endpoint
+fn default_telemetry_address() -> String {
+    "127.0.0.1:9464".to_string()
+}
+
+/// Default path of the metrics endpoint
+fn default_metrics_path() -> String {
+    "/metrics".to_string()
+}
+
+/// Default path of the admin socket
+fn default_admin_socket() -> String {
+    "/run/lognarrator/collector.sock".to_string()
+}
+
+/// Default address of the probe endpoints
+fn default_probes_address() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+/// Default longest time without a successful exporter flush while ready, in seconds
+pub(crate) fn default_max_flush_age() -> u64 {
+    300
+}
+
+/// Action to perform on an attribute
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AttributeAction {
+    /// Action type
+    pub action: ActionType,
+    /// Attribute key
+    pub key: String,
+    /// Attribute value (supports environment variable interpolation)
+    pub value: String,
+}
+
+/// Type of action to perform on an attribute
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    /// Insert a new attribute
+    Insert,
+    /// Update an existing attribute
+    Update,
+    /// Insert if not exists, otherwise update
+    Upsert,
+    /// Delete an attribute
+    Delete,
+}
+
+/// Filter configuration
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct FilterConfig {
+    /// Conditions a log must meet to be kept
+    pub include: Option<MatchRule>,
+    /// Conditions that drop a log when met
+    pub exclude: Option<MatchRule>,
+}
+
+/// Conditions of a filter's `include` or `exclude`
+///
+/// Either a single condition, or a list under `all` (every condition must
+/// match) or `any` (one matching condition is enough).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum MatchRule {
+    /// Every condition must match
+    All { all: Vec<MatchConfig> },
+    /// At least one condition must match
+    Any { any: Vec<MatchConfig> },
+    /// A single condition
+    One(MatchConfig),
+}
+
+impl MatchRule {
+    /// The rule's conditions
+    pub fn conditions(&self) -> &[MatchConfig] {
+        match self {
+            MatchRule::All { all } => all,
+            MatchRule::Any { any } => any,
+            MatchRule::One(condition) => std::slice::from_ref(condition),
+        }
+    }
+}
+
+/// Match configuration for filters
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MatchConfig {
+    /// Type of matching to perform
+    pub match_type: MatchType,
+    /// List of exact match strings (used if match_type is exact)
+    pub exact: Option<Vec<String>>,
+    /// List of regular expressions (used if match_type is regexp)
+    pub regexp: Option<Vec<String>>,
+    /// What is matched: `message`, `level` or `attributes.<key>`
+    ///
+    /// Exact strings match anywhere in the message, but must equal the
+    /// whole level or attribute value.
+    #[serde(default = "default_match_field")]
+    pub field: String,
+}
+
+/// Prefix of a filter `field` naming an attribute
+pub const ATTRIBUTE_FIELD_PREFIX: &str = "attributes.";
+
+fn default_match_field() -> String {
+    "message".to_string()
+}
+
+/// Type of matching to perform
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// Exact string matching
+    Exact,
+    /// Regular expression matching
+    Regexp,
+}
+
+/// Action taken when a log is missing a required attribute
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingAttributeAction {
+    /// Drop the log
+    Drop,
+    /// Keep the log and list the missing keys in a `schema_violation` attribute
+    Tag,
+    /// Fill the missing attribute from `defaults`, tagging keys without a default
+    Default,
+}
+
+/// Transform action to apply to logs
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TransformAction {
+    /// Type of transformation
+    pub transform_type: TransformType,
+    /// Field to transform
+    pub field: String,
+    /// Parameters for the transformation
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+}
+
+/// Type of transformation to apply
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransformType {
+    /// Mask sensitive information
+    Mask,
+    /// Extract fields using a regular expression
+    Extract,
+    /// Convert field to a different format
+    Convert,
+    /// Rename a field
+    Rename,
+}
+
+/// Load collector configuration from a file
+///
+/// Environment variables (`${VAR}`, `${VAR:-default}`) and secret references
+/// (`${file:/path}`, `${env:VAR}`) in string values are resolved before the
+/// configuration is deserialized, and the result is checked with
+/// [`CollectorConfig::validate_for_startup`].
+pub fn load_config<P: AsRef<Path>>(path: P) -> Result<CollectorConfig> {
+    let config = parse_config(path)?;
+    config.validate_for_startup()?;
+    Ok(config)
+}
+
+/// Load collector configuration as [`load_config`] does, checking it with [`CollectorConfig::validate`] only
+///
+/// For tools that use part of the configuration, such as its processors,
+/// on a host that may lack the files and listeners the collector needs.
+pub fn read_config<P: AsRef<Path>>(path: P) -> Result<CollectorConfig> {
+    let config = parse_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Read, resolve and deserialize a configuration file, without checking it
+fn parse_config<P: AsRef<Path>>(path: P) -> Result<CollectorConfig> {
+    let content = std::fs::read_to_string(path)?;
+    let mut value: serde_yaml::Value = serde_yaml::from_str(&content)?;
+    resolve_secrets(&mut value)?;
+    Ok(serde_yaml::from_value(value)?)
+}
+
+/// Replace environment variables and secret references in every string of a YAML document
+///
+/// Resolving on parsed values rather than raw text means a value containing
+/// YAML syntax cannot change the document's structure. `${VAR}` needs the
+/// variable to be set, while `${VAR:-default}` falls back to `default` when it
+/// is unset or empty. `$${...}` is kept as a literal `${...}`, for components
+/// that interpolate it at runtime. Every unresolvable reference is reported,
+/// never the secret values themselves.
+pub fn resolve_secrets(value: &mut serde_yaml::Value) -> Result<()> {
+    let reference_regex =
+        Regex::new(r"\$(\$)?\{(?:([a-z]+):([^-}][^}]*)|([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?)\}")?;
+    let mut errors = Vec::new();
+
+    resolve_value(value, &reference_regex, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Failed to resolve config references:\n  {}", errors.join("\n  ")))
+    }
+}
+
+fn resolve_value(value: &mut serde_yaml::Value, reference_regex: &Regex, errors: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(text) => {
+            if !reference_regex.is_match(text) {
+                return;
+            }
+
+            let resolved = reference_regex.replace_all(text, |captures: &regex::Captures| {
+                if captures.get(1).is_some() {
+                    return captures[0][1..].to_string();
+                }
+
+                let resolved = match (captures.get(2), captures.get(4)) {
+                    (Some(provider), _) => resolve_secret(provider.as_str(), &captures[3]),
+                    (None, Some(variable)) => resolve_variable(variable.as_str(), captures.get(5).map(|m| m.as_str())),
+                    (None, None) => unreachable!("the reference regex matches a provider or a variable"),
+                };
+                match resolved {
+                    Ok(value) => value,
+                    Err(e) => {
+                        errors.push(format!("{}: {}", &captures[0], e));
+                        String::new()
+                    },
+                }
+            });
+            *text = resolved.into_owned();
+        },
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                resolve_value(item, reference_regex, errors);
+            }
+        },
+        serde_yaml::Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                resolve_value(item, reference_regex, errors);
+            }
+        },
+        serde_yaml::Value::Tagged(tagged) => resolve_value(&mut tagged.value, reference_regex, errors),
+        _ => {},
+    }
+}
+
+/// Look up an environment variable, or its default when unset or empty
+fn resolve_variable(name: &str, default: Option<&str>) -> Result<String> {
+    match (std::env::var(name), default) {
+        (Ok(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => Ok(default.to_string()),
+        (Err(_), None) => Err(anyhow!("environment variable {} is not set and has no default", name)),
+    }
+}
+
+/// Look up a single secret from its provider
+fn resolve_secret(provider: &str, reference: &str) -> Result<String> {
+    match provider {
+        "env" => std::env::var(reference)
+            .map_err(|_| anyhow!("environment variable {} is not set", reference)),
+        "file" => {
+            let content = std::fs::read_to_string(reference)
+                .map_err(|e| anyhow!("cannot read secret file {}: {}", reference, e))?;
+            // Secret files usually end with a newline that is not part of the secret
+            Ok(content.trim_end_matches(['\r', '\n']).to_string())
+        },
+        other => Err(anyhow!("unknown secret provider '{}' (supported: env, file)", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_valid_config() -> Result<()> {
+        let dir = tempdir()?;
+        let config_path = dir.path().join("collector.yaml");
+        let key_path = dir.path().join("private.key");
+        std::fs::write(&key_path, "key")?;
+
+        let mut file = File::create(&config_path)?;
+        write!(file, r#"
+            sources:
+              - source_type: file
+                name: system-logs
+                include:
+                  - /var/log/syslog
+                  - /var/log/messages
+                exclude_filename_pattern: '.*\.gz$'
+                start_at: end
+            processors:
+              - processor_type: filter
+                name: error-filter
+                logs:
+                  include:
+                    match_type: regexp
+                    regexp:
+                      - '.*error.*'
+                      - '.*warning.*'
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: {}
+                allow_plaintext: true
+        "#, key_path.display())?;
+
+        let config = load_config(config_path)?;
+
+        assert_eq!(config.sources.len(), 1);
+        assert_eq!(config.processors.len(), 1);
+        assert_eq!(config.exporters.len(), 1);
+
+        if let SourceConfig::File { name, include, .. } = &config.sources[0] {
+            assert_eq!(name, "system-logs");
+            assert_eq!(include.len(), 2);
+        } else {
+            panic!("Expected File source");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_journald_config_loads_on_every_os() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: journald
+                name: journal
+                units:
+                  - sshd
+            processors: []
+            exporters: []
+            unsupported_sources: error
+        "#)?;
+
+        assert_eq!(config.sources[0].name(), "journal");
+        assert_eq!(config.unsupported_sources, UnsupportedSourcePolicy::Error);
+        assert_eq!(config.sources[0].is_supported(), cfg!(all(target_os = "linux", feature = "journald")));
+
+        Ok(())
+    }
+
+    fn secret_config(client_id: &str, key_path: &Path) -> String {
+        format!(r#"
+            sources:
+              - source_type: otlp
+                name: receiver
+                port: 4318
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: "{}"
+                key_path: {}
+                allow_plaintext: true
+        "#, client_id, key_path.display())
+    }
+
+    fn client_id(config: &CollectorConfig) -> &str {
+        match &config.exporters[0] {
+            ExporterConfig::LogNarrator { client_id, .. } => client_id,
+            _ => panic!("Expected LogNarrator exporter"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_file_secret() -> Result<()> {
+        let dir = tempdir()?;
+        let secret_path = dir.path().join("client_id");
+        std::fs::write(&secret_path, "client-from-file\n")?;
+        let key_path = dir.path().join("private.key");
+        std::fs::write(&key_path, "key")?;
+
+        let config_path = dir.path().join("collector.yaml");
+        std::fs::write(&config_path, secret_config(&format!("${{file:{}}}", secret_path.display()), &key_path))?;
+
+        let config = load_config(config_path)?;
+        assert_eq!(client_id(&config), "client-from-file");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_env_secret() -> Result<()> {
+        let dir = tempdir()?;
+        std::env::set_var("LOGNARRATOR_TEST_CLIENT_ID", "client: from env");
+        let key_path = dir.path().join("private.key");
+        std::fs::write(&key_path, "key")?;
+
+        let config_path = dir.path().join("collector.yaml");
+        std::fs::write(&config_path, secret_config("prefix-${env:LOGNARRATOR_TEST_CLIENT_ID}", &key_path))?;
+
+        // Characters that are meaningful in YAML come through verbatim
+        let config = load_config(config_path)?;
+        assert_eq!(client_id(&config), "prefix-client: from env");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_environment_variables() -> Result<()> {
+        std::env::set_var("LOGNARRATOR_TEST_ENDPOINT", "https://logs.example.com");
+        std::env::set_var("LOGNARRATOR_TEST_EMPTY", "");
+        std::env::remove_var("LOGNARRATOR_TEST_UNSET");
+
+        let mut value: serde_yaml::Value = serde_yaml::from_str(r#"
+            endpoint: ${LOGNARRATOR_TEST_ENDPOINT}/v1
+            endpoint_with_default: ${LOGNARRATOR_TEST_ENDPOINT:-https://fallback.example.com}
+            unset: ${LOGNARRATOR_TEST_UNSET:-/app/config/private.key}
+            empty: ${LOGNARRATOR_TEST_EMPTY:-fallback}
+            empty_default: "${LOGNARRATOR_TEST_UNSET:-}"
+            runtime: $${HOSTNAME}
+        "#)?;
+        resolve_secrets(&mut value)?;
+
+        assert_eq!(value["endpoint"], "https://logs.example.com/v1");
+        assert_eq!(value["endpoint_with_default"], "https://logs.example.com");
+        assert_eq!(value["unset"], "/app/config/private.key");
+        assert_eq!(value["empty"], "fallback");
+        assert_eq!(value["empty_default"], "");
+        assert_eq!(value["runtime"], "${HOSTNAME}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undefined_variables_are_named() -> Result<()> {
+        std::env::remove_var("LOGNARRATOR_TEST_MISSING_ID");
+        std::env::remove_var("LOGNARRATOR_TEST_MISSING_KEY");
+
+        let mut value: serde_yaml::Value = serde_yaml::from_str(r#"
+            client_id: ${LOGNARRATOR_TEST_MISSING_ID}
+            key_path: ${LOGNARRATOR_TEST_MISSING_KEY}/private.key
+        "#)?;
+
+        let error = resolve_secrets(&mut value).unwrap_err().to_string();
+        assert!(error.contains("${LOGNARRATOR_TEST_MISSING_ID}: environment variable LOGNARRATOR_TEST_MISSING_ID is not set and has no default"));
+        assert!(error.contains("${LOGNARRATOR_TEST_MISSING_KEY}: environment variable LOGNARRATOR_TEST_MISSING_KEY is not set and has no default"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_secrets_are_all_reported() -> Result<()> {
+        let dir = tempdir()?;
+        let config_path = dir.path().join("collector.yaml");
+        std::fs::write(
+            &config_path,
+            secret_config(
+                "${env:LOGNARRATOR_TEST_UNSET_SECRET}-${file:/nonexistent/lognarrator/secret}",
+                &dir.path().join("private.key"),
+            ),
+        )?;
+
+        let error = load_config(config_path).unwrap_err().to_string();
+        assert!(error.contains("LOGNARRATOR_TEST_UNSET_SECRET is not set"));
+        assert!(error.contains("/nonexistent/lognarrator/secret"));
+
+        Ok(())
+    }
+
+    fn batch_config(timeout: u64, send_batch_size: usize) -> CollectorConfig {
+        CollectorConfig {
+            sources: Vec::new(),
+            processors: vec![ProcessorConfig::Batch {
+                name: "batcher".to_string(),
+                timeout,
+                send_batch_size,
+                timeout_jitter: 0.0,
+            }],
+            exporters: Vec::new(),
+            pipelines: HashMap::new(),
+            queue_alert: None,
+            overflow: OverflowPolicy::default(),
+            internal_logs: None,
+            source_staleness_seconds: default_source_staleness(),
+            shutdown_timeout_seconds: default_shutdown_timeout(),
+            max_concurrent_exports: default_max_concurrent_exports(),
+            resilience: HashMap::new(),
+            max_source_connections: default_max_source_connections(),
+            unsupported_sources: UnsupportedSourcePolicy::default(),
+            overlapping_files: OverlappingFilesPolicy::default(),
+            commit: None,
+            telemetry: None,
+            probes: None,
+            admin: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_accepts_sane_batching() {
+        assert!(batch_config(1, 100).validate().is_ok());
+        assert!(batch_config(MAX_INTERVAL_SECONDS, MAX_BATCH_SIZE as usize).validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_batching() {
+        let error = batch_config(0, 0).validate().unwrap_err().to_string();
+        assert!(error.contains("processor 'batcher': timeout must be at least 1 (got 0)"));
+        assert!(error.contains("processor 'batcher': send_batch_size must be at least 1 (got 0)"));
+    }
+
+    #[test]
+    fn test_validate_rejects_absurd_batching() {
+        let error = batch_config(86_400, 10_000_000).validate().unwrap_err().to_string();
+        assert!(error.contains("timeout must be at most 3600"));
+        assert!(error.contains("send_batch_size must be at most 100000"));
+    }
+
+    #[test]
+    fn test_validate_checks_pipeline_references() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: otlp
+                name: audit-receiver
+                port: 4318
+                pipeline: audit
+              - source_type: otlp
+                name: app-receiver
+                port: 4319
+                pipeline: missing
+            processors:
+              - processor_type: batch
+                name: batcher
+                timeout: 5
+                send_batch_size: 100
+            exporters:
+              - exporter_type: database
+                name: audit-db
+                path: /var/lib/lognarrator/audit.db
+            pipelines:
+              audit:
+                sources: [audit-receiver, app-receiver, ghost]
+                processors: [batcher, redactor]
+                exporters: [audit-db, siem]
+        "#)?;
+
+        assert_eq!(config.sources[0].pipeline(), Some("audit"));
+        assert_eq!(config.source_pipelines(&config.sources[0]), vec!["audit"]);
+        assert_eq!(config.source_pipelines(&config.sources[1]), vec!["missing", "audit"]);
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("pipeline 'audit': unknown processor 'redactor'"));
+        assert!(error.contains("pipeline 'audit': unknown exporter 'siem'"));
+        assert!(error.contains("source 'app-receiver': unknown pipeline 'missing'"));
+        assert!(error.contains("pipeline 'audit': unknown source 'ghost'"));
+        assert!(!error.contains("batcher"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_rejects_zero_in_flight() -> Result<()> {
+        let dir = tempdir()?;
+        let config_path = dir.path().join("collector.yaml");
+        std::fs::write(&config_path, r#"
+            sources: []
+            processors:
+              - processor_type: coalesce
+                name: traces
+                window_seconds: 0
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+                max_in_flight: 0
+        "#)?;
+
+        let error = load_config(config_path).unwrap_err().to_string();
+        assert!(error.contains("processor 'traces': window_seconds must be at least 1"));
+        assert!(error.contains("exporter 'cloud-export': max_in_flight must be at least 1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_defaults_and_bounds() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+        "#)?;
+
+        match &config.exporters[0] {
+            ExporterConfig::LogNarrator { retry, dead_letter_path, .. } => {
+                assert_eq!(retry.base_delay_ms, 500);
+                assert_eq!(retry.max_retries, 3);
+                assert_eq!(retry.jitter, 0.2);
+                assert_eq!(dead_letter_path, &None);
+            },
+            other => panic!("unexpected exporter {:?}", other),
+        }
+
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+                retry:
+                  base_delay_ms: 2000
+                  max_delay_ms: 1000
+                  jitter: 1.5
+        "#)?;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("exporter 'cloud-export': retry.max_delay_ms must be at least 2000 (got 1000)"));
+        assert!(error.contains("exporter 'cloud-export': retry.jitter must be between 0 and 1 (got 1.5)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resilience_policies_are_checked_and_referenced() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            resilience:
+              shaky:
+                failure_threshold: 0
+              default-like: {}
+            exporters:
+              - exporter_type: stdout
+                name: console
+                resilience: default-like
+              - exporter_type: stdout
+                name: other
+                resilience: missing
+        "#)?;
+
+        assert_eq!(config.exporters[0].resilience(), Some("default-like"));
+        let policy = &config.resilience["default-like"];
+        assert_eq!(policy.failure_threshold, 5);
+        assert_eq!(policy.cooldown_seconds, 30);
+        assert_eq!(policy.retry.max_retries, 3);
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("resilience 'shaky': failure_threshold must be at least 1 (got 0)"));
+        assert!(error.contains("exporter 'other': unknown resilience policy 'missing'"));
+        assert!(!error.contains("'console'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encryption_scheme_requirements() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: sodium-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+                encryption: {}
+              - exporter_type: lognarrator
+                name: age-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+                encryption:
+                  scheme: age
+                  recipients:
+                    - age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p
+                    - ssh-ed25519 AAAA
+        "#)?;
+
+        match &config.exporters[0] {
+            ExporterConfig::LogNarrator { encryption: Some(encryption), .. } => {
+                assert_eq!(encryption.scheme, EncryptionScheme::Sodium);
+            },
+            other => panic!("unexpected exporter {:?}", other),
+        }
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("exporter 'sodium-export': encryption.server_public_key_path is required by the sodium scheme"));
+        assert!(error.contains("exporter 'age-export': 'ssh-ed25519 AAAA' is not an age X25519 recipient (age1...)"));
+        assert!(!error.contains("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"));
+        assert_eq!(error.contains("requires a build with the `age` feature"), !cfg!(feature = "age"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plaintext_uploads_need_opting_in() -> Result<()> {
+        let exporter = |extra: &str| -> Result<CollectorConfig> {
+            Ok(serde_yaml::from_str(&format!(r#"
+                sources: []
+                processors: []
+                exporters:
+                  - exporter_type: lognarrator
+                    name: cloud-export
+                    endpoint: https://api.lognarrator.com
+                    client_id: test-client
+                    key_path: /app/config/private.key
+                    {}
+            "#, extra))?)
+        };
+
+        let error = exporter("")?.validate().unwrap_err().to_string();
+        assert!(error.contains("exporter 'cloud-export': uploads would be readable plaintext"));
+
+        exporter("allow_plaintext: true")?.validate()?;
+        exporter("encryption: { server_public_key_path: /app/config/server.pub }")?.validate()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exporter_client_certificate_needs_its_key() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: /app/config/private.key
+                tls:
+                  verify_certs: false
+                  client_cert_path: /app/config/client.pem
+        "#)?;
+
+        match &config.exporters[0] {
+            ExporterConfig::LogNarrator { tls, .. } => {
+                assert!(!tls.verify_certs);
+                assert_eq!(tls.ca_cert_path, None);
+            },
+            other => panic!("unexpected exporter {:?}", other),
+        }
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("exporter 'cloud-export': tls.client_cert_path and tls.client_key_path must be set together"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_tls_requires_a_stream_protocol() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: syslog
+                name: network-devices
+                port: 6514
+                protocol: udp
+                tls:
+                  cert_path: /etc/collector/server.pem
+                  key_path: /etc/collector/server.key
+              - source_type: otlp
+                name: otel-grpc
+                port: 4317
+                protocol: grpc
+                tls:
+                  cert_path: /etc/collector/server.pem
+                  key_path: /etc/collector/server.key
+                  require_client_cert: true
+            processors: []
+            exporters: []
+        "#)?;
+
+        match &config.sources[0] {
+            SourceConfig::Syslog { tls: Some(tls), .. } => {
+                assert_eq!(tls.mode, TlsMode::Required);
+                assert_eq!(tls.client_ca_path, None);
+                assert!(!tls.require_client_cert);
+            },
+            other => panic!("unexpected source {:?}", other),
+        }
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("source 'network-devices': tls requires protocol: tcp"));
+        assert!(error.contains("source 'otel-grpc': tls requires protocol: http"));
+        assert!(error.contains("source 'otel-grpc': tls.require_client_cert needs tls.client_ca_path"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_socket_sources() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: tcp
+                name: app-stream
+                port: 6000
+                framing: length_prefixed
+              - source_type: udp
+                name: app-datagrams
+                port: 6000
+              - source_type: syslog
+                name: syslog-udp
+                port: 6000
+            processors: []
+            exporters: []
+        "#)?;
+
+        match &config.sources[0] {
+            SourceConfig::Tcp { framing, interface, .. } => {
+                assert_eq!(*framing, Framing::LengthPrefixed);
+                assert_eq!(interface, "0.0.0.0");
+            },
+            other => panic!("unexpected source {:?}", other),
+        }
+        assert!(matches!(&config.sources[1], SourceConfig::Udp { framing: Framing::Newline, .. }));
+
+        // TCP and UDP do not clash on a port, but two UDP listeners do
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("sources 'app-datagrams' and 'syslog-udp' both listen on udp port 6000"));
+        assert!(!error.contains("'app-stream'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_elasticsearch_config() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: elasticsearch
+                name: elastic
+                nodes: [http://localhost:9200]
+                auth:
+                  username: elastic
+                  password: changeme
+        "#)?;
+        config.validate()?;
+
+        match &config.exporters[0] {
+            ExporterConfig::Elasticsearch { index, batch_size, flush_interval_seconds, .. } => {
+                assert_eq!(index, "logs-%Y.%m.%d");
+                assert_eq!(*batch_size, 500);
+                assert_eq!(*flush_interval_seconds, 10);
+            },
+            other => panic!("unexpected exporter {:?}", other),
+        }
+
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: elasticsearch
+                name: elastic
+                nodes: []
+                index: logs-%Q
+                auth:
+                  username: elastic
+                  api_key: c2VjcmV0
+                batch_size: 0
+        "#)?;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("exporter 'elastic': nodes must not be empty"));
+        assert!(error.contains("exporter 'elastic': index 'logs-%Q' is not a valid index name or date pattern"));
+        assert!(error.contains("exporter 'elastic': auth needs either username and password, or api_key"));
+        assert!(error.contains("exporter 'elastic': batch_size must be at least 1 (got 0)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_needs_a_database_store() -> Result<()> {
+        let exporters = r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: database
+                name: local
+                path: /var/lib/lognarrator/logs.db
+              - exporter_type: elasticsearch
+                name: elastic
+                nodes: [http://localhost:9200]
+              - exporter_type: stdout
+                name: console
+        "#;
+
+        let config: CollectorConfig = serde_yaml::from_str(&format!("{}\n            commit: {{ store: local, require: [console] }}", exporters))?;
+        config.validate()?;
+
+        let config: CollectorConfig = serde_yaml::from_str(&format!("{}\n            commit: {{ store: console, require: [elastic, cloud] }}", exporters))?;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("commit: store 'console' is not a database exporter"));
+        assert!(error.contains("commit: exporter 'elastic' cannot confirm delivery"));
+        assert!(error.contains("commit: unknown exporter 'cloud'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping_file_sources() -> Result<()> {
+        let dir = tempdir()?;
+        std::fs::write(dir.path().join("app.log"), "")?;
+        let dir = dir.path().display();
+
+        let yaml = format!(r#"
+            sources:
+              - source_type: file
+                name: app
+                include: ["{dir}/app.log"]
+              - source_type: file
+                name: all-logs
+                include: ["{dir}/*.log"]
+              - source_type: file
+                name: all-files
+                include: ["{dir}/*"]
+              - source_type: file
+                name: other
+                include: ["{dir}/other/*.txt"]
+            processors: []
+            exporters: []
+            overlapping_files: error
+        "#);
+        let config: CollectorConfig = serde_yaml::from_str(&yaml)?;
+
+        assert_eq!(config.overlapping_file_sources(), vec![
+            format!("sources 'app' and 'all-logs' both include {}/app.log", dir),
+            format!("sources 'app' and 'all-files' both include {}/app.log", dir),
+            format!("sources 'all-logs' and 'all-files' both include {}/*.log", dir),
+        ]);
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains(&format!(
+            "sources 'app' and 'all-logs' both include {}/app.log (set overlapping_files: dedupe to read it once)",
+            dir,
+        )));
+
+        // The default only warns, and dedupe resolves overlaps at runtime
+        let config: CollectorConfig = serde_yaml::from_str(&yaml.replace("overlapping_files: error", ""))?;
+        assert_eq!(config.overlapping_files, OverlappingFilesPolicy::Warn);
+        config.validate()?;
+        let config: CollectorConfig = serde_yaml::from_str(&yaml.replace("error", "dedupe"))?;
+        config.validate()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicate_names() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - { source_type: otlp, name: receiver, port: 4318 }
+              - { source_type: otlp, name: receiver, port: 4319 }
+              - { source_type: otlp, name: receiver, port: 4320 }
+            processors:
+              - { processor_type: coalesce, name: merge }
+              - { processor_type: coalesce, name: merge }
+            exporters:
+              - { exporter_type: stdout, name: console }
+              - { exporter_type: stdout, name: console, format: logfmt }
+        "#)?;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert_eq!(error.matches("source name 'receiver' is used more than once").count(), 1);
+        assert!(error.contains("processor name 'merge' is used more than once"));
+        assert!(error.contains("exporter name 'console' is used more than once"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_regexes() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors:
+              - processor_type: filter
+                name: errors-only
+                logs:
+                  include: { match_type: regexp, regexp: ['error', '(unclosed'] }
+                  exclude: { match_type: exact, exact: ['(not a regex'] }
+              - processor_type: transform
+                name: mask-cards
+                transforms:
+                  - transform_type: mask
+                    field: message
+                    parameters: { pattern: '[0-9' }
+            exporters: []
+        "#)?;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("processor 'errors-only': invalid regex '(unclosed': unclosed group"));
+        assert!(error.contains("processor 'mask-cards': invalid regex '[0-9': unclosed character class"));
+        assert!(!error.contains("(not a regex"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_conditions_name_known_fields() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors:
+              - processor_type: filter
+                name: prod-only
+                logs:
+                  include:
+                    all:
+                      - { field: attributes.env, match_type: exact, exact: [prod] }
+                      - { field: attributes., match_type: exact, exact: [x] }
+                  exclude:
+                    any:
+                      - { field: level, match_type: exact, exact: [DEBUG] }
+                      - { field: severity, match_type: regexp, regexp: ['(unclosed'] }
+            exporters: []
+        "#)?;
+
+        let logs = match &config.processors[0] {
+            ProcessorConfig::Filter { logs, .. } => logs,
+            other => panic!("expected a filter, got {:?}", other),
+        };
+        assert!(matches!(logs.include, Some(MatchRule::All { .. })));
+        assert_eq!(logs.exclude.as_ref().map(|rule| rule.conditions().len()), Some(2));
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("processor 'prod-only': field must be message, level or attributes.<key> (got 'attributes.')"));
+        assert!(error.contains("processor 'prod-only': field must be message, level or attributes.<key> (got 'severity')"));
+        assert!(error.contains("processor 'prod-only': invalid regex '(unclosed'"));
+        assert_eq!(error.lines().count(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_min_severity_must_be_a_known_level() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors:
+              - { processor_type: filter, name: warnings, min_severity: warning }
+              - { processor_type: filter, name: loud, min_severity: LOUD, unknown_severity: drop }
+            exporters: []
+        "#)?;
+
+        match &config.processors[1] {
+            ProcessorConfig::Filter { logs, unknown_severity, .. } => {
+                assert!(logs.include.is_none() && logs.exclude.is_none());
+                assert_eq!(*unknown_severity, UnknownSeverityAction::Drop);
+            },
+            other => panic!("expected a filter, got {:?}", other),
+        }
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("processor 'loud': min_severity 'LOUD' is not a known level (use one of TRACE, DEBUG, INFO, WARN, ERROR, FATAL)"));
+        assert!(!error.contains("'warnings'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_rejects_port_conflicts() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - { source_type: otlp, name: otlp, port: 5140 }
+              - { source_type: syslog, name: syslog-tcp, port: 5140, interface: 127.0.0.1, protocol: tcp }
+              - { source_type: syslog, name: syslog-udp, port: 5140 }
+              - { source_type: syslog, name: syslog-lan, port: 514, interface: 10.0.0.1 }
+              - { source_type: syslog, name: syslog-local, port: 514, interface: 127.0.0.1 }
+            processors: []
+            exporters: []
+        "#)?;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("sources 'otlp' and 'syslog-tcp' both listen on tcp port 5140"));
+        // UDP may share a port with TCP, and distinct interfaces may share one too
+        assert!(!error.contains("syslog-udp"));
+        assert!(!error.contains("syslog-lan"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_startup_validation_lists_every_problem() -> Result<()> {
+        let dir = tempdir()?;
+        let key_path = dir.path().join("private.key");
+        let missing = dir.path().join("missing");
+
+        let empty = batch_config(1, 100);
+        empty.validate()?;
+        let error = empty.validate_for_startup().unwrap_err().to_string();
+        assert!(error.contains("sources must not be empty"));
+        assert!(error.contains("exporters must not be empty"));
+
+        let yaml = format!(r#"
+            sources:
+              - source_type: syslog
+                name: network-devices
+                port: 6514
+                protocol: tcp
+                tls:
+                  cert_path: {missing}/server.pem
+                  key_path: {key}
+            processors: []
+            exporters:
+              - exporter_type: lognarrator
+                name: cloud-export
+                endpoint: https://api.lognarrator.com
+                client_id: test-client
+                key_path: {key}
+                allow_plaintext: true
+              - exporter_type: database
+                name: local
+                path: {missing}/logs.db
+        "#, missing = missing.display(), key = key_path.display());
+        let config: CollectorConfig = serde_yaml::from_str(&yaml)?;
+        config.validate()?;
+
+        let error = config.validate_for_startup().unwrap_err().to_string();
+        assert!(error.contains(&format!("source 'network-devices': tls.cert_path '{}/server.pem' does not exist", missing.display())));
+        assert!(error.contains(&format!("source 'network-devices': tls.key_path '{}' does not exist", key_path.display())));
+        assert!(error.contains(&format!("exporter 'cloud-export': key_path '{}' does not exist", key_path.display())));
+        assert!(error.contains(&format!("exporter 'local': directory of path '{}/logs.db' does not exist", missing.display())));
+        assert_eq!(error.lines().count(), 5);
+
+        std::fs::write(&key_path, "key")?;
+        std::fs::create_dir(&missing)?;
+        std::fs::write(missing.join("server.pem"), "cert")?;
+        config.validate_for_startup()?;
 
         Ok(())
     }
@@ -0,0 +1,231 @@
+//! Dead-letter file sink for logs an exporter could not deliver
+//!
+//! Each failed entry is appended as one JSON line together with the exporter
+//! that gave up on it and the error that made it fail. The live file is
+//! rotated before it would grow past `max_file_bytes`: `dead.jsonl` becomes
+//! `dead.jsonl.1`, the previous `.1` becomes `.2`, and so on. The oldest
+//! rotated files are deleted so that they, plus a full live file, stay within
+//! `max_total_bytes`.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::collector::sources::LogEntry;
+
+/// One line of a dead-letter file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    /// When the entry was dead-lettered
+    pub failed_at: DateTime<Utc>,
+    /// Exporter that failed to deliver the entry
+    pub exporter: String,
+    /// Error that made delivery fail
+    pub reason: String,
+    /// The undelivered entry
+    pub entry: LogEntry,
+}
+
+/// Size-bounded JSONL file of undeliverable logs
+pub struct DeadLetterSink {
+    path: PathBuf,
+    max_file_bytes: u64,
+    max_total_bytes: u64,
+    current: Mutex<CurrentFile>,
+    written: AtomicU64,
+}
+
+/// The live dead-letter file, opened on first write
+struct CurrentFile {
+    file: Option<File>,
+    size: u64,
+}
+
+impl DeadLetterSink {
+    /// Create a sink writing to `path`, rotating at `max_file_bytes` and keeping at most `max_total_bytes`
+    pub fn new(path: impl Into<PathBuf>, max_file_bytes: u64, max_total_bytes: u64) -> Self {
+        Self {
+            path: path.into(),
+            max_file_bytes,
+            max_total_bytes,
+            current: Mutex::new(CurrentFile { file: None, size: 0 }),
+            written: AtomicU64::new(0),
+        }
+    }
+
+    /// Path of the live dead-letter file
+    #[cfg(test)]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Number of entries written since the sink was created
+    #[cfg(test)]
+    pub fn written_count(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Append `logs`, each tagged with the exporter and the reason it failed
+    pub fn write(&self, exporter: &str, logs: &[LogEntry], reason: &str) -> Result<()> {
+        let mut current = self.current.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let failed_at = Utc::now();
+
+        for log in logs {
+            let line = serde_json::to_string(&DeadLetterRecord {
+                failed_at,
+                exporter: exporter.to_string(),
+                reason: reason.to_string(),
+                entry: log.clone(),
+            })?;
+            let line_len = line.len() as u64 + 1; // +1 for newline
+
+            if current.file.is_none() {
+                let file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+                current.size = file.metadata()?.len();
+                current.file = Some(file);
+            }
+
+            if current.size > 0 && current.size + line_len > self.max_file_bytes {
+                self.rotate(&mut current)?;
+                let file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+                current.file = Some(file);
+            }
+
+            if let Some(file) = current.file.as_mut() {
+                writeln!(file, "{}", line)?;
+            }
+            current.size += line_len;
+            self.written.fetch_add(1, Ordering::Relaxed);
+            metrics::increment_counter!("collector_dead_lettered_logs_total", "exporter" => exporter.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Path of the `index`th most recent rotated file
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    /// Move the live file to `.1`, shifting older files up, then enforce the budget
+    fn rotate(&self, current: &mut CurrentFile) -> Result<()> {
+        current.file = None;
+        current.size = 0;
+
+        let mut oldest = 0;
+        while self.rotated_path(oldest + 1).exists() {
+            oldest += 1;
+        }
+        for index in (1..=oldest).rev() {
+            fs::rename(self.rotated_path(index), self.rotated_path(index + 1))?;
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.enforce_budget()
+    }
+
+    /// Delete the oldest rotated files until everything fits in `max_total_bytes`
+    fn enforce_budget(&self) -> Result<()> {
+        // Reserve room for the live file to grow to its limit
+        let mut total = self.max_file_bytes;
+        let mut index = 1;
+
+        while let Ok(metadata) = fs::metadata(self.rotated_path(index)) {
+            total += metadata.len();
+            if total > self.max_total_bytes {
+                fs::remove_file(self.rotated_path(index))?;
+            }
+            index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read every record from a dead-letter file
+#[cfg(test)]
+pub fn read_dead_letters<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<DeadLetterRecord>> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            source: "app".to_string(),
+            level: Some("ERROR".to_string()),
+            severity_number: None,
+            message: message.to_string(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_failed_entries_are_written_with_reasons() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = DeadLetterSink::new(dir.path().join("dead.jsonl"), 1024 * 1024, 10 * 1024 * 1024);
+
+        sink.write("database", &[entry("first"), entry("second")], "disk I/O error")?;
+        sink.write("cloud", &[entry("third")], "HTTP 503")?;
+
+        let records = read_dead_letters(sink.path())?;
+        let summary: Vec<_> = records
+            .iter()
+            .map(|record| (record.exporter.as_str(), record.reason.as_str(), record.entry.message.as_str()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("database", "disk I/O error", "first"),
+            ("database", "disk I/O error", "second"),
+            ("cloud", "HTTP 503", "third"),
+        ]);
+        assert_eq!(sink.written_count(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotation_and_budget_are_enforced() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dead.jsonl");
+
+        // Measure one record so the limits fit a known number of them
+        let probe = DeadLetterSink::new(dir.path().join("probe.jsonl"), u64::MAX, u64::MAX);
+        probe.write("database", &[entry("entry 00")], "write failed")?;
+        let record_len = fs::metadata(probe.path())?.len();
+
+        // Two records per file (timestamps vary the length slightly), and room
+        // for a full live file plus two rotated ones
+        let sink = DeadLetterSink::new(&path, record_len * 5 / 2, record_len * 7);
+        for i in 0..12 {
+            sink.write("database", &[entry(&format!("entry {:02}", i))], "write failed")?;
+        }
+
+        for file in [&path, &sink.rotated_path(1), &sink.rotated_path(2)] {
+            assert_eq!(read_dead_letters(file)?.len(), 2);
+        }
+        assert!(!sink.rotated_path(3).exists());
+
+        let mut kept = Vec::new();
+        for file in [sink.rotated_path(2), sink.rotated_path(1), path.clone()] {
+            kept.extend(read_dead_letters(file)?.into_iter().map(|record| record.entry.message));
+        }
+        assert_eq!(kept, vec!["entry 06", "entry 07", "entry 08", "entry 09", "entry 10", "entry 11"]);
+
+        Ok(())
+    }
+}
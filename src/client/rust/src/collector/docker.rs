@@ -0,0 +1,315 @@
+//! Minimal Docker Engine API client for the Docker log source
+//!
+//! Speaks HTTP/1.1 over the daemon's unix socket and covers only what the
+//! source needs: listing running containers, checking whether one has a TTY,
+//! and following its log stream. Non-TTY log streams multiplex stdout and
+//! stderr behind an 8-byte frame header, which [`FrameDecoder`] strips.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use hyper::{Body, Request, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Socket the Docker daemon listens on by default
+pub const DEFAULT_SOCKET_PATH: &str = "/var/run/docker.sock";
+
+/// Length of the header in front of each multiplexed log frame
+const FRAME_HEADER_LEN: usize = 8;
+
+/// A running container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    /// Full container ID
+    pub id: String,
+    /// Container name, without the leading `/`
+    pub name: String,
+}
+
+impl Container {
+    /// Whether `reference` names this container, by name or by (possibly shortened) ID
+    pub fn matches(&self, reference: &str) -> bool {
+        let reference = reference.trim_start_matches('/');
+        !reference.is_empty() && (self.name == reference || self.id.starts_with(reference))
+    }
+}
+
+/// Entry of `GET /containers/json`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerSummary {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+}
+
+/// The parts of `GET /containers/{id}/json` the source uses
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerInspect {
+    config: ContainerInspectConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerInspectConfig {
+    #[serde(default)]
+    tty: bool,
+}
+
+/// Client for the Docker Engine API on a unix socket
+#[derive(Debug, Clone)]
+pub struct DockerClient {
+    socket_path: PathBuf,
+}
+
+impl DockerClient {
+    /// Create a client for the daemon listening on `socket_path`
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self { socket_path: socket_path.into() }
+    }
+
+    /// List the running containers
+    pub async fn list_containers(&self) -> Result<Vec<Container>> {
+        let summaries: Vec<ContainerSummary> = self.get_json("/containers/json").await?;
+
+        Ok(summaries
+            .into_iter()
+            .map(|summary| {
+                let name = summary.names
+                    .first()
+                    .map(|name| name.trim_start_matches('/').to_string())
+                    .unwrap_or_else(|| summary.id.chars().take(12).collect());
+                Container { id: summary.id, name }
+            })
+            .collect())
+    }
+
+    /// Whether the container runs with a TTY, in which case its logs are not multiplexed
+    pub async fn has_tty(&self, id: &str) -> Result<bool> {
+        let inspect: ContainerInspect = self.get_json(&format!("/containers/{}/json", id)).await?;
+        Ok(inspect.config.tty)
+    }
+
+    /// Follow a container's stdout and stderr from `since`, each line prefixed with its timestamp
+    pub async fn logs(&self, id: &str, since: DateTime<Utc>) -> Result<Body> {
+        let path = format!(
+            "/containers/{}/logs?follow=true&stdout=true&stderr=true&timestamps=true&since={}.{:09}",
+            id,
+            since.timestamp(),
+            since.timestamp_subsec_nanos(),
+        );
+
+        Ok(self.get(&path).await?.into_body())
+    }
+
+    /// GET `path` and decode the JSON response
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = hyper::body::to_bytes(self.get(path).await?.into_body()).await?;
+        serde_json::from_slice(&body)
+            .map_err(|e| anyhow!("Invalid response from Docker API {}: {}", path, e))
+    }
+
+    /// GET `path`, failing on any non-success status
+    async fn get(&self, path: &str) -> Result<Response<Body>> {
+        let stream = connect(&self.socket_path).await?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!("Docker API connection closed: {}", e);
+            }
+        });
+
+        let request = Request::get(path)
+            .header(hyper::header::HOST, "docker")
+            .body(Body::empty())?;
+        let response = sender.send_request(request).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+            return Err(anyhow!(
+                "Docker API {} returned {}: {}",
+                path,
+                status,
+                String::from_utf8_lossy(&body).trim(),
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(unix)]
+async fn connect(socket_path: &Path) -> Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(socket_path).await.map_err(|e| {
+        anyhow!("Failed to connect to Docker socket {}: {}", socket_path.display(), e)
+    })
+}
+
+#[cfg(not(unix))]
+async fn connect(socket_path: &Path) -> Result<tokio::net::TcpStream> {
+    Err(anyhow!("Docker socket {} requires a Unix system", socket_path.display()))
+}
+
+/// Output stream a log frame came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamKind {
+    Stdin,
+    Stdout,
+    Stderr,
+}
+
+impl StreamKind {
+    /// Name used in the `stream` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamKind::Stdin => "stdin",
+            StreamKind::Stdout => "stdout",
+            StreamKind::Stderr => "stderr",
+        }
+    }
+}
+
+/// A chunk of log output from one stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFrame {
+    pub stream: StreamKind,
+    pub payload: Vec<u8>,
+}
+
+/// Splits a container log stream into frames
+///
+/// Each frame starts with a header of the stream type, three zero bytes and
+/// the big-endian payload length. Frames may arrive split across chunks, so
+/// incomplete ones are held until the rest arrives. A TTY container's stream
+/// has no framing and is reported as stdout.
+pub struct FrameDecoder {
+    tty: bool,
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    /// Create a decoder for a container with or without a TTY
+    pub fn new(tty: bool) -> Self {
+        Self { tty, buffer: Vec::new() }
+    }
+
+    /// Feed a chunk of the stream, returning every frame it completes
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<LogFrame>> {
+        if self.tty {
+            if chunk.is_empty() {
+                return Ok(Vec::new());
+            }
+            return Ok(vec![LogFrame { stream: StreamKind::Stdout, payload: chunk.to_vec() }]);
+        }
+
+        self.buffer.extend_from_slice(chunk);
+
+        let mut frames = Vec::new();
+        let mut consumed = 0;
+
+        while self.buffer.len() - consumed >= FRAME_HEADER_LEN {
+            let header = &self.buffer[consumed..consumed + FRAME_HEADER_LEN];
+            let stream = match header[0] {
+                0 => StreamKind::Stdin,
+                1 => StreamKind::Stdout,
+                2 => StreamKind::Stderr,
+                other => return Err(anyhow!("Unknown stream type {} in Docker log frame", other)),
+            };
+            let length = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+            let start = consumed + FRAME_HEADER_LEN;
+            if self.buffer.len() - start < length {
+                break;
+            }
+
+            frames.push(LogFrame { stream, payload: self.buffer[start..start + length].to_vec() });
+            consumed = start + length;
+        }
+
+        self.buffer.drain(..consumed);
+        Ok(frames)
+    }
+}
+
+/// Split off the RFC 3339 timestamp Docker puts in front of each line
+///
+/// Returns the parsed timestamp and the rest of the line, or `None` and the
+/// whole line if it does not start with a timestamp.
+pub fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    let (stamp, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+    match DateTime::parse_from_rfc3339(stamp) {
+        Ok(timestamp) => (Some(timestamp.with_timezone(&Utc)), rest),
+        Err(_) => (None, line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn frame(stream: u8, payload: &str) -> Vec<u8> {
+        let mut bytes = vec![stream, 0, 0, 0];
+        bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(payload.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_frames_are_demultiplexed_across_chunks() -> Result<()> {
+        let mut stream = frame(1, "out line\n");
+        stream.extend(frame(2, "err line\n"));
+
+        let mut decoder = FrameDecoder::new(false);
+        let mut frames = Vec::new();
+        for chunk in stream.chunks(5) {
+            frames.extend(decoder.push(chunk)?);
+        }
+
+        assert_eq!(frames, vec![
+            LogFrame { stream: StreamKind::Stdout, payload: b"out line\n".to_vec() },
+            LogFrame { stream: StreamKind::Stderr, payload: b"err line\n".to_vec() },
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tty_stream_is_stdout() -> Result<()> {
+        let mut decoder = FrameDecoder::new(true);
+        let frames = decoder.push(b"raw output\n")?;
+        assert_eq!(frames, vec![LogFrame { stream: StreamKind::Stdout, payload: b"raw output\n".to_vec() }]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_stream_type_is_rejected() {
+        let mut decoder = FrameDecoder::new(false);
+        assert!(decoder.push(&frame(7, "bad")).is_err());
+    }
+
+    #[test]
+    fn test_split_timestamp() {
+        let (timestamp, message) = split_timestamp("2024-03-01T12:30:45.123456789Z GET /health 200");
+        assert_eq!(timestamp, Some(Utc.timestamp_nanos(1_709_296_245_123_456_789)));
+        assert_eq!(message, "GET /health 200");
+
+        assert_eq!(split_timestamp("no timestamp here"), (None, "no timestamp here"));
+    }
+
+    #[test]
+    fn test_container_matches_name_or_id_prefix() {
+        let container = Container { id: "4f1c2a9b8e7d".to_string(), name: "web".to_string() };
+
+        assert!(container.matches("web"));
+        assert!(container.matches("/web"));
+        assert!(container.matches("4f1c"));
+        assert!(!container.matches("db"));
+        assert!(!container.matches(""));
+    }
+}
@@ -0,0 +1,192 @@
+//! Elasticsearch `_bulk` request encoding and response parsing
+//!
+//! A bulk body is newline-delimited JSON: an action line naming the target
+//! index, then the document, for every log. The response reports a status for
+//! each item in request order, so failures can be matched back to the logs
+//! that produced them and only those sent again.
+
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::SecondsFormat;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::collector::sources::LogEntry;
+
+/// Content type of a `_bulk` request body
+pub const BULK_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// What the cluster did with one item of a bulk request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemStatus {
+    /// Indexed
+    Indexed,
+    /// Failed in a way worth retrying (429, or a 5xx such as unavailable shards)
+    Retry(String),
+    /// Refused for good, e.g. a mapping conflict
+    Rejected(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkResponse {
+    #[serde(default)]
+    errors: bool,
+    #[serde(default)]
+    items: Vec<HashMap<String, BulkItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkItem {
+    status: u16,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+/// Check that an index pattern only uses strftime fields chrono understands
+pub fn check_index_pattern(pattern: &str) -> Result<()> {
+    if pattern.is_empty() || StrftimeItems::new(pattern).any(|item| item == Item::Error) {
+        return Err(anyhow!("'{}' is not a valid index name or date pattern", pattern));
+    }
+    Ok(())
+}
+
+/// Name of the index a log belongs in, filling the pattern's strftime fields from its timestamp
+pub fn index_name(pattern: &str, log: &LogEntry) -> String {
+    log.timestamp.format(pattern).to_string()
+}
+
+/// Document indexed for a log
+///
+/// Attributes keep their dotted names under `attributes`; `level` is left
+/// out when the log has none.
+pub fn document(log: &LogEntry) -> Value {
+    let mut document = json!({
+        "@timestamp": log.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "message": log.message,
+        "source": log.source,
+        "attributes": log.attributes,
+    });
+    if let Some(level) = &log.level {
+        document["level"] = json!(level);
+    }
+    document
+}
+
+/// Encode logs as a `_bulk` request body creating one document each
+pub fn bulk_body(pattern: &str, logs: &[LogEntry]) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    for log in logs {
+        serde_json::to_writer(&mut body, &json!({ "create": { "_index": index_name(pattern, log) } }))?;
+        body.push(b'\n');
+        serde_json::to_writer(&mut body, &document(log))?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+/// Status of each of the `sent` items of a successful `_bulk` response, in request order
+pub fn parse_bulk_response(body: &[u8], sent: usize) -> Result<Vec<ItemStatus>> {
+    let response: BulkResponse = serde_json::from_slice(body)
+        .map_err(|e| anyhow!("Invalid bulk response: {}", e))?;
+
+    if !response.errors {
+        return Ok(vec![ItemStatus::Indexed; sent]);
+    }
+    if response.items.len() != sent {
+        return Err(anyhow!("Bulk response lists {} items for {} sent", response.items.len(), sent));
+    }
+
+    Ok(response.items.into_iter().map(|item| {
+        // Each item is keyed by its action (`create`, `index`, ...)
+        match item.into_values().next() {
+            Some(item) if (200..300).contains(&item.status) => ItemStatus::Indexed,
+            Some(item) => {
+                let reason = describe_error(item.status, item.error.as_ref());
+                if item.status == 429 || item.status >= 500 {
+                    ItemStatus::Retry(reason)
+                } else {
+                    ItemStatus::Rejected(reason)
+                }
+            },
+            None => ItemStatus::Retry("empty bulk item".to_string()),
+        }
+    }).collect())
+}
+
+/// One-line description of a failed item, such as `429 es_rejected_execution_exception: queue full`
+fn describe_error(status: u16, error: Option<&Value>) -> String {
+    let kind = error.and_then(|error| error["type"].as_str()).unwrap_or("error");
+    match error.and_then(|error| error["reason"].as_str()) {
+        Some(reason) => format!("{} {}: {}", status, kind, reason),
+        None => format!("{} {}", status, kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn log(message: &str, level: Option<&str>) -> LogEntry {
+        LogEntry {
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 17, 23, 59, 58).unwrap(),
+            source: "app".to_string(),
+            level: level.map(str::to_string),
+            severity_number: None,
+            message: message.to_string(),
+            attributes: HashMap::from([("http.method".to_string(), "GET".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_bulk_body_targets_dated_indices() -> Result<()> {
+        let body = bulk_body("logs-%Y.%m.%d", &[log("first", Some("INFO")), log("second", None)])?;
+        let lines: Vec<Value> = String::from_utf8(body)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()?;
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], json!({ "create": { "_index": "logs-2024.05.17" } }));
+        assert_eq!(lines[1], json!({
+            "@timestamp": "2024-05-17T23:59:58.000Z",
+            "level": "INFO",
+            "message": "first",
+            "source": "app",
+            "attributes": { "http.method": "GET" },
+        }));
+        assert_eq!(lines[3].get("level"), None);
+
+        assert!(check_index_pattern("logs-%Y.%m.%d").is_ok());
+        assert!(check_index_pattern("logs").is_ok());
+        assert!(check_index_pattern("logs-%Q").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bulk_response_reports_each_item() -> Result<()> {
+        assert_eq!(
+            parse_bulk_response(br#"{"took": 3, "errors": false, "items": []}"#, 2)?,
+            vec![ItemStatus::Indexed, ItemStatus::Indexed],
+        );
+
+        let response = br#"{"took": 3, "errors": true, "items": [
+            {"create": {"_index": "logs", "status": 201}},
+            {"create": {"_index": "logs", "status": 429,
+                "error": {"type": "es_rejected_execution_exception", "reason": "queue full"}}},
+            {"create": {"_index": "logs", "status": 400,
+                "error": {"type": "mapper_parsing_exception", "reason": "failed to parse field"}}}
+        ]}"#;
+        assert_eq!(parse_bulk_response(response, 3)?, vec![
+            ItemStatus::Indexed,
+            ItemStatus::Retry("429 es_rejected_execution_exception: queue full".to_string()),
+            ItemStatus::Rejected("400 mapper_parsing_exception: failed to parse field".to_string()),
+        ]);
+
+        assert!(parse_bulk_response(response, 2).is_err());
+
+        Ok(())
+    }
+}
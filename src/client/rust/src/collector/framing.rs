@@ -0,0 +1,150 @@
+//! Framing of raw socket streams into records for the TCP and UDP sources
+//!
+//! Records are either newline-delimited text or prefixed with their length
+//! as a 4-byte big-endian integer. [`Framer`] buffers across reads, so a
+//! record may arrive split over any number of chunks.
+
+use anyhow::{anyhow, Result};
+
+use crate::collector::config::Framing;
+
+/// Size of the length prefix of a length-prefixed record
+const LENGTH_PREFIX_BYTES: usize = 4;
+
+/// Splits a byte stream into records
+///
+/// A newline-delimited record longer than `max_record_bytes` is emitted in
+/// pieces of that size; a length prefix over the limit fails the stream.
+pub struct Framer {
+    framing: Framing,
+    buffer: Vec<u8>,
+    max_record_bytes: usize,
+}
+
+impl Framer {
+    /// Create a framer accepting records up to `max_record_bytes`
+    pub fn new(framing: Framing, max_record_bytes: usize) -> Self {
+        Self { framing, buffer: Vec::new(), max_record_bytes }
+    }
+
+    /// Feed a chunk of the stream, returning every record it completes
+    ///
+    /// Fails on a length prefix over the limit, after which the stream
+    /// cannot be resynchronised.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut records = Vec::new();
+        let mut consumed = 0;
+
+        loop {
+            let pending = &self.buffer[consumed..];
+
+            match self.framing {
+                Framing::Newline => match pending.iter().position(|&b| b == b'\n') {
+                    Some(end) if end <= self.max_record_bytes => {
+                        let record = String::from_utf8_lossy(&pending[..end]).trim_end_matches('\r').to_string();
+                        if !record.is_empty() {
+                            records.push(record);
+                        }
+                        consumed += end + 1;
+                    },
+                    _ if pending.len() >= self.max_record_bytes => {
+                        records.push(String::from_utf8_lossy(&pending[..self.max_record_bytes]).into_owned());
+                        consumed += self.max_record_bytes;
+                    },
+                    _ => break,
+                },
+                Framing::LengthPrefixed => {
+                    if pending.len() < LENGTH_PREFIX_BYTES {
+                        break;
+                    }
+                    let prefix: [u8; LENGTH_PREFIX_BYTES] = pending[..LENGTH_PREFIX_BYTES].try_into().expect("prefix is 4 bytes");
+                    let length = u32::from_be_bytes(prefix) as usize;
+                    if length > self.max_record_bytes {
+                        return Err(anyhow!("Record of {} bytes exceeds the {} byte limit", length, self.max_record_bytes));
+                    }
+
+                    if pending.len() - LENGTH_PREFIX_BYTES < length {
+                        break;
+                    }
+                    let record = &pending[LENGTH_PREFIX_BYTES..LENGTH_PREFIX_BYTES + length];
+                    records.push(String::from_utf8_lossy(record).into_owned());
+                    consumed += LENGTH_PREFIX_BYTES + length;
+                },
+            }
+        }
+
+        self.buffer.drain(..consumed);
+        Ok(records)
+    }
+
+    /// Take a final newline-delimited record the sender closed without terminating
+    ///
+    /// A partial length-prefixed record is incomplete and discarded.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = match self.framing {
+            Framing::Newline => String::from_utf8_lossy(&self.buffer).trim_end_matches(['\r', '\n']).to_string(),
+            Framing::LengthPrefixed => String::new(),
+        };
+        self.buffer.clear();
+        Some(rest).filter(|rest| !rest.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefixed(record: &str) -> Vec<u8> {
+        let mut bytes = (record.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(record.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_newline_records_span_chunks() -> Result<()> {
+        let mut framer = Framer::new(Framing::Newline, 1024);
+
+        assert!(framer.push(b"first li")?.is_empty());
+        assert_eq!(framer.push(b"ne\r\n\nsecond\nthi")?, vec!["first line", "second"]);
+        assert_eq!(framer.push(b"rd\n")?, vec!["third"]);
+        assert_eq!(framer.push(b"unterminated")?, Vec::<String>::new());
+        assert_eq!(framer.finish(), Some("unterminated".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_newline_record_is_split() -> Result<()> {
+        let mut framer = Framer::new(Framing::Newline, 4);
+
+        assert_eq!(framer.push(b"abcdefghij\n")?, vec!["abcd", "efgh", "ij"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_length_prefixed_records_span_chunks() -> Result<()> {
+        let mut framer = Framer::new(Framing::LengthPrefixed, 1024);
+        let mut stream = prefixed("line with\nnewline");
+        stream.extend(prefixed("second"));
+
+        // Split inside the prefix and inside the body
+        assert!(framer.push(&stream[..2])?.is_empty());
+        assert!(framer.push(&stream[2..10])?.is_empty());
+        assert_eq!(framer.push(&stream[10..])?, vec!["line with\nnewline", "second"]);
+
+        assert!(framer.push(&prefixed("partial")[..6])?.is_empty());
+        assert_eq!(framer.finish(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized_length_prefix_fails() {
+        let mut framer = Framer::new(Framing::LengthPrefixed, 4);
+
+        assert!(framer.push(&prefixed("too long")).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Per-source health and readiness tracking for the log collector
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Health of a single source at a point in time
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SourceHealth {
+    /// Source name
+    pub source: String,
+    /// When the source last emitted a log, if ever
+    pub last_event: Option<DateTime<Utc>>,
+    /// Whether the source emitted within the staleness threshold
+    pub healthy: bool,
+}
+
+/// Tracks when each source last emitted a log
+///
+/// A source that has never emitted is measured from the moment it was
+/// registered, so a source that is silent from the start also goes stale.
+pub struct SourceHealthTracker {
+    stale_after: Duration,
+    sources: RwLock<HashMap<String, SourceState>>,
+}
+
+struct SourceState {
+    registered_at: DateTime<Utc>,
+    last_event: Option<DateTime<Utc>>,
+}
+
+impl SourceHealthTracker {
+    /// Create a tracker that reports sources silent for longer than `stale_after_seconds` as stale
+    pub fn new(stale_after_seconds: u64) -> Self {
+        Self {
+            stale_after: Duration::seconds(stale_after_seconds as i64),
+            sources: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Start tracking a source
+    pub fn register(&self, source: &str, now: DateTime<Utc>) {
+        let mut sources = self.sources.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        sources.entry(source.to_string()).or_insert(SourceState {
+            registered_at: now,
+            last_event: None,
+        });
+    }
+
+    /// Record that a registered source emitted a log
+    pub fn record(&self, source: &str, now: DateTime<Utc>) {
+        let mut sources = self.sources.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(state) = sources.get_mut(source) {
+            state.last_event = Some(now);
+        }
+    }
+
+    /// Health of every registered source, sorted by name
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<SourceHealth> {
+        let sources = self.sources.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut health: Vec<SourceHealth> = sources
+            .iter()
+            .map(|(source, state)| {
+                let reference = state.last_event.unwrap_or(state.registered_at);
+                SourceHealth {
+                    source: source.clone(),
+                    last_event: state.last_event,
+                    healthy: now - reference <= self.stale_after,
+                }
+            })
+            .collect();
+
+        health.sort_by(|a, b| a.source.cmp(&b.source));
+        health
+    }
+
+    /// Publish the current health of every source as metrics gauges
+    pub fn publish_metrics(&self, now: DateTime<Utc>) {
+        for health in self.snapshot(now) {
+            let last_event = health.last_event.map(|at| at.timestamp() as f64).unwrap_or(0.0);
+            let healthy = if health.healthy { 1.0 } else { 0.0 };
+
+            metrics::gauge!("collector_source_last_event_timestamp_seconds", last_event, "source" => health.source.clone());
+            metrics::gauge!("collector_source_healthy", healthy, "source" => health.source);
+        }
+    }
+}
+
+/// A component keeping the collector from being ready
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UnreadyComponent {
+    /// `sources`, `exporters` or `exporter:<name>`
+    pub component: String,
+    pub reason: String,
+}
+
+/// Tracks whether the collector is ready to take traffic
+///
+/// The collector is ready once its sources have started and its exporters
+/// are initialized, for as long as every exporter that flushes on a timer has
+/// flushed successfully within `max_flush_age`. A newly initialized exporter
+/// is measured from its initialization, so it has one threshold to flush.
+pub struct ReadinessTracker {
+    max_flush_age: Duration,
+    state: RwLock<ReadinessState>,
+}
+
+#[derive(Default)]
+struct ReadinessState {
+    sources_started: bool,
+    /// Exporters by name, with their initialization or last successful flush
+    /// when they flush on a timer
+    exporters: Option<HashMap<String, Option<DateTime<Utc>>>>,
+}
+
+impl ReadinessTracker {
+    /// Create a tracker requiring timed exporters to flush every `max_flush_age_seconds`
+    pub fn new(max_flush_age_seconds: u64) -> Self {
+        Self {
+            max_flush_age: Duration::seconds(max_flush_age_seconds as i64),
+            state: RwLock::new(ReadinessState::default()),
+        }
+    }
+
+    /// Record whether the sources are running
+    pub fn set_sources_started(&self, started: bool) {
+        self.write().sources_started = started;
+    }
+
+    /// Replace the tracked exporters with freshly initialized ones, by name
+    /// and whether each flushes on a timer
+    pub fn set_exporters<'a>(&self, exporters: impl IntoIterator<Item = (&'a str, bool)>, now: DateTime<Utc>) {
+        let exporters = exporters
+            .into_iter()
+            .map(|(name, timed)| (name.to_string(), timed.then_some(now)))
+            .collect();
+        self.write().exporters = Some(exporters);
+    }
+
+    /// Record a successful flush of an exporter
+    pub fn record_flush(&self, exporter: &str, now: DateTime<Utc>) {
+        let mut state = self.write();
+        if let Some(Some(last_flush)) = state.exporters.as_mut().and_then(|exporters| exporters.get_mut(exporter)) {
+            *last_flush = now;
+        }
+    }
+
+    /// Components keeping the collector from being ready, sorted; empty when ready
+    pub fn check(&self, now: DateTime<Utc>) -> Vec<UnreadyComponent> {
+        let state = self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut unready = Vec::new();
+
+        if !state.sources_started {
+            unready.push(UnreadyComponent { component: "sources".to_string(), reason: "not started".to_string() });
+        }
+
+        match &state.exporters {
+            None => {
+                unready.push(UnreadyComponent { component: "exporters".to_string(), reason: "not initialized".to_string() });
+            },
+            Some(exporters) => {
+                let mut stale: Vec<UnreadyComponent> = exporters
+                    .iter()
+                    .filter_map(|(name, last_flush)| {
+                        let age = now - (*last_flush)?;
+                        (age > self.max_flush_age).then(|| UnreadyComponent {
+                            component: format!("exporter:{}", name),
+                            reason: format!("no successful flush for {}s", age.num_seconds()),
+                        })
+                    })
+                    .collect();
+                stale.sort_by(|a, b| a.component.cmp(&b.component));
+                unready.extend(stale);
+            },
+        }
+
+        unready
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, ReadinessState> {
+        self.state.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_goes_stale_after_threshold() {
+        let tracker = SourceHealthTracker::new(30);
+        let start = Utc::now();
+
+        tracker.register("app", start);
+        tracker.record("app", start + Duration::seconds(5));
+
+        let health = tracker.snapshot(start + Duration::seconds(20));
+        assert_eq!(health.len(), 1);
+        assert!(health[0].healthy);
+        assert_eq!(health[0].last_event, Some(start + Duration::seconds(5)));
+
+        // The source stops emitting and flips to stale once the threshold passes
+        assert!(tracker.snapshot(start + Duration::seconds(35)).iter().all(|h| h.healthy));
+        assert!(!tracker.snapshot(start + Duration::seconds(36))[0].healthy);
+
+        // A new event makes it healthy again
+        tracker.record("app", start + Duration::seconds(40));
+        assert!(tracker.snapshot(start + Duration::seconds(41))[0].healthy);
+    }
+
+    #[test]
+    fn test_silent_source_is_measured_from_registration() {
+        let tracker = SourceHealthTracker::new(30);
+        let start = Utc::now();
+
+        tracker.register("journal", start);
+        // Unregistered sources are ignored
+        tracker.record("unknown", start);
+
+        let health = tracker.snapshot(start + Duration::seconds(31));
+        assert_eq!(health.len(), 1);
+        assert_eq!(health[0].last_event, None);
+        assert!(!health[0].healthy);
+    }
+
+    #[test]
+    fn test_readiness_needs_started_sources_and_recent_flushes() {
+        let readiness = ReadinessTracker::new(60);
+        let start = Utc::now();
+
+        let components = |now| -> Vec<String> {
+            readiness.check(now).into_iter().map(|unready| unready.component).collect()
+        };
+        assert_eq!(components(start), vec!["sources", "exporters"]);
+
+        readiness.set_sources_started(true);
+        readiness.set_exporters([("cloud", true), ("console", false)], start);
+        assert!(readiness.check(start + Duration::seconds(60)).is_empty());
+
+        // The timed exporter misses its flushes; the write-through one never goes stale
+        let unready = readiness.check(start + Duration::seconds(61));
+        assert_eq!(unready, vec![UnreadyComponent {
+            component: "exporter:cloud".to_string(),
+            reason: "no successful flush for 61s".to_string(),
+        }]);
+
+        readiness.record_flush("cloud", start + Duration::seconds(90));
+        assert!(readiness.check(start + Duration::seconds(120)).is_empty());
+
+        readiness.set_sources_started(false);
+        assert_eq!(components(start + Duration::seconds(120)), vec!["sources"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Journal entry mapping and reading for the journald source
+//!
+//! Turning journal fields into log entries is plain code that builds on every
+//! platform. Reading the journal goes through sd-journal, so [`JournalReader`]
+//! needs Linux and the `journald` cargo feature.
+
+// Without the reader, the mapping is only exercised by tests
+#![cfg_attr(not(all(target_os = "linux", feature = "journald")), allow(dead_code))]
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::collector::severity;
+use crate::collector::sources::LogEntry;
+
+/// Journal fields copied into entry attributes, with the attribute each becomes
+const ATTRIBUTE_FIELDS: [(&str, &str); 5] = [
+    ("_PID", "process.pid"),
+    ("_COMM", "process.command"),
+    ("_HOSTNAME", "host.name"),
+    ("_SYSTEMD_UNIT", "systemd.unit"),
+    ("SYSLOG_IDENTIFIER", "syslog.identifier"),
+];
+
+/// Map a syslog `PRIORITY` (0 = emerg ... 7 = debug) to a canonical level
+pub fn priority_level(priority: &str) -> Option<&'static str> {
+    priority.trim().parse().ok().and_then(severity::syslog_level)
+}
+
+/// Build a log entry from the fields of a journal record
+///
+/// The timestamp is the sender's `_SOURCE_REALTIME_TIMESTAMP` when present,
+/// and otherwise `received`, the time the journal recorded the entry.
+pub fn to_log_entry(source: &str, fields: &BTreeMap<String, String>, received: DateTime<Utc>) -> LogEntry {
+    let timestamp = fields
+        .get("_SOURCE_REALTIME_TIMESTAMP")
+        .and_then(|micros| micros.parse::<i64>().ok())
+        .and_then(|micros| Utc.timestamp_opt(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000).single())
+        .unwrap_or(received);
+
+    let attributes: HashMap<String, String> = ATTRIBUTE_FIELDS
+        .iter()
+        .filter_map(|(field, attribute)| {
+            fields.get(*field).map(|value| (attribute.to_string(), value.clone()))
+        })
+        .collect();
+
+    LogEntry {
+        timestamp,
+        source: source.to_string(),
+        level: fields
+            .get("PRIORITY")
+            .and_then(|priority| priority_level(priority))
+            .map(|level| level.to_string()),
+        severity_number: None,
+        message: fields.get("MESSAGE").cloned().unwrap_or_default(),
+        attributes,
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub use reader::JournalReader;
+
+#[cfg(all(target_os = "linux", feature = "journald"))]
+mod reader {
+    use anyhow::Result;
+    use chrono::{DateTime, Utc};
+    use std::time::Duration;
+    use systemd::journal::{self, Journal, JournalRecord};
+
+    /// Reads the journal in order, restricted to the configured units
+    pub struct JournalReader {
+        journal: Journal,
+    }
+
+    impl JournalReader {
+        /// Open the system journal, or the journal files under `directory`
+        ///
+        /// With `units`, only entries whose `_SYSTEMD_UNIT` is one of them are
+        /// read.
+        pub fn open(directory: Option<&str>, units: &[String]) -> Result<Self> {
+            let mut journal = match directory {
+                Some(directory) => journal::OpenDirectoryOptions::default().open_directory(directory)?,
+                None => journal::OpenOptions::default().system(true).local_only(false).open()?,
+            };
+
+            // Matches on the same field are ORed together by sd-journal
+            for unit in units {
+                journal.match_add("_SYSTEMD_UNIT", unit.as_str())?;
+            }
+
+            Ok(Self { journal })
+        }
+
+        /// Position the reader after `cursor`, or at the end of the journal without one
+        pub fn seek(&mut self, cursor: Option<&str>) -> Result<()> {
+            match cursor {
+                Some(cursor) => {
+                    self.journal.seek_cursor(cursor)?;
+                    // Step onto the saved entry so reading resumes after it,
+                    // unless it has since been vacuumed away
+                    if self.journal.next()? > 0 && self.journal.cursor()? != cursor {
+                        self.journal.previous()?;
+                    }
+                },
+                None => {
+                    self.journal.seek_tail()?;
+                    self.journal.previous()?;
+                },
+            }
+
+            Ok(())
+        }
+
+        /// The next entry and the time the journal received it, if any
+        pub fn next_entry(&mut self) -> Result<Option<(JournalRecord, DateTime<Utc>)>> {
+            match self.journal.next_entry()? {
+                Some(record) => {
+                    let received = DateTime::<Utc>::from(self.journal.timestamp()?);
+                    Ok(Some((record, received)))
+                },
+                None => Ok(None),
+            }
+        }
+
+        /// Cursor of the entry last read
+        pub fn cursor(&self) -> Result<String> {
+            Ok(self.journal.cursor()?)
+        }
+
+        /// Block until the journal changes or `timeout` passes
+        pub fn wait(&mut self, timeout: Duration) -> Result<()> {
+            self.journal.wait(Some(timeout))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_priority_maps_to_levels() {
+        let cases = [
+            ("0", Some("FATAL")),
+            ("2", Some("FATAL")),
+            ("3", Some("ERROR")),
+            ("4", Some("WARN")),
+            ("5", Some("INFO")),
+            ("6", Some("INFO")),
+            ("7", Some("DEBUG")),
+            ("8", None),
+            ("warning", None),
+        ];
+
+        for (priority, expected) in cases {
+            assert_eq!(priority_level(priority), expected, "priority {:?}", priority);
+        }
+    }
+
+    #[test]
+    fn test_journal_record_becomes_entry() {
+        let received = Utc.timestamp_opt(1_709_296_300, 0).unwrap();
+        let fields = record(&[
+            ("MESSAGE", "Accepted publickey for deploy"),
+            ("PRIORITY", "6"),
+            ("_PID", "812"),
+            ("_COMM", "sshd"),
+            ("_HOSTNAME", "web-1"),
+            ("_SYSTEMD_UNIT", "ssh.service"),
+            ("_SOURCE_REALTIME_TIMESTAMP", "1709296245123456"),
+            ("_BOOT_ID", "ignored"),
+        ]);
+
+        let log = to_log_entry("journal", &fields, received);
+
+        assert_eq!(log.source, "journal");
+        assert_eq!(log.message, "Accepted publickey for deploy");
+        assert_eq!(log.level, Some("INFO".to_string()));
+        assert_eq!(log.timestamp, Utc.timestamp_opt(1_709_296_245, 123_456_000).unwrap());
+        assert_eq!(log.attributes, HashMap::from([
+            ("process.pid".to_string(), "812".to_string()),
+            ("process.command".to_string(), "sshd".to_string()),
+            ("host.name".to_string(), "web-1".to_string()),
+            ("systemd.unit".to_string(), "ssh.service".to_string()),
+        ]));
+
+        // Without a source timestamp the journal's receive time is used
+        let log = to_log_entry("journal", &record(&[("MESSAGE", "bare")]), received);
+        assert_eq!(log.timestamp, received);
+        assert_eq!(log.level, None);
+    }
+}
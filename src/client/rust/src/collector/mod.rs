@@ -5,10 +5,29 @@
 //! configured destinations.
 
 pub mod config;
+pub mod clock;
+pub mod checkpoint;
 pub mod sources;
 pub mod processors;
 pub mod exporters;
 pub mod pipeline;
+pub mod health;
+pub mod otlp;
+pub mod docker;
+pub mod elasticsearch;
+pub mod framing;
+pub mod http_poll;
+pub mod commit;
+pub mod dead_letter;
+pub mod resilience;
+pub mod backoff;
+pub mod rate_limit;
+pub mod journald;
+pub mod severity;
+pub mod syslog;
+pub mod telemetry;
+pub mod tls;
+pub mod admin;
 
 use anyhow::Result;
 use config::CollectorConfig;
@@ -35,4 +54,9 @@ impl LogCollector {
     pub async fn stop(&mut self) -> Result<()> {
         self.pipeline.stop().await
     }
+
+    /// Swap in a new set of exporters while sources and processors keep running
+    pub async fn reload_exporters(&mut self, exporters: Vec<config::ExporterConfig>) -> Result<()> {
+        self.pipeline.reload_exporters(exporters).await
+    }
 }
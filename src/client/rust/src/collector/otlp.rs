@@ -0,0 +1,448 @@
+//! OTLP log payload decoding for the log collector
+//!
+//! The message types mirror the subset of `opentelemetry/proto/collector/logs/v1`
+//! and `opentelemetry/proto/logs/v1` the receiver needs. They decode both the
+//! protobuf encoding and the OTLP JSON encoding, which uses camelCase field
+//! names, strings for 64-bit integers and hex for trace and span IDs. The
+//! same protobuf types are the request and response of the gRPC
+//! `LogsService/Export` RPC.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use prost::Message;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+use crate::collector::clock::Clock;
+use crate::collector::config::MissingTimestamp;
+use crate::collector::sources::LogEntry;
+
+/// Content type of protobuf-encoded OTLP payloads
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Content type of JSON-encoded OTLP payloads
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Fully qualified name of the OTLP logs gRPC service
+pub const LOGS_SERVICE_NAME: &str = "opentelemetry.proto.collector.logs.v1.LogsService";
+
+/// Request path of the gRPC `Export` RPC
+pub const GRPC_EXPORT_PATH: &str = "/opentelemetry.proto.collector.logs.v1.LogsService/Export";
+
+/// Attribute set on entries whose record had no `time_unix_nano`, naming the time used instead
+pub const TIMESTAMP_SOURCE_ATTRIBUTE: &str = "otel.timestamp_source";
+
+/// Wire encoding of an OTLP payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Binary protobuf
+    Protobuf,
+    /// OTLP JSON
+    Json,
+}
+
+impl Encoding {
+    /// Pick the encoding from a request's `Content-Type`; protobuf is the OTLP default
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(content_type) if content_type.starts_with(JSON_CONTENT_TYPE) => Encoding::Json,
+            _ => Encoding::Protobuf,
+        }
+    }
+
+    /// Content type to answer with
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Protobuf => PROTOBUF_CONTENT_TYPE,
+            Encoding::Json => JSON_CONTENT_TYPE,
+        }
+    }
+}
+
+/// `ExportLogsServiceRequest` from the OTLP logs service
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportLogsServiceRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub resource_logs: Vec<ResourceLogs>,
+}
+
+/// `ExportLogsServiceResponse` from the OTLP logs service
+#[derive(Clone, PartialEq, Message, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLogsServiceResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_success: Option<ExportLogsPartialSuccess>,
+}
+
+/// `ExportLogsPartialSuccess` from the OTLP logs service
+#[derive(Clone, PartialEq, Message, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLogsPartialSuccess {
+    #[prost(int64, tag = "1")]
+    pub rejected_log_records: i64,
+    #[prost(string, tag = "2")]
+    pub error_message: String,
+}
+
+/// Logs produced by one resource
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResourceLogs {
+    #[prost(message, optional, tag = "1")]
+    pub resource: Option<Resource>,
+    #[prost(message, repeated, tag = "2")]
+    pub scope_logs: Vec<ScopeLogs>,
+    #[prost(string, tag = "3")]
+    pub schema_url: String,
+}
+
+/// Entity producing telemetry
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Resource {
+    #[prost(message, repeated, tag = "1")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(uint32, tag = "2")]
+    pub dropped_attributes_count: u32,
+}
+
+/// Logs produced by one instrumentation scope
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScopeLogs {
+    #[prost(message, optional, tag = "1")]
+    pub scope: Option<InstrumentationScope>,
+    #[prost(message, repeated, tag = "2")]
+    pub log_records: Vec<LogRecord>,
+    #[prost(string, tag = "3")]
+    pub schema_url: String,
+}
+
+/// Library or component that emitted the logs
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InstrumentationScope {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub version: String,
+    #[prost(message, repeated, tag = "3")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(uint32, tag = "4")]
+    pub dropped_attributes_count: u32,
+}
+
+/// A single log record
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogRecord {
+    #[prost(fixed64, tag = "1")]
+    #[serde(deserialize_with = "json_u64")]
+    pub time_unix_nano: u64,
+    #[prost(fixed64, tag = "11")]
+    #[serde(deserialize_with = "json_u64")]
+    pub observed_time_unix_nano: u64,
+    #[prost(int32, tag = "2")]
+    pub severity_number: i32,
+    #[prost(string, tag = "3")]
+    pub severity_text: String,
+    #[prost(message, optional, tag = "5")]
+    pub body: Option<AnyValue>,
+    #[prost(message, repeated, tag = "6")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(uint32, tag = "7")]
+    pub dropped_attributes_count: u32,
+    #[prost(fixed32, tag = "8")]
+    pub flags: u32,
+    #[prost(bytes = "vec", tag = "9")]
+    #[serde(deserialize_with = "json_hex")]
+    pub trace_id: Vec<u8>,
+    #[prost(bytes = "vec", tag = "10")]
+    #[serde(deserialize_with = "json_hex")]
+    pub span_id: Vec<u8>,
+}
+
+/// Attribute key and value
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeyValue {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(message, optional, tag = "2")]
+    pub value: Option<AnyValue>,
+}
+
+/// Value of an attribute or log body
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(default)]
+pub struct AnyValue {
+    #[prost(oneof = "Value", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[serde(flatten)]
+    pub value: Option<Value>,
+}
+
+/// The possible contents of an [`AnyValue`]
+///
+/// Variant names follow the OTLP protobuf, and give the JSON encoding its field names.
+#[derive(Clone, PartialEq, prost::Oneof, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::enum_variant_names)]
+pub enum Value {
+    #[prost(string, tag = "1")]
+    StringValue(String),
+    #[prost(bool, tag = "2")]
+    BoolValue(bool),
+    #[prost(int64, tag = "3")]
+    IntValue(#[serde(deserialize_with = "json_i64")] i64),
+    #[prost(double, tag = "4")]
+    DoubleValue(f64),
+    #[prost(message, tag = "5")]
+    ArrayValue(ArrayValue),
+    #[prost(message, tag = "6")]
+    KvlistValue(KeyValueList),
+    #[prost(bytes, tag = "7")]
+    BytesValue(#[serde(deserialize_with = "json_base64")] Vec<u8>),
+}
+
+/// List of values
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArrayValue {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<AnyValue>,
+}
+
+/// List of key-value pairs
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeyValueList {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<KeyValue>,
+}
+
+/// A JSON integer that may also arrive as a string, as OTLP JSON encodes 64-bit values
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonInteger<T> {
+    Number(T),
+    Text(String),
+}
+
+fn json_u64<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u64, D::Error> {
+    match JsonInteger::<u64>::deserialize(deserializer)? {
+        JsonInteger::Number(number) => Ok(number),
+        JsonInteger::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+fn json_i64<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<i64, D::Error> {
+    match JsonInteger::<i64>::deserialize(deserializer)? {
+        JsonInteger::Number(number) => Ok(number),
+        JsonInteger::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Trace and span IDs are hex strings in OTLP JSON
+fn json_hex<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<u8>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    if text.len() % 2 != 0 {
+        return Err(serde::de::Error::custom("hex ID has an odd number of digits"));
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Other bytes values use standard base64, as in the protobuf JSON mapping
+fn json_base64<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<u8>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Decode an export request in the given encoding
+pub fn decode_request(body: &[u8], encoding: Encoding) -> Result<ExportLogsServiceRequest> {
+    match encoding {
+        Encoding::Protobuf => ExportLogsServiceRequest::decode(body)
+            .map_err(|e| anyhow!("Invalid OTLP protobuf payload: {}", e)),
+        Encoding::Json => serde_json::from_slice(body)
+            .map_err(|e| anyhow!("Invalid OTLP JSON payload: {}", e)),
+    }
+}
+
+/// Encode the response to a fully accepted export request
+pub fn encode_success(encoding: Encoding) -> Vec<u8> {
+    let response = ExportLogsServiceResponse::default();
+
+    match encoding {
+        Encoding::Protobuf => response.encode_to_vec(),
+        Encoding::Json => serde_json::to_vec(&response).unwrap_or_else(|_| b"{}".to_vec()),
+    }
+}
+
+/// Turn every log record in a request into a log entry
+///
+/// Attributes are flattened with record attributes taking precedence over
+/// scope attributes, and scope attributes over resource attributes.
+pub fn to_log_entries(
+    request: ExportLogsServiceRequest,
+    source_name: &str,
+    missing_timestamp: MissingTimestamp,
+    clock: &dyn Clock,
+) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+
+    for resource_logs in request.resource_logs {
+        let mut resource_attributes = HashMap::new();
+        if let Some(resource) = &resource_logs.resource {
+            flatten_attributes(&resource.attributes, &mut resource_attributes);
+        }
+
+        for scope_logs in resource_logs.scope_logs {
+            let mut scope_attributes = resource_attributes.clone();
+            if let Some(scope) = &scope_logs.scope {
+                flatten_attributes(&scope.attributes, &mut scope_attributes);
+                if !scope.name.is_empty() {
+                    scope_attributes.insert("otel.scope.name".to_string(), scope.name.clone());
+                }
+                if !scope.version.is_empty() {
+                    scope_attributes.insert("otel.scope.version".to_string(), scope.version.clone());
+                }
+            }
+
+            for record in scope_logs.log_records {
+                entries.push(to_log_entry(record, &scope_attributes, source_name, missing_timestamp, clock));
+            }
+        }
+    }
+
+    entries
+}
+
+/// Build the log entry for a single record
+fn to_log_entry(
+    record: LogRecord,
+    inherited: &HashMap<String, String>,
+    source_name: &str,
+    missing_timestamp: MissingTimestamp,
+    clock: &dyn Clock,
+) -> LogEntry {
+    let mut attributes = inherited.clone();
+    flatten_attributes(&record.attributes, &mut attributes);
+
+    if !record.trace_id.is_empty() {
+        attributes.insert("trace_id".to_string(), hex(&record.trace_id));
+    }
+    if !record.span_id.is_empty() {
+        attributes.insert("span_id".to_string(), hex(&record.span_id));
+    }
+
+    // Zero means unset; substitute a time rather than report the epoch
+    let timestamp = if record.time_unix_nano > 0 {
+        timestamp_from_nanos(record.time_unix_nano)
+    } else if record.observed_time_unix_nano > 0 && missing_timestamp == MissingTimestamp::Observed {
+        attributes.insert(TIMESTAMP_SOURCE_ATTRIBUTE.to_string(), "observed".to_string());
+        timestamp_from_nanos(record.observed_time_unix_nano)
+    } else {
+        attributes.insert(TIMESTAMP_SOURCE_ATTRIBUTE.to_string(), "ingestion".to_string());
+        clock.now()
+    };
+
+    let severity_number = Some(record.severity_number).filter(|number| *number > 0);
+    let level = severity_number
+        .and_then(level_from_severity_number)
+        .map(str::to_string)
+        .or_else(|| Some(record.severity_text).filter(|text| !text.is_empty()));
+
+    LogEntry {
+        timestamp,
+        source: source_name.to_string(),
+        level,
+        severity_number,
+        message: record.body.as_ref().map(render_value).unwrap_or_default(),
+        attributes,
+    }
+}
+
+/// Level name for an OTLP severity number (1-24)
+pub fn level_from_severity_number(number: i32) -> Option<&'static str> {
+    let level = match number {
+        1..=4 => "TRACE",
+        5..=8 => "DEBUG",
+        9..=12 => "INFO",
+        13..=16 => "WARN",
+        17..=20 => "ERROR",
+        21..=24 => "FATAL",
+        _ => return None,
+    };
+
+    Some(level)
+}
+
+fn timestamp_from_nanos(nanos: u64) -> DateTime<Utc> {
+    Utc.timestamp_nanos(nanos.min(i64::MAX as u64) as i64)
+}
+
+/// Add attributes to `target`, flattening nested key-value lists into dotted keys
+fn flatten_attributes(attributes: &[KeyValue], target: &mut HashMap<String, String>) {
+    for attribute in attributes {
+        flatten_value(&attribute.key, attribute.value.as_ref(), target);
+    }
+}
+
+fn flatten_value(key: &str, value: Option<&AnyValue>, target: &mut HashMap<String, String>) {
+    match value {
+        Some(AnyValue { value: Some(Value::KvlistValue(list)) }) => {
+            for nested in &list.values {
+                flatten_value(&format!("{}.{}", key, nested.key), nested.value.as_ref(), target);
+            }
+        },
+        Some(value) => {
+            target.insert(key.to_string(), render_value(value));
+        },
+        None => {
+            target.insert(key.to_string(), String::new());
+        },
+    }
+}
+
+/// Render a value as text; strings stay bare, structured values become JSON
+fn render_value(value: &AnyValue) -> String {
+    match &value.value {
+        Some(Value::StringValue(text)) => text.clone(),
+        Some(_) => json_value(value).to_string(),
+        None => String::new(),
+    }
+}
+
+fn json_value(value: &AnyValue) -> serde_json::Value {
+    match &value.value {
+        Some(Value::StringValue(text)) => serde_json::Value::from(text.clone()),
+        Some(Value::BoolValue(flag)) => serde_json::Value::from(*flag),
+        Some(Value::IntValue(number)) => serde_json::Value::from(*number),
+        Some(Value::DoubleValue(number)) => serde_json::Value::from(*number),
+        Some(Value::ArrayValue(array)) => array.values.iter().map(json_value).collect(),
+        Some(Value::KvlistValue(list)) => list.values
+            .iter()
+            .map(|entry| {
+                let value = entry.value.as_ref().map(json_value).unwrap_or(serde_json::Value::Null);
+                (entry.key.clone(), value)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Some(Value::BytesValue(bytes)) => {
+            serde_json::Value::from(base64::engine::general_purpose::STANDARD.encode(bytes))
+        },
+        None => serde_json::Value::Null,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
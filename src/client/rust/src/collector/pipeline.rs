@@ -2,120 +2,792 @@
 
 use anyhow::{anyhow, Result};
 use futures::stream::{self, StreamExt};
-use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
-use tokio::task::JoinHandle;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time;
+use tracing::Instrument;
 
-use crate::collector::config::CollectorConfig;
-use crate::collector::exporters::{self, LogExporter};
+use crate::collector::admin;
+use crate::collector::checkpoint::{SharedCheckpointer, SqliteCheckpointer};
+use crate::collector::clock::{self, SharedClock};
+use crate::collector::commit::CommitCoordinator;
+use crate::collector::config::{
+    default_max_flush_age, CollectorConfig, ExporterConfig, InternalLogsConfig, OverflowPolicy, OverlappingFilesPolicy,
+    QueueAlertConfig, SourceConfig, DEFAULT_PIPELINE,
+};
+use crate::collector::exporters::{self, LimitedExporter, LogExporter, SharedExporter};
+use crate::collector::health::{ReadinessTracker, SourceHealth, SourceHealthTracker};
 use crate::collector::processors::{self, LogProcessor};
-use crate::collector::sources::{self, LogSource, LogEntry, LogSender};
+use crate::collector::rate_limit::TokenBucket;
+use crate::collector::resilience::RetryingExporter;
+use crate::collector::sources::{self, FileClaims, LogSource, LogEntry, LogSender};
+use crate::collector::telemetry;
+
+/// Number of log entries buffered between sources and processors
+const LOG_CHANNEL_CAPACITY: usize = 1000;
+
+/// How often processors holding entries back are asked to release them
+const PROCESSOR_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest the exporter flush timer sleeps, so reloaded exporters are scheduled promptly
+const EXPORTER_TIMER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often queue fill levels are sampled for saturation alerts
+const QUEUE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often per-source health is published as metrics
+const HEALTH_PUBLISH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Source name used for entries the collector generates about itself
+pub(crate) const INTERNAL_SOURCE: &str = "lognarrator-collector";
+
+/// Attribute marking entries the collector generated about itself
+pub(crate) const INTERNAL_MARKER: &str = "collector.internal";
 
 /// Pipeline for log processing
 pub struct Pipeline {
     config: CollectorConfig,
-    sources: Vec<Box<dyn LogSource>>,
-    processors: Vec<Box<dyn LogProcessor>>,
-    exporters: Vec<Box<dyn LogExporter>>,
+    /// Each source with the sender of the route it feeds, or of its fan-out
+    sources: Vec<(Box<dyn LogSource>, LogSender)>,
+    /// The default route first, then one per named pipeline
+    routes: Vec<Route>,
+    exporters: Arc<RwLock<Vec<SharedExporter>>>,
     task_handles: Vec<JoinHandle<()>>,
-    log_channel: (LogSender, mpsc::Receiver<LogEntry>),
+    /// Tasks copying entries of sources feeding several pipelines into each route
+    fan_out_handles: Vec<JoinHandle<()>>,
+    /// Tells the fan-out tasks their sources have stopped
+    sources_stopped: watch::Sender<bool>,
+    /// Overflow relay and processor tasks, which `stop` lets drain
+    drain_handles: Vec<JoinHandle<()>>,
+    /// Tells the relay and processor tasks to stop taking new entries
+    shutdown: watch::Sender<bool>,
+    internal_logs: Option<Arc<InternalLogRouter>>,
+    /// Marks stored logs exported once the required exporters confirmed them
+    commit: Option<Arc<CommitCoordinator>>,
+    health: Arc<SourceHealthTracker>,
+    readiness: Arc<ReadinessTracker>,
+    clock: SharedClock,
     running: bool,
 }
 
+/// What a running pipeline has loaded, for operators to inspect
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PipelineTopology {
+    /// Running sources, in start order
+    pub sources: Vec<SourceTopology>,
+    /// The default route first, then one per named pipeline
+    pub routes: Vec<RouteTopology>,
+    /// Names of the live exporters, reflecting any reload
+    pub exporters: Vec<String>,
+}
+
+/// Reads a pipeline's [`PipelineTopology`] and source health while it runs
+#[derive(Clone)]
+pub struct TopologyHandle {
+    sources: Vec<SourceTopology>,
+    routes: Vec<RouteLayout>,
+    exporters: Arc<RwLock<Vec<SharedExporter>>>,
+    health: Arc<SourceHealthTracker>,
+}
+
+/// A route as a [`TopologyHandle`] keeps it
+#[derive(Clone)]
+struct RouteLayout {
+    name: String,
+    processors: Vec<String>,
+    /// Exporters the route delivers to, unless it delivers to every exporter
+    exporters: Option<Arc<Vec<String>>>,
+}
+
+impl TopologyHandle {
+    /// The topology with the exporters live right now
+    pub async fn snapshot(&self) -> PipelineTopology {
+        let exporters: Vec<String> = self.exporters
+            .read()
+            .await
+            .iter()
+            .map(|exporter| exporter.name().to_string())
+            .collect();
+
+        let routes = self.routes
+            .iter()
+            .map(|route| RouteTopology {
+                name: route.name.clone(),
+                processors: route.processors.clone(),
+                exporters: match &route.exporters {
+                    Some(names) => names.to_vec(),
+                    None => exporters.clone(),
+                },
+            })
+            .collect();
+
+        PipelineTopology { sources: self.sources.clone(), routes, exporters }
+    }
+
+    /// Current health of every configured source
+    pub fn source_health(&self) -> Vec<SourceHealth> {
+        self.health.snapshot(Utc::now())
+    }
+}
+
+/// A source and the routes it feeds
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SourceTopology {
+    pub name: String,
+    pub pipelines: Vec<String>,
+}
+
+/// A route's processor chain and the exporters it delivers to
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct RouteTopology {
+    pub name: String,
+    /// Processor names, in the order entries pass through them
+    pub processors: Vec<String>,
+    /// Exporter names; a route without its own list delivers to every exporter
+    pub exporters: Vec<String>,
+}
+
+/// A processor chain and the exporters it delivers to, fed by its own channel
+struct Route {
+    name: String,
+    processors: Arc<Vec<Box<dyn LogProcessor>>>,
+    /// Names of the exporters delivered to; `None` delivers to all of them
+    exporters: Option<Arc<Vec<String>>>,
+    sender: LogSender,
+    receiver: Option<mpsc::Receiver<LogEntry>>,
+    /// Entries held by the overflow relay, when the policy drops instead of blocking
+    backlog: Arc<AtomicUsize>,
+    /// Entries the overflow policy discarded
+    dropped: Arc<AtomicU64>,
+}
+
+impl Route {
+    fn new(name: &str) -> Self {
+        let (sender, receiver) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+
+        Self {
+            name: name.to_string(),
+            processors: Arc::new(Vec::new()),
+            exporters: None,
+            sender,
+            receiver: Some(receiver),
+            backlog: Arc::new(AtomicUsize::new(0)),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Gauge of the route's queue, wherever `overflow` keeps it
+    fn gauge(&self, overflow: OverflowPolicy) -> QueueGauge {
+        QueueGauge {
+            sender: self.sender.clone(),
+            backlog: self.backlog.clone(),
+            overflow,
+        }
+    }
+}
+
+/// Reads how full a route's queue is, for the saturation monitor
+struct QueueGauge {
+    sender: LogSender,
+    backlog: Arc<AtomicUsize>,
+    overflow: OverflowPolicy,
+}
+
+impl QueueGauge {
+    /// Fraction of the queue in use
+    fn fill(&self) -> f64 {
+        let queued = match self.overflow {
+            OverflowPolicy::Block => LOG_CHANNEL_CAPACITY - self.sender.capacity(),
+            _ => self.backlog.load(Ordering::Relaxed),
+        };
+        queued as f64 / LOG_CHANNEL_CAPACITY as f64
+    }
+}
+
+/// Queues a route's entries under a dropping overflow policy
+///
+/// Sources feed the relay, which never waits on the processor task: it holds
+/// up to `capacity` entries itself and, once they are all taken, discards by
+/// `policy`. A warning marks the start and end of each episode of drops.
+///
+/// On shutdown the relay stops accepting entries, and finishes once it has
+/// handed over everything it had taken.
+struct OverflowRelay {
+    route: String,
+    policy: OverflowPolicy,
+    capacity: usize,
+    backlog: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl OverflowRelay {
+    /// Move entries from the sources to the processor task until the sources are gone or `shutdown`
+    async fn run(
+        self,
+        mut incoming: mpsc::Receiver<LogEntry>,
+        outgoing: mpsc::Sender<LogEntry>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        let mut queue = VecDeque::with_capacity(self.capacity);
+        let mut dropped_in_episode = 0u64;
+        let mut closing = false;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.changed(), if !closing => {
+                    // Entries already sent are still received, then `recv` ends
+                    closing = true;
+                    incoming.close();
+                },
+                received = incoming.recv() => {
+                    let log = match received {
+                        Some(log) => log,
+                        None => break,
+                    };
+                    if queue.len() < self.capacity {
+                        queue.push_back(log);
+                    } else {
+                        if self.policy == OverflowPolicy::DropOldest {
+                            queue.pop_front();
+                            queue.push_back(log);
+                        }
+                        if dropped_in_episode == 0 {
+                            tracing::warn!("Pipeline {} queue is full, dropping logs ({:?}) until it drains", self.route, self.policy);
+                        }
+                        dropped_in_episode += 1;
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        metrics::increment_counter!("collector_pipeline_dropped_logs_total", "pipeline" => self.route.clone());
+                    }
+                },
+                permit = outgoing.reserve(), if !queue.is_empty() => {
+                    let permit = match permit {
+                        Ok(permit) => permit,
+                        Err(_) => return,
+                    };
+                    permit.send(queue.pop_front().expect("relay queue is not empty"));
+                    if queue.is_empty() && dropped_in_episode > 0 {
+                        tracing::warn!("Pipeline {} queue drained after dropping {} logs", self.route, dropped_in_episode);
+                        dropped_in_episode = 0;
+                    }
+                },
+            }
+
+            self.backlog.store(queue.len(), Ordering::Relaxed);
+        }
+
+        // The sources are gone; hand over whatever is still queued
+        for log in queue {
+            if outgoing.send(log).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
 impl Pipeline {
     /// Create a new pipeline from configuration
     pub fn new(config: CollectorConfig) -> Result<Self> {
-        let (sender, receiver) = mpsc::channel(1000); // Buffer up to 1000 log entries
+        let mut named: Vec<&String> = config.pipelines
+            .keys()
+            .filter(|name| name.as_str() != DEFAULT_PIPELINE)
+            .collect();
+        named.sort();
+
+        let mut routes = vec![Route::new(DEFAULT_PIPELINE)];
+        routes.extend(named.into_iter().map(|name| Route::new(name)));
+
+        // The collector's own logs go through the default pipeline
+        let internal_logs = config.internal_logs
+            .as_ref()
+            .map(|internal_config| Arc::new(InternalLogRouter::new(internal_config, routes[0].sender.clone())));
+
+        let health = Arc::new(SourceHealthTracker::new(config.source_staleness_seconds));
+        let commit = config.commit.as_ref().map(|commit_config| Arc::new(CommitCoordinator::new(commit_config)));
+        let max_flush_age = config.probes
+            .as_ref()
+            .map_or_else(default_max_flush_age, |probes| probes.max_flush_age_seconds);
+        let readiness = Arc::new(ReadinessTracker::new(max_flush_age));
 
         Ok(Self {
             config,
             sources: Vec::new(),
-            processors: Vec::new(),
-            exporters: Vec::new(),
+            routes,
+            exporters: Arc::new(RwLock::new(Vec::new())),
             task_handles: Vec::new(),
-            log_channel: (sender, receiver),
+            fan_out_handles: Vec::new(),
+            sources_stopped: watch::channel(false).0,
+            drain_handles: Vec::new(),
+            shutdown: watch::channel(false).0,
+            internal_logs,
+            commit,
+            health,
+            readiness,
+            clock: clock::system_clock(),
             running: false,
         })
     }
 
     /// Initialize the pipeline components
     async fn initialize(&mut self) -> Result<()> {
-        // Initialize sources
-        for source_config in &self.config.sources {
-            let source = sources::create_source(source_config).await?;
-            self.sources.push(source);
-        }
+        // Initialize sources, each feeding the routes of its pipelines
+        let claims = (self.config.overlapping_files == OverlappingFilesPolicy::Dedupe).then(FileClaims::default);
+        let supported: Vec<SourceConfig> = sources::supported_sources(&self.config.sources, self.config.unsupported_sources)?
+            .into_iter()
+            .cloned()
+            .collect();
+        // Sources naming the same database share one connection to it
+        let mut checkpointers: HashMap<String, SharedCheckpointer> = HashMap::new();
+        for source_config in &supported {
+            let checkpoints = match source_config.checkpoint_path() {
+                Some(path) => match checkpointers.get(path) {
+                    Some(checkpoints) => Some(checkpoints.clone()),
+                    None => {
+                        let checkpoints: SharedCheckpointer = Arc::new(SqliteCheckpointer::open(path).map_err(|e| {
+                            anyhow!("Source {}: {}", source_config.name(), e)
+                        })?);
+                        checkpointers.insert(path.to_string(), checkpoints.clone());
+                        Some(checkpoints)
+                    },
+                },
+                None => None,
+            };
 
-        // Initialize processors
-        for processor_config in &self.config.processors {
-            let processor = processors::create_processor(processor_config)?;
-            self.processors.push(processor);
+            let sender = self.source_sender(source_config)?;
+            let source = sources::create_source(
+                source_config,
+                self.clock.clone(),
+                claims.as_ref(),
+                checkpoints,
+                self.config.max_source_connections,
+            )
+            .await?;
+            self.health.register(source.name(), Utc::now());
+            self.sources.push((source, sender));
         }
 
+        self.initialize_routes()?;
+
         // Initialize exporters
+        let mut exporters_guard = self.exporters.write().await;
         for exporter_config in &self.config.exporters {
-            let exporter = exporters::create_exporter(exporter_config).await?;
-            self.exporters.push(exporter);
+            exporters_guard.push(self.build_exporter(exporter_config).await?.into());
+        }
+
+        for route in &self.routes {
+            for name in route.exporters.iter().flat_map(|names| names.iter()) {
+                if !exporters_guard.iter().any(|exporter| exporter.name() == name) {
+                    return Err(anyhow!("Pipeline {} exports to unknown exporter {}", route.name, name));
+                }
+            }
+        }
+
+        self.readiness.set_exporters(timed_exporters(&exporters_guard), Utc::now());
+
+        Ok(())
+    }
+
+    /// Create each route's processors and select its exporters
+    ///
+    /// A named pipeline gets its own instances of the processors it lists. The
+    /// default route, unless declared in `pipelines`, runs every processor and
+    /// exports to every exporter.
+    fn initialize_routes(&mut self) -> Result<()> {
+        for route in &mut self.routes {
+            route.processors = Arc::new(processor_chain(&self.config, &route.name)?);
+            route.exporters = self.config.pipelines
+                .get(&route.name)
+                .map(|pipeline| Arc::new(pipeline.exporters.clone()));
         }
 
         Ok(())
     }
 
-    /// Start the log processor task
+    /// Sender of the route a source bound to `pipeline` feeds
+    fn route_sender(&self, pipeline: Option<&str>) -> Result<LogSender> {
+        let name = pipeline.unwrap_or(DEFAULT_PIPELINE);
+
+        self.routes
+            .iter()
+            .find(|route| route.name == name)
+            .map(|route| route.sender.clone())
+            .ok_or_else(|| anyhow!("Pipeline {} is not defined", name))
+    }
+
+    /// Sender for a source, fanning its entries out when it feeds several pipelines
+    fn source_sender(&mut self, source: &SourceConfig) -> Result<LogSender> {
+        let pipelines = self.config.source_pipelines(source);
+        if let [pipeline] = pipelines[..] {
+            return self.route_sender(Some(pipeline));
+        }
+
+        let mut targets = Vec::with_capacity(pipelines.len());
+        for pipeline in pipelines {
+            let route = self.routes
+                .iter()
+                .find(|route| route.name == pipeline)
+                .ok_or_else(|| anyhow!("Pipeline {} is not defined", pipeline))?;
+            targets.push(FanOutTarget {
+                route: route.name.clone(),
+                sender: route.sender.clone(),
+                dropped: route.dropped.clone(),
+                dropped_in_episode: 0,
+            });
+        }
+
+        let (sender, receiver) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+        self.fan_out_handles.push(tokio::spawn(fan_out(receiver, targets, self.sources_stopped.subscribe())));
+        Ok(sender)
+    }
+
+    /// Start one processor task per route
     async fn start_processor_task(&mut self) -> Result<()> {
-        let processors = Arc::new(RwLock::new(self.processors.clone()));
-        let exporters = Arc::new(RwLock::new(self.exporters.clone()));
-        let mut receiver = self.log_channel.1.clone();
+        for route in &mut self.routes {
+            let processors = route.processors.clone();
+            let selected = route.exporters.clone();
+            let exporters = self.exporters.clone();
+            let internal_logs = self.internal_logs.clone();
+            let commit = self.commit.clone();
+            let health = self.health.clone();
+            let mut receiver = route.receiver.take()
+                .ok_or_else(|| anyhow!("Log channel of pipeline {} already consumed", route.name))?;
+            let mut shutdown = Some(self.shutdown.subscribe());
 
-        // Start the processor task
-        let handle = tokio::spawn(async move {
-            while let Some(log) = receiver.recv().await {
-                // Process the log through the processor chain
-                let processors_guard = processors.read().await;
-                let mut current_log = Some(log);
-
-                for processor in processors_guard.iter() {
-                    if let Some(log) = current_log {
-                        match processor.process(log).await {
-                            Ok(processed_log) => current_log = processed_log,
-                            Err(e) => {
-                                tracing::error!("Error processing log: {}", e);
-                                current_log = None;
-                                break;
+            // A dropping policy puts a relay between the sources and the processors
+            if self.config.overflow != OverflowPolicy::Block {
+                let relay = OverflowRelay {
+                    route: route.name.clone(),
+                    policy: self.config.overflow,
+                    capacity: LOG_CHANNEL_CAPACITY,
+                    backlog: route.backlog.clone(),
+                    dropped: route.dropped.clone(),
+                };
+                let (relayed, relay_receiver) = mpsc::channel(1);
+                self.drain_handles.push(tokio::spawn(relay.run(receiver, relayed, self.shutdown.subscribe())));
+                receiver = relay_receiver;
+                // The relay ends the processor's input once it has drained
+                shutdown = None;
+            }
+
+            // Start the processor task
+            let handle = tokio::spawn(async move {
+                let mut flush_timer = time::interval(PROCESSOR_FLUSH_INTERVAL);
+                let internal = internal_logs.as_deref();
+                let selected = selected.as_deref().map(|names| names.as_slice());
+                let commit = commit.as_deref();
+
+                loop {
+                    tokio::select! {
+                        _ = shutdown_requested(&mut shutdown) => {
+                            // Entries already queued are still received, then `recv` ends
+                            shutdown = None;
+                            receiver.close();
+                        },
+                        received = receiver.recv() => {
+                            match received {
+                                Some(log) => {
+                                    if !is_internal(&log) {
+                                        health.record(&log.source, Utc::now());
+                                        metrics::increment_counter!("collector_logs_received_total", "source" => log.source.clone());
+                                    }
+
+                                    // Process the log through the processor chain
+                                    deliver(&processors, 0, &exporters, selected, commit, vec![log], internal).await;
+                                },
+                                None => break,
                             }
-                        }
-                    } else {
-                        break;
+                        },
+                        _ = flush_timer.tick() => {
+                            flush_processors(&processors, &exporters, selected, commit, false, internal).await;
+                        },
                     }
                 }
 
-                // If the log was processed successfully, export it
-                if let Some(log) = current_log {
-                    let exporters_guard = exporters.read().await;
+                // The input has ended, release whatever the processors still hold
+                flush_processors(&processors, &exporters, selected, commit, true, internal).await;
+            });
 
-                    // Export to all exporters in parallel
-                    let export_futures = exporters_guard.iter().map(|exporter| {
-                        let log_clone = log.clone();
-                        async move {
-                            if let Err(e) = exporter.export(log_clone).await {
-                                tracing::error!("Error exporting log to {}: {}", exporter.name(), e);
-                            }
+            self.drain_handles.push(handle);
+        }
+
+        Ok(())
+    }
+
+    /// Start the task that flushes exporters on their own, jittered timers
+    ///
+    /// Each exporter's next flush is scheduled from a fresh
+    /// [`LogExporter::next_flush_delay`], so exporters sharing an interval
+    /// drift apart instead of uploading together. Exporters are tracked by
+    /// name, which carries their schedule across a reload. Each flush runs in
+    /// its own task on a handle cloned out of the exporter lock, so an exporter
+    /// stuck retrying delays neither the others nor a reload.
+    fn start_exporter_timer(&mut self) {
+        let exporters = self.exporters.clone();
+        let readiness = self.readiness.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut due: HashMap<String, time::Instant> = HashMap::new();
+            let mut flushing = JoinSet::new();
+            // Exporters with a flush running, by the task flushing them
+            let mut in_flight: HashMap<tokio::task::Id, String> = HashMap::new();
+
+            loop {
+                let now = time::Instant::now();
+                let mut wake = now + EXPORTER_TIMER_POLL_INTERVAL;
+
+                let live: Vec<SharedExporter> = exporters.read().await.clone();
+                due.retain(|name, _| live.iter().any(|exporter| exporter.name() == name));
+
+                for exporter in live {
+                    let delay = match exporter.next_flush_delay() {
+                        Some(delay) => delay,
+                        None => continue,
+                    };
+                    let name = exporter.name().to_string();
+                    if in_flight.values().any(|flushing| *flushing == name) {
+                        continue;
+                    }
+
+                    let deadline = *due.entry(name.clone()).or_insert(now + delay);
+                    if deadline > now {
+                        wake = wake.min(deadline);
+                        continue;
+                    }
+
+                    let readiness = readiness.clone();
+                    let task = flushing.spawn(async move {
+                        match exporter.flush().await {
+                            Ok(()) => readiness.record_flush(exporter.name(), Utc::now()),
+                            Err(e) => tracing::error!("Error flushing exporter {} on its timer: {}", exporter.name(), e),
                         }
+                        delay
                     });
+                    in_flight.insert(task.id(), name);
+                }
+
+                tokio::select! {
+                    _ = time::sleep_until(wake) => {},
+                    Some(finished) = flushing.join_next_with_id(), if !flushing.is_empty() => match finished {
+                        Ok((id, delay)) => {
+                            if let Some(name) = in_flight.remove(&id) {
+                                due.insert(name, time::Instant::now() + delay);
+                            }
+                        },
+                        Err(e) => {
+                            // The exporter is flushed again on its next poll
+                            if let Some(name) = in_flight.remove(&e.id()) {
+                                tracing::error!("Flushing exporter {} on its timer failed: {}", name, e);
+                                due.remove(&name);
+                            }
+                        },
+                    },
+                }
+            }
+        });
+
+        self.task_handles.push(handle);
+    }
+
+    /// Start one task per local cache with a `drain`, forwarding its completed files
+    ///
+    /// Both exporters are looked up by name on every pass, so a reload is
+    /// picked up; a pass is skipped while either is missing.
+    fn start_cache_drains(&mut self) {
+        for exporter_config in &self.config.exporters {
+            let (cache, directory, drain) = match exporter_config {
+                ExporterConfig::LocalCache { name, directory, drain: Some(drain), .. } => {
+                    (name.clone(), PathBuf::from(directory), drain.clone())
+                },
+                _ => continue,
+            };
+            let exporters = self.exporters.clone();
+
+            let handle = tokio::spawn(async move {
+                let mut pass_timer = time::interval(Duration::from_secs(drain.interval_seconds));
+
+                loop {
+                    pass_timer.tick().await;
+
+                    let live = exporters.read().await;
+                    let find = |name: &str| live.iter().find(|exporter| exporter.name() == name);
+                    let (Some(source), Some(target)) = (find(&cache), find(&drain.exporter)) else {
+                        continue;
+                    };
+
+                    match exporters::drain_cache(&directory, source.as_ref(), target.as_ref()).await {
+                        Ok(0) => {},
+                        Ok(drained) => tracing::info!("Forwarded {} cache files from {} to {}", drained, cache, drain.exporter),
+                        Err(e) => tracing::warn!("Draining cache {} to {} stopped, retrying next pass: {}", cache, drain.exporter, e),
+                    }
+                }
+            });
+
+            self.task_handles.push(handle);
+        }
+    }
+
+    /// Start the task that alerts when a route's log channel stays saturated
+    fn start_queue_monitor(&mut self, alert_config: QueueAlertConfig) {
+        let gauges: Vec<QueueGauge> = self.routes.iter().map(|route| route.gauge(self.config.overflow)).collect();
+        let exporters = self.exporters.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut monitor = SaturationMonitor::new(Duration::from_secs(alert_config.sustain_seconds));
+            let mut check_timer = time::interval(QUEUE_CHECK_INTERVAL);
 
-                    stream::iter(export_futures)
-                        .buffer_unordered(10) // Process up to 10 exports in parallel
-                        .collect::<Vec<_>>()
-                        .await;
+            loop {
+                check_timer.tick().await;
+
+                let fill = gauges.iter().map(QueueGauge::fill).fold(0.0, f64::max);
+                if !monitor.observe(fill >= alert_config.saturation_ratio, Instant::now()) {
+                    continue;
                 }
+
+                let message = format!(
+                    "Pipeline queue has been saturated for over {}s ({:.0}% full); exporters are not keeping up",
+                    alert_config.sustain_seconds,
+                    fill * 100.0,
+                );
+                tracing::error!("{}", message);
+                metrics::increment_counter!("collector_queue_saturation_alerts_total", "queue" => "pipeline");
+
+                // The queue itself is full, so hand the alert straight to the exporters
+                export_logs(&exporters, None, None, vec![internal_entry("ERROR", message)], None).await;
+            }
+        });
+
+        self.task_handles.push(handle);
+    }
+
+    /// Start the task that periodically publishes source health metrics
+    fn start_health_reporter(&mut self) {
+        let health = self.health.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut publish_timer = time::interval(HEALTH_PUBLISH_INTERVAL);
+
+            loop {
+                publish_timer.tick().await;
+                health.publish_metrics(Utc::now());
             }
         });
 
         self.task_handles.push(handle);
+    }
+
+    /// Replace the exporters without touching sources or processors
+    ///
+    /// The new exporters are all created before anything changes, so a bad
+    /// configuration leaves the current set in place. The swap holds the
+    /// exporter lock: exports already running finish first, the old exporters
+    /// are flushed, and entries arriving meanwhile wait in the log channel for
+    /// the new set.
+    pub async fn reload_exporters(&mut self, exporter_configs: Vec<ExporterConfig>) -> Result<()> {
+        let mut replacements: Vec<Box<dyn LogExporter>> = Vec::new();
+        for exporter_config in &exporter_configs {
+            replacements.push(self.build_exporter(exporter_config).await?);
+        }
+
+        if replacements.is_empty() {
+            return Err(anyhow!("No log exporters configured"));
+        }
+
+        for route in &self.routes {
+            for name in route.exporters.iter().flat_map(|names| names.iter()) {
+                if !replacements.iter().any(|exporter| exporter.name() == name) {
+                    tracing::warn!("Pipeline {} exports to {}, which the reloaded exporters do not include", route.name, name);
+                }
+            }
+        }
+
+        self.swap_exporters(replacements).await;
+        self.config.exporters = exporter_configs;
+        tracing::info!("Reloaded {} exporters", self.config.exporters.len());
 
         Ok(())
     }
 
+    /// Create an exporter wrapped in its resilience policy, if any, and the concurrency limit
+    async fn build_exporter(&self, exporter_config: &ExporterConfig) -> Result<Box<dyn LogExporter>> {
+        let mut exporter = exporters::create_exporter(exporter_config).await?;
+
+        if let Some(policy_name) = exporter_config.resilience() {
+            let policy = self.config.resilience.get(policy_name).ok_or_else(|| {
+                anyhow!("Exporter {} uses unknown resilience policy {}", exporter_config.name(), policy_name)
+            })?;
+            exporter = Box::new(RetryingExporter::new(exporter, policy, self.clock.clone()));
+        }
+
+        Ok(Box::new(LimitedExporter::new(exporter, self.config.max_concurrent_exports)))
+    }
+
+    /// Flush the current exporters and replace them with `replacements`
+    async fn swap_exporters(&self, replacements: Vec<Box<dyn LogExporter>>) {
+        let mut exporters_guard = self.exporters.write().await;
+
+        for exporter in exporters_guard.iter() {
+            if let Err(e) = exporter.flush().await {
+                tracing::error!("Error flushing exporter {} before reload: {}", exporter.name(), e);
+            }
+        }
+
+        let replacements: Vec<SharedExporter> = replacements.into_iter().map(SharedExporter::from).collect();
+        self.readiness.set_exporters(timed_exporters(&replacements), Utc::now());
+        *exporters_guard = replacements;
+    }
+
+    /// Sources, routes and exporters as currently loaded
+    pub async fn topology(&self) -> PipelineTopology {
+        self.topology_handle().snapshot().await
+    }
+
+    /// A handle reading the topology from other tasks, such as the admin socket
+    ///
+    /// Sources and routes are fixed once the pipeline is initialized; the
+    /// exporters are read live, so the handle reflects any reload.
+    pub fn topology_handle(&self) -> TopologyHandle {
+        let sources = self.sources
+            .iter()
+            .map(|(source, _)| {
+                let pipelines = self.config.sources
+                    .iter()
+                    .find(|config| config.name() == source.name())
+                    .map_or_else(|| vec![DEFAULT_PIPELINE], |config| self.config.source_pipelines(config));
+                SourceTopology {
+                    name: source.name().to_string(),
+                    pipelines: pipelines.into_iter().map(str::to_string).collect(),
+                }
+            })
+            .collect();
+
+        let routes = self.routes
+            .iter()
+            .map(|route| RouteLayout {
+                name: route.name.clone(),
+                processors: route.processors.iter().map(|processor| processor.name().to_string()).collect(),
+                exporters: route.exporters.clone(),
+            })
+            .collect();
+
+        TopologyHandle {
+            sources,
+            routes,
+            exporters: self.exporters.clone(),
+            health: self.health.clone(),
+        }
+    }
+
+    /// Number of logs the overflow policy discarded, across all routes
+    pub fn dropped_count(&self) -> u64 {
+        self.routes.iter().map(|route| route.dropped.load(Ordering::Relaxed)).sum()
+    }
+
     /// Start the log collection pipeline
     pub async fn start(&mut self) -> Result<()> {
         if self.running {
@@ -129,18 +801,41 @@ impl Pipeline {
             return Err(anyhow!("No log sources configured"));
         }
 
-        if self.exporters.is_empty() {
+        if self.exporters.read().await.is_empty() {
             return Err(anyhow!("No log exporters configured"));
         }
 
         // Start the processor task
         self.start_processor_task().await?;
+        self.start_exporter_timer();
+        self.start_cache_drains();
+
+        if let Some(alert_config) = self.config.queue_alert.clone() {
+            self.start_queue_monitor(alert_config);
+        }
+
+        self.start_health_reporter();
+
+        if let Some(telemetry_config) = &self.config.telemetry {
+            let (_, handle) = telemetry::start_metrics_server(telemetry_config).await?;
+            self.task_handles.push(handle);
+        }
+
+        if let Some(probes_config) = &self.config.probes {
+            let (_, handle) = telemetry::start_probe_server(probes_config, self.readiness.clone()).await?;
+            self.task_handles.push(handle);
+        }
+
+        if let Some(admin_config) = &self.config.admin {
+            let handle = admin::start_admin_server(admin_config, self.topology_handle()).await?;
+            self.task_handles.push(handle);
+        }
 
         // Start all sources
-        for source in &mut self.sources {
-            let sender = self.log_channel.0.clone();
-            source.start(sender).await?;
+        for (source, sender) in &mut self.sources {
+            source.start(sender.clone()).await?;
         }
+        self.readiness.set_sources_started(true);
 
         self.running = true;
         tracing::info!("Log collection pipeline started");
@@ -149,27 +844,51 @@ impl Pipeline {
     }
 
     /// Stop the log collection pipeline
+    ///
+    /// Sources are stopped first. Entries already queued then drain through
+    /// the processors, which release whatever they hold, and on to the
+    /// exporters, for up to `shutdown_timeout_seconds`. The exporters are
+    /// flushed last, and anything still draining after the timeout is lost.
     pub async fn stop(&mut self) -> Result<()> {
         if !self.running {
             return Err(anyhow!("Pipeline not running"));
         }
 
         // Stop all sources
-        for source in &mut self.sources {
+        self.readiness.set_sources_started(false);
+        for (source, _) in &mut self.sources {
             if let Err(e) = source.stop().await {
                 tracing::error!("Error stopping source {}: {}", source.name(), e);
             }
         }
 
+        // Let queued entries drain through any fan-outs, then the processors, to the exporters
+        let timeout = Duration::from_secs(self.config.shutdown_timeout_seconds);
+        let drained = time::timeout(timeout, async {
+            let _ = self.sources_stopped.send(true);
+            for handle in self.fan_out_handles.iter_mut() {
+                let _ = handle.await;
+            }
+            let _ = self.shutdown.send(true);
+            for handle in self.drain_handles.iter_mut() {
+                let _ = handle.await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!("Pipeline did not drain within {}s, dropping the logs still queued", timeout.as_secs());
+        }
+
         // Flush all exporters
-        for exporter in &self.exporters {
+        for exporter in self.exporters.read().await.iter() {
             if let Err(e) = exporter.flush().await {
                 tracing::error!("Error flushing exporter {}: {}", exporter.name(), e);
             }
         }
 
         // Cancel all tasks
-        for handle in self.task_handles.drain(..) {
+        let handles = self.task_handles.drain(..).chain(self.fan_out_handles.drain(..)).chain(self.drain_handles.drain(..));
+        for handle in handles {
             handle.abort();
         }
 
@@ -179,3 +898,1222 @@ impl Pipeline {
         Ok(())
     }
 }
+
+/// A route fed by [`fan_out`], and the copies it has had to drop
+struct FanOutTarget {
+    route: String,
+    sender: LogSender,
+    dropped: Arc<AtomicU64>,
+    dropped_in_episode: u64,
+}
+
+impl FanOutTarget {
+    /// Hand the route its copy of `log`, dropping it if the route's queue is full
+    fn offer(&mut self, log: LogEntry) {
+        match self.sender.try_send(log) {
+            Ok(()) => {
+                if self.dropped_in_episode > 0 {
+                    tracing::warn!("Pipeline {} accepts logs again after dropping {} copies", self.route, self.dropped_in_episode);
+                    self.dropped_in_episode = 0;
+                }
+            },
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                if self.dropped_in_episode == 0 {
+                    tracing::warn!("Pipeline {} queue is full, dropping its copies of shared logs until it drains", self.route);
+                }
+                self.dropped_in_episode += 1;
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                metrics::increment_counter!("collector_pipeline_dropped_logs_total", "pipeline" => self.route.clone());
+            },
+            // A closed route is shutting down; the others still get the entry
+            Err(mpsc::error::TrySendError::Closed(_)) => {},
+        }
+    }
+}
+
+/// Copy each entry of a source feeding several pipelines into every route's channel
+///
+/// A route whose queue is full loses its copy rather than holding up the
+/// others, so one stalled pipeline cannot stop the rest. Once `stopped`
+/// fires, entries already sent are still copied, then the task ends.
+async fn fan_out(mut incoming: mpsc::Receiver<LogEntry>, mut routes: Vec<FanOutTarget>, mut stopped: watch::Receiver<bool>) {
+    let mut closing = false;
+
+    loop {
+        tokio::select! {
+            _ = stopped.changed(), if !closing => {
+                closing = true;
+                incoming.close();
+            },
+            received = incoming.recv() => {
+                let log = match received {
+                    Some(log) => log,
+                    None => break,
+                };
+                for route in &mut routes {
+                    route.offer(log.clone());
+                }
+            },
+        }
+    }
+}
+
+/// Wait until `shutdown` is requested; never completes without a receiver
+async fn shutdown_requested(shutdown: &mut Option<watch::Receiver<bool>>) {
+    match shutdown {
+        // An error means the pipeline is gone, which is a shutdown too
+        Some(shutdown) => {
+            let _ = shutdown.changed().await;
+        },
+        None => std::future::pending().await,
+    }
+}
+
+/// Exporters by name, and whether each flushes on a timer
+fn timed_exporters(exporters: &[SharedExporter]) -> impl Iterator<Item = (&str, bool)> {
+    exporters.iter().map(|exporter| (exporter.name(), exporter.next_flush_delay().is_some()))
+}
+
+/// Detects a queue that stays saturated for longer than a threshold
+///
+/// Brief spikes are ignored: the alert fires once per episode, only after
+/// every sample for `sustain` has been saturated, and re-arms when the queue
+/// drains.
+pub struct SaturationMonitor {
+    sustain: Duration,
+    saturated_since: Option<Instant>,
+    alerted: bool,
+}
+
+impl SaturationMonitor {
+    /// Create a monitor that alerts after `sustain` of continuous saturation
+    pub fn new(sustain: Duration) -> Self {
+        Self {
+            sustain,
+            saturated_since: None,
+            alerted: false,
+        }
+    }
+
+    /// Record a sample, returning true when the alert should fire
+    pub fn observe(&mut self, saturated: bool, now: Instant) -> bool {
+        if !saturated {
+            self.saturated_since = None;
+            self.alerted = false;
+            return false;
+        }
+
+        let since = *self.saturated_since.get_or_insert(now);
+        if !self.alerted && now.duration_since(since) >= self.sustain {
+            self.alerted = true;
+            return true;
+        }
+
+        false
+    }
+}
+
+/// Feeds the collector's own error logs back into the pipeline
+///
+/// Two guards keep this from amplifying itself: entries are rate limited by a
+/// token bucket, and failures while handling an entry that already carries the
+/// internal marker are only traced, never routed back in.
+pub struct InternalLogRouter {
+    sender: LogSender,
+    limiter: Mutex<TokenBucket>,
+    dropped: AtomicU64,
+}
+
+impl InternalLogRouter {
+    /// Create a router sending into the pipeline channel
+    pub fn new(config: &InternalLogsConfig, sender: LogSender) -> Self {
+        Self {
+            sender,
+            limiter: Mutex::new(TokenBucket::new(config.rate_per_second, config.burst, Instant::now())),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Route a message about `origin` into the pipeline, unless `origin` is itself internal
+    pub fn report(&self, origin: bool, level: &str, message: String) {
+        if origin {
+            return;
+        }
+
+        let admitted = self.limiter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .try_acquire(Instant::now());
+
+        // Never wait on the pipeline from inside the pipeline
+        if !admitted || self.sender.try_send(internal_entry(level, message)).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            metrics::increment_counter!("collector_internal_logs_dropped_total");
+        }
+    }
+
+    /// Number of internal entries dropped by the rate limit or a full queue
+    #[cfg(test)]
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Build a log entry describing the collector's own state
+pub(crate) fn internal_entry(level: &str, message: String) -> LogEntry {
+    let mut attributes = HashMap::new();
+    attributes.insert(INTERNAL_MARKER.to_string(), "true".to_string());
+
+    LogEntry {
+        timestamp: Utc::now(),
+        source: INTERNAL_SOURCE.to_string(),
+        level: Some(level.to_string()),
+        severity_number: None,
+        message,
+        attributes,
+    }
+}
+
+/// Whether an entry was generated by the collector itself
+pub(crate) fn is_internal(log: &LogEntry) -> bool {
+    log.attributes.contains_key(INTERNAL_MARKER)
+}
+
+/// Run log entries through the processors from index `start` and export them
+///
+/// A single entry travels inside a `log_entry` span and a batch released by a
+/// processor inside a `log_batch` span, with one child span per processor and
+/// exporter, so span timings show where latency accrues.
+async fn deliver(
+    processors: &[Box<dyn LogProcessor>],
+    start: usize,
+    exporters: &RwLock<Vec<SharedExporter>>,
+    selected: Option<&[String]>,
+    commit: Option<&CommitCoordinator>,
+    logs: Vec<LogEntry>,
+    internal: Option<&InternalLogRouter>,
+) {
+    let span = match logs.as_slice() {
+        [log] => tracing::debug_span!(
+            "log_entry",
+            source = %log.source,
+            age_ms = (Utc::now() - log.timestamp).num_milliseconds(),
+        ),
+        _ => tracing::debug_span!("log_batch", entries = logs.len()),
+    };
+
+    async move {
+        let logs = run_processors(processors, start, logs, internal).await;
+        export_logs(exporters, selected, commit, logs, internal).await;
+    }
+    .instrument(span)
+    .await
+}
+
+/// Create the processors of `pipeline`, or every processor for an undeclared default pipeline
+fn processor_chain(config: &CollectorConfig, pipeline: &str) -> Result<Vec<Box<dyn LogProcessor>>> {
+    let declared = match config.pipelines.get(pipeline) {
+        Some(declared) => declared,
+        None if pipeline == DEFAULT_PIPELINE => {
+            return config.processors.iter().map(processors::create_processor).collect();
+        },
+        None => return Err(anyhow!("Unknown pipeline {}", pipeline)),
+    };
+
+    declared.processors
+        .iter()
+        .map(|name| {
+            let processor_config = config.processors
+                .iter()
+                .find(|config| config.name() == name)
+                .ok_or_else(|| anyhow!("Pipeline {} uses unknown processor {}", pipeline, name))?;
+            processors::create_processor(processor_config)
+        })
+        .collect()
+}
+
+/// One pipeline's processors, run on their own without sources or exporters
+///
+/// Entries pass through the chain as they would in a running pipeline, which
+/// makes it a dry run for filter, transform and redaction settings.
+pub struct ProcessorChain {
+    processors: Vec<Box<dyn LogProcessor>>,
+}
+
+impl ProcessorChain {
+    /// Build the processors of `pipeline` as configured
+    pub fn new(config: &CollectorConfig, pipeline: &str) -> Result<Self> {
+        Ok(Self { processors: processor_chain(config, pipeline)? })
+    }
+
+    /// Whether a processor may hold entries back until [`finish`](Self::finish)
+    pub fn holds_entries(&self) -> bool {
+        self.processors.iter().any(|processor| processor.holds_entries())
+    }
+
+    /// Run one entry through the chain, returning what comes out the other end
+    pub async fn process(&self, log: LogEntry) -> Vec<LogEntry> {
+        run_processors(&self.processors, 0, vec![log], None).await
+    }
+
+    /// Release whatever the processors still hold, as on shutdown
+    pub async fn finish(&self) -> Vec<LogEntry> {
+        let mut finished = Vec::new();
+        for (index, processor) in self.processors.iter().enumerate() {
+            match processor.flush(true).await {
+                Ok(released) if !released.is_empty() => {
+                    finished.extend(run_processors(&self.processors, index + 1, released, None).await);
+                },
+                Ok(_) => {},
+                Err(e) => tracing::error!("Error flushing processor {}: {}", processor.name(), e),
+            }
+        }
+        finished
+    }
+}
+
+/// Run log entries through the processor chain, starting at index `start`
+///
+/// Entries a processor drops, holds or fails on are left out of the result.
+/// When a processor reports `flush_ready` after taking its input, whatever
+/// it releases joins the entries passed on to the next processor.
+async fn run_processors(
+    processors: &[Box<dyn LogProcessor>],
+    start: usize,
+    logs: Vec<LogEntry>,
+    internal: Option<&InternalLogRouter>,
+) -> Vec<LogEntry> {
+    let mut current_logs = logs;
+
+    for processor in &processors[start..] {
+        if current_logs.is_empty() {
+            break;
+        }
+
+        let span = tracing::debug_span!("process", processor = processor.name());
+        let mut processed_logs = Vec::with_capacity(current_logs.len());
+
+        async {
+            for log in current_logs {
+                let origin = is_internal(&log);
+                match processor.process(log).await {
+                    Ok(Some(processed_log)) => processed_logs.push(processed_log),
+                    Ok(None) if processor.holds_entries() => {},
+                    Ok(None) => {
+                        metrics::increment_counter!("collector_processor_dropped_total", "processor" => processor.name().to_string());
+                    },
+                    Err(e) => {
+                        tracing::error!("Error processing log: {}", e);
+                        if let Some(internal) = internal {
+                            internal.report(origin, "ERROR", format!("Processor {} failed: {}", processor.name(), e));
+                        }
+                    }
+                }
+            }
+
+            if processor.flush_ready() {
+                match processor.flush(false).await {
+                    Ok(released) => processed_logs.extend(released),
+                    Err(e) => tracing::error!("Error flushing processor {}: {}", processor.name(), e),
+                }
+            }
+        }
+        .instrument(span)
+        .await;
+
+        current_logs = processed_logs;
+    }
+
+    current_logs
+}
+
+/// Export log entries to the `selected` exporters, or all of them, in parallel
+///
+/// Several entries go to each exporter as one `export_batch` call. Under a
+/// coordinated `commit` they go through `export_committed` instead, sharing
+/// one ticket.
+async fn export_logs(
+    exporters: &RwLock<Vec<SharedExporter>>,
+    selected: Option<&[String]>,
+    commit: Option<&CommitCoordinator>,
+    logs: Vec<LogEntry>,
+    internal: Option<&InternalLogRouter>,
+) {
+    if logs.is_empty() {
+        return;
+    }
+
+    // Failures on a batch holding internal entries are not routed back in
+    let origin = logs.iter().any(is_internal);
+    let exporters_guard = exporters.read().await;
+
+    let targets: Vec<&SharedExporter> = exporters_guard
+        .iter()
+        .filter(|exporter| selected.is_none_or(|names| names.iter().any(|name| name == exporter.name())))
+        .collect();
+    let ticket = commit.and_then(|commit| commit.ticket(targets.iter().map(|exporter| exporter.name())));
+
+    let export_futures: Vec<_> = targets
+        .into_iter()
+        .map(|exporter| {
+            let mut logs_clone = logs.clone();
+            let ack = ticket.as_ref().map(|ticket| ticket.ack(exporter.name()));
+            let span = tracing::debug_span!("export", exporter = exporter.name(), entries = logs_clone.len());
+            async move {
+                let entries = logs_clone.len();
+                let started = Instant::now();
+                let result = match (ack, entries) {
+                    (Some(ack), _) => exporter.export_committed(logs_clone, ack).await,
+                    (None, 1) => exporter.export(logs_clone.remove(0)).await,
+                    (None, _) => exporter.export_batch(logs_clone).await,
+                };
+                record_export(exporter.name(), entries, started.elapsed(), result.is_ok());
+
+                if let Err(e) = result {
+                    tracing::error!("Error exporting log to {}: {}", exporter.name(), e);
+                    if let Some(internal) = internal {
+                        internal.report(origin, "ERROR", format!("Exporter {} failed: {}", exporter.name(), e));
+                    }
+                }
+            }
+            .instrument(span)
+        })
+        .collect();
+
+    stream::iter(export_futures)
+        .buffer_unordered(10) // Process up to 10 exports in parallel
+        .collect::<Vec<_>>()
+        .await;
+}
+
+/// Record one hand-off of `entries` logs to an exporter in the export metrics
+fn record_export(exporter: &str, entries: usize, elapsed: Duration, succeeded: bool) {
+    let name = if succeeded { "collector_logs_exported_total" } else { "collector_logs_export_failed_total" };
+    metrics::counter!(name, entries as u64, "exporter" => exporter.to_string());
+    metrics::histogram!("collector_export_duration_seconds", elapsed.as_secs_f64(), "exporter" => exporter.to_string());
+    metrics::histogram!("collector_export_batch_size", entries as f64, "exporter" => exporter.to_string());
+}
+
+/// Ask every processor to release held entries and push them down the rest of the chain
+async fn flush_processors(
+    processors: &[Box<dyn LogProcessor>],
+    exporters: &RwLock<Vec<SharedExporter>>,
+    selected: Option<&[String]>,
+    commit: Option<&CommitCoordinator>,
+    force: bool,
+    internal: Option<&InternalLogRouter>,
+) {
+    for (index, processor) in processors.iter().enumerate() {
+        let released = match processor.flush(force).await {
+            Ok(released) => released,
+            Err(e) => {
+                tracing::error!("Error flushing processor {}: {}", processor.name(), e);
+                continue;
+            }
+        };
+
+        if !released.is_empty() {
+            deliver(processors, index + 1, exporters, selected, commit, released, internal).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// Processor that rejects every entry
+    struct FailingProcessor;
+
+    #[async_trait]
+    impl LogProcessor for FailingProcessor {
+        async fn process(&self, _log: LogEntry) -> Result<Option<LogEntry>> {
+            Err(anyhow!("rejected"))
+        }
+
+        fn name(&self) -> &str {
+            "failing"
+        }
+    }
+
+    /// Processor that passes every entry through unchanged
+    struct PassthroughProcessor;
+
+    #[async_trait]
+    impl LogProcessor for PassthroughProcessor {
+        async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+            Ok(Some(log))
+        }
+
+        fn name(&self) -> &str {
+            "passthrough"
+        }
+    }
+
+    /// Processor that drops entries whose message starts with `noise`
+    struct NoiseProcessor;
+
+    #[async_trait]
+    impl LogProcessor for NoiseProcessor {
+        async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+            Ok(Some(log).filter(|log| !log.message.starts_with("noise")))
+        }
+
+        fn name(&self) -> &str {
+            "noise"
+        }
+    }
+
+    /// Processor passing only ERROR entries
+    struct ErrorsOnlyProcessor;
+
+    #[async_trait]
+    impl LogProcessor for ErrorsOnlyProcessor {
+        async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+            Ok(Some(log).filter(|log| log.level.as_deref() == Some("ERROR")))
+        }
+
+        fn name(&self) -> &str {
+            "errors-only"
+        }
+    }
+
+    /// Exporter that discards every entry
+    struct NullExporter;
+
+    #[async_trait]
+    impl LogExporter for NullExporter {
+        async fn export(&self, _log: LogEntry) -> Result<()> {
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "null"
+        }
+    }
+
+    /// Name of a span and of its parent, if it has one
+    type SpanName = (String, Option<String>);
+
+    /// Layer that records each span's name alongside its parent's name
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<SpanName>>>);
+
+    impl<S> Layer<S> for SpanRecorder
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).expect("new span is registered");
+            let parent = span.parent().map(|parent| parent.name().to_string());
+            self.0.lock().unwrap().push((span.name().to_string(), parent));
+        }
+    }
+
+    /// Shared record of what a [`RecordingExporter`] has seen
+    #[derive(Default)]
+    struct Recorded {
+        buffered: Mutex<Vec<String>>,
+        delivered: Mutex<Vec<String>>,
+        batch_sizes: Mutex<Vec<usize>>,
+    }
+
+    /// Named exporter that buffers messages until flushed, like the batching exporters
+    struct RecordingExporter(Arc<Recorded>, &'static str);
+
+    #[async_trait]
+    impl LogExporter for RecordingExporter {
+        async fn export(&self, log: LogEntry) -> Result<()> {
+            self.0.buffered.lock().unwrap().push(log.message);
+            Ok(())
+        }
+
+        async fn export_batch(&self, logs: Vec<LogEntry>) -> Result<()> {
+            self.0.batch_sizes.lock().unwrap().push(logs.len());
+            self.0.buffered.lock().unwrap().extend(logs.into_iter().map(|log| log.message));
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            let flushed: Vec<String> = self.0.buffered.lock().unwrap().drain(..).collect();
+            self.0.delivered.lock().unwrap().extend(flushed);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.1
+        }
+    }
+
+    /// Exporter asking to be flushed every `interval`, counting its flushes
+    struct TimedExporter(Arc<AtomicU64>, Duration);
+
+    #[async_trait]
+    impl LogExporter for TimedExporter {
+        async fn export(&self, _log: LogEntry) -> Result<()> {
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn next_flush_delay(&self) -> Option<Duration> {
+            Some(self.1)
+        }
+
+        fn name(&self) -> &str {
+            "timed"
+        }
+    }
+
+    /// Exporter asking to be flushed every 10ms, whose flushes hang until its gate is closed
+    struct HungFlushExporter(Arc<tokio::sync::Semaphore>);
+
+    #[async_trait]
+    impl LogExporter for HungFlushExporter {
+        async fn export(&self, _log: LogEntry) -> Result<()> {
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            let _ = self.0.acquire().await;
+            Ok(())
+        }
+
+        fn next_flush_delay(&self) -> Option<Duration> {
+            Some(Duration::from_millis(10))
+        }
+
+        fn name(&self) -> &str {
+            "hung"
+        }
+    }
+
+    /// Exporter counting how many of its exports run at once, and the most seen
+    struct CountingExporter {
+        running: AtomicUsize,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl LogExporter for CountingExporter {
+        async fn export(&self, _log: LogEntry) -> Result<()> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    /// Exporter whose exports hang until its gate is closed
+    struct StalledExporter(Arc<Recorded>, Arc<tokio::sync::Semaphore>);
+
+    #[async_trait]
+    impl LogExporter for StalledExporter {
+        async fn export(&self, log: LogEntry) -> Result<()> {
+            // The gate never has permits; closing it releases every waiter
+            let _ = self.1.acquire().await;
+            self.0.delivered.lock().unwrap().push(log.message);
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "stalled"
+        }
+    }
+
+    fn empty_config() -> CollectorConfig {
+        serde_yaml::from_str("sources: []\nprocessors: []\nexporters: []").unwrap()
+    }
+
+    fn internal_router(rate_per_second: u32, burst: u32) -> (InternalLogRouter, mpsc::Receiver<LogEntry>) {
+        let (sender, receiver) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+        let config = InternalLogsConfig { rate_per_second, burst };
+        (InternalLogRouter::new(&config, sender), receiver)
+    }
+
+    #[tokio::test]
+    async fn test_internal_log_burst_is_rate_limited() {
+        let (router, mut receiver) = internal_router(1, 5);
+
+        for i in 0..100 {
+            router.report(false, "DEBUG", format!("debug message {}", i));
+        }
+
+        let mut admitted = 0;
+        while let Ok(log) = receiver.try_recv() {
+            assert!(is_internal(&log));
+            admitted += 1;
+        }
+
+        assert_eq!(admitted, 5);
+        assert_eq!(router.dropped_count(), 95);
+    }
+
+    #[tokio::test]
+    async fn test_internal_log_failures_do_not_loop() {
+        let (router, mut receiver) = internal_router(100, 100);
+        let processors: Vec<Box<dyn LogProcessor>> = vec![Box::new(FailingProcessor)];
+
+        let mut log = internal_entry("INFO", "external".to_string());
+        log.attributes.clear();
+        assert!(run_processors(&processors, 0, vec![log], Some(&router)).await.is_empty());
+
+        // The failure on an ordinary entry is reported once...
+        let report = receiver.try_recv().expect("failure should be reported");
+        assert!(is_internal(&report));
+
+        // ...and failing on that report produces nothing further
+        assert!(run_processors(&processors, 0, vec![report], Some(&router)).await.is_empty());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entry_spans_follow_the_pipeline() {
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        // The current-thread test runtime keeps every poll on this thread
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let processors: Vec<Box<dyn LogProcessor>> = vec![Box::new(PassthroughProcessor)];
+        let exporters: RwLock<Vec<SharedExporter>> = RwLock::new(vec![Arc::new(NullExporter)]);
+
+        let mut log = internal_entry("INFO", "traced".to_string());
+        log.attributes.clear();
+        deliver(&processors, 0, &exporters, None, None, vec![log], None).await;
+
+        let spans = recorder.0.lock().unwrap().clone();
+        assert_eq!(spans, vec![
+            ("log_entry".to_string(), None),
+            ("process".to_string(), Some("log_entry".to_string())),
+            ("export".to_string(), Some("log_entry".to_string())),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_processor_releases_full_batches_then_the_rest() -> Result<()> {
+        let recorded = Arc::new(Recorded::default());
+        let processors: Vec<Box<dyn LogProcessor>> = vec![
+            Box::new(processors::BatchProcessor::new("batcher".to_string(), 60, 100, 0.0)?),
+            Box::new(PassthroughProcessor),
+        ];
+        let exporters: RwLock<Vec<SharedExporter>> =
+            RwLock::new(vec![Arc::new(RecordingExporter(recorded.clone(), "recording"))]);
+
+        for i in 0..250 {
+            let mut log = internal_entry("INFO", format!("entry {}", i));
+            log.attributes.clear();
+            deliver(&processors, 0, &exporters, None, None, vec![log], None).await;
+        }
+
+        // Two full batches went out as soon as they filled up
+        assert_eq!(*recorded.batch_sizes.lock().unwrap(), vec![100, 100]);
+
+        // The periodic tick leaves the partial batch alone until its timeout
+        flush_processors(&processors, &exporters, None, None, false, None).await;
+        assert_eq!(*recorded.batch_sizes.lock().unwrap(), vec![100, 100]);
+
+        flush_processors(&processors, &exporters, None, None, true, None).await;
+        assert_eq!(*recorded.batch_sizes.lock().unwrap(), vec![100, 100, 50]);
+
+        let expected: Vec<String> = (0..250).map(|i| format!("entry {}", i)).collect();
+        assert_eq!(*recorded.buffered.lock().unwrap(), expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exporter_swap_mid_stream_loses_nothing() -> Result<()> {
+        let mut pipeline = Pipeline::new(empty_config())?;
+        let old = Arc::new(Recorded::default());
+        let new = Arc::new(Recorded::default());
+
+        pipeline.swap_exporters(vec![Box::new(RecordingExporter(old.clone(), "recording"))]).await;
+        pipeline.start_processor_task().await?;
+
+        let sender = pipeline.route_sender(None)?;
+        let producer = tokio::spawn(async move {
+            for i in 0..200 {
+                let mut log = internal_entry("INFO", format!("entry {}", i));
+                log.attributes.clear();
+                sender.send(log).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+
+        // Swap once the stream is under way
+        while old.buffered.lock().unwrap().len() < 50 {
+            tokio::task::yield_now().await;
+        }
+        pipeline.swap_exporters(vec![Box::new(RecordingExporter(new.clone(), "recording"))]).await;
+        producer.await?;
+
+        // Old exporters were flushed during the swap and see nothing afterwards
+        let old_delivered = old.delivered.lock().unwrap().len();
+        assert!(old_delivered >= 50);
+        assert!(old.buffered.lock().unwrap().is_empty());
+
+        time::timeout(Duration::from_secs(5), async {
+            while old_delivered + new.buffered.lock().unwrap().len() < 200 {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+        for exporter in pipeline.exporters.read().await.iter() {
+            exporter.flush().await?;
+        }
+
+        let mut seen: Vec<String> = old.delivered.lock().unwrap().clone();
+        seen.extend(new.delivered.lock().unwrap().iter().cloned());
+        let expected: Vec<String> = (0..200).map(|i| format!("entry {}", i)).collect();
+        assert_eq!(seen, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stop_drains_queued_logs() -> Result<()> {
+        for overflow in ["block", "drop_oldest"] {
+            let config = format!("sources: []\nprocessors: []\nexporters: []\noverflow: {}", overflow);
+            let mut pipeline = Pipeline::new(serde_yaml::from_str(&config)?)?;
+            let recorded = Arc::new(Recorded::default());
+
+            pipeline.swap_exporters(vec![Box::new(RecordingExporter(recorded.clone(), "recording"))]).await;
+            pipeline.start_processor_task().await?;
+            pipeline.running = true;
+
+            // Queue entries and stop straight away, while they are still in flight
+            let sender = pipeline.route_sender(None)?;
+            for i in 0..500 {
+                let mut log = internal_entry("INFO", format!("entry {}", i));
+                log.attributes.clear();
+                sender.send(log).await?;
+            }
+            time::timeout(Duration::from_secs(5), pipeline.stop()).await??;
+
+            let expected: Vec<String> = (0..500).map(|i| format!("entry {}", i)).collect();
+            assert_eq!(*recorded.delivered.lock().unwrap(), expected, "overflow: {}", overflow);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest_sheds_load_without_blocking_sources() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str("sources: []\nprocessors: []\nexporters: []\noverflow: drop_newest")?;
+        let mut pipeline = Pipeline::new(config)?;
+        let recorded = Arc::new(Recorded::default());
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+
+        pipeline.swap_exporters(vec![Box::new(StalledExporter(recorded.clone(), gate.clone()))]).await;
+        pipeline.start_processor_task().await?;
+
+        // Twice what the queue holds goes in while the exporter is stuck on the first entry
+        let sent = 2 * LOG_CHANNEL_CAPACITY;
+        let sender = pipeline.route_sender(None)?;
+        time::timeout(Duration::from_secs(5), async {
+            for i in 0..sent {
+                let mut log = internal_entry("INFO", format!("entry {}", i));
+                log.attributes.clear();
+                sender.send(log).await.unwrap();
+            }
+        }).await?;
+
+        time::timeout(Duration::from_secs(5), async {
+            while pipeline.dropped_count() < (sent - LOG_CHANNEL_CAPACITY - 2) as u64 {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+
+        gate.close();
+        let dropped = pipeline.dropped_count() as usize;
+        time::timeout(Duration::from_secs(5), async {
+            while recorded.delivered.lock().unwrap().len() + dropped < sent {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+
+        // The oldest entries were kept, in order, and only the newest were shed
+        let delivered = recorded.delivered.lock().unwrap().clone();
+        let expected: Vec<String> = (0..sent - dropped).map(|i| format!("entry {}", i)).collect();
+        assert_eq!(delivered, expected);
+        assert_eq!(pipeline.dropped_count() as usize, dropped);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_pipeline_traffic() -> Result<()> {
+        let telemetry_config: crate::collector::config::TelemetryConfig = serde_yaml::from_str("listen_address: 127.0.0.1:0")?;
+        let (address, server) = telemetry::start_metrics_server(&telemetry_config).await?;
+
+        let mut pipeline = Pipeline::new(empty_config())?;
+        let recorded = Arc::new(Recorded::default());
+        pipeline.routes[0].processors = Arc::new(vec![Box::new(NoiseProcessor)]);
+        pipeline.swap_exporters(vec![Box::new(RecordingExporter(recorded.clone(), "scraped"))]).await;
+        pipeline.start_processor_task().await?;
+
+        // Labels unique to this test, as the recorder is shared by the whole process
+        let sender = pipeline.route_sender(None)?;
+        for message in ["kept 1", "noise", "kept 2", "noise", "kept 3"] {
+            let mut log = internal_entry("INFO", message.to_string());
+            log.attributes.clear();
+            log.source = "metrics-test".to_string();
+            sender.send(log).await?;
+        }
+        time::timeout(Duration::from_secs(5), async {
+            while recorded.buffered.lock().unwrap().len() < 3 {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+
+        let scraped = reqwest::get(format!("http://{}/metrics", address)).await?.text().await?;
+        for expected in [
+            r#"collector_logs_received_total{source="metrics-test"} 5"#,
+            r#"collector_processor_dropped_total{processor="noise"} 2"#,
+            r#"collector_logs_exported_total{exporter="scraped"} 3"#,
+            r#"collector_export_batch_size_count{exporter="scraped"} 3"#,
+            r#"collector_export_duration_seconds_count{exporter="scraped"} 3"#,
+        ] {
+            assert!(scraped.contains(expected), "missing {} in:\n{}", expected, scraped);
+        }
+
+        let missing = reqwest::get(format!("http://{}/other", address)).await?;
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+        server.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_exports_are_limited() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let counting = CountingExporter { running: AtomicUsize::new(0), peak: peak.clone() };
+        let exporters: RwLock<Vec<SharedExporter>> = RwLock::new(vec![Arc::new(LimitedExporter::new(Box::new(counting), 2))]);
+
+        let exports = (0..10).map(|i| export_logs(&exporters, None, None, vec![internal_entry("INFO", format!("log {}", i))], None));
+        futures::future::join_all(exports).await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_source_pipeline_selects_exporters() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters: []
+            pipelines:
+              audit:
+                exporters: [audit]
+        "#)?;
+        let mut pipeline = Pipeline::new(config)?;
+        pipeline.initialize_routes()?;
+
+        let everything = Arc::new(Recorded::default());
+        let audit = Arc::new(Recorded::default());
+        let exporters: Vec<Box<dyn LogExporter>> = vec![
+            Box::new(RecordingExporter(everything.clone(), "everything")),
+            Box::new(RecordingExporter(audit.clone(), "audit")),
+        ];
+        pipeline.swap_exporters(exporters).await;
+        pipeline.start_processor_task().await?;
+
+        // A source bound to `audit` and an unbound one on the default pipeline
+        for (bound_to, message) in [(Some("audit"), "login failed"), (None, "cache warmed")] {
+            let mut log = internal_entry("INFO", message.to_string());
+            log.attributes.clear();
+            pipeline.route_sender(bound_to)?.send(log).await?;
+        }
+
+        time::timeout(Duration::from_secs(5), async {
+            while audit.buffered.lock().unwrap().len() < 2 || everything.buffered.lock().unwrap().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+
+        let mut audited = audit.buffered.lock().unwrap().clone();
+        audited.sort();
+        assert_eq!(audited, vec!["cache warmed", "login failed"]);
+        assert_eq!(*everything.buffered.lock().unwrap(), vec!["cache warmed"]);
+
+        assert!(pipeline.route_sender(Some("undeclared")).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pipelines_listing_a_source_route_levels_apart() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: file
+                name: app
+                include: [/var/log/myapp/*.log]
+            processors: []
+            exporters: []
+            pipelines:
+              errors:
+                sources: [app]
+                exporters: [narrator]
+              everything:
+                sources: [app]
+                exporters: [cache]
+        "#)?;
+        let mut pipeline = Pipeline::new(config)?;
+        pipeline.initialize_routes()?;
+        let errors = pipeline.routes.iter_mut().find(|route| route.name == "errors").unwrap();
+        errors.processors = Arc::new(vec![Box::new(ErrorsOnlyProcessor)]);
+
+        let narrator = Arc::new(Recorded::default());
+        let cache = Arc::new(Recorded::default());
+        let exporters: Vec<Box<dyn LogExporter>> = vec![
+            Box::new(RecordingExporter(narrator.clone(), "narrator")),
+            Box::new(RecordingExporter(cache.clone(), "cache")),
+        ];
+        pipeline.swap_exporters(exporters).await;
+        pipeline.start_processor_task().await?;
+
+        let source = pipeline.config.sources[0].clone();
+        let sender = pipeline.source_sender(&source)?;
+        for (level, message) in [("INFO", "cache warmed"), ("ERROR", "disk full")] {
+            let mut log = internal_entry(level, message.to_string());
+            log.attributes.clear();
+            sender.send(log).await?;
+        }
+
+        time::timeout(Duration::from_secs(5), async {
+            while narrator.buffered.lock().unwrap().is_empty() || cache.buffered.lock().unwrap().len() < 2 {
+                tokio::task::yield_now().await;
+            }
+        }).await?;
+
+        assert_eq!(*narrator.buffered.lock().unwrap(), vec!["disk full"]);
+        let mut cached = cache.buffered.lock().unwrap().clone();
+        cached.sort();
+        assert_eq!(cached, vec!["cache warmed", "disk full"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stalled_pipeline_does_not_hold_up_a_shared_source() -> Result<()> {
+        let config: CollectorConfig = serde_yaml::from_str(r#"
+            sources:
+              - source_type: file
+                name: app
+                include: [/var/log/myapp/*.log]
+            processors: []
+            exporters: []
+            pipelines:
+              cloud:
+                sources: [app]
+                exporters: [stalled]
+              cache:
+                sources: [app]
+                exporters: [cache]
+        "#)?;
+        let mut pipeline = Pipeline::new(config)?;
+        pipeline.initialize_routes()?;
+
+        let stalled = Arc::new(Recorded::default());
+        let cache = Arc::new(Recorded::default());
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let exporters: Vec<Box<dyn LogExporter>> = vec![
+            Box::new(StalledExporter(stalled.clone(), gate.clone())),
+            Box::new(RecordingExporter(cache.clone(), "cache")),
+        ];
+        pipeline.swap_exporters(exporters).await;
+        pipeline.start_processor_task().await?;
+
+        // Three times what a queue holds, while the cloud exporter is stuck
+        let source = pipeline.config.sources[0].clone();
+        let sender = pipeline.source_sender(&source)?;
+        let sent = 3 * LOG_CHANNEL_CAPACITY;
+        time::timeout(Duration::from_secs(5), async {
+            for chunk in 0..sent / 100 {
+                for i in chunk * 100..(chunk + 1) * 100 {
+                    let mut log = internal_entry("INFO", format!("entry {}", i));
+                    log.attributes.clear();
+                    sender.send(log).await.unwrap();
+                }
+                while cache.buffered.lock().unwrap().len() < (chunk + 1) * 100 {
+                    tokio::task::yield_now().await;
+                }
+            }
+        }).await?;
+
+        // The cache pipeline got every entry; only the stalled one dropped copies
+        let expected: Vec<String> = (0..sent).map(|i| format!("entry {}", i)).collect();
+        assert_eq!(*cache.buffered.lock().unwrap(), expected);
+        assert!(pipeline.dropped_count() >= (sent - 2 * LOG_CHANNEL_CAPACITY) as u64);
+        gate.close();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topology_matches_loaded_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let yaml = format!(r#"
+            sources:
+              - source_type: file
+                name: auth
+                include: ["{dir}/auth.log"]
+                pipeline: audit
+              - source_type: file
+                name: app
+                include: ["{dir}/*.log"]
+            processors:
+              - processor_type: normalizelevel
+                name: levels
+              - processor_type: levelprefix
+                name: prefixes
+            exporters:
+              - exporter_type: stdout
+                name: console
+              - exporter_type: stdout
+                name: audit-console
+                format: logfmt
+            pipelines:
+              audit:
+                processors: [prefixes]
+                exporters: [audit-console]
+        "#, dir = dir.path().display());
+        let mut pipeline = Pipeline::new(serde_yaml::from_str(&yaml)?)?;
+        pipeline.initialize().await?;
+
+        let route = |name: &str, processors: &[&str], exporters: &[&str]| RouteTopology {
+            name: name.to_string(),
+            processors: processors.iter().map(|name| name.to_string()).collect(),
+            exporters: exporters.iter().map(|name| name.to_string()).collect(),
+        };
+        let source = |name: &str, pipeline: &str| SourceTopology { name: name.to_string(), pipelines: vec![pipeline.to_string()] };
+        assert_eq!(pipeline.topology().await, PipelineTopology {
+            sources: vec![source("auth", "audit"), source("app", DEFAULT_PIPELINE)],
+            routes: vec![
+                route(DEFAULT_PIPELINE, &["levels", "prefixes"], &["console", "audit-console"]),
+                route("audit", &["prefixes"], &["audit-console"]),
+            ],
+            exporters: vec!["console".to_string(), "audit-console".to_string()],
+        });
+
+        // A reload shows up in the exporters the default route delivers to
+        let reloaded: CollectorConfig = serde_yaml::from_str(r#"
+            sources: []
+            processors: []
+            exporters:
+              - exporter_type: stdout
+                name: audit-console
+        "#)?;
+        pipeline.reload_exporters(reloaded.exporters).await?;
+        let topology = pipeline.topology().await;
+        assert_eq!(topology.exporters, vec!["audit-console"]);
+        assert_eq!(topology.routes[0].exporters, vec!["audit-console"]);
+
+        let json = serde_json::to_value(&topology)?;
+        assert_eq!(json["routes"][1]["processors"], serde_json::json!(["prefixes"]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exporters_are_flushed_on_their_timers() -> Result<()> {
+        let mut pipeline = Pipeline::new(empty_config())?;
+        let flushes = Arc::new(AtomicU64::new(0));
+        let untimed = Arc::new(Recorded::default());
+        let exporters: Vec<Box<dyn LogExporter>> = vec![
+            Box::new(TimedExporter(flushes.clone(), Duration::from_millis(50))),
+            Box::new(RecordingExporter(untimed.clone(), "untimed")),
+        ];
+        pipeline.swap_exporters(exporters).await;
+        pipeline.exporters.read().await[1].export(internal_entry("INFO", "held".to_string())).await?;
+
+        pipeline.start_exporter_timer();
+        time::sleep(Duration::from_millis(275)).await;
+
+        let flushed = flushes.load(Ordering::SeqCst);
+        assert!((3..=5).contains(&flushed), "expected about 5 timed flushes, got {}", flushed);
+        // Exporters without a timer are left to flush themselves
+        assert_eq!(*untimed.buffered.lock().unwrap(), vec!["held"]);
+        assert!(untimed.delivered.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hung_flush_holds_up_neither_other_exporters_nor_reloads() -> Result<()> {
+        let mut pipeline = Pipeline::new(empty_config())?;
+        let flushes = Arc::new(AtomicU64::new(0));
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let exporters: Vec<Box<dyn LogExporter>> = vec![
+            Box::new(HungFlushExporter(gate.clone())),
+            Box::new(TimedExporter(flushes.clone(), Duration::from_millis(20))),
+        ];
+        pipeline.swap_exporters(exporters).await;
+
+        pipeline.start_exporter_timer();
+        time::sleep(Duration::from_millis(150)).await;
+
+        let flushed = flushes.load(Ordering::SeqCst);
+        assert!(flushed >= 3, "expected the timed exporter to keep flushing, got {}", flushed);
+        // A reload can take the exporters while the hung flush is still pending
+        let locked = time::timeout(Duration::from_secs(1), pipeline.exporters.write()).await;
+        assert!(locked.is_ok(), "taking the exporters for a reload waited on a hung flush");
+        drop(locked);
+
+        gate.close();
+        Ok(())
+    }
+
+    #[test]
+    fn test_brief_saturation_does_not_alert() {
+        let mut monitor = SaturationMonitor::new(Duration::from_secs(30));
+        let start = Instant::now();
+
+        assert!(!monitor.observe(true, start));
+        assert!(!monitor.observe(true, start + Duration::from_secs(10)));
+        assert!(!monitor.observe(false, start + Duration::from_secs(12)));
+        // Saturation starts over after draining
+        assert!(!monitor.observe(true, start + Duration::from_secs(35)));
+    }
+
+    #[test]
+    fn test_sustained_saturation_alerts_once() {
+        let mut monitor = SaturationMonitor::new(Duration::from_secs(30));
+        let start = Instant::now();
+
+        assert!(!monitor.observe(true, start));
+        assert!(!monitor.observe(true, start + Duration::from_secs(29)));
+        assert!(monitor.observe(true, start + Duration::from_secs(30)));
+        assert!(!monitor.observe(true, start + Duration::from_secs(60)));
+
+        // Draining re-arms the alert for the next episode
+        assert!(!monitor.observe(false, start + Duration::from_secs(61)));
+        assert!(!monitor.observe(true, start + Duration::from_secs(62)));
+        assert!(monitor.observe(true, start + Duration::from_secs(92)));
+    }
+}
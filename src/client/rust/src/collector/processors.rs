@@ -2,18 +2,46 @@
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use regex::Regex;
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
-use crate::collector::config::{ProcessorConfig, FilterConfig, MatchConfig, MatchType, ActionType, AttributeAction, TransformAction, TransformType};
+use crate::collector::config::{ProcessorConfig, BinaryAction, FilterConfig, LanguageFormat, ListFormat, MatchConfig, MatchRule, MatchType, ATTRIBUTE_FIELD_PREFIX, ActionType, AttributeAction, MissingAttributeAction, QuotaBudget, TransformAction, TransformType, UnknownSeverityAction};
+use crate::collector::backoff;
+use crate::collector::clock::{self, SharedClock};
+use crate::collector::pipeline::{internal_entry, is_internal};
+use crate::collector::rate_limit::TokenBucket;
+use crate::collector::severity::{LevelAliases, Severity};
 use crate::collector::sources::LogEntry;
 
 /// Interface for log processors
+///
+/// Processors that hold entries back (for example to merge repeats or build
+/// batches) return `None` from `process` and release them through `flush`.
+/// The pipeline calls `flush(false)` on a periodic tick, right after
+/// `process` whenever `flush_ready` reports a full buffer, and `flush(true)`
+/// once on shutdown. Released entries continue through the rest of the chain
+/// together, and reach the exporters as one `export_batch` call.
 #[async_trait]
 pub trait LogProcessor: Send + Sync {
     /// Process a log entry
     async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>>;
+    /// Release held entries that are due, or all of them when `force` is set
+    async fn flush(&self, _force: bool) -> Result<Vec<LogEntry>> {
+        Ok(Vec::new())
+    }
+    /// Whether held entries should be released without waiting for the next tick
+    fn flush_ready(&self) -> bool {
+        false
+    }
+    /// Whether `None` from `process` means the entry is held rather than dropped
+    fn holds_entries(&self) -> bool {
+        false
+    }
     /// Get the name of this processor
     fn name(&self) -> &str;
 }
@@ -27,17 +55,24 @@ pub fn create_processor(config: &ProcessorConfig) -> Result<Box<dyn LogProcessor
                 attributes.clone(),
             )?))
         },
-        ProcessorConfig::Filter { name, logs } => {
+        ProcessorConfig::Filter { name, logs, min_severity, unknown_severity } => {
+            let min_severity = min_severity
+                .as_deref()
+                .map(|level| Severity::parse(level).ok_or_else(|| anyhow!("Unknown min_severity {}", level)))
+                .transpose()?;
             Ok(Box::new(FilterProcessor::new(
                 name.clone(),
                 logs.clone(),
+                min_severity,
+                *unknown_severity,
             )?))
         },
-        ProcessorConfig::Batch { name, timeout, send_batch_size } => {
+        ProcessorConfig::Batch { name, timeout, send_batch_size, timeout_jitter } => {
             Ok(Box::new(BatchProcessor::new(
                 name.clone(),
                 *timeout,
                 *send_batch_size,
+                *timeout_jitter,
             )?))
         },
         ProcessorConfig::Transform { name, transforms } => {
@@ -46,6 +81,117 @@ pub fn create_processor(config: &ProcessorConfig) -> Result<Box<dyn LogProcessor
                 transforms.clone(),
             )?))
         },
+        ProcessorConfig::Coalesce { name, window_seconds } => {
+            Ok(Box::new(CoalesceProcessor::new(
+                name.clone(),
+                *window_seconds,
+            )?))
+        },
+        ProcessorConfig::LevelPrefix { name, tokens, strip } => {
+            Ok(Box::new(LevelPrefixProcessor::new(
+                name.clone(),
+                tokens.clone(),
+                *strip,
+            )?))
+        },
+        ProcessorConfig::RequireAttributes { name, required, on_missing, defaults } => {
+            Ok(Box::new(RequireAttributesProcessor::new(
+                name.clone(),
+                required.clone(),
+                *on_missing,
+                defaults.clone(),
+            )?))
+        },
+        ProcessorConfig::Partition { name, timezone } => {
+            Ok(Box::new(PartitionProcessor::new(
+                name.clone(),
+                timezone,
+            )?))
+        },
+        ProcessorConfig::LanguageParser { name, formats } => {
+            Ok(Box::new(LanguageParserProcessor::new(
+                name.clone(),
+                formats.clone(),
+            )?))
+        },
+        ProcessorConfig::BinaryFilter { name, min_printable_ratio, action } => {
+            Ok(Box::new(BinaryFilterProcessor::new(
+                name.clone(),
+                *min_printable_ratio,
+                *action,
+            )?))
+        },
+        ProcessorConfig::RateAnomaly { name, window_seconds, sensitivity } => {
+            Ok(Box::new(RateAnomalyProcessor::new(
+                name.clone(),
+                *window_seconds,
+                *sensitivity,
+                clock::system_clock(),
+            )?))
+        },
+        ProcessorConfig::NormalizeLevel { name, aliases } => {
+            Ok(Box::new(NormalizeLevelProcessor::new(
+                name.clone(),
+                aliases,
+            )?))
+        },
+        ProcessorConfig::SourceTemplate { name, template, fallback } => {
+            Ok(Box::new(SourceTemplateProcessor::new(
+                name.clone(),
+                template,
+                fallback.clone(),
+            )?))
+        },
+        ProcessorConfig::Bucket { name, attribute, target, boundaries, unit } => {
+            Ok(Box::new(BucketProcessor::new(
+                name.clone(),
+                attribute.clone(),
+                target.clone(),
+                boundaries.clone(),
+                unit,
+            )?))
+        },
+        ProcessorConfig::Sample { name, one_in, rate_per_second, burst, key } => {
+            Ok(Box::new(SampleProcessor::new(
+                name.clone(),
+                *one_in,
+                *rate_per_second,
+                *burst,
+                key.clone(),
+            )?))
+        },
+        ProcessorConfig::ExpandList { name, attribute, format } => {
+            Ok(Box::new(ExpandListProcessor::new(
+                name.clone(),
+                attribute.clone(),
+                *format,
+            )?))
+        },
+        ProcessorConfig::JsonParse { name, field, level_key, timestamp_key, message_key } => {
+            Ok(Box::new(JsonParseProcessor::new(
+                name.clone(),
+                field.clone(),
+                level_key.clone(),
+                timestamp_key.clone(),
+                message_key.clone(),
+            )?))
+        },
+        ProcessorConfig::StripAnsi { name, attributes } => {
+            Ok(Box::new(StripAnsiProcessor::new(
+                name.clone(),
+                *attributes,
+            )?))
+        },
+        ProcessorConfig::Quota { name, attribute, window_seconds, default, tenants } => {
+            Ok(Box::new(QuotaProcessor::new(
+                name.clone(),
+                attribute.clone(),
+                *window_seconds,
+                *default,
+                tenants.clone(),
+                clock::system_clock(),
+            )?))
+        },
     }
 }
 
@@ -123,113 +269,177 @@ impl LogProcessor for ResourceProcessor {
 }
 
 /// Filter processor includes or excludes logs based on patterns
+///
+/// Each condition matches the message, the level or one attribute. A log
+/// matching the `exclude` rule is dropped; with an `include` rule, a log must
+/// also match it to be kept. With `min_severity`, logs whose level is less
+/// severe are dropped before any pattern is tried.
 pub struct FilterProcessor {
     name: String,
-    filter: FilterConfig,
-    include_matchers: Vec<Matcher>,
-    exclude_matchers: Vec<Matcher>,
+    include: Option<Rule>,
+    exclude: Option<Rule>,
+    min_severity: Option<Severity>,
+    unknown_severity: UnknownSeverityAction,
 }
 
 enum Matcher {
-    Exact(String),
+    Contains(String),
+    Equals(String),
     Regexp(Regex),
 }
 
 impl Matcher {
     fn matches(&self, text: &str) -> bool {
         match self {
-            Matcher::Exact(pattern) => text.contains(pattern),
+            Matcher::Contains(pattern) => text.contains(pattern),
+            Matcher::Equals(pattern) => text == pattern,
             Matcher::Regexp(regex) => regex.is_match(text),
         }
     }
 }
 
+/// Part of a log a filter condition looks at
+enum MatchField {
+    Message,
+    Level,
+    Attribute(String),
+}
+
+impl MatchField {
+    fn parse(field: &str) -> Result<Self> {
+        match field {
+            "message" => Ok(MatchField::Message),
+            "level" => Ok(MatchField::Level),
+            _ => match field.strip_prefix(ATTRIBUTE_FIELD_PREFIX) {
+                Some(key) if !key.is_empty() => Ok(MatchField::Attribute(key.to_string())),
+                _ => Err(anyhow!("Unknown filter field {}", field)),
+            },
+        }
+    }
+
+    fn value<'a>(&self, log: &'a LogEntry) -> Option<&'a str> {
+        match self {
+            MatchField::Message => Some(&log.message),
+            MatchField::Level => log.level.as_deref(),
+            MatchField::Attribute(key) => log.attributes.get(key).map(String::as_str),
+        }
+    }
+}
+
+/// One field and the patterns, any of which matches it
+struct Condition {
+    field: MatchField,
+    matchers: Vec<Matcher>,
+}
+
+impl Condition {
+    fn new(config: &MatchConfig) -> Result<Self> {
+        let field = MatchField::parse(&config.field)?;
+        let matchers = match config.match_type {
+            MatchType::Exact => config.exact
+                .iter()
+                .flatten()
+                .map(|pattern| match field {
+                    // Messages keep their historical substring match
+                    MatchField::Message => Matcher::Contains(pattern.clone()),
+                    _ => Matcher::Equals(pattern.clone()),
+                })
+                .collect(),
+            MatchType::Regexp => config.regexp
+                .iter()
+                .flatten()
+                .map(|pattern| Ok(Matcher::Regexp(Regex::new(pattern)?)))
+                .collect::<Result<_>>()?,
+        };
+        Ok(Self { field, matchers })
+    }
+
+    /// A missing level or attribute matches nothing
+    fn matches(&self, log: &LogEntry) -> bool {
+        self.field
+            .value(log)
+            .is_some_and(|value| self.matchers.iter().any(|matcher| matcher.matches(value)))
+    }
+}
+
+/// Conditions combined as configured
+struct Rule {
+    all: bool,
+    conditions: Vec<Condition>,
+}
+
+impl Rule {
+    /// Compile a rule, or `None` when it lists no patterns at all and so filters nothing
+    fn new(config: &MatchRule) -> Result<Option<Self>> {
+        let conditions = config
+            .conditions()
+            .iter()
+            .map(Condition::new)
+            .filter(|condition| condition.as_ref().map_or(true, |condition| !condition.matchers.is_empty()))
+            .collect::<Result<Vec<_>>>()?;
+        let all = matches!(config, MatchRule::All { .. });
+
+        Ok((!conditions.is_empty()).then_some(Self { all, conditions }))
+    }
+
+    fn matches(&self, log: &LogEntry) -> bool {
+        if self.all {
+            self.conditions.iter().all(|condition| condition.matches(log))
+        } else {
+            self.conditions.iter().any(|condition| condition.matches(log))
+        }
+    }
+}
+
 impl FilterProcessor {
     /// Create a new filter processor
     pub fn new(
         name: String,
         filter: FilterConfig,
+        min_severity: Option<Severity>,
+        unknown_severity: UnknownSeverityAction,
     ) -> Result<Self> {
-        let mut include_matchers = Vec::new();
-        let mut exclude_matchers = Vec::new();
-
-        // Setup include matchers
-        if let Some(include) = &filter.include {
-            match include.match_type {
-                MatchType::Exact => {
-                    if let Some(patterns) = &include.exact {
-                        for pattern in patterns {
-                            include_matchers.push(Matcher::Exact(pattern.clone()));
-                        }
-                    }
-                },
-                MatchType::Regexp => {
-                    if let Some(patterns) = &include.regexp {
-                        for pattern in patterns {
-                            let regex = Regex::new(pattern)?;
-                            include_matchers.push(Matcher::Regexp(regex));
-                        }
-                    }
-                },
-            }
-        }
-
-        // Setup exclude matchers
-        if let Some(exclude) = &filter.exclude {
-            match exclude.match_type {
-                MatchType::Exact => {
-                    if let Some(patterns) = &exclude.exact {
-                        for pattern in patterns {
-                            exclude_matchers.push(Matcher::Exact(pattern.clone()));
-                        }
-                    }
-                },
-                MatchType::Regexp => {
-                    if let Some(patterns) = &exclude.regexp {
-                        for pattern in patterns {
-                            let regex = Regex::new(pattern)?;
-                            exclude_matchers.push(Matcher::Regexp(regex));
-                        }
-                    }
-                },
-            }
-        }
+        let include = filter.include.as_ref().map(Rule::new).transpose()?.flatten();
+        let exclude = filter.exclude.as_ref().map(Rule::new).transpose()?.flatten();
 
         Ok(Self {
             name,
-            filter,
-            include_matchers,
-            exclude_matchers,
+            include,
+            exclude,
+            min_severity,
+            unknown_severity,
         })
     }
+
+    /// Whether the log's level reaches `min_severity`, if one is set
+    fn severe_enough(&self, log: &LogEntry) -> bool {
+        let min_severity = match self.min_severity {
+            Some(min_severity) => min_severity,
+            None => return true,
+        };
+
+        match log.level.as_deref().and_then(Severity::parse) {
+            Some(severity) => severity >= min_severity,
+            None => self.unknown_severity == UnknownSeverityAction::Keep,
+        }
+    }
 }
 
 #[async_trait]
 impl LogProcessor for FilterProcessor {
     async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
-        let message = &log.message;
-
-        // Check exclude patterns first (if any log matches an exclude pattern, drop the log)
-        for matcher in &self.exclude_matchers {
-            if matcher.matches(message) {
-                return Ok(None);
-            }
+        if !self.severe_enough(&log) {
+            return Ok(None);
         }
 
-        // If there are include patterns, the log must match at least one to be included
-        if !self.include_matchers.is_empty() {
-            let mut included = false;
-
-            for matcher in &self.include_matchers {
-                if matcher.matches(message) {
-                    included = true;
-                    break;
-                }
-            }
+        // Check the exclude rule next (a log matching it is dropped)
+        if self.exclude.as_ref().map_or(false, |rule| rule.matches(&log)) {
+            return Ok(None);
+        }
 
-            if !included {
-                return Ok(None);
-            }
+        // With an include rule, the log must match it to be kept
+        if self.include.as_ref().map_or(false, |rule| !rule.matches(&log)) {
+            return Ok(None);
         }
 
         // If we get here, the log passed all filters
@@ -242,10 +452,27 @@ impl LogProcessor for FilterProcessor {
 }
 
 /// Batch processor groups logs for efficient transmission
+///
+/// Entries are held until `batch_size` have accumulated, or until `timeout`
+/// has passed since the first entry of the current batch; the whole batch is
+/// then released at once so exporters receive it as one payload. With
+/// `timeout_jitter`, each batch's timeout is spread randomly by that fraction
+/// so collectors started together do not release in lockstep.
 pub struct BatchProcessor {
     name: String,
     timeout: Duration,
+    timeout_jitter: f64,
     batch_size: usize,
+    pending: Mutex<PendingBatch>,
+    pending_len: AtomicUsize,
+}
+
+/// Entries collected for the next batch
+#[derive(Default)]
+struct PendingBatch {
+    logs: Vec<LogEntry>,
+    /// When the batch is released even if it is not full
+    deadline: Option<Instant>,
 }
 
 impl BatchProcessor {
@@ -254,11 +481,15 @@ impl BatchProcessor {
         name: String,
         timeout_seconds: u64,
         batch_size: usize,
+        timeout_jitter: f64,
     ) -> Result<Self> {
         Ok(Self {
             name,
             timeout: Duration::from_secs(timeout_seconds),
+            timeout_jitter,
             batch_size,
+            pending: Mutex::new(PendingBatch::default()),
+            pending_len: AtomicUsize::new(0),
         })
     }
 }
@@ -266,10 +497,37 @@ impl BatchProcessor {
 #[async_trait]
 impl LogProcessor for BatchProcessor {
     async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
-        // The batch processor just passes logs through in this simple implementation
-        // In a real implementation, it would buffer logs and only release them when the batch is full
-        // or when the timeout expires
-        Ok(Some(log))
+        let mut pending = self.pending.lock().await;
+
+        if pending.deadline.is_none() {
+            pending.deadline = Some(Instant::now() + backoff::jitter(self.timeout, self.timeout_jitter));
+        }
+        pending.logs.push(log);
+        self.pending_len.store(pending.logs.len(), Ordering::Relaxed);
+
+        Ok(None)
+    }
+
+    async fn flush(&self, force: bool) -> Result<Vec<LogEntry>> {
+        let mut pending = self.pending.lock().await;
+
+        let expired = pending.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if pending.logs.is_empty() || !(force || expired || pending.logs.len() >= self.batch_size) {
+            return Ok(Vec::new());
+        }
+
+        let released = std::mem::take(&mut *pending);
+        self.pending_len.store(0, Ordering::Relaxed);
+
+        Ok(released.logs)
+    }
+
+    fn flush_ready(&self) -> bool {
+        self.pending_len.load(Ordering::Relaxed) >= self.batch_size
+    }
+
+    fn holds_entries(&self) -> bool {
+        true
     }
 
     fn name(&self) -> &str {
@@ -312,8 +570,8 @@ impl TransformProcessor {
     /// Apply mask transformation
     fn apply_mask(&self, value: &str, field: &str, parameters: &HashMap<String, String>) -> String {
         if let Some(regex) = self.regexes.get(field) {
-            let replacement = parameters.get("replacement").unwrap_or(&"*****".to_string());
-            regex.replace_all(value, replacement.as_str()).to_string()
+            let replacement = parameters.get("replacement").map_or("*****", String::as_str);
+            regex.replace_all(value, replacement).to_string()
         } else {
             value.to_string()
         }
@@ -330,13 +588,14 @@ impl TransformProcessor {
                 return Ok(());
             };
 
-            if let Some(captures) = regex.captures(value) {
-                for name in regex.capture_names().flatten() {
-                    if let Some(m) = captures.name(name) {
-                        log.attributes.insert(name.to_string(), m.as_str().to_string());
-                    }
-                }
-            }
+            let extracted: Vec<(String, String)> = match regex.captures(value) {
+                Some(captures) => regex.capture_names()
+                    .flatten()
+                    .filter_map(|name| captures.name(name).map(|m| (name.to_string(), m.as_str().to_string())))
+                    .collect(),
+                None => return Ok(()),
+            };
+            log.attributes.extend(extracted);
         }
 
         Ok(())
@@ -387,3 +646,2475 @@ impl LogProcessor for TransformProcessor {
         &self.name
     }
 }
+
+/// Coalesce processor collapses identical multi-line messages within a window
+///
+/// Stack traces repeated across threads arrive as identical assembled blocks.
+/// Each distinct block is held for `window`; repeats from the same source
+/// only bump its `occurrence_count`. Single-line messages pass straight
+/// through.
+pub struct CoalesceProcessor {
+    name: String,
+    window: Duration,
+    pending: Mutex<Vec<CoalescedEntry>>,
+}
+
+/// A multi-line entry held back while repeats are counted
+struct CoalescedEntry {
+    log: LogEntry,
+    count: u64,
+    first_seen: Instant,
+}
+
+impl CoalesceProcessor {
+    /// Create a new coalesce processor
+    pub fn new(
+        name: String,
+        window_seconds: u64,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            window: Duration::from_secs(window_seconds),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl LogProcessor for CoalesceProcessor {
+    async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+        if !log.message.contains('\n') {
+            return Ok(Some(log));
+        }
+
+        let mut pending = self.pending.lock().await;
+
+        let existing = pending.iter_mut().find(|entry| {
+            entry.first_seen.elapsed() < self.window
+                && entry.log.source == log.source
+                && entry.log.message == log.message
+        });
+
+        match existing {
+            Some(entry) => entry.count += 1,
+            None => pending.push(CoalescedEntry {
+                log,
+                count: 1,
+                first_seen: Instant::now(),
+            }),
+        }
+
+        Ok(None)
+    }
+
+    async fn flush(&self, force: bool) -> Result<Vec<LogEntry>> {
+        let mut pending = self.pending.lock().await;
+        let mut released = Vec::new();
+
+        let mut index = 0;
+        while index < pending.len() {
+            if force || pending[index].first_seen.elapsed() >= self.window {
+                let entry = pending.remove(index);
+                let mut log = entry.log;
+                log.attributes.insert("occurrence_count".to_string(), entry.count.to_string());
+                released.push(log);
+            } else {
+                index += 1;
+            }
+        }
+
+        Ok(released)
+    }
+
+    fn holds_entries(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Level prefix processor extracts a leading level token such as `[ERROR]` or `WARN:`
+///
+/// Only entries whose `level` is still unset are touched, so levels reported
+/// by structured sources always win over text heuristics.
+pub struct LevelPrefixProcessor {
+    name: String,
+    tokens: HashSet<String>,
+    strip: bool,
+    prefix_regex: Regex,
+}
+
+impl LevelPrefixProcessor {
+    /// Create a new level prefix processor
+    pub fn new(
+        name: String,
+        tokens: Vec<String>,
+        strip: bool,
+    ) -> Result<Self> {
+        let tokens = tokens
+            .iter()
+            .map(|token| token.to_ascii_uppercase())
+            .collect();
+
+        Ok(Self {
+            name,
+            tokens,
+            strip,
+            prefix_regex: Regex::new(r"^\s*(?:\[([A-Za-z]+)\]:?|([A-Za-z]+):)\s*")?,
+        })
+    }
+}
+
+#[async_trait]
+impl LogProcessor for LevelPrefixProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        if log.level.is_some() {
+            return Ok(Some(log));
+        }
+
+        let extracted = self.prefix_regex.captures(&log.message).and_then(|captures| {
+            let token = captures.get(1).or_else(|| captures.get(2))?;
+            let level = token.as_str().to_ascii_uppercase();
+
+            if self.tokens.contains(&level) {
+                Some((level, captures.get(0)?.end()))
+            } else {
+                None
+            }
+        });
+
+        if let Some((level, end)) = extracted {
+            if self.strip {
+                log.message = log.message[end..].to_string();
+            }
+            log.level = Some(level);
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Require attributes processor checks logs against a set of mandatory attribute keys
+pub struct RequireAttributesProcessor {
+    name: String,
+    required: Vec<String>,
+    on_missing: MissingAttributeAction,
+    defaults: HashMap<String, String>,
+}
+
+impl RequireAttributesProcessor {
+    /// Create a new require attributes processor
+    pub fn new(
+        name: String,
+        required: Vec<String>,
+        on_missing: MissingAttributeAction,
+        defaults: HashMap<String, String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            required,
+            on_missing,
+            defaults,
+        })
+    }
+}
+
+#[async_trait]
+impl LogProcessor for RequireAttributesProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let mut missing: Vec<&str> = self.required
+            .iter()
+            .filter(|key| !log.attributes.contains_key(*key))
+            .map(|key| key.as_str())
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(Some(log));
+        }
+
+        match self.on_missing {
+            MissingAttributeAction::Drop => return Ok(None),
+            MissingAttributeAction::Tag => {},
+            MissingAttributeAction::Default => {
+                missing.retain(|key| match self.defaults.get(*key) {
+                    Some(value) => {
+                        log.attributes.insert(key.to_string(), value.clone());
+                        false
+                    },
+                    None => true,
+                });
+            },
+        }
+
+        if !missing.is_empty() {
+            log.attributes.insert("schema_violation".to_string(), missing.join(","));
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Partition processor writes time partition fields for date-partitioned sinks
+///
+/// Fields are zero-padded (`2024`, `03`, `09`, `07`) so they can be dropped
+/// straight into object paths or index names.
+pub struct PartitionProcessor {
+    name: String,
+    timezone: Tz,
+}
+
+impl PartitionProcessor {
+    /// Create a new partition processor for an IANA timezone name
+    pub fn new(
+        name: String,
+        timezone: &str,
+    ) -> Result<Self> {
+        let timezone = timezone
+            .parse::<Tz>()
+            .map_err(|e| anyhow!("Invalid timezone {}: {}", timezone, e))?;
+
+        Ok(Self {
+            name,
+            timezone,
+        })
+    }
+}
+
+#[async_trait]
+impl LogProcessor for PartitionProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let local = log.timestamp.with_timezone(&self.timezone);
+
+        log.attributes.insert("year".to_string(), local.format("%Y").to_string());
+        log.attributes.insert("month".to_string(), local.format("%m").to_string());
+        log.attributes.insert("day".to_string(), local.format("%d").to_string());
+        log.attributes.insert("hour".to_string(), local.format("%H").to_string());
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Fields extracted from a line by one of the language parsers
+struct ParsedLine {
+    timestamp: Option<DateTime<Utc>>,
+    level: Option<String>,
+    module: Option<String>,
+    message: String,
+    attributes: HashMap<String, String>,
+}
+
+/// Language parser processor extracts level, timestamp, module and message
+/// from the default output of common Go, Rust and Python loggers
+///
+/// Python `asctime` carries no zone and is taken as UTC. Lines matching none
+/// of the configured formats pass through unchanged.
+pub struct LanguageParserProcessor {
+    name: String,
+    formats: Vec<LanguageFormat>,
+    tracing_regex: Regex,
+    env_logger_regex: Regex,
+    python_basic_regex: Regex,
+    python_asctime_regex: Regex,
+}
+
+impl LanguageParserProcessor {
+    /// Create a new language parser processor
+    pub fn new(
+        name: String,
+        formats: Vec<LanguageFormat>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            formats,
+            // 2024-03-09T07:45:00.123456Z  INFO my_crate::server: request handled
+            tracing_regex: Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+([\w:]+)(?:\{[^}]*\})*:\s(.*)$")?,
+            // [2024-03-09T07:45:00Z INFO  my_crate::server] request handled
+            env_logger_regex: Regex::new(r"^\[(\d{4}-\d{2}-\d{2}T\S+)\s+(TRACE|DEBUG|INFO|WARN|ERROR)\s+([^\]\s]+)\]\s(.*)$")?,
+            // WARNING:app.db:slow query
+            python_basic_regex: Regex::new(r"^(DEBUG|INFO|WARNING|ERROR|CRITICAL):([^:]+):(.*)$")?,
+            // 2024-03-09 07:45:00,123 - app.db - WARNING - slow query
+            python_asctime_regex: Regex::new(r"^(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2},\d{3}) - (\S+) - (DEBUG|INFO|WARNING|ERROR|CRITICAL) - (.*)$")?,
+        })
+    }
+
+    fn parse(&self, format: LanguageFormat, line: &str) -> Option<ParsedLine> {
+        match format {
+            LanguageFormat::GoJson => parse_go_json(line),
+            LanguageFormat::RustTracing => {
+                let captures = self.tracing_regex.captures(line)?;
+                Some(ParsedLine {
+                    timestamp: parse_rfc3339(&captures[1]),
+                    level: Some(captures[2].to_string()),
+                    module: Some(captures[3].to_string()),
+                    message: captures[4].to_string(),
+                    attributes: HashMap::new(),
+                })
+            },
+            LanguageFormat::EnvLogger => {
+                let captures = self.env_logger_regex.captures(line)?;
+                Some(ParsedLine {
+                    timestamp: parse_rfc3339(&captures[1]),
+                    level: Some(captures[2].to_string()),
+                    module: Some(captures[3].to_string()),
+                    message: captures[4].to_string(),
+                    attributes: HashMap::new(),
+                })
+            },
+            LanguageFormat::Python => {
+                if let Some(captures) = self.python_asctime_regex.captures(line) {
+                    let timestamp = NaiveDateTime::parse_from_str(&captures[1], "%Y-%m-%d %H:%M:%S,%3f")
+                        .ok()
+                        .map(|naive| Utc.from_utc_datetime(&naive));
+
+                    return Some(ParsedLine {
+                        timestamp,
+                        level: Some(captures[3].to_string()),
+                        module: Some(captures[2].to_string()),
+                        message: captures[4].to_string(),
+                        attributes: HashMap::new(),
+                    });
+                }
+
+                let captures = self.python_basic_regex.captures(line)?;
+                Some(ParsedLine {
+                    timestamp: None,
+                    level: Some(captures[1].to_string()),
+                    module: Some(captures[2].to_string()),
+                    message: captures[3].to_string(),
+                    attributes: HashMap::new(),
+                })
+            },
+        }
+    }
+}
+
+/// Parse an RFC 3339 timestamp into UTC
+fn parse_rfc3339(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Parse a Go `slog` or `logrus` JSON line; other fields become attributes
+fn parse_go_json(line: &str) -> Option<ParsedLine> {
+    let serde_json::Value::Object(mut fields) = serde_json::from_str(line.trim()).ok()? else {
+        return None;
+    };
+
+    let message = match fields.remove("msg")? {
+        serde_json::Value::String(message) => message,
+        _ => return None,
+    };
+
+    let timestamp = fields.remove("time")
+        .and_then(|time| time.as_str().and_then(parse_rfc3339));
+    let level = fields.remove("level")
+        .and_then(|level| level.as_str().map(|level| level.to_ascii_uppercase()));
+    let module = fields.remove("logger")
+        .and_then(|logger| logger.as_str().map(|logger| logger.to_string()));
+
+    let attributes = fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match value {
+            serde_json::Value::String(text) => (key, text),
+            other => (key, other.to_string()),
+        })
+        .collect();
+
+    Some(ParsedLine {
+        timestamp,
+        level,
+        module,
+        message,
+        attributes,
+    })
+}
+
+#[async_trait]
+impl LogProcessor for LanguageParserProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let parsed = self.formats
+            .iter()
+            .find_map(|format| self.parse(*format, &log.message));
+
+        if let Some(parsed) = parsed {
+            if let Some(timestamp) = parsed.timestamp {
+                log.timestamp = timestamp;
+            }
+            if parsed.level.is_some() {
+                log.level = parsed.level;
+            }
+            if let Some(module) = parsed.module {
+                log.attributes.insert("module".to_string(), module);
+            }
+            for (key, value) in parsed.attributes {
+                log.attributes.entry(key).or_insert(value);
+            }
+            log.message = parsed.message;
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Binary filter processor catches binary data read by misconfigured sources
+pub struct BinaryFilterProcessor {
+    name: String,
+    min_printable_ratio: f64,
+    action: BinaryAction,
+}
+
+impl BinaryFilterProcessor {
+    /// Create a new binary filter processor
+    pub fn new(
+        name: String,
+        min_printable_ratio: f64,
+        action: BinaryAction,
+    ) -> Result<Self> {
+        if !(0.0..=1.0).contains(&min_printable_ratio) {
+            return Err(anyhow!("min_printable_ratio must be between 0.0 and 1.0, got {}", min_printable_ratio));
+        }
+
+        Ok(Self {
+            name,
+            min_printable_ratio,
+            action,
+        })
+    }
+}
+
+/// Score how much a message looks like text, from 0.0 (binary) to 1.0
+///
+/// Text logs never contain NUL, so any NUL scores 0.0. Otherwise the score is
+/// the share of characters that are neither control characters (other than
+/// whitespace) nor the U+FFFD left behind by invalid UTF-8.
+pub fn textiness(message: &str) -> f64 {
+    if message.contains('\0') {
+        return 0.0;
+    }
+
+    let mut total = 0usize;
+    let mut printable = 0usize;
+
+    for c in message.chars() {
+        total += 1;
+        let is_garbage = c == char::REPLACEMENT_CHARACTER || (c.is_control() && !matches!(c, '\t' | '\n' | '\r'));
+        if !is_garbage {
+            printable += 1;
+        }
+    }
+
+    if total == 0 {
+        1.0
+    } else {
+        printable as f64 / total as f64
+    }
+}
+
+#[async_trait]
+impl LogProcessor for BinaryFilterProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let score = textiness(&log.message);
+        if score >= self.min_printable_ratio {
+            return Ok(Some(log));
+        }
+
+        match self.action {
+            BinaryAction::Drop => Ok(None),
+            BinaryAction::Tag => {
+                log.attributes.insert("binary_content".to_string(), "true".to_string());
+                log.attributes.insert("textiness".to_string(), format!("{:.2}", score));
+                Ok(Some(log))
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Weight of the newest window in the smoothed rate
+const ANOMALY_SMOOTHING: f64 = 0.3;
+
+/// Windows observed before a source's baseline is trusted
+const ANOMALY_WARMUP_WINDOWS: u32 = 5;
+
+/// Normalize level processor rewrites level spellings to canonical names
+///
+/// Unknown levels are left as they are. Place it ahead of exporters so their
+/// severity mapping only ever sees canonical names.
+pub struct NormalizeLevelProcessor {
+    name: String,
+    aliases: LevelAliases,
+}
+
+impl NormalizeLevelProcessor {
+    /// Create a new normalize level processor with extra `aliases`
+    pub fn new(name: String, aliases: &HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            name,
+            aliases: LevelAliases::new(aliases)?,
+        })
+    }
+}
+
+#[async_trait]
+impl LogProcessor for NormalizeLevelProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        if let Some(canonical) = log.level.as_deref().and_then(|level| self.aliases.normalize(level)) {
+            log.level = Some(canonical.to_string());
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Source template processor composes `source` from attributes
+///
+/// The template mixes literal text with `{key}` placeholders, so
+/// `{tenant}/{service}/{component}` yields `acme/checkout/api`. When a
+/// referenced attribute is missing or empty the source becomes `fallback`,
+/// or stays as it was if no fallback is configured. Write `{{` and `}}` for
+/// literal braces.
+pub struct SourceTemplateProcessor {
+    name: String,
+    segments: Vec<TemplateSegment>,
+    fallback: Option<String>,
+}
+
+/// A piece of a parsed source template
+#[derive(Debug, PartialEq)]
+enum TemplateSegment {
+    Literal(String),
+    Attribute(String),
+}
+
+impl SourceTemplateProcessor {
+    /// Create a new source template processor
+    pub fn new(
+        name: String,
+        template: &str,
+        fallback: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            segments: Self::parse(template)?,
+            fallback,
+        })
+    }
+
+    /// Split a template into literal text and attribute placeholders
+    fn parse(template: &str) -> Result<Vec<TemplateSegment>> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut key = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => key.push(c),
+                            None => return Err(anyhow!("Unclosed placeholder in source template {:?}", template)),
+                        }
+                    }
+
+                    let key = key.trim();
+                    if key.is_empty() {
+                        return Err(anyhow!("Empty placeholder in source template {:?}", template));
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(TemplateSegment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(TemplateSegment::Attribute(key.to_string()));
+                },
+                '}' => return Err(anyhow!("Unmatched '}}' in source template {:?}", template)),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(TemplateSegment::Literal(literal));
+        }
+
+        Ok(segments)
+    }
+
+    /// Render the template for `log`, or `None` if an attribute is missing
+    fn render(&self, log: &LogEntry) -> Option<String> {
+        let mut source = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Literal(text) => source.push_str(text),
+                TemplateSegment::Attribute(key) => {
+                    let value = log.attributes.get(key).filter(|value| !value.is_empty())?;
+                    source.push_str(value);
+                },
+            }
+        }
+
+        Some(source)
+    }
+}
+
+#[async_trait]
+impl LogProcessor for SourceTemplateProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        if let Some(source) = self.render(&log).or_else(|| self.fallback.clone()) {
+            log.source = source;
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Rate anomaly processor tracks each source's event rate and flags outliers
+///
+/// Events are counted per source in fixed windows. Each closed window is
+/// compared with an exponentially weighted mean and variance of the earlier
+/// ones; a count more than `sensitivity` standard deviations away produces an
+/// internal `WARN` entry, released on the next flush. Logs themselves pass
+/// through unchanged.
+pub struct RateAnomalyProcessor {
+    name: String,
+    window: chrono::Duration,
+    sensitivity: f64,
+    clock: SharedClock,
+    state: Mutex<RateState>,
+}
+
+/// Per-source rate tracking and anomalies waiting to be flushed
+#[derive(Default)]
+struct RateState {
+    sources: HashMap<String, SourceRate>,
+    anomalies: Vec<LogEntry>,
+}
+
+/// Event rate statistics for one source
+struct SourceRate {
+    window_start: DateTime<Utc>,
+    count: u64,
+    mean: f64,
+    variance: f64,
+    windows: u32,
+}
+
+impl RateAnomalyProcessor {
+    /// Create a new rate anomaly processor
+    pub fn new(
+        name: String,
+        window_seconds: u64,
+        sensitivity: f64,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        if window_seconds == 0 {
+            return Err(anyhow!("window_seconds for processor {} must be at least 1", name));
+        }
+
+        Ok(Self {
+            name,
+            window: chrono::Duration::seconds(window_seconds as i64),
+            sensitivity,
+            clock,
+            state: Mutex::new(RateState::default()),
+        })
+    }
+
+    /// Close the window of `source` if it has ended, returning an anomaly entry if one fired
+    fn close_window(&self, source: &str, rate: &mut SourceRate, now: DateTime<Utc>) -> Option<LogEntry> {
+        if now - rate.window_start < self.window {
+            return None;
+        }
+
+        let count = rate.count as f64;
+        let mut anomaly = None;
+
+        if rate.windows == 0 {
+            rate.mean = count;
+        } else {
+            // Steady sources have near-zero variance; never let one event count as several deviations
+            let deviation = rate.variance.sqrt().max(1.0);
+            let score = (count - rate.mean) / deviation;
+
+            if rate.windows >= ANOMALY_WARMUP_WINDOWS && score.abs() > self.sensitivity {
+                anomaly = Some(self.anomaly_entry(source, count, rate.mean, score));
+            }
+
+            let difference = count - rate.mean;
+            rate.mean += ANOMALY_SMOOTHING * difference;
+            rate.variance = (1.0 - ANOMALY_SMOOTHING) * (rate.variance + ANOMALY_SMOOTHING * difference * difference);
+        }
+
+        // Idle gaps are not counted as empty windows; the next window starts now
+        rate.windows += 1;
+        rate.count = 0;
+        rate.window_start = now;
+
+        anomaly
+    }
+
+    fn anomaly_entry(&self, source: &str, count: f64, expected: f64, score: f64) -> LogEntry {
+        let mut log = internal_entry(
+            "WARN",
+            format!(
+                "Event rate anomaly for source {}: {} events in {}s, expected about {:.1}",
+                source,
+                count,
+                self.window.num_seconds(),
+                expected,
+            ),
+        );
+
+        log.attributes.insert("anomaly".to_string(), "rate".to_string());
+        log.attributes.insert("anomaly.source".to_string(), source.to_string());
+        log.attributes.insert("anomaly.count".to_string(), count.to_string());
+        log.attributes.insert("anomaly.expected".to_string(), format!("{:.1}", expected));
+        log.attributes.insert("anomaly.score".to_string(), format!("{:.1}", score));
+        log
+    }
+}
+
+#[async_trait]
+impl LogProcessor for RateAnomalyProcessor {
+    async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+        if is_internal(&log) {
+            return Ok(Some(log));
+        }
+
+        let now = self.clock.now();
+        let mut state = self.state.lock().await;
+        let RateState { sources, anomalies } = &mut *state;
+
+        let rate = sources.entry(log.source.clone()).or_insert_with(|| SourceRate {
+            window_start: now,
+            count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            windows: 0,
+        });
+
+        if let Some(anomaly) = self.close_window(&log.source, rate, now) {
+            anomalies.push(anomaly);
+        }
+        rate.count += 1;
+
+        Ok(Some(log))
+    }
+
+    async fn flush(&self, _force: bool) -> Result<Vec<LogEntry>> {
+        let now = self.clock.now();
+        let mut state = self.state.lock().await;
+        let RateState { sources, anomalies } = &mut *state;
+
+        // Quiet periods still close windows, so a source dropping off is noticed
+        for (source, rate) in sources.iter_mut() {
+            if let Some(anomaly) = self.close_window(source, rate, now) {
+                anomalies.push(anomaly);
+            }
+        }
+
+        Ok(std::mem::take(anomalies))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Bucket processor maps a numeric attribute to a categorical range label
+///
+/// With boundaries `[10, 100]` and unit `ms`, values map to `<10ms`,
+/// `10-100ms` and `>=100ms`. Each bucket includes its lower bound, so `10`
+/// lands in `10-100ms`. Entries whose attribute is missing or not a number
+/// pass through unlabelled.
+pub struct BucketProcessor {
+    name: String,
+    attribute: String,
+    target: String,
+    boundaries: Vec<f64>,
+    /// One label per bucket, `boundaries.len() + 1` in all
+    labels: Vec<String>,
+}
+
+impl BucketProcessor {
+    /// Create a new bucket processor
+    pub fn new(
+        name: String,
+        attribute: String,
+        target: Option<String>,
+        boundaries: Vec<f64>,
+        unit: &str,
+    ) -> Result<Self> {
+        if boundaries.is_empty() {
+            return Err(anyhow!("Bucket processor {} needs at least one boundary", name));
+        }
+        if boundaries.iter().any(|bound| !bound.is_finite()) || boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(anyhow!("Bucket processor {} boundaries must be finite and strictly ascending", name));
+        }
+
+        let mut labels = vec![format!("<{}{}", boundaries[0], unit)];
+        labels.extend(boundaries.windows(2).map(|pair| format!("{}-{}{}", pair[0], pair[1], unit)));
+        labels.push(format!(">={}{}", boundaries[boundaries.len() - 1], unit));
+
+        Ok(Self {
+            target: target.unwrap_or_else(|| format!("{}.bucket", attribute)),
+            name,
+            attribute,
+            boundaries,
+            labels,
+        })
+    }
+
+    /// Label of the bucket `value` falls in
+    fn label(&self, value: f64) -> &str {
+        &self.labels[self.boundaries.partition_point(|bound| *bound <= value)]
+    }
+}
+
+#[async_trait]
+impl LogProcessor for BucketProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let value = log.attributes
+            .get(&self.attribute)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| !value.is_nan());
+
+        if let Some(value) = value {
+            let label = self.label(value).to_string();
+            log.attributes.insert(self.target.clone(), label);
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Minimum time between reports of entries dropped by a sample processor
+const SAMPLE_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most keys a sample processor tracks; the longest idle is forgotten for a new one
+const MAX_SAMPLE_KEYS: usize = 10_000;
+
+/// Time after which a sample processor forgets a key it has not seen
+const SAMPLE_KEY_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How a sample processor decides which entries to keep
+#[derive(Debug, Clone, Copy)]
+enum Sampling {
+    /// Keep the first entry of every `n`
+    OneIn(u64),
+    /// Keep entries while a token bucket has tokens
+    Limit { rate_per_second: u32, burst: u32 },
+}
+
+/// Sampling progress for one key
+enum SampleProgress {
+    /// Entries seen so far, of which every `n`th is kept
+    OneIn { n: u64, seen: u64 },
+    Limit(TokenBucket),
+}
+
+/// Sampling progress for one key, and when the key was last seen
+struct SampleState {
+    progress: SampleProgress,
+    last_seen: Instant,
+}
+
+/// Sample processor keeps a fixed share of entries, or at most a fixed rate
+///
+/// With `key` set, each source (`key: source`) or each value of the named
+/// attribute is sampled on its own, so one noisy service cannot use up the
+/// budget of the others; entries without the attribute share one budget.
+/// Keys idle for five minutes are forgotten, and at most 10,000 are tracked,
+/// so a high-cardinality key starts a forgotten value over with a fresh budget
+/// rather than growing without bound. Dropped entries are counted and
+/// reported at most once a minute.
+pub struct SampleProcessor {
+    name: String,
+    sampling: Sampling,
+    key: Option<String>,
+    state: Mutex<HashMap<String, SampleState>>,
+    dropped: AtomicU64,
+    /// When drops were last reported, and the drop count at the time
+    last_report: std::sync::Mutex<(Instant, u64)>,
+}
+
+impl SampleProcessor {
+    /// Create a new sample processor
+    pub fn new(
+        name: String,
+        one_in: Option<u64>,
+        rate_per_second: Option<u32>,
+        burst: Option<u32>,
+        key: Option<String>,
+    ) -> Result<Self> {
+        let sampling = match (one_in, rate_per_second) {
+            (Some(0), None) => return Err(anyhow!("one_in for processor {} must be at least 1", name)),
+            (Some(n), None) => Sampling::OneIn(n),
+            (None, Some(0)) => return Err(anyhow!("rate_per_second for processor {} must be at least 1", name)),
+            (None, Some(rate_per_second)) => Sampling::Limit {
+                rate_per_second,
+                burst: burst.unwrap_or(rate_per_second).max(1),
+            },
+            _ => return Err(anyhow!("Sample processor {} needs exactly one of one_in or rate_per_second", name)),
+        };
+
+        Ok(Self {
+            name,
+            sampling,
+            key,
+            state: Mutex::new(HashMap::new()),
+            dropped: AtomicU64::new(0),
+            last_report: std::sync::Mutex::new((Instant::now(), 0)),
+        })
+    }
+
+    /// Key whose budget `log` draws on
+    fn key_of(&self, log: &LogEntry) -> String {
+        match self.key.as_deref() {
+            None => String::new(),
+            Some("source") => log.source.clone(),
+            Some(attribute) => log.attributes.get(attribute).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Number of entries dropped so far
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Forget keys not seen for [`SAMPLE_KEY_IDLE_TIMEOUT`]
+    fn forget_idle(state: &mut HashMap<String, SampleState>, now: Instant) {
+        state.retain(|_, key| now.saturating_duration_since(key.last_seen) < SAMPLE_KEY_IDLE_TIMEOUT);
+    }
+
+    /// Make room for a new key: forget idle keys, then the longest idle if still full
+    fn evict(state: &mut HashMap<String, SampleState>, now: Instant) {
+        Self::forget_idle(state, now);
+        if state.len() < MAX_SAMPLE_KEYS {
+            return;
+        }
+
+        let oldest = state.iter().min_by_key(|(_, key)| key.last_seen).map(|(name, _)| name.clone());
+        if let Some(oldest) = oldest {
+            state.remove(&oldest);
+        }
+    }
+}
+
+#[async_trait]
+impl LogProcessor for SampleProcessor {
+    async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+        let now = Instant::now();
+        let key = self.key_of(&log);
+        let mut state = self.state.lock().await;
+        if !state.contains_key(&key) && state.len() >= MAX_SAMPLE_KEYS {
+            Self::evict(&mut state, now);
+        }
+
+        let entry = state.entry(key).or_insert_with(|| SampleState {
+            progress: match self.sampling {
+                Sampling::OneIn(n) => SampleProgress::OneIn { n, seen: 0 },
+                Sampling::Limit { rate_per_second, burst } => SampleProgress::Limit(TokenBucket::new(rate_per_second, burst, now)),
+            },
+            last_seen: now,
+        });
+        entry.last_seen = now;
+
+        let keep = match &mut entry.progress {
+            SampleProgress::OneIn { n, seen } => {
+                let keep = *seen % *n == 0;
+                *seen += 1;
+                keep
+            },
+            SampleProgress::Limit(bucket) => bucket.try_acquire(now),
+        };
+
+        if keep {
+            Ok(Some(log))
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            Ok(None)
+        }
+    }
+
+    async fn flush(&self, force: bool) -> Result<Vec<LogEntry>> {
+        Self::forget_idle(&mut *self.state.lock().await, Instant::now());
+
+        let mut last_report = self.last_report.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (reported_at, reported) = *last_report;
+        if force || reported_at.elapsed() >= SAMPLE_REPORT_INTERVAL {
+            let dropped = self.dropped_count();
+            if dropped > reported {
+                tracing::info!(
+                    "Sample processor {} dropped {} entries in the last {:?} ({} in all)",
+                    self.name, dropped - reported, reported_at.elapsed(), dropped,
+                );
+            }
+            *last_report = (Instant::now(), dropped);
+        }
+
+        Ok(Vec::new())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Item of a bracketed list
+#[derive(Debug, Clone, PartialEq)]
+enum ListItem {
+    Value(String),
+    List(Vec<ListItem>),
+}
+
+impl ListItem {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ListItem::Value(value) => serde_json::Value::String(value.clone()),
+            ListItem::List(items) => items.iter().map(ListItem::to_json).collect(),
+        }
+    }
+}
+
+/// Lists nested deeper than this are kept as their plain text
+const MAX_LIST_DEPTH: usize = 32;
+
+/// Parse the bracketed list at the start of `input`, returning it and the number of bytes it spans
+///
+/// Items are separated by commas and may be nested lists or quoted with `"`
+/// or `'` (with `\` escapes) to contain commas and brackets; unquoted items
+/// are trimmed. A list nested more than [`MAX_LIST_DEPTH`] deep is an item
+/// holding its text, brackets included.
+fn parse_list(input: &str) -> Option<(Vec<ListItem>, usize)> {
+    parse_list_at(input, 1).ok()
+}
+
+/// Parse the list at the start of `input`, `depth` lists deep, or give the offset parsing stopped at
+fn parse_list_at(input: &str, depth: usize) -> std::result::Result<(Vec<ListItem>, usize), usize> {
+    let end = input.len();
+    let mut chars = input.char_indices().peekable();
+    if chars.next().ok_or(end)?.1 != '[' {
+        return Err(0);
+    }
+
+    let mut items = Vec::new();
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = *chars.peek().ok_or(end)?;
+
+        let item = match c {
+            ']' if items.is_empty() => {
+                chars.next();
+                return Ok((items, start + 1));
+            },
+            '[' if depth >= MAX_LIST_DEPTH => {
+                let length = bracketed_length(&input[start..]).ok_or(end)?;
+                while chars.next_if(|(i, _)| *i < start + length).is_some() {}
+                ListItem::Value(input[start..start + length].to_string())
+            },
+            '[' => {
+                let (nested, length) = parse_list_at(&input[start..], depth + 1).map_err(|stop| start + stop)?;
+                while chars.next_if(|(i, _)| *i < start + length).is_some() {}
+                ListItem::List(nested)
+            },
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or(end)? {
+                        (_, '\\') => value.push(chars.next().ok_or(end)?.1),
+                        (_, quote) if quote == c => break,
+                        (_, other) => value.push(other),
+                    }
+                }
+                ListItem::Value(value)
+            },
+            _ => {
+                let mut value = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| !matches!(c, ',' | ']' | '[')) {
+                    value.push(c);
+                }
+                ListItem::Value(value.trim().to_string())
+            },
+        };
+        items.push(item);
+
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            Some((_, ',')) => continue,
+            Some((close, ']')) => return Ok((items, close + 1)),
+            Some((stop, _)) => return Err(stop),
+            None => return Err(end),
+        }
+    }
+}
+
+/// Length of the bracketed text at the start of `input`, through the bracket closing it
+fn bracketed_length(input: &str) -> Option<usize> {
+    let mut open = 0usize;
+    for (i, c) in input.char_indices() {
+        match c {
+            '[' => open += 1,
+            ']' => {
+                open -= 1;
+                if open == 0 {
+                    return Some(i + 1);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Expand list processor turns bracketed lists into attributes
+///
+/// With `attribute` set, that attribute's value is expanded in place when it
+/// is a complete list. Otherwise every `key=[...]` in the message adds an
+/// attribute `key`. Lists are stored as a JSON array, or as one attribute per
+/// item (`key.0`, `key.1`, and `key.1.0` for nested lists). Text that does
+/// not parse as a list is left alone, and the search for the next `key=[`
+/// resumes where parsing stopped, so no text is scanned twice.
+pub struct ExpandListProcessor {
+    name: String,
+    attribute: Option<String>,
+    format: ListFormat,
+    pair: Regex,
+}
+
+impl ExpandListProcessor {
+    /// Create a new expand list processor
+    pub fn new(name: String, attribute: Option<String>, format: ListFormat) -> Result<Self> {
+        Ok(Self {
+            name,
+            attribute,
+            format,
+            pair: Regex::new(r"([A-Za-z_][\w.-]*)=\[")?,
+        })
+    }
+
+    /// Store `items` under `key` in the configured format
+    fn insert(&self, attributes: &mut HashMap<String, String>, key: &str, items: &[ListItem]) {
+        match self.format {
+            ListFormat::Json => {
+                let array: serde_json::Value = items.iter().map(ListItem::to_json).collect();
+                attributes.insert(key.to_string(), array.to_string());
+            },
+            ListFormat::Indexed => {
+                for (index, item) in items.iter().enumerate() {
+                    let indexed = format!("{}.{}", key, index);
+                    match item {
+                        ListItem::Value(value) => {
+                            attributes.insert(indexed, value.clone());
+                        },
+                        ListItem::List(nested) => self.insert(attributes, &indexed, nested),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Every `key=[...]` list in `message`
+    fn message_lists(&self, message: &str) -> Vec<(String, Vec<ListItem>)> {
+        let mut lists = Vec::new();
+        let mut position = 0;
+
+        while let Some(captures) = self.pair.captures_at(message, position) {
+            let whole = captures.get(0).unwrap();
+            let open = whole.end() - 1;
+            match parse_list_at(&message[open..], 1) {
+                Ok((items, length)) => {
+                    lists.push((captures[1].to_string(), items));
+                    position = open + length;
+                },
+                Err(stop) => position = (open + stop).max(whole.end()),
+            }
+        }
+
+        lists
+    }
+}
+
+#[async_trait]
+impl LogProcessor for ExpandListProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        match &self.attribute {
+            Some(attribute) => {
+                let value = log.attributes.get(attribute).map(|value| value.trim());
+                let items = value
+                    .and_then(parse_list)
+                    .filter(|(_, length)| Some(*length) == value.map(str::len))
+                    .map(|(items, _)| items);
+
+                if let Some(items) = items {
+                    log.attributes.remove(attribute);
+                    self.insert(&mut log.attributes, attribute, &items);
+                }
+            },
+            None => {
+                for (key, items) in self.message_lists(&log.message) {
+                    self.insert(&mut log.attributes, &key, &items);
+                }
+            },
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// JSON parse processor lifts the fields of a JSON log line into attributes
+///
+/// Nested objects are flattened into dotted keys (`http.status`), arrays are
+/// kept as JSON text and nulls are skipped. Attributes the entry already has
+/// are not overwritten. The configured level, timestamp and message keys are
+/// moved onto the entry rather than copied into attributes. A value that is
+/// not a JSON object leaves the entry as it was, with the reason in a
+/// `json_parse_error` attribute.
+pub struct JsonParseProcessor {
+    name: String,
+    field: String,
+    level_key: Option<String>,
+    timestamp_key: Option<String>,
+    message_key: Option<String>,
+}
+
+impl JsonParseProcessor {
+    /// Create a new JSON parse processor
+    pub fn new(
+        name: String,
+        field: String,
+        level_key: Option<String>,
+        timestamp_key: Option<String>,
+        message_key: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            field,
+            level_key,
+            timestamp_key,
+            message_key,
+        })
+    }
+
+    /// Parse the configured field into flattened fields
+    fn parse(&self, log: &LogEntry) -> Option<std::result::Result<HashMap<String, String>, String>> {
+        let text = if self.field == "message" {
+            &log.message
+        } else {
+            log.attributes.get(&self.field)?
+        };
+
+        let parsed = match serde_json::from_str(text.trim()) {
+            Ok(object @ serde_json::Value::Object(_)) => {
+                let mut fields = HashMap::new();
+                flatten_json(String::new(), object, &mut fields);
+                Ok(fields)
+            },
+            Ok(_) => Err("not a JSON object".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        Some(parsed)
+    }
+}
+
+/// Add the leaves of a JSON value to `target`, joining object keys with dots
+fn flatten_json(key: String, value: serde_json::Value, target: &mut HashMap<String, String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (field, value) in fields {
+                let nested = if key.is_empty() { field } else { format!("{}.{}", key, field) };
+                flatten_json(nested, value, target);
+            }
+        },
+        serde_json::Value::Null => {},
+        serde_json::Value::String(text) => {
+            target.insert(key, text);
+        },
+        other => {
+            target.insert(key, other.to_string());
+        },
+    }
+}
+
+/// Parse an RFC 3339 timestamp, or Unix seconds or milliseconds
+pub(crate) fn parse_json_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    parse_rfc3339(value).or_else(|| {
+        let number = value.parse::<f64>().ok().filter(|number| number.is_finite() && *number >= 0.0)?;
+
+        // As seconds, values this large would be thousands of years away
+        if number >= 1e11 {
+            Utc.timestamp_millis_opt(number as i64).single()
+        } else {
+            Utc.timestamp_opt(number.trunc() as i64, (number.fract() * 1e9) as u32).single()
+        }
+    })
+}
+
+#[async_trait]
+impl LogProcessor for JsonParseProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        let mut fields = match self.parse(&log) {
+            Some(Ok(fields)) => fields,
+            Some(Err(error)) => {
+                log.attributes.insert("json_parse_error".to_string(), error);
+                return Ok(Some(log));
+            },
+            None => return Ok(Some(log)),
+        };
+
+        if let Some(level) = self.level_key.as_ref().and_then(|key| fields.remove(key)) {
+            log.level = Some(level);
+        }
+        if let Some(key) = &self.timestamp_key {
+            if let Some(timestamp) = fields.get(key).and_then(|value| parse_json_timestamp(value)) {
+                fields.remove(key);
+                log.timestamp = timestamp;
+            }
+        }
+        if let Some(message) = self.message_key.as_ref().and_then(|key| fields.remove(key)) {
+            log.message = message;
+        }
+
+        for (key, value) in fields {
+            log.attributes.entry(key).or_insert(value);
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Strip ANSI processor removes escape sequences left by programs writing to a terminal
+///
+/// Removes CSI sequences (colors such as `\x1b[31m`, cursor movement, line
+/// erasing), OSC sequences (window titles, hyperlinks) and two-character
+/// escapes such as `\x1b7`, keeping the text around them.
+pub struct StripAnsiProcessor {
+    name: String,
+    attributes: bool,
+    escapes: Regex,
+}
+
+impl StripAnsiProcessor {
+    /// Create a new strip ANSI processor
+    pub fn new(name: String, attributes: bool) -> Result<Self> {
+        Ok(Self {
+            name,
+            attributes,
+            escapes: Regex::new(r"\x1b(?:\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[0-~])")?,
+        })
+    }
+
+    /// `text` without escape sequences, or `None` when it has none
+    fn strip(&self, text: &str) -> Option<String> {
+        if !text.contains('\x1b') {
+            return None;
+        }
+        Some(self.escapes.replace_all(text, "").into_owned())
+    }
+}
+
+#[async_trait]
+impl LogProcessor for StripAnsiProcessor {
+    async fn process(&self, mut log: LogEntry) -> Result<Option<LogEntry>> {
+        if let Some(message) = self.strip(&log.message) {
+            log.message = message;
+        }
+
+        if self.attributes {
+            for value in log.attributes.values_mut() {
+                if let Some(stripped) = self.strip(value) {
+                    *value = stripped;
+                }
+            }
+        }
+
+        Ok(Some(log))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Quota processor caps each tenant's log volume over a rolling window
+///
+/// Entries are metered by the tenant named in their `attribute`, against the
+/// tenant's budget in `tenants` or else the `default` one. An entry that would
+/// take its tenant past `max_entries` or `max_bytes` within the last
+/// `window_seconds` is dropped and counted. The first entry shed after a
+/// tenant goes over quota produces an internal `WARN` entry, released on the
+/// next flush. Entries without the attribute are not metered.
+pub struct QuotaProcessor {
+    name: String,
+    attribute: String,
+    window: chrono::Duration,
+    default: QuotaBudget,
+    tenants: HashMap<String, QuotaBudget>,
+    clock: SharedClock,
+    state: Mutex<QuotaState>,
+    shed: AtomicU64,
+}
+
+/// Per-tenant usage and quota notices waiting to be flushed
+#[derive(Default)]
+struct QuotaState {
+    usage: HashMap<String, TenantUsage>,
+    notices: Vec<LogEntry>,
+}
+
+/// What one tenant sent within the window
+#[derive(Default)]
+struct TenantUsage {
+    /// Arrival time and size of each admitted entry, oldest first
+    admitted: VecDeque<(DateTime<Utc>, u64)>,
+    bytes: u64,
+    /// Whether entries are being shed, so the notice is sent once per episode
+    over_quota: bool,
+}
+
+impl QuotaProcessor {
+    /// Create a new quota processor
+    pub fn new(
+        name: String,
+        attribute: String,
+        window_seconds: u64,
+        default: QuotaBudget,
+        tenants: HashMap<String, QuotaBudget>,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        if window_seconds == 0 {
+            return Err(anyhow!("window_seconds for processor {} must be at least 1", name));
+        }
+
+        Ok(Self {
+            name,
+            attribute,
+            window: chrono::Duration::seconds(window_seconds as i64),
+            default,
+            tenants,
+            clock,
+            state: Mutex::new(QuotaState::default()),
+            shed: AtomicU64::new(0),
+        })
+    }
+
+    /// Number of entries shed so far
+    #[cfg(test)]
+    pub fn shed_count(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    /// Bytes an entry counts against a byte budget: its message and attributes
+    fn entry_bytes(log: &LogEntry) -> u64 {
+        let attributes: usize = log.attributes.iter().map(|(key, value)| key.len() + value.len()).sum();
+        (log.message.len() + attributes) as u64
+    }
+
+    fn notice(&self, tenant: &str, budget: &QuotaBudget) -> LogEntry {
+        let limits: Vec<String> = [
+            budget.max_entries.map(|max| format!("{} entries", max)),
+            budget.max_bytes.map(|max| format!("{} bytes", max)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut log = internal_entry(
+            "WARN",
+            format!(
+                "Tenant {} exceeded its quota of {} per {}s; shedding its logs",
+                tenant,
+                limits.join(" and "),
+                self.window.num_seconds(),
+            ),
+        );
+
+        log.attributes.insert("quota.tenant".to_string(), tenant.to_string());
+        log.attributes.insert("quota.processor".to_string(), self.name.clone());
+        log
+    }
+}
+
+#[async_trait]
+impl LogProcessor for QuotaProcessor {
+    async fn process(&self, log: LogEntry) -> Result<Option<LogEntry>> {
+        if is_internal(&log) {
+            return Ok(Some(log));
+        }
+        let tenant = match log.attributes.get(&self.attribute) {
+            Some(tenant) => tenant.clone(),
+            None => return Ok(Some(log)),
+        };
+        let budget = self.tenants.get(&tenant).unwrap_or(&self.default);
+
+        let now = self.clock.now();
+        let size = Self::entry_bytes(&log);
+        let mut state = self.state.lock().await;
+        let usage = state.usage.entry(tenant.clone()).or_default();
+
+        while let Some(&(arrived, bytes)) = usage.admitted.front() {
+            if now - arrived < self.window {
+                break;
+            }
+            usage.admitted.pop_front();
+            usage.bytes -= bytes;
+        }
+
+        let within_entries = budget.max_entries.is_none_or(|max| (usage.admitted.len() as u64) < max);
+        let within_bytes = budget.max_bytes.is_none_or(|max| usage.bytes + size <= max);
+        if within_entries && within_bytes {
+            usage.admitted.push_back((now, size));
+            usage.bytes += size;
+            usage.over_quota = false;
+            return Ok(Some(log));
+        }
+
+        self.shed.fetch_add(1, Ordering::Relaxed);
+        metrics::increment_counter!("collector_quota_shed_logs_total", "processor" => self.name.clone());
+        if !usage.over_quota {
+            usage.over_quota = true;
+            let notice = self.notice(&tenant, budget);
+            state.notices.push(notice);
+        }
+
+        Ok(None)
+    }
+
+    async fn flush(&self, _force: bool) -> Result<Vec<LogEntry>> {
+        let mut state = self.state.lock().await;
+
+        // Tenants that have gone quiet within their quota no longer need tracking
+        let now = self.clock.now();
+        state.usage.retain(|_, usage| {
+            usage.over_quota || usage.admitted.back().is_some_and(|&(arrived, _)| now - arrived < self.window)
+        });
+
+        Ok(std::mem::take(&mut state.notices))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            source: "app".to_string(),
+            level: Some("ERROR".to_string()),
+            severity_number: None,
+            message: message.to_string(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_releases_partial_batch_after_timeout() -> Result<()> {
+        let processor = BatchProcessor::new("batcher".to_string(), 0, 100, 0.0)?;
+
+        assert!(processor.process(entry("first")).await?.is_none());
+        assert!(processor.process(entry("second")).await?.is_none());
+        assert!(!processor.flush_ready());
+
+        let released = processor.flush(false).await?;
+        let messages: Vec<_> = released.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, vec!["first", "second"]);
+        assert!(processor.flush(true).await?.is_empty());
+
+        Ok(())
+    }
+
+    fn filter(yaml: &str) -> Result<FilterProcessor> {
+        FilterProcessor::new("filter".to_string(), serde_yaml::from_str(yaml)?, None, UnknownSeverityAction::Keep)
+    }
+
+    fn tagged(message: &str, level: &str, attributes: &[(&str, &str)]) -> LogEntry {
+        let mut log = entry(message);
+        log.level = Some(level.to_string());
+        log.attributes = attributes.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        log
+    }
+
+    #[tokio::test]
+    async fn test_filter_includes_on_attribute_equality() -> Result<()> {
+        let processor = filter(r#"
+            include: { field: attributes.env, match_type: exact, exact: [prod] }
+        "#)?;
+
+        assert!(processor.process(tagged("served", "INFO", &[("env", "prod")])).await?.is_some());
+        // Exact attribute matches compare the whole value
+        assert!(processor.process(tagged("served", "INFO", &[("env", "preprod")])).await?.is_none());
+        assert!(processor.process(tagged("served", "INFO", &[])).await?.is_none());
+
+        // Message matching still finds substrings
+        let processor = filter("include: { match_type: exact, exact: [prod] }")?;
+        assert!(processor.process(tagged("deployed to preprod", "INFO", &[])).await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_excludes_on_level() -> Result<()> {
+        let processor = filter(r#"
+            exclude: { field: level, match_type: exact, exact: [DEBUG] }
+        "#)?;
+
+        assert!(processor.process(tagged("cache miss", "DEBUG", &[])).await?.is_none());
+        assert!(processor.process(tagged("DEBUG mode on", "INFO", &[])).await?.is_some());
+
+        let mut unleveled = entry("no level");
+        unleveled.level = None;
+        assert!(processor.process(unleveled).await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_combines_conditions_with_all_or_any() -> Result<()> {
+        let processor = filter(r#"
+            include:
+              all:
+                - { field: attributes.env, match_type: exact, exact: [prod] }
+                - { field: level, match_type: regexp, regexp: ['^(WARN|ERROR)$'] }
+            exclude:
+              any:
+                - { match_type: regexp, regexp: ['healthcheck'] }
+                - { field: attributes.team, match_type: exact, exact: [qa] }
+        "#)?;
+
+        assert!(processor.process(tagged("disk full", "ERROR", &[("env", "prod")])).await?.is_some());
+        assert!(processor.process(tagged("disk full", "ERROR", &[("env", "dev")])).await?.is_none());
+        assert!(processor.process(tagged("disk full", "INFO", &[("env", "prod")])).await?.is_none());
+        assert!(processor.process(tagged("healthcheck failed", "ERROR", &[("env", "prod")])).await?.is_none());
+        assert!(processor.process(tagged("disk full", "ERROR", &[("env", "prod"), ("team", "qa")])).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_drops_logs_below_min_severity() -> Result<()> {
+        let processor = FilterProcessor::new(
+            "warnings".to_string(),
+            FilterConfig::default(),
+            Some(Severity::Warn),
+            UnknownSeverityAction::Keep,
+        )?;
+
+        assert!(processor.process(tagged("cache warm", "INFO", &[])).await?.is_none());
+        assert!(processor.process(tagged("disk full", "ERROR", &[])).await?.is_some());
+        assert!(processor.process(tagged("slow query", "Warning", &[])).await?.is_some());
+        assert!(processor.process(tagged("syslog warning", "4", &[])).await?.is_some());
+
+        // Unknown or missing levels follow `unknown_severity`
+        let mut unleveled = entry("no level");
+        unleveled.level = None;
+        assert!(processor.process(tagged("odd level", "LOUD", &[])).await?.is_some());
+        assert!(processor.process(unleveled.clone()).await?.is_some());
+
+        let processor = FilterProcessor::new(
+            "warnings".to_string(),
+            FilterConfig::default(),
+            Some(Severity::Warn),
+            UnknownSeverityAction::Drop,
+        )?;
+        assert!(processor.process(tagged("odd level", "LOUD", &[])).await?.is_none());
+        assert!(processor.process(unleveled).await?.is_none());
+        assert!(processor.process(tagged("disk full", "ERROR", &[])).await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_identical_stack_traces() -> Result<()> {
+        let processor = CoalesceProcessor::new("coalesce".to_string(), 60)?;
+        let trace = "java.lang.NullPointerException\n\tat com.example.Foo.bar(Foo.java:10)\n\tat com.example.Main.main(Main.java:5)";
+
+        for _ in 0..3 {
+            assert!(processor.process(entry(trace)).await?.is_none());
+        }
+
+        // Still inside the window, nothing is due yet
+        assert!(processor.flush(false).await?.is_empty());
+
+        let released = processor.flush(true).await?;
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].message, trace);
+        assert_eq!(released[0].attributes.get("occurrence_count"), Some(&"3".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_keeps_distinct_and_single_line() -> Result<()> {
+        let processor = CoalesceProcessor::new("coalesce".to_string(), 60)?;
+
+        let single = processor.process(entry("plain line")).await?;
+        assert_eq!(single.map(|log| log.message), Some("plain line".to_string()));
+
+        processor.process(entry("first\n\tat a")).await?;
+        processor.process(entry("second\n\tat b")).await?;
+
+        let released = processor.flush(true).await?;
+        assert_eq!(released.len(), 2);
+        assert!(released.iter().all(|log| log.attributes.get("occurrence_count") == Some(&"1".to_string())));
+
+        Ok(())
+    }
+
+    fn level_prefix(strip: bool) -> LevelPrefixProcessor {
+        LevelPrefixProcessor::new(
+            "level-prefix".to_string(),
+            vec!["ERROR".to_string(), "WARN".to_string(), "INFO".to_string()],
+            strip,
+        ).unwrap()
+    }
+
+    fn unleveled(message: &str) -> LogEntry {
+        let mut log = entry(message);
+        log.level = None;
+        log
+    }
+
+    #[tokio::test]
+    async fn test_level_prefix_bracketed() -> Result<()> {
+        let log = level_prefix(true).process(unleveled("[ERROR] disk full")).await?.unwrap();
+        assert_eq!(log.level, Some("ERROR".to_string()));
+        assert_eq!(log.message, "disk full");
+
+        let log = level_prefix(false).process(unleveled("[error] disk full")).await?.unwrap();
+        assert_eq!(log.level, Some("ERROR".to_string()));
+        assert_eq!(log.message, "[error] disk full");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_level_prefix_colon_delimited() -> Result<()> {
+        let log = level_prefix(true).process(unleveled("WARN: retrying connection")).await?.unwrap();
+        assert_eq!(log.level, Some("WARN".to_string()));
+        assert_eq!(log.message, "retrying connection");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_level_prefix_without_token() -> Result<()> {
+        let processor = level_prefix(true);
+
+        for message in ["plain message", "Note: not a level", "[worker-3] started"] {
+            let log = processor.process(unleveled(message)).await?.unwrap();
+            assert_eq!(log.level, None);
+            assert_eq!(log.message, message);
+        }
+
+        // An existing level is never overwritten
+        let log = processor.process(entry("[WARN] already leveled")).await?.unwrap();
+        assert_eq!(log.level, Some("ERROR".to_string()));
+
+        Ok(())
+    }
+
+    fn require_attributes(on_missing: MissingAttributeAction) -> RequireAttributesProcessor {
+        let mut defaults = HashMap::new();
+        defaults.insert("env".to_string(), "production".to_string());
+
+        RequireAttributesProcessor::new(
+            "contract".to_string(),
+            vec!["service.name".to_string(), "env".to_string()],
+            on_missing,
+            defaults,
+        ).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_require_attributes_present() -> Result<()> {
+        let mut log = entry("request handled");
+        log.attributes.insert("service.name".to_string(), "api".to_string());
+        log.attributes.insert("env".to_string(), "staging".to_string());
+
+        let processed = require_attributes(MissingAttributeAction::Drop).process(log).await?.unwrap();
+        assert_eq!(processed.attributes.get("env"), Some(&"staging".to_string()));
+        assert!(!processed.attributes.contains_key("schema_violation"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_require_attributes_missing_with_default() -> Result<()> {
+        let mut log = entry("request handled");
+        log.attributes.insert("service.name".to_string(), "api".to_string());
+
+        let processed = require_attributes(MissingAttributeAction::Default).process(log).await?.unwrap();
+        assert_eq!(processed.attributes.get("env"), Some(&"production".to_string()));
+        assert!(!processed.attributes.contains_key("schema_violation"));
+
+        // Keys without a default are tagged instead
+        let processed = require_attributes(MissingAttributeAction::Default)
+            .process(entry("request handled"))
+            .await?
+            .unwrap();
+        assert_eq!(processed.attributes.get("env"), Some(&"production".to_string()));
+        assert_eq!(processed.attributes.get("schema_violation"), Some(&"service.name".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_require_attributes_missing_with_drop_or_tag() -> Result<()> {
+        let dropped = require_attributes(MissingAttributeAction::Drop).process(entry("no context")).await?;
+        assert!(dropped.is_none());
+
+        let tagged = require_attributes(MissingAttributeAction::Tag).process(entry("no context")).await?.unwrap();
+        assert_eq!(tagged.attributes.get("schema_violation"), Some(&"service.name,env".to_string()));
+
+        Ok(())
+    }
+
+    fn partition_fields(log: &LogEntry) -> Vec<&str> {
+        ["year", "month", "day", "hour"]
+            .iter()
+            .map(|key| log.attributes.get(*key).map(|value| value.as_str()).unwrap_or(""))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_partition_fields_in_utc() -> Result<()> {
+        let mut log = entry("request handled");
+        log.timestamp = "2024-03-09T07:45:00Z".parse()?;
+
+        let processor = PartitionProcessor::new("partition".to_string(), "UTC")?;
+        let log = processor.process(log).await?.unwrap();
+        assert_eq!(partition_fields(&log), vec!["2024", "03", "09", "07"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_partition_fields_in_other_timezone() -> Result<()> {
+        // Late evening UTC is already the next day, and the next year, in Tokyo
+        let mut log = entry("request handled");
+        log.timestamp = "2023-12-31T22:30:00Z".parse()?;
+
+        let processor = PartitionProcessor::new("partition".to_string(), "Asia/Tokyo")?;
+        let log = processor.process(log).await?.unwrap();
+        assert_eq!(partition_fields(&log), vec!["2024", "01", "01", "07"]);
+
+        assert!(PartitionProcessor::new("partition".to_string(), "Mars/Olympus").is_err());
+
+        Ok(())
+    }
+
+    async fn parse_line(line: &str) -> Result<LogEntry> {
+        let processor = LanguageParserProcessor::new(
+            "language".to_string(),
+            vec![LanguageFormat::GoJson, LanguageFormat::RustTracing, LanguageFormat::EnvLogger, LanguageFormat::Python],
+        )?;
+
+        Ok(processor.process(unleveled(line)).await?.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_language_parser_go_json() -> Result<()> {
+        let log = parse_line(r#"{"time":"2024-03-09T07:45:00.5Z","level":"WARN","msg":"slow request","path":"/api","status":504}"#).await?;
+        assert_eq!(log.level, Some("WARN".to_string()));
+        assert_eq!(log.message, "slow request");
+        assert_eq!(log.timestamp, "2024-03-09T07:45:00.5Z".parse::<DateTime<Utc>>()?);
+        assert_eq!(log.attributes.get("path"), Some(&"/api".to_string()));
+        assert_eq!(log.attributes.get("status"), Some(&"504".to_string()));
+
+        // logrus uses lowercase levels
+        let log = parse_line(r#"{"level":"error","msg":"db down","time":"2024-03-09T07:45:00Z"}"#).await?;
+        assert_eq!(log.level, Some("ERROR".to_string()));
+        assert_eq!(log.message, "db down");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_language_parser_rust_formats() -> Result<()> {
+        let log = parse_line("2024-03-09T07:45:00.123456Z  INFO my_app::server: listening on 0.0.0.0:8080").await?;
+        assert_eq!(log.level, Some("INFO".to_string()));
+        assert_eq!(log.attributes.get("module"), Some(&"my_app::server".to_string()));
+        assert_eq!(log.message, "listening on 0.0.0.0:8080");
+
+        let log = parse_line("[2024-03-09T07:45:00Z ERROR my_app::db] connection refused").await?;
+        assert_eq!(log.level, Some("ERROR".to_string()));
+        assert_eq!(log.attributes.get("module"), Some(&"my_app::db".to_string()));
+        assert_eq!(log.message, "connection refused");
+        assert_eq!(log.timestamp, "2024-03-09T07:45:00Z".parse::<DateTime<Utc>>()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_language_parser_python_logging() -> Result<()> {
+        let log = parse_line("2024-03-09 07:45:00,250 - app.db - WARNING - slow query: 2.1s").await?;
+        assert_eq!(log.level, Some("WARNING".to_string()));
+        assert_eq!(log.attributes.get("module"), Some(&"app.db".to_string()));
+        assert_eq!(log.message, "slow query: 2.1s");
+        assert_eq!(log.timestamp, "2024-03-09T07:45:00.250Z".parse::<DateTime<Utc>>()?);
+
+        let log = parse_line("CRITICAL:app.worker:queue lost").await?;
+        assert_eq!(log.level, Some("CRITICAL".to_string()));
+        assert_eq!(log.attributes.get("module"), Some(&"app.worker".to_string()));
+        assert_eq!(log.message, "queue lost");
+
+        // Unrecognized lines are left alone
+        let log = parse_line("just some text").await?;
+        assert_eq!(log.level, None);
+        assert_eq!(log.message, "just some text");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_binary_filter_keeps_clean_text() -> Result<()> {
+        let processor = BinaryFilterProcessor::new("binary".to_string(), 0.8, BinaryAction::Drop)?;
+        let message = "GET /api/users 200 12ms\tuser=ünïcode";
+
+        assert_eq!(textiness(message), 1.0);
+        let log = processor.process(entry(message)).await?.unwrap();
+        assert_eq!(log.message, message);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_binary_filter_embedded_nul() -> Result<()> {
+        let message = "session started\0\0\0user=alice";
+        assert_eq!(textiness(message), 0.0);
+
+        let processor = BinaryFilterProcessor::new("binary".to_string(), 0.8, BinaryAction::Tag)?;
+        let log = processor.process(entry(message)).await?.unwrap();
+        assert_eq!(log.attributes.get("binary_content"), Some(&"true".to_string()));
+        assert_eq!(log.attributes.get("textiness"), Some(&"0.00".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_binary_filter_drops_binary_blob() -> Result<()> {
+        let blob: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x1a, 0xff, 0xfe, 0x03, 0x7f, 0x90, 0x01, 0xc3];
+        let message = String::from_utf8_lossy(&blob).into_owned();
+        assert!(textiness(&message) < 0.5);
+
+        let processor = BinaryFilterProcessor::new("binary".to_string(), 0.8, BinaryAction::Drop)?;
+        assert!(processor.process(entry(&message)).await?.is_none());
+
+        assert!(BinaryFilterProcessor::new("binary".to_string(), 1.5, BinaryAction::Drop).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_anomaly_on_sudden_burst() -> Result<()> {
+        let start: DateTime<Utc> = "2024-03-09T07:00:00Z".parse()?;
+        let clock = std::sync::Arc::new(crate::collector::clock::MockClock::new(start));
+        let processor = RateAnomalyProcessor::new("rate".to_string(), 10, 3.0, clock.clone())?;
+
+        // A steady 10 events per 10s window establishes the baseline
+        for window in 0..8 {
+            for second in 0..10 {
+                clock.set(start + chrono::Duration::seconds(window * 10 + second));
+                assert!(processor.process(entry("steady")).await?.is_some());
+            }
+        }
+        assert!(processor.flush(false).await?.is_empty());
+
+        // Then the source suddenly logs ten times as much
+        let burst_start = start + chrono::Duration::seconds(80);
+        for i in 0..100 {
+            clock.set(burst_start + chrono::Duration::milliseconds(i * 90));
+            processor.process(entry("burst")).await?;
+        }
+
+        clock.set(burst_start + chrono::Duration::seconds(11));
+        let anomalies = processor.flush(false).await?;
+        assert_eq!(anomalies.len(), 1);
+        assert!(is_internal(&anomalies[0]));
+        assert_eq!(anomalies[0].attributes.get("anomaly.source"), Some(&"app".to_string()));
+        assert_eq!(anomalies[0].attributes.get("anomaly.count"), Some(&"100".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_normalize_level_rewrites_known_spellings() -> Result<()> {
+        let aliases = HashMap::from([("OOPS".to_string(), "ERROR".to_string())]);
+        let processor = NormalizeLevelProcessor::new("levels".to_string(), &aliases)?;
+
+        let mut log = entry("disk almost full");
+        log.level = Some("Warning".to_string());
+        assert_eq!(processor.process(log).await?.unwrap().level, Some("WARN".to_string()));
+
+        let mut log = entry("custom");
+        log.level = Some("oops".to_string());
+        assert_eq!(processor.process(log).await?.unwrap().level, Some("ERROR".to_string()));
+
+        let mut log = entry("syslog severity");
+        log.level = Some("4".to_string());
+        assert_eq!(processor.process(log).await?.unwrap().level, Some("WARN".to_string()));
+
+        // Unknown and missing levels pass through untouched
+        let mut log = entry("odd");
+        log.level = Some("Loud".to_string());
+        assert_eq!(processor.process(log).await?.unwrap().level, Some("Loud".to_string()));
+
+        let mut log = entry("bare");
+        log.level = None;
+        assert_eq!(processor.process(log).await?.unwrap().level, None);
+
+        Ok(())
+    }
+
+    fn source_template(fallback: Option<&str>) -> SourceTemplateProcessor {
+        SourceTemplateProcessor::new(
+            "source-path".to_string(),
+            "{tenant}/{service}/{component}",
+            fallback.map(|fallback| fallback.to_string()),
+        ).unwrap()
+    }
+
+    fn with_attributes(pairs: &[(&str, &str)]) -> LogEntry {
+        let mut log = entry("request served");
+        for (key, value) in pairs {
+            log.attributes.insert(key.to_string(), value.to_string());
+        }
+        log
+    }
+
+    #[tokio::test]
+    async fn test_source_template_composes_source_path() -> Result<()> {
+        let log = with_attributes(&[("tenant", "acme"), ("service", "checkout"), ("component", "api")]);
+        let processed = source_template(Some("unassigned")).process(log).await?.unwrap();
+        assert_eq!(processed.source, "acme/checkout/api");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_source_template_falls_back_when_attributes_are_missing() -> Result<()> {
+        let partial = || with_attributes(&[("tenant", "acme"), ("service", ""), ("component", "api")]);
+
+        let processed = source_template(Some("unassigned")).process(partial()).await?.unwrap();
+        assert_eq!(processed.source, "unassigned");
+
+        // Without a fallback the original source is kept
+        let original = partial().source;
+        let processed = source_template(None).process(partial()).await?.unwrap();
+        assert_eq!(processed.source, original);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_template_parsing() {
+        assert_eq!(
+            SourceTemplateProcessor::parse("{{{tenant}}}-x").unwrap(),
+            vec![
+                TemplateSegment::Literal("{".to_string()),
+                TemplateSegment::Attribute("tenant".to_string()),
+                TemplateSegment::Literal("}-x".to_string()),
+            ],
+        );
+
+        for bad in ["{tenant", "{}/svc", "tenant}"] {
+            assert!(SourceTemplateProcessor::parse(bad).is_err(), "template {:?}", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bucket_labels_numeric_attribute() -> Result<()> {
+        let processor = BucketProcessor::new(
+            "latency".to_string(),
+            "duration_ms".to_string(),
+            None,
+            vec![10.0, 100.0, 1000.0],
+            "ms",
+        )?;
+
+        let cases = [
+            ("0", "<10ms"),
+            ("9.99", "<10ms"),
+            ("10", "10-100ms"),
+            ("99.5", "10-100ms"),
+            ("100", "100-1000ms"),
+            (" 999 ", "100-1000ms"),
+            ("1000", ">=1000ms"),
+            ("1e6", ">=1000ms"),
+            ("-5", "<10ms"),
+        ];
+        for (value, label) in cases {
+            let processed = processor.process(with_attributes(&[("duration_ms", value)])).await?.unwrap();
+            assert_eq!(processed.attributes.get("duration_ms.bucket"), Some(&label.to_string()), "value {:?}", value);
+            assert_eq!(processed.attributes.get("duration_ms"), Some(&value.to_string()));
+        }
+
+        // Missing and non-numeric values pass through unlabelled
+        let unlabelled = [
+            entry("no duration"),
+            with_attributes(&[("duration_ms", "slow")]),
+            with_attributes(&[("duration_ms", "NaN")]),
+        ];
+        for log in unlabelled {
+            let processed = processor.process(log).await?.unwrap();
+            assert_eq!(processed.attributes.get("duration_ms.bucket"), None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_rejects_unordered_boundaries() {
+        let bucket = |boundaries: Vec<f64>| {
+            BucketProcessor::new("b".to_string(), "size".to_string(), Some("size_class".to_string()), boundaries, "")
+        };
+
+        assert!(bucket(vec![]).is_err());
+        assert!(bucket(vec![10.0, 10.0]).is_err());
+        assert!(bucket(vec![100.0, 10.0]).is_err());
+        assert!(bucket(vec![1.0, f64::INFINITY]).is_err());
+        assert_eq!(bucket(vec![0.5, 2.0]).unwrap().labels, vec!["<0.5", "0.5-2", ">=2"]);
+    }
+
+    #[tokio::test]
+    async fn test_sample_keeps_one_in_n() -> Result<()> {
+        let processor = SampleProcessor::new("sampler".to_string(), Some(10), None, None, None)?;
+
+        let mut kept = 0;
+        for i in 0..1000 {
+            if processor.process(entry(&format!("event {}", i))).await?.is_some() {
+                kept += 1;
+            }
+        }
+        assert!((90..=110).contains(&kept), "kept {} of 1000", kept);
+        assert_eq!(processor.dropped_count(), 1000 - kept);
+        assert!(processor.flush(true).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sample_limits_rate_per_key() -> Result<()> {
+        let processor = SampleProcessor::new("limiter".to_string(), None, Some(1), Some(5), Some("service".to_string()))?;
+        let from = |service: &str| {
+            let mut log = entry("request");
+            log.attributes.insert("service".to_string(), service.to_string());
+            log
+        };
+
+        // A burst from one service does not use up the budget of another
+        let mut kept = HashMap::new();
+        for service in ["noisy"; 50].into_iter().chain(["quiet"; 3]) {
+            if processor.process(from(service)).await?.is_some() {
+                *kept.entry(service).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(kept.get("noisy"), Some(&5));
+        assert_eq!(kept.get("quiet"), Some(&3));
+        assert_eq!(processor.dropped_count(), 45);
+
+        assert!(SampleProcessor::new("both".to_string(), Some(10), Some(1), None, None).is_err());
+        assert!(SampleProcessor::new("neither".to_string(), None, None, None, None).is_err());
+        assert!(SampleProcessor::new("zero".to_string(), Some(0), None, None, None).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sample_tracks_a_bounded_number_of_keys() -> Result<()> {
+        let processor = SampleProcessor::new("limiter".to_string(), None, Some(1), Some(1), Some("request_id".to_string()))?;
+        let with_id = |id: usize| {
+            let mut log = entry("request");
+            log.attributes.insert("request_id".to_string(), id.to_string());
+            log
+        };
+
+        for id in 0..MAX_SAMPLE_KEYS + 100 {
+            assert!(processor.process(with_id(id)).await?.is_some());
+        }
+        assert_eq!(processor.state.lock().await.len(), MAX_SAMPLE_KEYS);
+
+        // The longest idle keys went first; the latest still have their budget spent
+        assert!(processor.process(with_id(MAX_SAMPLE_KEYS + 99)).await?.is_none());
+        assert!(processor.process(with_id(0)).await?.is_some());
+
+        // Keys idle for the timeout are forgotten
+        let mut state = processor.state.lock().await;
+        SampleProcessor::forget_idle(&mut state, Instant::now() + SAMPLE_KEY_IDLE_TIMEOUT);
+        assert!(state.is_empty());
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_expand_list_parses_bracketed_lists() -> Result<()> {
+        let message = r#"deploy done tags=[a,b,c] hosts=[ "web-1", 'db,primary', [edge-1, edge-2] ] broken=[x"#;
+
+        let json = ExpandListProcessor::new("lists".to_string(), None, ListFormat::Json)?;
+        let log = json.process(entry(message)).await?.unwrap();
+        assert_eq!(log.attributes.get("tags").map(String::as_str), Some(r#"["a","b","c"]"#));
+        assert_eq!(
+            log.attributes.get("hosts").map(String::as_str),
+            Some(r#"["web-1","db,primary",["edge-1","edge-2"]]"#),
+        );
+        assert_eq!(log.attributes.get("broken"), None);
+        assert_eq!(log.message, message);
+
+        let indexed = ExpandListProcessor::new("lists".to_string(), None, ListFormat::Indexed)?;
+        let log = indexed.process(entry(message)).await?.unwrap();
+        let attributes: HashMap<&str, &str> = log.attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(attributes, HashMap::from([
+            ("tags.0", "a"),
+            ("tags.1", "b"),
+            ("tags.2", "c"),
+            ("hosts.0", "web-1"),
+            ("hosts.1", "db,primary"),
+            ("hosts.2.0", "edge-1"),
+            ("hosts.2.1", "edge-2"),
+        ]));
+
+        // An attribute is expanded in place, and only when it is a whole list
+        let in_place = ExpandListProcessor::new("lists".to_string(), Some("tags".to_string()), ListFormat::Indexed)?;
+        let mut log = entry("tagged");
+        log.attributes.insert("tags".to_string(), "[a, b]".to_string());
+        let log = in_place.process(log).await?.unwrap();
+        assert_eq!(log.attributes.get("tags"), None);
+        assert_eq!(log.attributes.get("tags.1").map(String::as_str), Some("b"));
+
+        let mut log = entry("tagged");
+        log.attributes.insert("tags".to_string(), "[a, b] and more".to_string());
+        let log = in_place.process(log).await?.unwrap();
+        assert_eq!(log.attributes.get("tags").map(String::as_str), Some("[a, b] and more"));
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_expand_list_keeps_deep_lists_as_text() -> Result<()> {
+        let processor = ExpandListProcessor::new("lists".to_string(), None, ListFormat::Json)?;
+
+        // An unterminated line of brackets as long as a line may be neither
+        // overflows the stack nor takes time quadratic in its length
+        let message = format!("deep={}", "[".repeat(100_000));
+        let log = processor.process(entry(&message)).await?.unwrap();
+        assert_eq!(log.attributes.get("deep"), None);
+
+        let message = "k=[".repeat(100_000);
+        let log = processor.process(entry(&message)).await?.unwrap();
+        assert!(log.attributes.is_empty());
+
+        // Below the cap lists nest; past it the rest is one item of plain text
+        let nested = format!("{}x{}", "[".repeat(100_000), "]".repeat(100_000));
+        let log = processor.process(entry(&format!("deep={}", nested))).await?.unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&log.attributes["deep"])?;
+        for _ in 1..MAX_LIST_DEPTH {
+            value = value.as_array().unwrap()[0].clone();
+        }
+        assert_eq!(value, serde_json::json!([nested[MAX_LIST_DEPTH..nested.len() - MAX_LIST_DEPTH]]));
+
+        Ok(())
+    }
+
+    fn json_parser(message_key: Option<&str>) -> Result<JsonParseProcessor> {
+        JsonParseProcessor::new(
+            "json".to_string(),
+            "message".to_string(),
+            Some("log.level".to_string()),
+            Some("ts".to_string()),
+            message_key.map(str::to_string),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_json_parse_flattens_nested_fields() -> Result<()> {
+        let line = r#"{"ts": "2024-03-09T07:45:00Z", "log": {"level": "warn", "logger": "db"},
+            "msg": "slow query", "http": {"status": 503, "retry": true, "client": {"ip": "10.0.0.7"}},
+            "tags": ["db", {"shard": 2}], "trace": null, "service": "checkout"}"#;
+
+        let mut log = entry(line);
+        log.attributes.insert("service".to_string(), "from-source".to_string());
+        let log = json_parser(Some("msg"))?.process(log).await?.unwrap();
+
+        assert_eq!(log.message, "slow query");
+        assert_eq!(log.level.as_deref(), Some("warn"));
+        assert_eq!(log.timestamp, "2024-03-09T07:45:00Z".parse::<DateTime<Utc>>()?);
+
+        let attributes: HashMap<&str, &str> = log.attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(attributes, HashMap::from([
+            ("log.logger", "db"),
+            ("http.status", "503"),
+            ("http.retry", "true"),
+            ("http.client.ip", "10.0.0.7"),
+            ("tags", r#"["db",{"shard":2}]"#),
+            // Existing attributes win
+            ("service", "from-source"),
+        ]));
+
+        // Without a message key the original line is kept; epoch milliseconds are understood
+        let log = json_parser(None)?.process(entry(r#"{"ts": 1709970300250, "msg": "kept"}"#)).await?.unwrap();
+        assert_eq!(log.message, r#"{"ts": 1709970300250, "msg": "kept"}"#);
+        assert_eq!(log.attributes.get("msg").map(String::as_str), Some("kept"));
+        assert_eq!(log.timestamp, "2024-03-09T07:45:00.250Z".parse::<DateTime<Utc>>()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_parse_tags_unparsable_entries() -> Result<()> {
+        let parser = json_parser(Some("msg"))?;
+
+        for line in ["not json", r#"["an", "array"]"#, r#"{"msg": "cut off"#] {
+            let log = parser.process(entry(line)).await?.unwrap();
+            assert_eq!(log.message, line);
+            assert_eq!(log.level.as_deref(), Some("ERROR"));
+            assert!(log.attributes.contains_key("json_parse_error"), "{}", line);
+        }
+
+        // An attribute can be parsed instead, and entries without it pass untouched
+        let parser = JsonParseProcessor::new("json".to_string(), "payload".to_string(), None, None, None)?;
+        let mut log = entry("request");
+        log.attributes.insert("payload".to_string(), r#"{"user": {"id": 7}}"#.to_string());
+        let log = parser.process(log).await?.unwrap();
+        assert_eq!(log.attributes.get("user.id").map(String::as_str), Some("7"));
+        assert_eq!(parser.process(entry("plain")).await?.unwrap().attributes, HashMap::new());
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_strip_ansi_keeps_text() -> Result<()> {
+        let colored = "\x1b[1;31mERROR\x1b[0m payment \x1b]8;;https://pay.example/42\x1b\\declined\x1b]8;;\x1b\\ \x1b[2K\x1b7(retry \x1b[38;5;208m2\x1b[39m)";
+        let mut log = entry(colored);
+        log.attributes.insert("status".to_string(), "\x1b[32mok\x1b[0m".to_string());
+
+        let messages_only = StripAnsiProcessor::new("ansi".to_string(), false)?;
+        let stripped = messages_only.process(log.clone()).await?.unwrap();
+        assert_eq!(stripped.message, "ERROR payment declined (retry 2)");
+        assert_eq!(stripped.attributes.get("status").map(String::as_str), Some("\x1b[32mok\x1b[0m"));
+
+        let everything = StripAnsiProcessor::new("ansi".to_string(), true)?;
+        let stripped = everything.process(log).await?.unwrap();
+        assert_eq!(stripped.attributes.get("status").map(String::as_str), Some("ok"));
+
+        let plain = everything.process(entry("no escapes [here]")).await?.unwrap();
+        assert_eq!(plain.message, "no escapes [here]");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quota_sheds_only_the_tenant_over_budget() -> Result<()> {
+        let start: DateTime<Utc> = "2024-03-09T07:00:00Z".parse()?;
+        let clock = std::sync::Arc::new(crate::collector::clock::MockClock::new(start));
+        let tenants = HashMap::from([(
+            "big".to_string(),
+            QuotaBudget { max_entries: Some(10), max_bytes: None },
+        )]);
+        let default = QuotaBudget { max_entries: Some(3), max_bytes: Some(1000) };
+        let processor = QuotaProcessor::new("quota".to_string(), "tenant_id".to_string(), 60, default, tenants, clock.clone())?;
+        let tenant_entry = |tenant: &str| {
+            let mut log = entry("request served");
+            log.attributes.insert("tenant_id".to_string(), tenant.to_string());
+            log
+        };
+
+        // `noisy` has the default budget of three entries a minute, `big` its own of ten
+        let mut admitted = HashMap::new();
+        for second in 0..8 {
+            clock.set(start + chrono::Duration::seconds(second));
+            for tenant in ["noisy", "big"] {
+                if processor.process(tenant_entry(tenant)).await?.is_some() {
+                    *admitted.entry(tenant).or_insert(0) += 1;
+                }
+            }
+        }
+        assert_eq!(admitted["noisy"], 3);
+        assert_eq!(admitted["big"], 8);
+        assert_eq!(processor.shed_count(), 5);
+
+        // Unmetered entries pass whatever the quotas say
+        assert!(processor.process(entry("no tenant")).await?.is_some());
+
+        // Going over quota is announced once
+        let notices = processor.flush(false).await?;
+        assert_eq!(notices.len(), 1);
+        assert!(is_internal(&notices[0]));
+        assert_eq!(notices[0].attributes.get("quota.tenant"), Some(&"noisy".to_string()));
+        assert!(processor.flush(false).await?.is_empty());
+
+        // The window rolls: the first entries age out and make room again
+        clock.set(start + chrono::Duration::seconds(60));
+        assert!(processor.process(tenant_entry("noisy")).await?.is_some());
+        assert!(processor.process(tenant_entry("noisy")).await?.is_none());
+
+        // An entry larger than the byte budget never fits
+        let mut huge = tenant_entry("other");
+        huge.message = "x".repeat(2000);
+        assert!(processor.process(huge).await?.is_none());
+
+        Ok(())
+    }
+}
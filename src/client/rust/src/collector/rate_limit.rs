@@ -0,0 +1,59 @@
+//! Token buckets limiting how often something may happen
+//!
+//! A bucket holds up to `burst` tokens and refills at a steady rate; each
+//! admitted event takes one. Bursts up to the bucket's size go through at
+//! once, after which events are admitted at the refill rate.
+
+use std::time::Instant;
+
+/// Token bucket admitting a sustained rate with bounded bursts
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket holding `burst` tokens and refilling at `rate_per_second`
+    pub fn new(rate_per_second: u32, burst: u32, now: Instant) -> Self {
+        Self {
+            capacity: burst as f64,
+            tokens: burst as f64,
+            refill_per_second: rate_per_second as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Take a token if one is available
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bucket_admits_a_burst_then_the_refill_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 3, start);
+
+        assert_eq!((0..5).filter(|_| bucket.try_acquire(start)).count(), 3);
+        assert!(!bucket.try_acquire(start + Duration::from_millis(400)));
+        assert!(bucket.try_acquire(start + Duration::from_millis(600)));
+        // Refills stop at the bucket's size
+        assert_eq!((0..5).filter(|_| bucket.try_acquire(start + Duration::from_secs(60))).count(), 3);
+    }
+}
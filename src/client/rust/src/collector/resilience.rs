@@ -0,0 +1,402 @@
+//! Retries and circuit breaking for any exporter
+//!
+//! [`RetryingExporter`] wraps an exporter named in a `resilience` policy. Each
+//! failed export or flush is retried with exponential backoff, and a
+//! [`CircuitBreaker`] counts the calls that still fail. Once enough fail in a
+//! row the breaker opens: exports go straight to the dead-letter file and
+//! flushes are skipped, sparing a backend that is down from a stream of
+//! doomed requests. After the cooldown a single call probes the exporter;
+//! its success closes the breaker and its failure opens it again.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::collector::backoff::Backoff;
+use crate::collector::clock::SharedClock;
+use crate::collector::commit::Ack;
+use crate::collector::config::ResilienceConfig;
+use crate::collector::dead_letter::DeadLetterSink;
+use crate::collector::exporters::LogExporter;
+use crate::collector::sources::LogEntry;
+use crate::collector::telemetry;
+
+/// Whether a circuit breaker lets calls through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls pass; failures are being counted
+    Closed,
+    /// Calls are short-circuited until the cooldown ends
+    Open,
+    /// The cooldown has ended and one probing call is allowed
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// Value of the `collector_exporter_breaker_state` gauge
+    fn gauge(self) -> f64 {
+        match self {
+            BreakerState::Closed => 0.0,
+            BreakerState::HalfOpen => 1.0,
+            BreakerState::Open => 2.0,
+        }
+    }
+}
+
+/// Counts consecutive failures and decides whether the next call may run
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: chrono::Duration,
+    clock: SharedClock,
+    state: Mutex<BreakerInner>,
+}
+
+struct BreakerInner {
+    consecutive_failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+    probing: bool,
+}
+
+impl CircuitBreaker {
+    /// Open after `failure_threshold` failures in a row, probing again after `cooldown`
+    pub fn new(failure_threshold: u32, cooldown: Duration, clock: SharedClock) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown: chrono::Duration::from_std(cooldown).unwrap_or(chrono::Duration::MAX),
+            clock,
+            state: Mutex::new(BreakerInner { consecutive_failures: 0, opened_at: None, probing: false }),
+        }
+    }
+
+    /// Current state; an open breaker whose cooldown has ended reads as half-open
+    pub fn state(&self) -> BreakerState {
+        let inner = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.state_of(&inner)
+    }
+
+    fn state_of(&self, inner: &BreakerInner) -> BreakerState {
+        match inner.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if self.clock.now() - opened_at >= self.cooldown => BreakerState::HalfOpen,
+            Some(_) => BreakerState::Open,
+        }
+    }
+
+    /// Whether a call may run now
+    ///
+    /// A half-open breaker admits one call at a time; its outcome must be
+    /// reported with [`record_success`](Self::record_success) or
+    /// [`record_failure`](Self::record_failure).
+    pub fn try_acquire(&self) -> bool {
+        let mut inner = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match self.state_of(&inner) {
+            BreakerState::Closed => true,
+            BreakerState::Open => false,
+            BreakerState::HalfOpen if inner.probing => false,
+            BreakerState::HalfOpen => {
+                inner.probing = true;
+                true
+            },
+        }
+    }
+
+    /// Close the breaker after a call succeeded
+    pub fn record_success(&self) {
+        let mut inner = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probing = false;
+    }
+
+    /// Count a failed call, opening the breaker at the threshold or when a probe failed
+    pub fn record_failure(&self) {
+        let mut inner = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if inner.probing || inner.consecutive_failures >= self.failure_threshold {
+            inner.opened_at = Some(self.clock.now());
+        }
+        inner.probing = false;
+    }
+}
+
+/// Exporter wrapper retrying failed calls and circuit-breaking a failing exporter
+///
+/// `export` and `export_batch` are retried with the policy's backoff; logs
+/// that still fail, or that arrive while the breaker is open, are written to
+/// the dead-letter file, or reported as failed when there is none. `flush`
+/// is retried the same way and skipped while the breaker is open, leaving a
+/// buffering exporter's logs in its buffer. `export_committed` is tried once,
+/// since its acknowledgement cannot be handed to a second attempt.
+pub struct RetryingExporter {
+    inner: Box<dyn LogExporter>,
+    breaker: CircuitBreaker,
+    backoff: Backoff,
+    max_retries: u32,
+    dead_letter: Option<DeadLetterSink>,
+}
+
+impl RetryingExporter {
+    /// Wrap `inner` with the retries, breaker and dead-letter file of `policy`
+    pub fn new(inner: Box<dyn LogExporter>, policy: &ResilienceConfig, clock: SharedClock) -> Self {
+        let exporter = Self {
+            breaker: CircuitBreaker::new(policy.failure_threshold, Duration::from_secs(policy.cooldown_seconds), clock),
+            backoff: Backoff::new(
+                Duration::from_millis(policy.retry.base_delay_ms),
+                Duration::from_millis(policy.retry.max_delay_ms),
+                policy.retry.jitter,
+            ),
+            max_retries: policy.retry.max_retries,
+            dead_letter: policy.dead_letter_path.as_ref().map(|path| {
+                DeadLetterSink::new(
+                    path,
+                    policy.dead_letter.max_file_mb * 1024 * 1024,
+                    policy.dead_letter.max_total_mb * 1024 * 1024,
+                )
+            }),
+            inner,
+        };
+        exporter.publish_state();
+        exporter
+    }
+
+    /// State of the exporter's circuit breaker
+    #[cfg(test)]
+    pub fn breaker_state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    fn publish_state(&self) {
+        telemetry::record_breaker_state(self.inner.name(), self.breaker.state().gauge());
+    }
+
+    /// Run `call` through the breaker, retrying it until it succeeds or the retries run out
+    async fn attempt<F, Fut>(&self, mut call: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        if !self.breaker.try_acquire() {
+            return Err(anyhow!("Exporter {}: circuit breaker is open", self.inner.name()));
+        }
+
+        let mut attempt = 0;
+        let result = loop {
+            match call().await {
+                Ok(()) => break Ok(()),
+                Err(e) if attempt < self.max_retries => {
+                    let delay = self.backoff.delay(attempt);
+                    tracing::warn!("Exporter {}: attempt {} failed, retrying in {:?}: {}", self.inner.name(), attempt + 1, delay, e);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                },
+                Err(e) => break Err(e),
+            }
+        };
+
+        match &result {
+            Ok(()) => self.breaker.record_success(),
+            Err(_) => self.breaker.record_failure(),
+        }
+        self.publish_state();
+
+        result
+    }
+
+    /// Export `logs`, dead-lettering them if that fails
+    async fn deliver(&self, logs: Vec<LogEntry>) -> Result<()> {
+        let result = self.attempt(|| self.inner.export_batch(logs.clone())).await;
+
+        match (result, &self.dead_letter) {
+            (Ok(()), _) => Ok(()),
+            (Err(e), Some(dead_letter)) => {
+                tracing::error!("Exporter {}: moving {} logs to dead-letter: {}", self.inner.name(), logs.len(), e);
+                dead_letter.write(self.inner.name(), &logs, &e.to_string())
+            },
+            (Err(e), None) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl LogExporter for RetryingExporter {
+    async fn export(&self, log: LogEntry) -> Result<()> {
+        self.deliver(vec![log]).await
+    }
+
+    async fn export_batch(&self, logs: Vec<LogEntry>) -> Result<()> {
+        self.deliver(logs).await
+    }
+
+    async fn export_committed(&self, logs: Vec<LogEntry>, ack: Ack) -> Result<()> {
+        if !self.breaker.try_acquire() {
+            return Err(anyhow!("Exporter {}: circuit breaker is open", self.inner.name()));
+        }
+
+        let result = self.inner.export_committed(logs, ack).await;
+        match &result {
+            Ok(()) => self.breaker.record_success(),
+            Err(_) => self.breaker.record_failure(),
+        }
+        self.publish_state();
+
+        result
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.attempt(|| self.inner.flush()).await
+    }
+
+    fn next_flush_delay(&self) -> Option<Duration> {
+        self.inner.next_flush_delay()
+    }
+
+    fn open_cache_file(&self) -> Option<PathBuf> {
+        self.inner.open_cache_file()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::clock::MockClock;
+    use crate::collector::config::RetryConfig;
+    use crate::collector::dead_letter::read_dead_letters;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Exporter that fails while `failing` is set, counting every call
+    #[derive(Default)]
+    struct FlakyExporter {
+        failing: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl LogExporter for FlakyExporter {
+        async fn export(&self, _log: LogEntry) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(anyhow!("backend unavailable"));
+            }
+            Ok(())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+    }
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            source: "app".to_string(),
+            level: None,
+            severity_number: None,
+            message: message.to_string(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    fn policy(dead_letter_path: Option<String>) -> ResilienceConfig {
+        ResilienceConfig {
+            retry: RetryConfig { base_delay_ms: 1, max_delay_ms: 1, max_retries: 1, jitter: 0.0 },
+            failure_threshold: 2,
+            cooldown_seconds: 30,
+            dead_letter_path,
+            ..ResilienceConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_half_opens_and_closes() -> Result<()> {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let flaky = FlakyExporter::default();
+        let (failing, calls) = (flaky.failing.clone(), flaky.calls.clone());
+        let exporter = RetryingExporter::new(Box::new(flaky), &policy(None), clock.clone());
+
+        // Two calls fail after their retry and open the breaker
+        failing.store(true, Ordering::SeqCst);
+        assert!(exporter.export(entry("one")).await.is_err());
+        assert_eq!(exporter.breaker_state(), BreakerState::Closed);
+        assert!(exporter.export(entry("two")).await.is_err());
+        assert_eq!(exporter.breaker_state(), BreakerState::Open);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // While open, calls never reach the exporter
+        let error = exporter.export(entry("three")).await.unwrap_err().to_string();
+        assert!(error.contains("circuit breaker is open"));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // A failed probe after the cooldown opens it again
+        clock.advance(chrono::Duration::seconds(30));
+        assert_eq!(exporter.breaker_state(), BreakerState::HalfOpen);
+        assert!(exporter.export(entry("probe")).await.is_err());
+        assert_eq!(exporter.breaker_state(), BreakerState::Open);
+
+        // A successful probe closes it
+        clock.advance(chrono::Duration::seconds(30));
+        failing.store(false, Ordering::SeqCst);
+        exporter.export(entry("probe")).await?;
+        assert_eq!(exporter.breaker_state(), BreakerState::Closed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_half_open_breaker_admits_one_probe() {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10), clock.clone());
+
+        breaker.record_failure();
+        assert!(!breaker.try_acquire());
+
+        clock.advance(chrono::Duration::seconds(10));
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+
+        breaker.record_success();
+        assert!(breaker.try_acquire());
+        assert!(breaker.try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_failed_and_short_circuited_logs_are_dead_lettered() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dead.jsonl");
+        let flaky = FlakyExporter::default();
+        flaky.failing.store(true, Ordering::SeqCst);
+        let calls = flaky.calls.clone();
+        let exporter = RetryingExporter::new(
+            Box::new(flaky),
+            &policy(Some(path.to_string_lossy().to_string())),
+            Arc::new(MockClock::new(Utc::now())),
+        );
+
+        for message in ["one", "two", "three"] {
+            exporter.export(entry(message)).await?;
+        }
+
+        // The third was short-circuited without reaching the exporter
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        let records = read_dead_letters(&path)?;
+        let messages: Vec<&str> = records.iter().map(|record| record.entry.message.as_str()).collect();
+        assert_eq!(messages, ["one", "two", "three"]);
+        assert!(records[2].reason.contains("circuit breaker is open"));
+        assert_eq!(records[0].exporter, "flaky");
+
+        Ok(())
+    }
+}
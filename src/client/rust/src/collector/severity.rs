@@ -0,0 +1,240 @@
+//! Log level normalization for the log collector
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Canonical level names, from least to most severe
+pub const CANONICAL_LEVELS: [&str; 6] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+
+/// A canonical log level, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+/// Built-in spellings of each canonical level
+const BUILTIN_ALIASES: [(Severity, &[&str]); 6] = [
+    (Severity::Trace, &["T", "TRC", "TRACE", "FINEST", "VERBOSE"]),
+    (Severity::Debug, &["D", "DBG", "DEBUG", "FINE", "FINER"]),
+    (Severity::Info, &["I", "INF", "INFO", "INFORMATION", "INFORMATIONAL", "NOTICE"]),
+    (Severity::Warn, &["W", "WRN", "WARN", "WARNING"]),
+    (Severity::Error, &["E", "ERR", "ERROR", "SEVERE"]),
+    (Severity::Fatal, &["F", "FTL", "FATAL", "CRIT", "CRITICAL", "ALERT", "EMERG", "EMERGENCY", "PANIC"]),
+];
+
+impl Severity {
+    /// Every level, from least to most severe
+    pub const ALL: [Severity; 6] = [
+        Severity::Trace,
+        Severity::Debug,
+        Severity::Info,
+        Severity::Warn,
+        Severity::Error,
+        Severity::Fatal,
+    ];
+
+    /// Parse a built-in spelling such as `Warning` or `ERR`, or a numeric syslog severity
+    ///
+    /// Case and surrounding whitespace are ignored.
+    pub fn parse(level: &str) -> Option<Self> {
+        let level = level.trim();
+        if let Ok(number) = level.parse::<u8>() {
+            return Self::from_syslog(number);
+        }
+
+        BUILTIN_ALIASES
+            .iter()
+            .find(|(_, spellings)| spellings.iter().any(|spelling| spelling.eq_ignore_ascii_case(level)))
+            .map(|(severity, _)| *severity)
+    }
+
+    /// Level of a syslog severity (0 = emergency ... 7 = debug)
+    ///
+    /// Emergency, alert and critical all map to `Fatal`, notice to `Info`.
+    pub fn from_syslog(severity: u8) -> Option<Self> {
+        match severity {
+            0..=2 => Some(Severity::Fatal),
+            3 => Some(Severity::Error),
+            4 => Some(Severity::Warn),
+            5 | 6 => Some(Severity::Info),
+            7 => Some(Severity::Debug),
+            _ => None,
+        }
+    }
+
+    /// Canonical name, one of [`CANONICAL_LEVELS`]
+    pub fn as_str(self) -> &'static str {
+        CANONICAL_LEVELS[self as usize]
+    }
+
+    /// Base OTLP severity number of the level's range (`TRACE` = 1 ... `FATAL` = 21)
+    pub fn otlp_number(self) -> i32 {
+        self as i32 * 4 + 1
+    }
+}
+
+/// Canonical level for a syslog severity (0 = emergency ... 7 = debug)
+pub fn syslog_level(severity: u8) -> Option<&'static str> {
+    Severity::from_syslog(severity).map(Severity::as_str)
+}
+
+/// Maps level spellings such as `Warning`, `warn` or `W` to canonical levels
+///
+/// Lookups ignore case and surrounding whitespace. Numeric syslog
+/// severities (`0` to `7`) are understood unless an alias claims them.
+#[derive(Debug, Clone)]
+pub struct LevelAliases {
+    aliases: HashMap<String, Severity>,
+}
+
+impl LevelAliases {
+    /// Build the alias table from the built-in spellings plus `extra`
+    ///
+    /// Each extra alias must map to a canonical level; extras override
+    /// built-in spellings.
+    pub fn new(extra: &HashMap<String, String>) -> Result<Self> {
+        let mut aliases = HashMap::new();
+
+        for (severity, spellings) in BUILTIN_ALIASES {
+            for spelling in spellings {
+                aliases.insert(spelling.to_string(), severity);
+            }
+        }
+
+        for (alias, target) in extra {
+            let target = target.trim().to_ascii_uppercase();
+            let severity = Severity::ALL
+                .into_iter()
+                .find(|severity| severity.as_str() == target)
+                .ok_or_else(|| anyhow!(
+                    "Level alias {} maps to {}, which is not one of {}",
+                    alias,
+                    target,
+                    CANONICAL_LEVELS.join(", "),
+                ))?;
+
+            aliases.insert(alias.trim().to_ascii_uppercase(), severity);
+        }
+
+        Ok(Self { aliases })
+    }
+
+    /// Level for `level`, or `None` if it is not a known spelling
+    pub fn parse(&self, level: &str) -> Option<Severity> {
+        let level = level.trim();
+        match self.aliases.get(&level.to_ascii_uppercase()) {
+            Some(severity) => Some(*severity),
+            None => level.parse().ok().and_then(Severity::from_syslog),
+        }
+    }
+
+    /// Canonical name for `level`, or `None` if it is not a known spelling
+    pub fn normalize(&self, level: &str) -> Option<&'static str> {
+        self.parse(level).map(Severity::as_str)
+    }
+}
+
+impl Default for LevelAliases {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).expect("built-in aliases are canonical")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_aliases_map_to_canonical_names() {
+        let aliases = LevelAliases::default();
+
+        let cases = [
+            ("trace", Some("TRACE")),
+            ("Verbose", Some("TRACE")),
+            ("dbg", Some("DEBUG")),
+            ("Debug", Some("DEBUG")),
+            ("I", Some("INFO")),
+            ("notice", Some("INFO")),
+            ("Warning", Some("WARN")),
+            ("WARN", Some("WARN")),
+            ("warn", Some("WARN")),
+            ("W", Some("WARN")),
+            (" err ", Some("ERROR")),
+            ("SEVERE", Some("ERROR")),
+            ("crit", Some("FATAL")),
+            ("panic", Some("FATAL")),
+            ("loud", None),
+            ("", None),
+        ];
+
+        for (level, expected) in cases {
+            assert_eq!(aliases.normalize(level), expected, "level {:?}", level);
+        }
+    }
+
+    #[test]
+    fn test_real_world_spellings_parse_to_canonical_levels() {
+        let cases = [
+            ("ERR", Some(Severity::Error)),
+            ("Error", Some(Severity::Error)),
+            ("SEVERE", Some(Severity::Error)),
+            ("warning", Some(Severity::Warn)),
+            ("WARN", Some(Severity::Warn)),
+            ("Informational", Some(Severity::Info)),
+            ("notice", Some(Severity::Info)),
+            ("dbg", Some(Severity::Debug)),
+            ("FINER", Some(Severity::Debug)),
+            ("trace", Some(Severity::Trace)),
+            ("CRITICAL", Some(Severity::Fatal)),
+            ("emerg", Some(Severity::Fatal)),
+            ("3", Some(Severity::Error)),
+            ("4", Some(Severity::Warn)),
+            (" 7 ", Some(Severity::Debug)),
+            ("0", Some(Severity::Fatal)),
+            ("8", None),
+            ("loud", None),
+        ];
+
+        for (level, expected) in cases {
+            assert_eq!(Severity::parse(level), expected, "level {:?}", level);
+        }
+    }
+
+    #[test]
+    fn test_severity_names_and_otlp_numbers() {
+        let named: Vec<&str> = Severity::ALL.iter().map(|severity| severity.as_str()).collect();
+        assert_eq!(named, CANONICAL_LEVELS);
+
+        let numbers: Vec<i32> = Severity::ALL.iter().map(|severity| severity.otlp_number()).collect();
+        assert_eq!(numbers, [1, 5, 9, 13, 17, 21]);
+        assert!(Severity::Warn < Severity::Error);
+    }
+
+    #[test]
+    fn test_configured_aliases_extend_and_override() -> Result<()> {
+        let extra = HashMap::from([
+            ("oops".to_string(), "error".to_string()),
+            ("notice".to_string(), "WARN".to_string()),
+        ]);
+        let aliases = LevelAliases::new(&extra)?;
+
+        assert_eq!(aliases.normalize("OOPS"), Some("ERROR"));
+        assert_eq!(aliases.normalize("Notice"), Some("WARN"));
+        assert_eq!(aliases.normalize("warning"), Some("WARN"));
+        assert_eq!(aliases.normalize("3"), Some("ERROR"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alias_to_unknown_level_is_rejected() {
+        let extra = HashMap::from([("oops".to_string(), "BAD".to_string())]);
+        let error = LevelAliases::new(&extra).unwrap_err().to_string();
+        assert!(error.contains("not one of TRACE, DEBUG, INFO, WARN, ERROR, FATAL"));
+    }
+}
@@ -4,11 +4,43 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::sync::mpsc;
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::task::JoinHandle;
 
-use crate::collector::config::{SourceConfig, StartAt};
+use crate::collector::clock::{Clock, SharedClock};
+use crate::collector::docker::{self, Container, DockerClient, FrameDecoder, StreamKind};
+use crate::collector::checkpoint::SharedCheckpointer;
+use crate::collector::framing::Framer;
+use crate::collector::http_poll::{Fetch, HttpPoller};
+#[cfg(all(target_os = "linux", feature = "journald"))]
+use crate::collector::journald::{self, JournalReader};
+use crate::collector::otlp::{self, Encoding};
+use crate::collector::syslog;
+use crate::collector::tls::{Connection, TlsListener};
+use crate::collector::config::{
+    BindRetryConfig, Framing, HttpServerConfig, LongLineAction, MissingTimestamp, MultilineConfig, OtlpProtocol, SourceConfig, StartAt,
+    SyslogProtocol, TlsConfig, UnsupportedSourcePolicy,
+};
 
 /// A log entry collected from a source
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +51,9 @@ pub struct LogEntry {
     pub source: String,
     /// Log level or severity
     pub level: Option<String>,
+    /// Numeric severity reported by the source (OTLP 1-24 scale), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity_number: Option<i32>,
     /// Log message content
     pub message: String,
     /// Additional attributes/metadata
@@ -39,49 +74,234 @@ pub trait LogSource: Send + Sync {
     fn name(&self) -> &str;
 }
 
-/// Create a log source from configuration
-pub async fn create_source(config: &SourceConfig) -> Result<Box<dyn LogSource>> {
+/// Create a log source from configuration, stamping entries with `clock`
+///
+/// File sources given `claims` skip files another source already reads.
+/// Sources that resume after a restart keep their position in
+/// `checkpoints`; without one they start afresh each time.
+pub async fn create_source(
+    config: &SourceConfig,
+    clock: SharedClock,
+    claims: Option<&FileClaims>,
+    checkpoints: Option<SharedCheckpointer>,
+    max_connections: usize,
+) -> Result<Box<dyn LogSource>> {
     match config {
-        SourceConfig::File { name, include, exclude_filename_pattern, start_at } => {
+        SourceConfig::File {
+            name,
+            include,
+            exclude_filename_pattern,
+            start_at,
+            delimiter,
+            poll_interval_ms,
+            multiline,
+            max_line_bytes,
+            long_lines,
+            ..
+        } => {
+            let options = FileSourceOptions {
+                start_at: *start_at,
+                delimiter: delimiter.clone(),
+                poll_interval: Duration::from_millis(*poll_interval_ms),
+                multiline: multiline.clone(),
+                max_line_bytes: *max_line_bytes,
+                long_lines: *long_lines,
+                claims: claims.cloned(),
+            };
+
             Ok(Box::new(FileSource::new(
                 name.clone(),
                 include.clone(),
                 exclude_filename_pattern.clone(),
-                *start_at,
+                options,
+                checkpoints,
+                clock,
             )?))
         },
-        #[cfg(target_os = "linux")]
-        SourceConfig::Journald { name, directory, units } => {
+        #[cfg(all(target_os = "linux", feature = "journald"))]
+        SourceConfig::Journald { name, directory, units, .. } => {
             Ok(Box::new(JournaldSource::new(
                 name.clone(),
                 directory.clone(),
                 units.clone(),
+                checkpoints,
             )?))
         },
-        SourceConfig::Docker { name, containers, all_containers } => {
+        #[cfg(not(all(target_os = "linux", feature = "journald")))]
+        SourceConfig::Journald { name, .. } => {
+            Err(anyhow!("Journald source {} requires Linux and a build with the `journald` feature", name))
+        },
+        SourceConfig::Docker { name, containers, all_containers, socket_path, refresh_interval_seconds, .. } => {
             Ok(Box::new(DockerSource::new(
                 name.clone(),
                 containers.clone(),
                 *all_containers,
+                socket_path.clone(),
+                Duration::from_secs(*refresh_interval_seconds),
+                clock,
             )?))
         },
-        SourceConfig::Otlp { name, port, interface } => {
+        SourceConfig::Otlp { name, port, interface, protocol, bind_retry, server, tls, missing_timestamp, .. } => {
             Ok(Box::new(OtlpSource::new(
                 name.clone(),
                 *port,
                 interface.clone(),
+                *protocol,
+                bind_retry.clone(),
+                server.clone(),
+                tls.as_ref(),
+                *missing_timestamp,
+                clock,
+            )?))
+        },
+        SourceConfig::Syslog { name, port, interface, protocol, tls, .. } => {
+            Ok(Box::new(SyslogSource::new(
+                name.clone(),
+                *port,
+                interface.clone(),
+                *protocol,
+                tls.as_ref(),
+                max_connections,
+                clock,
             )?))
         },
+        SourceConfig::Tcp { name, port, interface, framing, .. } => {
+            Ok(Box::new(TcpSource::new(name.clone(), *port, interface.clone(), *framing, max_connections, clock)))
+        },
+        SourceConfig::Udp { name, port, interface, framing, .. } => {
+            Ok(Box::new(UdpSource::new(name.clone(), *port, interface.clone(), *framing, clock)))
+        },
+        SourceConfig::HttpPoll {
+            name,
+            url,
+            method,
+            headers,
+            auth,
+            poll_interval_seconds,
+            records_pointer,
+            message_pointer,
+            timestamp_pointer,
+            cursor,
+            ..
+        } => {
+            let poller = HttpPoller::new(
+                url.clone(),
+                *method,
+                headers.clone(),
+                auth.clone(),
+                records_pointer.clone(),
+                message_pointer.clone(),
+                timestamp_pointer.clone(),
+                cursor.clone(),
+            )?;
+            Ok(Box::new(HttpPollSource::new(
+                name.clone(),
+                poller,
+                Duration::from_secs(*poll_interval_seconds),
+                checkpoints,
+                clock,
+            )))
+        },
+    }
+}
+
+/// Select the sources that can run on this operating system
+///
+/// Unsupported sources are skipped with a warning, or rejected when the
+/// policy is `error`, so one config can be shared across platforms.
+pub fn supported_sources(
+    configs: &[SourceConfig],
+    policy: UnsupportedSourcePolicy,
+) -> Result<Vec<&SourceConfig>> {
+    let mut supported = Vec::new();
+
+    for config in configs {
+        if config.is_supported() {
+            supported.push(config);
+            continue;
+        }
+
+        match policy {
+            UnsupportedSourcePolicy::Skip => {
+                tracing::warn!(
+                    "Skipping source {}: not supported on {} or not enabled in this build",
+                    config.name(),
+                    std::env::consts::OS,
+                );
+            },
+            UnsupportedSourcePolicy::Error => {
+                return Err(anyhow!(
+                    "Source {} is not supported on {} or not enabled in this build; remove it or set unsupported_sources: skip",
+                    config.name(),
+                    std::env::consts::OS,
+                ));
+            },
+        }
+    }
+
+    Ok(supported)
+}
+
+/// Options controlling how a file source reads and tracks its files
+#[derive(Debug, Clone)]
+pub struct FileSourceOptions {
+    /// Where to start reading files that already exist at startup
+    pub start_at: StartAt,
+    /// Record delimiter
+    pub delimiter: String,
+    /// How often files are checked for new data, rotation and new matches
+    pub poll_interval: Duration,
+    /// Joining of multi-line entries, if enabled
+    pub multiline: Option<MultilineConfig>,
+    /// Longest record kept whole, in bytes
+    pub max_line_bytes: usize,
+    /// What to do with records longer than `max_line_bytes`
+    pub long_lines: LongLineAction,
+    /// Files already read by other sources, when overlapping files are deduplicated
+    pub claims: Option<FileClaims>,
+}
+
+/// Which file source reads each file, so overlapping sources read it only once
+///
+/// One registry is shared by every file source when `overlapping_files` is
+/// `dedupe`. The first source to open a file keeps it until that source stops.
+#[derive(Debug, Clone, Default)]
+pub struct FileClaims {
+    owners: Arc<Mutex<HashMap<PathBuf, String>>>,
+}
+
+impl FileClaims {
+    /// Claim `path` for `source`, returning false if another source holds it
+    pub fn claim(&self, path: &Path, source: &str) -> bool {
+        // The same file can be reached through differently spelled paths
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut owners = self.owners.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        owners.entry(path).or_insert_with(|| source.to_string()) == source
+    }
+
+    /// Give up every file held by `source`
+    pub fn release(&self, source: &str) {
+        let mut owners = self.owners.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        owners.retain(|_, owner| owner != source);
     }
 }
 
 /// File-based log source
+///
+/// Files matching the `include` glob patterns are tailed by polling. A file
+/// replaced under the same path (rotation, or deletion and re-creation) is
+/// finished through the old handle and then read from the start, as is a file
+/// that shrinks (truncation). Files that appear after startup are picked up
+/// on the next poll. With a checkpointer, read offsets are kept in it so a
+/// restart resumes where the last run stopped.
 pub struct FileSource {
     name: String,
-    file_paths: Vec<PathBuf>,
+    include: Vec<String>,
     exclude_pattern: Option<regex::Regex>,
-    start_at: StartAt,
-    running: bool,
+    options: FileSourceOptions,
+    checkpoints: Option<SharedCheckpointer>,
+    clock: SharedClock,
+    task: Option<JoinHandle<()>>,
 }
 
 impl FileSource {
@@ -90,336 +310,4045 @@ impl FileSource {
         name: String,
         include: Vec<String>,
         exclude_pattern: Option<String>,
-        start_at: StartAt,
+        options: FileSourceOptions,
+        checkpoints: Option<SharedCheckpointer>,
+        clock: SharedClock,
     ) -> Result<Self> {
+        if options.delimiter.is_empty() {
+            return Err(anyhow!("Record delimiter for source {} must not be empty", name));
+        }
+
         let exclude_regex = match exclude_pattern {
             Some(pattern) => Some(regex::Regex::new(&pattern)?),
             None => None,
         };
 
-        let file_paths = include
-            .iter()
-            .map(|path| PathBuf::from(path))
-            .collect();
+        for pattern in &include {
+            glob::Pattern::new(pattern)
+                .map_err(|e| anyhow!("Invalid include pattern {} for source {}: {}", pattern, name, e))?;
+        }
+
+        if let Some(multiline) = &options.multiline {
+            MultilineJoiner::new(multiline)
+                .map_err(|e| anyhow!("Invalid multiline start_pattern for source {}: {}", name, e))?;
+        }
 
         Ok(Self {
             name,
-            file_paths,
+            include,
             exclude_pattern: exclude_regex,
-            start_at,
-            running: false,
+            options,
+            checkpoints,
+            clock,
+            task: None,
         })
     }
 }
 
-#[async_trait]
-impl LogSource for FileSource {
-    async fn start(&mut self, sender: LogSender) -> Result<()> {
-        if self.running {
-            return Err(anyhow!("Source already running"));
+/// A record produced by a [`RecordSplitter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Record text
+    pub text: String,
+    /// How the record was cut down, if it exceeded the length limit
+    pub cut: Option<LongLineAction>,
+    /// Offset in the splitter's stream at which the record's bytes begin
+    pub start: u64,
+}
+
+impl Record {
+    /// Attribute flagging an entry built from a cut-down record
+    fn cut_attribute(&self) -> Option<(&'static str, String)> {
+        match self.cut? {
+            LongLineAction::Truncate => Some(("record.truncated", "true".to_string())),
+            LongLineAction::Split => Some(("record.split", "true".to_string())),
         }
+    }
+}
 
-        self.running = true;
+impl From<&str> for Record {
+    fn from(text: &str) -> Self {
+        Self { text: text.to_string(), cut: None, start: 0 }
+    }
+}
 
-        // Setup file watchers and start collecting logs
-        // Implementation will monitor files and send logs to the sender channel
-
-        // For each file path
-        for file_path in &self.file_paths {
-            // Skip if file matches exclude pattern
-            if let Some(ref pattern) = self.exclude_pattern {
-                if let Some(file_name) = file_path.file_name() {
-                    if let Some(name_str) = file_name.to_str() {
-                        if pattern.is_match(name_str) {
-                            continue;
-                        }
-                    }
-                }
-            }
+impl PartialEq<&str> for Record {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
 
-            // Start a task to monitor this file
-            // This is just a placeholder - actual implementation would be more complex
-            let path = file_path.clone();
-            let source_name = self.name.clone();
-            let sender_clone = sender.clone();
-            let start_at = self.start_at;
-
-            tokio::spawn(async move {
-                // Real implementation would use proper file monitoring
-                // This is just a placeholder for the structure
-                tracing::info!("Monitoring file: {:?}", path);
-
-                // Example log entry creation
-                let log = LogEntry {
-                    timestamp: Utc::now(),
-                    source: source_name.clone(),
-                    level: Some("INFO".to_string()),
-                    message: format!("Started monitoring file: {:?}", path),
-                    attributes: HashMap::new(),
-                };
+impl PartialEq<String> for Record {
+    fn eq(&self, other: &String) -> bool {
+        self.text == *other
+    }
+}
 
-                // Send the log entry
-                if let Err(e) = sender_clone.send(log).await {
-                    tracing::error!("Failed to send log: {}", e);
-                }
-            });
-        }
+/// Splits a byte stream into records on a delimiter
+///
+/// Partial records are carried over between chunks. With the `\n` delimiter
+/// a trailing `\r` is stripped so CRLF files split cleanly. Empty records,
+/// including the one after a trailing delimiter, are never emitted.
+///
+/// With a record limit, nothing longer than the limit is ever buffered: an
+/// over-long record is either truncated, with the rest skipped up to the next
+/// delimiter, or split into limit-sized pieces.
+pub struct RecordSplitter {
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    max_record_bytes: Option<usize>,
+    long_records: LongLineAction,
+    /// Skipping the remainder of a truncated record
+    discarding: bool,
+    /// Stream offset of the first buffered byte
+    position: u64,
+}
 
-        Ok(())
+impl RecordSplitter {
+    /// Create a splitter for the given delimiter
+    #[cfg(test)]
+    pub fn new(delimiter: &str) -> Self {
+        Self::with_limit(delimiter, None, LongLineAction::Truncate)
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        if !self.running {
-            return Err(anyhow!("Source not running"));
+    /// Create a splitter that handles records over `max_record_bytes` with `long_records`
+    pub fn with_limit(delimiter: &str, max_record_bytes: Option<usize>, long_records: LongLineAction) -> Self {
+        Self {
+            delimiter: delimiter.as_bytes().to_vec(),
+            buffer: Vec::new(),
+            max_record_bytes: max_record_bytes.map(|max| max.max(1)),
+            long_records,
+            discarding: false,
+            position: 0,
         }
+    }
 
-        self.running = false;
-        // Stop file watchers and clean up resources
+    /// Count stream offsets from `position`, such as the file offset reading starts at
+    pub fn starting_at(mut self, position: u64) -> Self {
+        self.position = position;
+        self
+    }
 
-        Ok(())
+    /// Feed a chunk of bytes, returning every record it completes
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Record> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut records = Vec::new();
+        let mut start = 0;
+
+        while let Some(position) = find_subslice(&self.buffer[start..], &self.delimiter) {
+            let end = start + position;
+            if self.discarding {
+                self.discarding = false;
+            } else {
+                self.decode_into(&self.buffer[start..end], self.position + start as u64, &mut records);
+            }
+            start = end + self.delimiter.len();
+        }
+
+        self.consume(start);
+        self.bound_buffer(&mut records);
+        records
     }
 
-    fn name(&self) -> &str {
-        &self.name
+    /// Number of bytes held back as an incomplete record
+    pub fn pending_len(&self) -> usize {
+        self.buffer.len()
     }
-}
 
-#[cfg(target_os = "linux")]
-/// Journald log source (Linux only)
-pub struct JournaldSource {
-    name: String,
-    directory: Option<String>,
-    units: Vec<String>,
-    running: bool,
-}
+    /// Return the final unterminated record, if any
+    pub fn finish(&mut self) -> Option<Record> {
+        let remaining = std::mem::take(&mut self.buffer);
+        let start = self.position;
+        self.position += remaining.len() as u64;
+        if std::mem::take(&mut self.discarding) {
+            return None;
+        }
 
-#[cfg(target_os = "linux")]
-impl JournaldSource {
-    /// Create a new journald source
-    pub fn new(
-        name: String,
-        directory: Option<String>,
-        units: Vec<String>,
-    ) -> Result<Self> {
-        Ok(Self {
-            name,
-            directory,
-            units,
-            running: false,
-        })
+        let mut records = Vec::new();
+        self.decode_into(&remaining, start, &mut records);
+        records.pop()
     }
-}
 
-#[cfg(target_os = "linux")]
-#[async_trait]
-impl LogSource for JournaldSource {
-    async fn start(&mut self, sender: LogSender) -> Result<()> {
-        if self.running {
-            return Err(anyhow!("Source already running"));
-        }
+    /// Drop the first `length` buffered bytes
+    fn consume(&mut self, length: usize) {
+        self.buffer.drain(..length);
+        self.position += length as u64;
+    }
 
-        self.running = true;
+    /// Keep the buffered partial record within the limit
+    fn bound_buffer(&mut self, records: &mut Vec<Record>) {
+        // Enough bytes to recognise a delimiter that straddles two chunks
+        let keep = self.delimiter.len() - 1;
 
-        // Setup journal monitoring and start collecting logs
-        // Implementation will monitor journald and send logs to the sender channel
+        if self.discarding {
+            let excess = self.buffer.len().saturating_sub(keep);
+            self.consume(excess);
+            return;
+        }
 
-        let source_name = self.name.clone();
-        let units = self.units.clone();
-        let directory = self.directory.clone();
+        let max = match self.max_record_bytes {
+            Some(max) => max,
+            None => return,
+        };
 
-        tokio::spawn(async move {
-            // Real implementation would use systemd journal API
-            // This is just a placeholder for the structure
-            tracing::info!("Monitoring journald for units: {:?}", units);
-
-            // Example log entry creation
-            let log = LogEntry {
-                timestamp: Utc::now(),
-                source: source_name.clone(),
-                level: Some("INFO".to_string()),
-                message: format!("Started monitoring journald for units: {:?}", units),
-                attributes: HashMap::new(),
-            };
+        while self.buffer.len() > max {
+            let cut = char_boundary(&self.buffer, max);
+            let text = String::from_utf8_lossy(&self.buffer[..cut]).into_owned();
+            records.push(Record { text, cut: Some(self.long_records), start: self.position });
 
-            // Send the log entry
-            if let Err(e) = sender.send(log).await {
-                tracing::error!("Failed to send log: {}", e);
+            match self.long_records {
+                LongLineAction::Split => {
+                    self.consume(cut);
+                },
+                LongLineAction::Truncate => {
+                    self.discarding = true;
+                    let excess = self.buffer.len().saturating_sub(keep);
+                    self.consume(excess);
+                    return;
+                },
             }
-        });
-
-        Ok(())
+        }
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        if !self.running {
-            return Err(anyhow!("Source not running"));
+    /// Turn raw record bytes found at stream offset `start` into records, dropping CR line endings and empty records
+    fn decode_into(&self, bytes: &[u8], mut start: u64, records: &mut Vec<Record>) {
+        let mut bytes = match (self.delimiter.as_slice(), bytes.split_last()) {
+            (b"\n", Some((b'\r', rest))) => rest,
+            _ => bytes,
+        };
+
+        let max = match self.max_record_bytes {
+            Some(max) if bytes.len() > max => max,
+            _ => {
+                if !bytes.is_empty() {
+                    records.push(Record { text: String::from_utf8_lossy(bytes).into_owned(), cut: None, start });
+                }
+                return;
+            },
+        };
+
+        while !bytes.is_empty() {
+            let cut = char_boundary(bytes, max);
+            records.push(Record {
+                text: String::from_utf8_lossy(&bytes[..cut]).into_owned(),
+                cut: Some(self.long_records),
+                start,
+            });
+
+            if self.long_records == LongLineAction::Truncate {
+                return;
+            }
+            bytes = &bytes[cut..];
+            start += cut as u64;
         }
+    }
+}
 
-        self.running = false;
-        // Stop journal monitoring and clean up resources
+/// Largest cut point at or below `max` that does not split a UTF-8 character
+fn char_boundary(bytes: &[u8], max: usize) -> usize {
+    if bytes.len() <= max {
+        return bytes.len();
+    }
 
-        Ok(())
+    let mut cut = max;
+    while cut > 0 && bytes[cut] & 0xC0 == 0x80 {
+        cut -= 1;
     }
 
-    fn name(&self) -> &str {
-        &self.name
+    // A limit smaller than one character still has to make progress
+    if cut == 0 {
+        max
+    } else {
+        cut
     }
 }
 
-/// Docker container log source
-pub struct DockerSource {
-    name: String,
-    containers: Vec<String>,
-    all_containers: bool,
-    running: bool,
+/// Joins continuation lines onto the entry begun by the last start line
+///
+/// Lines ahead of the first start line form an entry of their own. An entry
+/// is complete when the next start line arrives, when it reaches `max_lines`,
+/// or once no line has been added for the flush timeout. Joined entries keep
+/// the time their first line was read.
+pub struct MultilineJoiner {
+    start_pattern: regex::Regex,
+    max_lines: usize,
+    flush_timeout: chrono::Duration,
+    pending: Option<PendingEntry>,
 }
 
-impl DockerSource {
-    /// Create a new Docker log source
-    pub fn new(
-        name: String,
-        containers: Vec<String>,
-        all_containers: bool,
-    ) -> Result<Self> {
+/// A multi-line entry still collecting lines
+struct PendingEntry {
+    timestamp: DateTime<Utc>,
+    /// Stream offset of the first line
+    start: u64,
+    last_line_at: DateTime<Utc>,
+    lines: Vec<String>,
+    /// How the first cut-down line among `lines` was cut, if any
+    cut: Option<LongLineAction>,
+}
+
+impl MultilineJoiner {
+    /// Create a joiner from configuration
+    pub fn new(config: &MultilineConfig) -> Result<Self> {
         Ok(Self {
-            name,
-            containers,
-            all_containers,
-            running: false,
+            start_pattern: regex::Regex::new(&config.start_pattern)?,
+            max_lines: config.max_lines.max(1),
+            flush_timeout: chrono::Duration::milliseconds(config.flush_timeout_ms as i64),
+            pending: None,
         })
     }
-}
-
-#[async_trait]
-impl LogSource for DockerSource {
-    async fn start(&mut self, sender: LogSender) -> Result<()> {
-        if self.running {
-            return Err(anyhow!("Source already running"));
-        }
-
-        self.running = true;
 
-        // Setup Docker API client and start collecting logs
-        // Implementation will monitor Docker containers and send logs to the sender channel
+    /// Add a line read at `now`, returning the `(timestamp, record)` entries it completes
+    pub fn push(&mut self, line: Record, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, Record)> {
+        let mut completed = Vec::new();
 
-        let source_name = self.name.clone();
-        let containers = self.containers.clone();
-        let all_containers = self.all_containers;
+        if self.start_pattern.is_match(&line.text) {
+            completed.extend(self.take());
+        }
 
-        tokio::spawn(async move {
-            // Real implementation would use Docker API
-            // This is just a placeholder for the structure
-            tracing::info!("Monitoring Docker containers: {:?}, all: {}", containers, all_containers);
-
-            // Example log entry creation
-            let log = LogEntry {
-                timestamp: Utc::now(),
-                source: source_name.clone(),
-                level: Some("INFO".to_string()),
-                message: format!("Started monitoring Docker containers: {:?}", containers),
-                attributes: HashMap::new(),
-            };
+        match &mut self.pending {
+            Some(pending) => {
+                pending.lines.push(line.text);
+                pending.last_line_at = now;
+                pending.cut = pending.cut.or(line.cut);
+            },
+            None => {
+                self.pending = Some(PendingEntry {
+                    timestamp: now,
+                    start: line.start,
+                    last_line_at: now,
+                    lines: vec![line.text],
+                    cut: line.cut,
+                });
+            },
+        }
 
-            // Send the log entry
-            if let Err(e) = sender.send(log).await {
-                tracing::error!("Failed to send log: {}", e);
+        if let Some(pending) = &self.pending {
+            if pending.lines.len() >= self.max_lines {
+                completed.extend(self.take());
             }
-        });
+        }
 
-        Ok(())
+        completed
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        if !self.running {
-            return Err(anyhow!("Source not running"));
+    /// Release the pending entry if no line has been added for the flush timeout
+    pub fn flush_expired(&mut self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, Record)> {
+        match &self.pending {
+            Some(pending) if now - pending.last_line_at >= self.flush_timeout => self.take(),
+            _ => None,
         }
+    }
 
-        self.running = false;
-        // Stop Docker monitoring and clean up resources
+    /// Release the pending entry, if any
+    pub fn finish(&mut self) -> Option<(DateTime<Utc>, Record)> {
+        self.take()
+    }
 
-        Ok(())
+    /// Stream offset of the first line held for an incomplete entry
+    pub fn pending_start(&self) -> Option<u64> {
+        self.pending.as_ref().map(|pending| pending.start)
     }
 
-    fn name(&self) -> &str {
-        &self.name
+    fn take(&mut self) -> Option<(DateTime<Utc>, Record)> {
+        self.pending.take().map(|pending| {
+            let record = Record { text: pending.lines.join("\n"), cut: pending.cut, start: pending.start };
+            (pending.timestamp, record)
+        })
     }
 }
 
-/// OpenTelemetry Protocol HTTP receiver source
-pub struct OtlpSource {
-    name: String,
-    port: u16,
-    interface: String,
-    running: bool,
+/// Find the first occurrence of `needle` in `haystack`
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
 }
 
-impl OtlpSource {
-    /// Create a new OTLP source
-    pub fn new(
-        name: String,
-        port: u16,
-        interface: String,
-    ) -> Result<Self> {
-        Ok(Self {
-            name,
-            port,
-            interface,
-            running: false,
-        })
+/// Read offset persisted for one file
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredOffset {
+    /// Identity of the file the offset belongs to
+    identity: String,
+    /// Byte offset just past the last complete record
+    offset: u64,
+}
+
+/// Persists per-file read offsets through the source's checkpointer
+struct OffsetStore {
+    checkpoints: SharedCheckpointer,
+    source_name: String,
+}
+
+impl OffsetStore {
+    fn key(&self, path: &Path) -> String {
+        format!("file_offset:{}:{}", self.source_name, path.display())
+    }
+
+    /// Look up the stored offset for a file; failures only cost a re-read
+    fn load(&self, path: &Path) -> Option<StoredOffset> {
+        match self.checkpoints.load(&self.key(path)) {
+            Ok(value) => value.and_then(|value| serde_json::from_str(&value).ok()),
+            Err(e) => {
+                tracing::warn!("Failed to load offset for {:?}: {}", path, e);
+                None
+            },
+        }
+    }
+
+    fn save(&self, path: &Path, stored: &StoredOffset) {
+        let result = serde_json::to_string(stored)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| self.checkpoints.store(&self.key(path), &value));
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to save offset for {:?}: {}", path, e);
+        }
     }
 }
 
-#[async_trait]
-impl LogSource for OtlpSource {
-    async fn start(&mut self, sender: LogSender) -> Result<()> {
-        if self.running {
-            return Err(anyhow!("Source already running"));
+/// Identity of a file, used to notice when a path points at a new file
+#[cfg(unix)]
+fn file_identity(metadata: &std::fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    format!("{}:{}", metadata.dev(), metadata.ino())
+}
+
+/// Identity of a file, used to notice when a path points at a new file
+///
+/// Without inodes every file looks the same, so only truncation is detected.
+#[cfg(not(unix))]
+fn file_identity(_metadata: &std::fs::Metadata) -> String {
+    String::new()
+}
+
+/// An open file being followed
+struct FileTail {
+    file: tokio::fs::File,
+    identity: String,
+    /// Bytes read from the file so far
+    offset: u64,
+    /// Offset last written to the offset store
+    saved: u64,
+    splitter: RecordSplitter,
+    joiner: Option<MultilineJoiner>,
+}
+
+/// Follows every file matched by a file source
+struct FileTailer {
+    source_name: String,
+    include: Vec<String>,
+    exclude_pattern: Option<regex::Regex>,
+    options: FileSourceOptions,
+    offsets: Option<OffsetStore>,
+    clock: SharedClock,
+    sender: LogSender,
+    tails: HashMap<PathBuf, FileTail>,
+    /// Matching files left to another source that already reads them
+    skipped: HashSet<PathBuf>,
+    /// Gzipped rotations found at startup, read before following the live files
+    rotations: Vec<PathBuf>,
+}
+
+impl FileTailer {
+    /// Poll the matched files until the log channel closes
+    async fn run(mut self) {
+        self.ingest_rotations().await;
+
+        loop {
+            self.follow_all().await;
+
+            if self.sender.is_closed() {
+                return;
+            }
+
+            tokio::time::sleep(self.options.poll_interval).await;
+            self.discover(false).await;
         }
+    }
 
-        self.running = true;
+    /// Open files that newly match the include patterns
+    ///
+    /// The `initial` scan at startup honours `start_at`; files found later
+    /// did not exist before and are read in full.
+    async fn discover(&mut self, initial: bool) {
+        for path in self.matching_paths() {
+            if self.tails.contains_key(&path) || self.skipped.contains(&path) {
+                continue;
+            }
+            if let Some(claims) = &self.options.claims {
+                if !claims.claim(&path, &self.source_name) {
+                    tracing::info!("{}: skipping {:?}, another source already reads it", self.source_name, path);
+                    self.skipped.insert(path);
+                    continue;
+                }
+            }
 
-        // Setup HTTP server to receive OTLP logs
-        // Implementation will start an HTTP server and send logs to the sender channel
+            match self.open(&path, initial).await {
+                Ok(tail) => {
+                    self.tails.insert(path, tail);
+                },
+                Err(e) => tracing::warn!("Failed to open {:?}: {}", path, e),
+            }
+        }
+    }
 
-        let source_name = self.name.clone();
-        let port = self.port;
-        let interface = self.interface.clone();
+    /// Paths currently matching the include patterns and not excluded
+    fn matching_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
 
-        tokio::spawn(async move {
-            // Real implementation would start an HTTP server
-            // This is just a placeholder for the structure
-            tracing::info!("Starting OTLP receiver on {}:{}", interface, port);
-
-            // Example log entry creation
-            let log = LogEntry {
-                timestamp: Utc::now(),
-                source: source_name.clone(),
-                level: Some("INFO".to_string()),
-                message: format!("Started OTLP receiver on {}:{}", interface, port),
-                attributes: HashMap::new(),
+        for pattern in &self.include {
+            let entries = match glob::glob(pattern) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("Invalid include pattern {}: {}", pattern, e);
+                    continue;
+                },
             };
 
-            // Send the log entry
-            if let Err(e) = sender.send(log).await {
-                tracing::error!("Failed to send log: {}", e);
+            for path in entries.flatten() {
+                if path.is_file() && !self.is_excluded(&path) && !paths.contains(&path) {
+                    paths.push(path);
+                }
             }
-        });
+        }
 
-        Ok(())
+        paths
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        if !self.running {
-            return Err(anyhow!("Source not running"));
+    /// Whether the file name matches the exclude pattern
+    fn is_excluded(&self, path: &Path) -> bool {
+        match (&self.exclude_pattern, path.file_name().and_then(|name| name.to_str())) {
+            (Some(pattern), Some(name)) => pattern.is_match(name),
+            _ => false,
         }
+    }
 
-        self.running = false;
-        // Stop HTTP server and clean up resources
+    /// Open a newly matched file, choosing where to start reading
+    ///
+    /// A stored offset for the same file takes precedence over `start_at`.
+    /// Reading a file from the beginning at startup also queues its gzipped
+    /// rotations, so the history before it is collected too.
+    async fn open(&mut self, path: &Path, initial: bool) -> Result<FileTail> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let identity = file_identity(&metadata);
 
-        Ok(())
-    }
+        let stored = self.offsets.as_ref()
+            .and_then(|offsets| offsets.load(path))
+            .filter(|stored| stored.identity == identity && stored.offset <= metadata.len());
 
-    fn name(&self) -> &str {
+        let offset = match stored {
+            Some(stored) => stored.offset,
+            None if initial && self.options.start_at == StartAt::End => metadata.len(),
+            None if initial => {
+                let rotations = self.rotated_siblings(path);
+                self.rotations.extend(rotations);
+                0
+            },
+            None => 0,
+        };
+
+        self.open_at(path, offset).await
+    }
+
+    /// Gzipped rotations of a live file, oldest first
+    ///
+    /// Siblings named `<name>.gz`, `<name>.<suffix>.gz` or `<name>-<suffix>.gz`
+    /// are ordered by modification time; rotations written in the same instant
+    /// fall back to the rotation number, where a higher number is older.
+    fn rotated_siblings(&self, path: &Path) -> Vec<PathBuf> {
+        let (Some(dir), Some(live)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+            return Vec::new();
+        };
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Failed to list rotations of {:?}: {}", path, e);
+                return Vec::new();
+            },
+        };
+
+        let mut rotations = Vec::new();
+        for entry in entries.flatten() {
+            let sibling = entry.path();
+            let suffix = match sibling.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(live))
+                .and_then(|rest| rest.strip_suffix(".gz"))
+            {
+                Some(suffix) if suffix.is_empty() || suffix.starts_with(['.', '-']) => suffix,
+                _ => continue,
+            };
+            if !sibling.is_file() || self.is_excluded(&sibling) {
+                continue;
+            }
+
+            let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok();
+            let number = suffix.trim_start_matches(['.', '-']).parse::<u64>().unwrap_or(0);
+            rotations.push((modified, std::cmp::Reverse(number), sibling));
+        }
+
+        rotations.sort();
+        rotations.into_iter().map(|(_, _, sibling)| sibling).collect()
+    }
+
+    /// Read every gzipped rotation queued at startup
+    ///
+    /// Rotations are complete, so each one is read to its end and never followed.
+    /// A rotation that fails to decompress is skipped with a warning.
+    async fn ingest_rotations(&mut self) {
+        for rotation in std::mem::take(&mut self.rotations) {
+            tracing::info!("Reading rotated file {:?}", rotation);
+            if let Err(e) = self.ingest_gzip(&rotation).await {
+                if self.sender.is_closed() {
+                    return;
+                }
+                tracing::warn!("Failed to read rotated file {:?}: {}", rotation, e);
+            }
+        }
+    }
+
+    /// Emit every record of one gzipped file
+    async fn ingest_gzip(&self, path: &Path) -> Result<()> {
+        use std::io::Read;
+
+        let mut decoder = flate2::read::MultiGzDecoder::new(std::fs::File::open(path)?);
+        let mut splitter = self.splitter();
+        let mut joiner = self.options.multiline.as_ref().map(MultilineJoiner::new).transpose()?;
+        let mut chunk = vec![0u8; 64 * 1024];
+
+        loop {
+            let read = decoder.read(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+
+            for record in splitter.push(&chunk[..read]) {
+                self.collect(path, joiner.as_mut(), record).await?;
+            }
+        }
+
+        if let Some(record) = splitter.finish() {
+            self.collect(path, joiner.as_mut(), record).await?;
+        }
+        if let Some((timestamp, record)) = joiner.as_mut().and_then(MultilineJoiner::finish) {
+            self.emit(path, record, timestamp).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Open a file positioned at `offset`
+    async fn open_at(&self, path: &Path, offset: u64) -> Result<FileTail> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let identity = file_identity(&file.metadata().await?);
+        file.seek(SeekFrom::Start(offset)).await?;
+
+        tracing::info!("Monitoring file {:?} from offset {}", path, offset);
+
+        Ok(FileTail {
+            file,
+            identity,
+            offset,
+            saved: offset,
+            splitter: self.splitter().starting_at(offset),
+            joiner: self.options.multiline.as_ref().map(MultilineJoiner::new).transpose()?,
+        })
+    }
+
+    /// Splitter for a newly opened or truncated file
+    fn splitter(&self) -> RecordSplitter {
+        RecordSplitter::with_limit(&self.options.delimiter, Some(self.options.max_line_bytes), self.options.long_lines)
+    }
+
+    /// Read new data from every followed file
+    async fn follow_all(&mut self) {
+        let paths: Vec<PathBuf> = self.tails.keys().cloned().collect();
+
+        for path in paths {
+            if let Err(e) = self.follow(&path).await {
+                tracing::warn!("Failed to read {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// Read whatever was appended to a followed file, handling rotation, truncation and deletion
+    async fn follow(&mut self, path: &Path) -> Result<()> {
+        let mut tail = match self.tails.remove(path) {
+            Some(tail) => tail,
+            None => return Ok(()),
+        };
+
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // The open handle still reaches whatever was written before removal;
+                // a file re-created at this path is picked up by the next discovery
+                tracing::info!("File {:?} was removed", path);
+                return self.drain(path, &mut tail, true).await;
+            },
+            Err(e) => {
+                self.tails.insert(path.to_path_buf(), tail);
+                return Err(e.into());
+            },
+        };
+
+        if file_identity(&metadata) != tail.identity {
+            tracing::info!("File {:?} was rotated", path);
+            self.drain(path, &mut tail, true).await?;
+            tail = self.open_at(path, 0).await?;
+        } else if metadata.len() < tail.offset {
+            tracing::info!("File {:?} was truncated", path);
+            tail.file.seek(SeekFrom::Start(0)).await?;
+            tail.offset = 0;
+            tail.splitter = self.splitter();
+        }
+
+        let result = self.drain(path, &mut tail, false).await;
+        self.tails.insert(path.to_path_buf(), tail);
+        result
+    }
+
+    /// Read a file to its current end, emitting every complete record
+    ///
+    /// A `finished` file will not grow any further, so its unterminated last
+    /// record and any pending multi-line entry are emitted too; otherwise the
+    /// offset of the last complete record, or of the first line of a pending
+    /// multi-line entry, is persisted.
+    async fn drain(&self, path: &Path, tail: &mut FileTail, finished: bool) -> Result<()> {
+        let mut chunk = vec![0u8; 64 * 1024];
+
+        loop {
+            let read = tail.file.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+
+            tail.offset += read as u64;
+            for record in tail.splitter.push(&chunk[..read]) {
+                self.collect(path, tail.joiner.as_mut(), record).await?;
+            }
+        }
+
+        if finished {
+            if let Some(record) = tail.splitter.finish() {
+                self.collect(path, tail.joiner.as_mut(), record).await?;
+            }
+            if let Some((timestamp, record)) = tail.joiner.as_mut().and_then(MultilineJoiner::finish) {
+                self.emit(path, record, timestamp).await?;
+            }
+            return Ok(());
+        }
+
+        let expired = tail.joiner.as_mut().and_then(|joiner| joiner.flush_expired(self.clock.now()));
+        if let Some((timestamp, record)) = expired {
+            self.emit(path, record, timestamp).await?;
+        }
+
+        // Lines the joiner holds are not emitted yet, so a restart reads the pending entry again
+        let committed = match tail.joiner.as_ref().and_then(MultilineJoiner::pending_start) {
+            Some(start) => start,
+            None => tail.offset - tail.splitter.pending_len() as u64,
+        };
+        if let Some(offsets) = &self.offsets {
+            if committed != tail.saved {
+                offsets.save(path, &StoredOffset { identity: tail.identity.clone(), offset: committed });
+                tail.saved = committed;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Emit a record, or hand it to the multiline joiner
+    async fn collect(&self, path: &Path, joiner: Option<&mut MultilineJoiner>, record: Record) -> Result<()> {
+        let now = self.clock.now();
+
+        match joiner {
+            Some(joiner) => {
+                for (timestamp, joined) in joiner.push(record, now) {
+                    self.emit(path, joined, timestamp).await?;
+                }
+                Ok(())
+            },
+            None => self.emit(path, record, now).await,
+        }
+    }
+
+    /// Send one record as a log entry
+    async fn emit(&self, path: &Path, record: Record, timestamp: DateTime<Utc>) -> Result<()> {
+        let mut attributes = HashMap::new();
+        attributes.insert("file.path".to_string(), path.display().to_string());
+        if let Some((key, value)) = record.cut_attribute() {
+            attributes.insert(key.to_string(), value);
+        }
+
+        let log = LogEntry {
+            timestamp,
+            source: self.source_name.clone(),
+            level: None,
+            severity_number: None,
+            message: record.text,
+            attributes,
+        };
+
+        self.sender.send(log).await.map_err(|_| anyhow!("Log channel closed"))
+    }
+}
+
+#[async_trait]
+impl LogSource for FileSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.task.is_some() {
+            return Err(anyhow!("Source already running"));
+        }
+
+        let offsets = self.checkpoints.clone().map(|checkpoints| OffsetStore {
+            checkpoints,
+            source_name: self.name.clone(),
+        });
+
+        let mut tailer = FileTailer {
+            source_name: self.name.clone(),
+            include: self.include.clone(),
+            exclude_pattern: self.exclude_pattern.clone(),
+            options: self.options.clone(),
+            offsets,
+            clock: self.clock.clone(),
+            sender,
+            tails: HashMap::new(),
+            skipped: HashSet::new(),
+            rotations: Vec::new(),
+        };
+
+        // Settle starting offsets now, so anything written after `start` returns is collected
+        tailer.discover(true).await;
+        self.task = Some(tokio::spawn(tailer.run()));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        match self.task.take() {
+            Some(task) => {
+                task.abort();
+                if let Some(claims) = &self.options.claims {
+                    claims.release(&self.name);
+                }
+                Ok(())
+            },
+            None => Err(anyhow!("Source not running")),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// How long the journald reader blocks waiting for new entries before checking for stop
+#[cfg(all(target_os = "linux", feature = "journald"))]
+const JOURNAL_WAIT: Duration = Duration::from_millis(250);
+
+/// Most journal entries read before the cursor is saved
+#[cfg(all(target_os = "linux", feature = "journald"))]
+const JOURNAL_CURSOR_BATCH: usize = 1000;
+
+/// Journald log source (Linux with the `journald` feature only)
+///
+/// Follows the journal through sd-journal on a blocking thread, filtered to
+/// `units`. With a checkpointer the cursor of the last entry read is saved
+/// in it, so a restart resumes right after it; otherwise reading starts at
+/// the end of the journal.
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub struct JournaldSource {
+    name: String,
+    directory: Option<String>,
+    units: Vec<String>,
+    checkpoints: Option<SharedCheckpointer>,
+    stop: Arc<AtomicBool>,
+    task: Option<JoinHandle<()>>,
+}
+
+#[cfg(all(target_os = "linux", feature = "journald"))]
+impl JournaldSource {
+    /// Create a new journald source
+    pub fn new(
+        name: String,
+        directory: Option<String>,
+        units: Vec<String>,
+        checkpoints: Option<SharedCheckpointer>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            directory,
+            units,
+            checkpoints,
+            stop: Arc::new(AtomicBool::new(false)),
+            task: None,
+        })
+    }
+
+    /// Metadata key holding this source's journal cursor
+    fn cursor_key(source_name: &str) -> String {
+        format!("journald_cursor:{}", source_name)
+    }
+}
+
+/// Reads journal entries into the pipeline until stopped or the channel closes
+#[cfg(all(target_os = "linux", feature = "journald"))]
+fn follow_journal(
+    mut reader: JournalReader,
+    source_name: String,
+    cursors: Option<SharedCheckpointer>,
+    sender: LogSender,
+    stop: Arc<AtomicBool>,
+) {
+    let cursor_key = JournaldSource::cursor_key(&source_name);
+
+    while !stop.load(Ordering::Relaxed) {
+        let mut read = 0;
+
+        while read < JOURNAL_CURSOR_BATCH {
+            match reader.next_entry() {
+                Ok(Some((fields, received))) => {
+                    let log = journald::to_log_entry(&source_name, &fields, received);
+                    if sender.blocking_send(log).is_err() {
+                        return;
+                    }
+                    read += 1;
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("{}: failed to read journal entry: {}", source_name, e);
+                    break;
+                }
+            }
+        }
+
+        if read > 0 {
+            if let Some(cursors) = &cursors {
+                let saved = reader.cursor().and_then(|cursor| cursors.store(&cursor_key, &cursor));
+                if let Err(e) = saved {
+                    tracing::warn!("{}: failed to save journal cursor: {}", source_name, e);
+                }
+            }
+        }
+
+        if read < JOURNAL_CURSOR_BATCH {
+            if let Err(e) = reader.wait(JOURNAL_WAIT) {
+                tracing::warn!("{}: failed to wait for journal changes: {}", source_name, e);
+                std::thread::sleep(JOURNAL_WAIT);
+            }
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "journald"))]
+#[async_trait]
+impl LogSource for JournaldSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.task.is_some() {
+            return Err(anyhow!("Source already running"));
+        }
+
+        let cursors = self.checkpoints.clone();
+        let cursor = match &cursors {
+            Some(cursors) => cursors.load(&Self::cursor_key(&self.name))?,
+            None => None,
+        };
+
+        let source_name = self.name.clone();
+        let directory = self.directory.clone();
+        let units = self.units.clone();
+        let stop = self.stop.clone();
+        stop.store(false, Ordering::Relaxed);
+
+        // The journal handle stays on the blocking thread; report how opening it went
+        let (opened_tx, opened_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::task::spawn_blocking(move || {
+            let reader = JournalReader::open(directory.as_deref(), &units).and_then(|mut reader| {
+                reader.seek(cursor.as_deref())?;
+                Ok(reader)
+            });
+
+            match reader {
+                Ok(reader) => {
+                    let _ = opened_tx.send(Ok(()));
+                    follow_journal(reader, source_name, cursors, sender, stop);
+                },
+                Err(e) => {
+                    let _ = opened_tx.send(Err(e));
+                },
+            }
+        });
+
+        opened_rx
+            .await
+            .map_err(|_| anyhow!("Journal reader for source {} exited during startup", self.name))?
+            .map_err(|e| anyhow!("Failed to open journal for source {}: {}", self.name, e))?;
+
+        tracing::info!("Following journald for units: {:?}", self.units);
+        self.task = Some(task);
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        match self.task.take() {
+            Some(_) => {
+                // The blocking reader notices within one wait interval
+                self.stop.store(true, Ordering::Relaxed);
+                Ok(())
+            },
+            None => Err(anyhow!("Source not running")),
+        }
+    }
+
+    fn name(&self) -> &str {
         &self.name
     }
 }
+
+/// Longest Docker log line kept whole; longer lines are truncated
+const DOCKER_MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Docker container log source
+///
+/// Follows the stdout and stderr of the selected containers through the
+/// Docker Engine API. Running containers are re-listed every
+/// `refresh_interval`, so containers started later are picked up. A
+/// follower ends when its container stops; if the container comes back it
+/// resumes after the last line already collected.
+pub struct DockerSource {
+    name: String,
+    containers: Vec<String>,
+    all_containers: bool,
+    client: DockerClient,
+    refresh_interval: Duration,
+    clock: SharedClock,
+    task: Option<JoinHandle<()>>,
+}
+
+impl DockerSource {
+    /// Create a new Docker log source talking to the daemon on `socket_path`
+    pub fn new(
+        name: String,
+        containers: Vec<String>,
+        all_containers: bool,
+        socket_path: String,
+        refresh_interval: Duration,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        if containers.is_empty() && !all_containers {
+            return Err(anyhow!(
+                "Docker source {} selects no containers; list them in `containers` or set `all_containers`",
+                name,
+            ));
+        }
+
+        Ok(Self {
+            name,
+            containers,
+            all_containers,
+            client: DockerClient::new(socket_path),
+            refresh_interval,
+            clock,
+            task: None,
+        })
+    }
+}
+
+/// Keeps one follower task per selected running container
+struct DockerWatcher {
+    containers: Vec<String>,
+    all_containers: bool,
+    client: DockerClient,
+    refresh_interval: Duration,
+    clock: SharedClock,
+    sender: LogSender,
+    /// Running followers by container ID; each returns the last timestamp it saw
+    followers: HashMap<String, JoinHandle<DateTime<Utc>>>,
+    /// Where to resume containers whose follower has ended
+    resume_from: HashMap<String, DateTime<Utc>>,
+    started: DateTime<Utc>,
+}
+
+impl DockerWatcher {
+    /// Re-list containers every refresh interval until aborted
+    async fn run(mut self) {
+        let mut refresh_timer = tokio::time::interval(self.refresh_interval);
+
+        loop {
+            refresh_timer.tick().await;
+            if let Err(e) = self.refresh().await {
+                tracing::warn!("Failed to list Docker containers: {}", e);
+            }
+        }
+    }
+
+    /// Whether `container` is one this source collects from
+    fn selects(&self, container: &Container) -> bool {
+        self.all_containers || self.containers.iter().any(|reference| container.matches(reference))
+    }
+
+    /// Reap ended followers and start one for each newly running container
+    async fn refresh(&mut self) -> Result<()> {
+        self.reap().await;
+
+        for container in self.client.list_containers().await? {
+            if !self.selects(&container) || self.followers.contains_key(&container.id) {
+                continue;
+            }
+
+            let tty = match self.client.has_tty(&container.id).await {
+                Ok(tty) => tty,
+                Err(e) => {
+                    tracing::warn!("Failed to inspect Docker container {}: {}", container.name, e);
+                    continue;
+                }
+            };
+
+            let resume_from = self.resume_from.get(&container.id).copied();
+            let follower = ContainerFollower {
+                since: resume_from.unwrap_or(self.started),
+                skip_through: resume_from,
+                container: container.clone(),
+                tty,
+                client: self.client.clone(),
+                clock: self.clock.clone(),
+                sender: self.sender.clone(),
+            };
+
+            tracing::info!("Following logs of Docker container {}", container.name);
+            self.followers.insert(container.id, tokio::spawn(follower.run()));
+        }
+
+        Ok(())
+    }
+
+    /// Collect followers whose container stopped, remembering where each left off
+    async fn reap(&mut self) {
+        let finished: Vec<String> = self.followers
+            .iter()
+            .filter(|(_, follower)| follower.is_finished())
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in finished {
+            if let Some(follower) = self.followers.remove(&id) {
+                match follower.await {
+                    Ok(last_seen) => {
+                        self.resume_from.insert(id, last_seen);
+                    },
+                    Err(e) => tracing::warn!("Docker log follower for {} failed: {}", id, e),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DockerWatcher {
+    fn drop(&mut self) {
+        // Aborting the watcher task drops it, which must take the followers down too
+        for follower in self.followers.values() {
+            follower.abort();
+        }
+    }
+}
+
+/// Streams one container's logs into the pipeline
+struct ContainerFollower {
+    container: Container,
+    tty: bool,
+    since: DateTime<Utc>,
+    /// Lines up to this timestamp were already collected by an earlier follower
+    skip_through: Option<DateTime<Utc>>,
+    client: DockerClient,
+    clock: SharedClock,
+    sender: LogSender,
+}
+
+impl ContainerFollower {
+    /// Follow until the stream ends, returning the last timestamp seen
+    async fn run(self) -> DateTime<Utc> {
+        let mut last_seen = self.since;
+
+        if let Err(e) = self.follow(&mut last_seen).await {
+            tracing::warn!("Stopped following Docker container {}: {}", self.container.name, e);
+        }
+
+        last_seen
+    }
+
+    async fn follow(&self, last_seen: &mut DateTime<Utc>) -> Result<()> {
+        let mut body = self.client.logs(&self.container.id, self.since).await?;
+        let mut decoder = FrameDecoder::new(self.tty);
+        let mut splitters: HashMap<StreamKind, RecordSplitter> = HashMap::new();
+
+        while let Some(chunk) = body.data().await {
+            for frame in decoder.push(&chunk?)? {
+                let splitter = splitters.entry(frame.stream).or_insert_with(|| {
+                    RecordSplitter::with_limit("\n", Some(DOCKER_MAX_LINE_BYTES), LongLineAction::Truncate)
+                });
+
+                for record in splitter.push(&frame.payload) {
+                    self.emit(frame.stream, record, last_seen).await?;
+                }
+            }
+        }
+
+        // The container stopped; emit whatever was left unterminated
+        for (stream, mut splitter) in splitters {
+            if let Some(record) = splitter.finish() {
+                self.emit(stream, record, last_seen).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn emit(&self, stream: StreamKind, record: Record, last_seen: &mut DateTime<Utc>) -> Result<()> {
+        let (timestamp, message) = docker::split_timestamp(&record.text);
+
+        if let Some(timestamp) = timestamp {
+            if self.skip_through.is_some_and(|skip_through| timestamp <= skip_through) {
+                return Ok(());
+            }
+            *last_seen = timestamp;
+        }
+
+        let mut attributes = HashMap::new();
+        attributes.insert("container.id".to_string(), self.container.id.clone());
+        attributes.insert("container.name".to_string(), self.container.name.clone());
+        attributes.insert("stream".to_string(), stream.as_str().to_string());
+        if let Some((key, value)) = record.cut_attribute() {
+            attributes.insert(key.to_string(), value);
+        }
+
+        let log = LogEntry {
+            timestamp: timestamp.unwrap_or_else(|| self.clock.now()),
+            source: self.container.name.clone(),
+            level: None,
+            severity_number: None,
+            message: message.to_string(),
+            attributes,
+        };
+
+        self.sender.send(log).await.map_err(|_| anyhow!("Log channel closed"))
+    }
+}
+
+#[async_trait]
+impl LogSource for DockerSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.task.is_some() {
+            return Err(anyhow!("Source already running"));
+        }
+
+        let watcher = DockerWatcher {
+            containers: self.containers.clone(),
+            all_containers: self.all_containers,
+            client: self.client.clone(),
+            refresh_interval: self.refresh_interval,
+            clock: self.clock.clone(),
+            sender,
+            followers: HashMap::new(),
+            resume_from: HashMap::new(),
+            started: Utc::now(),
+        };
+
+        self.task = Some(tokio::spawn(watcher.run()));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        match self.task.take() {
+            Some(task) => {
+                task.abort();
+                Ok(())
+            },
+            None => Err(anyhow!("Source not running")),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// OpenTelemetry Protocol receiver source, over HTTP or gRPC
+///
+/// Both transports decode the same protobuf and turn records into entries
+/// through [`process_otlp_logs`], so an export yields identical entries
+/// whichever way it arrives.
+pub struct OtlpSource {
+    name: String,
+    port: u16,
+    interface: String,
+    protocol: OtlpProtocol,
+    bind_retry: Option<BindRetryConfig>,
+    server: HttpServerConfig,
+    tls: Option<TlsListener>,
+    missing_timestamp: MissingTimestamp,
+    clock: SharedClock,
+    server_task: Option<ServerTask>,
+    running: bool,
+}
+
+impl OtlpSource {
+    /// Create a new OTLP source
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: String,
+        port: u16,
+        interface: String,
+        protocol: OtlpProtocol,
+        bind_retry: Option<BindRetryConfig>,
+        server: HttpServerConfig,
+        tls: Option<&TlsConfig>,
+        missing_timestamp: MissingTimestamp,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        // Load certificates now so a bad path fails at startup, not on the first client
+        // A client must finish its handshake within the idle timeout
+        let tls = tls
+            .map(|tls| TlsListener::new(tls).map(|tls| tls.with_handshake_timeout(Duration::from_secs(server.idle_timeout_seconds))))
+            .transpose()
+            .map_err(|e| anyhow!("OTLP source {}: {}", name, e))?;
+
+        Ok(Self {
+            name,
+            port,
+            interface,
+            protocol,
+            bind_retry,
+            server,
+            tls,
+            missing_timestamp,
+            clock,
+            server_task: None,
+            running: false,
+        })
+    }
+}
+
+#[async_trait]
+impl LogSource for OtlpSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.running {
+            return Err(anyhow!("Source already running"));
+        }
+
+        // Bind before reporting success so a taken port fails startup
+        // instead of leaving a source that silently receives nothing
+        let address = format!("{}:{}", self.interface, self.port);
+        let listener = bind_with_retry(&address, self.bind_retry.as_ref())
+            .await
+            .map_err(|e| anyhow!("OTLP source {}: {}", self.name, e))?;
+
+        self.server_task = Some(match self.protocol {
+            OtlpProtocol::Http => {
+                let (tls, name, clock, server) = (self.tls.clone(), self.name.clone(), self.clock.clone(), self.server.clone());
+                let missing_timestamp = self.missing_timestamp;
+                ServerTask::spawn(move |shutdown| {
+                    start_otlp_server(listener, tls, name, missing_timestamp, sender, clock, server, shutdown)
+                })
+            },
+            OtlpProtocol::Grpc => {
+                let service = OtlpGrpcService {
+                    source_name: self.name.clone(),
+                    missing_timestamp: self.missing_timestamp,
+                    sender,
+                    clock: self.clock.clone(),
+                };
+                ServerTask::spawn(move |shutdown| start_otlp_grpc_server(listener, service, shutdown))
+            },
+        });
+        self.running = true;
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if !self.running {
+            return Err(anyhow!("Source not running"));
+        }
+
+        self.running = false;
+
+        // Wait for the listener to close so a restart can bind the port again
+        if let Some(task) = self.server_task.take() {
+            task.stop(SHUTDOWN_GRACE).await;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// How long a stopping network source waits for in-flight requests before cutting them off
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// A network server task and the signal asking it to shut down
+///
+/// Servers watch the signal, drop their listener as soon as it fires and
+/// then let open connections wind down, so the port can be bound again
+/// once [`ServerTask::stop`] returns.
+struct ServerTask {
+    task: JoinHandle<()>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl ServerTask {
+    /// Spawn `serve` with the receiver it should watch for shutdown
+    fn spawn<F>(serve: impl FnOnce(watch::Receiver<bool>) -> F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (shutdown, receiver) = watch::channel(false);
+        Self { task: tokio::spawn(serve(receiver)), shutdown }
+    }
+
+    /// Signal shutdown and wait for the server to finish, aborting it after `grace`
+    async fn stop(mut self, grace: Duration) {
+        let _ = self.shutdown.send(true);
+        if tokio::time::timeout(grace, &mut self.task).await.is_err() {
+            self.task.abort();
+            let _ = self.task.await;
+        }
+    }
+}
+
+/// Bind a TCP listener, retrying with exponential backoff when configured
+pub async fn bind_with_retry(address: &str, retry: Option<&BindRetryConfig>) -> Result<TcpListener> {
+    let max_attempts = retry.map_or(1, |retry| retry.max_attempts.max(1));
+    let max_backoff = Duration::from_millis(retry.map_or(0, |retry| retry.max_backoff_ms));
+    let mut backoff = Duration::from_millis(retry.map_or(0, |retry| retry.initial_backoff_ms));
+    let mut attempt = 1;
+
+    loop {
+        match TcpListener::bind(address).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if attempt < max_attempts => {
+                tracing::warn!(
+                    "Failed to bind {} (attempt {}/{}): {}; retrying in {:?}",
+                    address, attempt, max_attempts, e, backoff,
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            },
+            Err(e) => {
+                return Err(anyhow!(
+                    "failed to bind {} after {} attempt(s): {} (is the port already in use?)",
+                    address, attempt, e,
+                ));
+            },
+        }
+    }
+}
+
+/// Serve OTLP/HTTP log exports on an already bound listener
+///
+/// Each connection speaks HTTP/1.1, or HTTP/2 when the client opens with the
+/// HTTP/2 preface and `server.http2` is set. At most `server.max_connections`
+/// are served at once; further clients wait in the listen backlog. With
+/// `tls`, connections are sorted into HTTPS and plain HTTP per its mode.
+/// On `shutdown` the listener is closed and open connections are given
+/// their request in progress before closing.
+#[allow(clippy::too_many_arguments)]
+async fn start_otlp_server(
+    listener: TcpListener,
+    tls: Option<TlsListener>,
+    source_name: String,
+    missing_timestamp: MissingTimestamp,
+    sender: LogSender,
+    clock: SharedClock,
+    server: HttpServerConfig,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let local_address = listener.local_addr().ok();
+
+    let mut http = Http::new();
+    http.http1_keep_alive(server.keep_alive);
+    if !server.http2 {
+        http.http1_only(true);
+    }
+
+    let idle_timeout = Duration::from_secs(server.idle_timeout_seconds);
+    let slots = Arc::new(Semaphore::new(server.max_connections));
+    // Dropping the set when this task is aborted aborts every connection
+    let mut connections = tokio::task::JoinSet::new();
+
+    tracing::info!("OTLP receiver listening on {:?}", local_address);
+
+    loop {
+        let slot = tokio::select! {
+            slot = slots.clone().acquire_owned() => match slot {
+                Ok(slot) => slot,
+                Err(_) => return,
+            },
+            _ = shutdown.changed() => break,
+        };
+
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("OTLP source {}: failed to accept connection: {}", source_name, e);
+                    continue;
+                },
+            },
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            _ = shutdown.changed() => break,
+        };
+
+        let connection = accept_otlp_connection(
+            stream,
+            peer,
+            tls.clone(),
+            http.clone(),
+            idle_timeout,
+            source_name.clone(),
+            missing_timestamp,
+            sender.clone(),
+            clock.clone(),
+            shutdown.clone(),
+        );
+        connections.spawn(async move {
+            connection.await;
+            drop(slot);
+        });
+    }
+
+    // Free the port at once, then let each connection finish its request in progress
+    drop(listener);
+    tracing::info!("OTLP receiver on {:?} stopped accepting; draining {} connection(s)", local_address, connections.len());
+    while connections.join_next().await.is_some() {}
+}
+
+/// Complete any TLS handshake on a new OTLP connection, then serve it
+///
+/// A handshake that fails, such as a client refused for its certificate, or
+/// that is still unfinished after `idle_timeout`, closes the connection.
+#[allow(clippy::too_many_arguments)]
+async fn accept_otlp_connection(
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    tls: Option<TlsListener>,
+    http: Http,
+    idle_timeout: Duration,
+    source_name: String,
+    missing_timestamp: MissingTimestamp,
+    sender: LogSender,
+    clock: SharedClock,
+    shutdown: watch::Receiver<bool>,
+) {
+    let tls = match tls {
+        Some(tls) => tls,
+        None => {
+            let stream = ActivityStream::new(stream);
+            return serve_otlp_connection(http, stream, peer, idle_timeout, source_name, missing_timestamp, sender, clock, shutdown).await;
+        },
+    };
+
+    match tls.accept(stream).await {
+        Ok(Some(Connection::Tls(stream))) => {
+            let stream = ActivityStream::new(stream);
+            serve_otlp_connection(http, stream, peer, idle_timeout, source_name, missing_timestamp, sender, clock, shutdown).await
+        },
+        Ok(Some(Connection::Plain(stream))) => {
+            let stream = ActivityStream::new(stream);
+            serve_otlp_connection(http, stream, peer, idle_timeout, source_name, missing_timestamp, sender, clock, shutdown).await
+        },
+        Ok(None) => {},
+        Err(e) => tracing::warn!("OTLP source {}: rejected connection from {}: {}", source_name, peer, e),
+    }
+}
+
+/// Serve one OTLP connection, closing it after `idle_timeout` without traffic or on shutdown
+#[allow(clippy::too_many_arguments)]
+async fn serve_otlp_connection<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
+    http: Http,
+    stream: ActivityStream<S>,
+    peer: SocketAddr,
+    idle_timeout: Duration,
+    source_name: String,
+    missing_timestamp: MissingTimestamp,
+    sender: LogSender,
+    clock: SharedClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let last_active = stream.last_active.clone();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let requests = in_flight.clone();
+    let service = service_fn(move |request| {
+        let requests = requests.clone();
+        requests.fetch_add(1, Ordering::SeqCst);
+        let handled = handle_otlp_request(request, source_name.clone(), missing_timestamp, sender.clone(), clock.clone());
+        async move {
+            let response = handled.await;
+            requests.fetch_sub(1, Ordering::SeqCst);
+            response
+        }
+    });
+
+    let connection = http.serve_connection(stream, service);
+    tokio::pin!(connection);
+    let mut closing = false;
+
+    loop {
+        let deadline = *last_active.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) + idle_timeout;
+
+        tokio::select! {
+            result = connection.as_mut() => {
+                if let Err(e) = result {
+                    tracing::debug!("OTLP connection from {} closed: {}", peer, e);
+                }
+                return;
+            },
+            _ = tokio::time::sleep_until(deadline), if !closing => {
+                if tokio::time::Instant::now() >= deadline {
+                    // Lets a request in progress finish before closing
+                    if in_flight.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                    connection.as_mut().graceful_shutdown();
+                    closing = true;
+                }
+            },
+            _ = shutdown.changed(), if !closing => {
+                if in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                connection.as_mut().graceful_shutdown();
+                closing = true;
+            },
+        }
+    }
+}
+
+/// TCP stream that records when data last moved in either direction
+struct ActivityStream<S> {
+    inner: S,
+    last_active: Arc<Mutex<tokio::time::Instant>>,
+}
+
+impl<S> ActivityStream<S> {
+    fn new(inner: S) -> Self {
+        Self { inner, last_active: Arc::new(Mutex::new(tokio::time::Instant::now())) }
+    }
+
+    fn touch(&self) {
+        *self.last_active.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = tokio::time::Instant::now();
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ActivityStream<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if buf.filled().len() > filled {
+            self.touch();
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ActivityStream<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            if written > 0 {
+                self.touch();
+            }
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Handle a single request to the OTLP receiver
+async fn handle_otlp_request(
+    request: Request<Body>,
+    source_name: String,
+    missing_timestamp: MissingTimestamp,
+    sender: LogSender,
+    clock: SharedClock,
+) -> std::result::Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST || request.uri().path() != "/v1/logs" {
+        return Ok(status_response(StatusCode::NOT_FOUND));
+    }
+
+    let encoding = Encoding::from_content_type(
+        request.headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok()),
+    );
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("OTLP source {}: failed to read request body: {}", source_name, e);
+            return Ok(status_response(StatusCode::BAD_REQUEST));
+        }
+    };
+
+    let export_request = match otlp::decode_request(&body, encoding) {
+        Ok(export_request) => export_request,
+        Err(e) => {
+            tracing::warn!("OTLP source {}: {}", source_name, e);
+            return Ok(status_response(StatusCode::BAD_REQUEST));
+        }
+    };
+
+    match process_otlp_logs(export_request, &source_name, missing_timestamp, &sender, clock.as_ref()).await {
+        Ok(()) => {
+            let mut response = Response::new(Body::from(otlp::encode_success(encoding)));
+            response.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static(encoding.content_type()),
+            );
+            Ok(response)
+        },
+        Err(e) => {
+            tracing::error!("OTLP source {}: failed to forward logs: {}", source_name, e);
+            Ok(status_response(StatusCode::SERVICE_UNAVAILABLE))
+        }
+    }
+}
+
+/// Forward every log record of a decoded OTLP export request
+async fn process_otlp_logs(
+    export_request: otlp::ExportLogsServiceRequest,
+    source_name: &str,
+    missing_timestamp: MissingTimestamp,
+    sender: &LogSender,
+    clock: &dyn Clock,
+) -> Result<()> {
+    for log in otlp::to_log_entries(export_request, source_name, missing_timestamp, clock) {
+        sender.send(log).await?;
+    }
+
+    Ok(())
+}
+
+/// Serve the OTLP gRPC logs service, and gRPC health checking, on an already bound listener
+async fn start_otlp_grpc_server(listener: TcpListener, service: OtlpGrpcService, mut shutdown: watch::Receiver<bool>) {
+    tracing::info!("OTLP gRPC receiver listening on {:?}", listener.local_addr().ok());
+
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter.set_serving::<OtlpGrpcService>().await;
+
+    // Failed accepts are logged and skipped rather than ending the server
+    let source_name = service.source_name.clone();
+    let incoming = futures::stream::unfold(listener, move |listener| {
+        let source_name = source_name.clone();
+        async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => return Some((Ok::<_, std::io::Error>(stream), listener)),
+                    Err(e) => tracing::warn!("OTLP source {}: failed to accept connection: {}", source_name, e),
+                }
+            }
+        }
+    });
+
+    let source_name = service.source_name.clone();
+    let served = tonic::transport::Server::builder()
+        .add_service(health_service)
+        .add_service(service)
+        .serve_with_incoming_shutdown(incoming, async move {
+            let _ = shutdown.changed().await;
+        })
+        .await;
+    if let Err(e) = served {
+        tracing::error!("OTLP source {}: gRPC server failed: {}", source_name, e);
+    }
+}
+
+/// The OTLP `LogsService`, forwarding each `Export` through [`process_otlp_logs`]
+#[derive(Clone)]
+struct OtlpGrpcService {
+    source_name: String,
+    missing_timestamp: MissingTimestamp,
+    sender: LogSender,
+    clock: SharedClock,
+}
+
+impl tonic::server::NamedService for OtlpGrpcService {
+    const NAME: &'static str = otlp::LOGS_SERVICE_NAME;
+}
+
+impl tonic::server::UnaryService<otlp::ExportLogsServiceRequest> for OtlpGrpcService {
+    type Response = otlp::ExportLogsServiceResponse;
+    type Future = tonic::codegen::BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+
+    fn call(&mut self, request: tonic::Request<otlp::ExportLogsServiceRequest>) -> Self::Future {
+        let service = self.clone();
+
+        Box::pin(async move {
+            let source_name = &service.source_name;
+            match process_otlp_logs(request.into_inner(), source_name, service.missing_timestamp, &service.sender, service.clock.as_ref()).await {
+                Ok(()) => Ok(tonic::Response::new(otlp::ExportLogsServiceResponse { partial_success: None })),
+                Err(e) => {
+                    tracing::error!("OTLP source {}: failed to forward logs: {}", source_name, e);
+                    Err(tonic::Status::unavailable("collector is not accepting logs"))
+                },
+            }
+        })
+    }
+}
+
+impl tonic::codegen::Service<tonic::codegen::http::Request<Body>> for OtlpGrpcService {
+    type Response = tonic::codegen::http::Response<tonic::body::BoxBody>;
+    type Error = Infallible;
+    type Future = tonic::codegen::BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: tonic::codegen::http::Request<Body>) -> Self::Future {
+        let service = self.clone();
+
+        Box::pin(async move {
+            if request.uri().path() != otlp::GRPC_EXPORT_PATH {
+                // The only RPC of the service; anything else is UNIMPLEMENTED
+                let response = tonic::codegen::http::Response::builder()
+                    .header("grpc-status", tonic::Code::Unimplemented as i32)
+                    .header("content-type", "application/grpc")
+                    .body(tonic::codegen::empty_body())
+                    .expect("static response parts are valid");
+                return Ok(response);
+            }
+
+            let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+            Ok(grpc.unary(service, request).await)
+        })
+    }
+}
+
+/// Build an empty response with the given status
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Longest syslog message accepted over TCP, matching common relay limits
+const SYSLOG_MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Syslog receiver source for RFC 3164 and RFC 5424 messages
+///
+/// Over TCP the source can also accept TLS. Entries from a TLS-enabled
+/// listener carry a `tls` attribute, plus the subject and issuer of any
+/// client certificate.
+pub struct SyslogSource {
+    name: String,
+    port: u16,
+    interface: String,
+    protocol: SyslogProtocol,
+    tls: Option<TlsListener>,
+    max_connections: usize,
+    clock: SharedClock,
+    server_task: Option<ServerTask>,
+    running: bool,
+}
+
+impl SyslogSource {
+    /// Create a new syslog source
+    pub fn new(
+        name: String,
+        port: u16,
+        interface: String,
+        protocol: SyslogProtocol,
+        tls: Option<&TlsConfig>,
+        max_connections: usize,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        // Load certificates now so a bad path fails at startup, not on the first client
+        let tls = tls
+            .map(TlsListener::new)
+            .transpose()
+            .map_err(|e| anyhow!("Syslog source {}: {}", name, e))?;
+
+        Ok(Self {
+            name,
+            port,
+            interface,
+            protocol,
+            tls,
+            max_connections: max_connections.max(1),
+            clock,
+            server_task: None,
+            running: false,
+        })
+    }
+}
+
+#[async_trait]
+impl LogSource for SyslogSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.running {
+            return Err(anyhow!("Source already running"));
+        }
+
+        // Bind before reporting success, as the OTLP receiver does
+        let address = format!("{}:{}", self.interface, self.port);
+        let task = match self.protocol {
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind(&address)
+                    .await
+                    .map_err(|e| anyhow!("Syslog source {}: failed to bind {}: {}", self.name, address, e))?;
+                let (name, clock) = (self.name.clone(), self.clock.clone());
+                ServerTask::spawn(move |shutdown| receive_syslog_udp(socket, name, sender, clock, shutdown))
+            },
+            SyslogProtocol::Tcp => {
+                let listener = bind_with_retry(&address, None)
+                    .await
+                    .map_err(|e| anyhow!("Syslog source {}: {}", self.name, e))?;
+                let (tls, max_connections, name, clock) = (self.tls.clone(), self.max_connections, self.name.clone(), self.clock.clone());
+                ServerTask::spawn(move |shutdown| accept_syslog_tcp(listener, tls, max_connections, name, sender, clock, shutdown))
+            },
+        };
+
+        self.server_task = Some(task);
+        self.running = true;
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if !self.running {
+            return Err(anyhow!("Source not running"));
+        }
+
+        self.running = false;
+
+        // Wait for the listener to close so a restart can bind the port again
+        if let Some(task) = self.server_task.take() {
+            task.stop(SHUTDOWN_GRACE).await;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Build an entry from one syslog message, tagged with the sender's address
+fn syslog_entry(text: &str, peer: SocketAddr, source_name: &str, clock: &dyn Clock) -> LogEntry {
+    let now = clock.now();
+    let mut log = syslog::parse(text, now).into_log_entry(source_name, now);
+    log.attributes.insert("net.peer.ip".to_string(), peer.ip().to_string());
+    log
+}
+
+/// Receive one syslog message per datagram
+async fn receive_syslog_udp(
+    socket: UdpSocket,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("Syslog receiver listening on udp {:?}", socket.local_addr().ok());
+
+    // Large enough for any UDP payload
+    let mut buffer = vec![0u8; 65_536];
+
+    loop {
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buffer) => received,
+            _ = shutdown.changed() => return,
+        };
+        let (length, peer) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                tracing::warn!("Syslog source {}: failed to receive datagram: {}", source_name, e);
+                continue;
+            }
+        };
+
+        let text = String::from_utf8_lossy(&buffer[..length]);
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if sender.send(syslog_entry(&text, peer, &source_name, clock.as_ref())).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Accept syslog connections, reading each on its own task until the source stops
+///
+/// At most `max_connections` are read at once; further clients wait in the
+/// listen backlog until a connection closes.
+async fn accept_syslog_tcp(
+    listener: TcpListener,
+    tls: Option<TlsListener>,
+    max_connections: usize,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("Syslog receiver listening on tcp {:?}", listener.local_addr().ok());
+
+    // Dropping the set when this task ends aborts every connection
+    let mut connections = tokio::task::JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept(), if connections.len() < max_connections => match accepted {
+                Ok((stream, peer)) => {
+                    connections.spawn(serve_syslog_tcp(
+                        stream,
+                        peer,
+                        tls.clone(),
+                        source_name.clone(),
+                        sender.clone(),
+                        clock.clone(),
+                    ));
+                },
+                Err(e) => {
+                    tracing::warn!("Syslog source {}: failed to accept connection: {}", source_name, e);
+                },
+            },
+            Some(_) = connections.join_next(), if !connections.is_empty() => {},
+            _ = shutdown.changed() => return,
+        }
+    }
+}
+
+/// Sort one connection into TLS or plaintext, when TLS is enabled, then read it
+async fn serve_syslog_tcp(
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    tls: Option<TlsListener>,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+) {
+    let tls = match tls {
+        Some(tls) => tls,
+        None => return read_syslog_tcp(stream, peer, HashMap::new(), source_name, sender, clock).await,
+    };
+
+    match tls.accept(stream).await {
+        Ok(Some(connection)) => {
+            let attributes = connection.attributes();
+            match connection {
+                Connection::Tls(stream) => read_syslog_tcp(stream, peer, attributes, source_name, sender, clock).await,
+                Connection::Plain(stream) => read_syslog_tcp(stream, peer, attributes, source_name, sender, clock).await,
+            }
+        },
+        Ok(None) => {},
+        Err(e) => tracing::warn!("Syslog source {}: rejected connection from {}: {}", source_name, peer, e),
+    }
+}
+
+/// Read framed syslog messages from one connection until it closes
+///
+/// `attributes` describe the connection and are added to every entry.
+async fn read_syslog_tcp<S: AsyncRead + Unpin>(
+    mut stream: S,
+    peer: SocketAddr,
+    attributes: HashMap<String, String>,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+) {
+    let entry = |message: &str| {
+        let mut log = syslog_entry(message, peer, &source_name, clock.as_ref());
+        log.attributes.extend(attributes.clone());
+        log
+    };
+
+    let mut framer = syslog::TcpFramer::new(SYSLOG_MAX_MESSAGE_BYTES);
+    let mut buffer = vec![0u8; 8192];
+
+    loop {
+        let read = match stream.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) => {
+                tracing::debug!("Syslog source {}: connection from {} failed: {}", source_name, peer, e);
+                break;
+            }
+        };
+
+        let messages = match framer.push(&buffer[..read]) {
+            Ok(messages) => messages,
+            Err(e) => {
+                tracing::warn!("Syslog source {}: closing connection from {}: {}", source_name, peer, e);
+                return;
+            }
+        };
+
+        for message in messages {
+            if sender.send(entry(&message)).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    if let Some(message) = framer.finish() {
+        let _ = sender.send(entry(&message)).await;
+    }
+}
+
+/// Longest record accepted by the TCP and UDP sources
+const SOCKET_MAX_RECORD_BYTES: usize = 64 * 1024;
+
+/// Build an entry from one raw record, tagged with the sender's address
+fn socket_entry(record: String, peer: SocketAddr, source_name: &str, clock: &dyn Clock) -> LogEntry {
+    let mut attributes = HashMap::new();
+    attributes.insert("net.peer.ip".to_string(), peer.ip().to_string());
+    attributes.insert("net.peer.port".to_string(), peer.port().to_string());
+
+    LogEntry {
+        timestamp: clock.now(),
+        source: source_name.to_string(),
+        level: None,
+        severity_number: None,
+        message: record,
+        attributes,
+    }
+}
+
+/// Source reading raw text records from TCP connections
+///
+/// Each connection is read on its own task and framed independently, so
+/// records split across reads are reassembled per client.
+pub struct TcpSource {
+    name: String,
+    port: u16,
+    interface: String,
+    framing: Framing,
+    max_connections: usize,
+    clock: SharedClock,
+    server_task: Option<ServerTask>,
+    running: bool,
+}
+
+impl TcpSource {
+    /// Create a new TCP source
+    pub fn new(name: String, port: u16, interface: String, framing: Framing, max_connections: usize, clock: SharedClock) -> Self {
+        Self {
+            name,
+            port,
+            interface,
+            framing,
+            max_connections: max_connections.max(1),
+            clock,
+            server_task: None,
+            running: false,
+        }
+    }
+}
+
+#[async_trait]
+impl LogSource for TcpSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.running {
+            return Err(anyhow!("Source already running"));
+        }
+
+        let address = format!("{}:{}", self.interface, self.port);
+        let listener = bind_with_retry(&address, None)
+            .await
+            .map_err(|e| anyhow!("TCP source {}: {}", self.name, e))?;
+        let (framing, max_connections, name, clock) = (self.framing, self.max_connections, self.name.clone(), self.clock.clone());
+        self.server_task = Some(ServerTask::spawn(move |shutdown| {
+            accept_socket_tcp(listener, framing, max_connections, name, sender, clock, shutdown)
+        }));
+        self.running = true;
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if !self.running {
+            return Err(anyhow!("Source not running"));
+        }
+
+        self.running = false;
+
+        // Wait for the listener to close so a restart can bind the port again
+        if let Some(task) = self.server_task.take() {
+            task.stop(SHUTDOWN_GRACE).await;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Accept TCP connections, reading each on its own task until the source stops
+///
+/// At most `max_connections` are read at once; further clients wait in the
+/// listen backlog until a connection closes.
+async fn accept_socket_tcp(
+    listener: TcpListener,
+    framing: Framing,
+    max_connections: usize,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("TCP source {} listening on {:?}", source_name, listener.local_addr().ok());
+
+    // Dropping the set when this task ends aborts every connection
+    let mut connections = tokio::task::JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept(), if connections.len() < max_connections => match accepted {
+                Ok((stream, peer)) => {
+                    connections.spawn(read_socket_tcp(stream, peer, framing, source_name.clone(), sender.clone(), clock.clone()));
+                },
+                Err(e) => {
+                    tracing::warn!("TCP source {}: failed to accept connection: {}", source_name, e);
+                },
+            },
+            Some(_) = connections.join_next(), if !connections.is_empty() => {},
+            _ = shutdown.changed() => return,
+        }
+    }
+}
+
+/// Read framed records from one connection until it closes
+async fn read_socket_tcp(
+    mut stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    framing: Framing,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+) {
+    let mut framer = Framer::new(framing, SOCKET_MAX_RECORD_BYTES);
+    let mut buffer = vec![0u8; 8192];
+
+    loop {
+        let read = match stream.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) => {
+                tracing::debug!("TCP source {}: connection from {} failed: {}", source_name, peer, e);
+                break;
+            }
+        };
+
+        let records = match framer.push(&buffer[..read]) {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::warn!("TCP source {}: closing connection from {}: {}", source_name, peer, e);
+                return;
+            }
+        };
+
+        for record in records {
+            if sender.send(socket_entry(record, peer, &source_name, clock.as_ref())).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    if let Some(record) = framer.finish() {
+        let _ = sender.send(socket_entry(record, peer, &source_name, clock.as_ref())).await;
+    }
+}
+
+/// Source reading raw text records from UDP datagrams
+///
+/// Each datagram is framed on its own, so it may carry several records but
+/// a record never spans datagrams.
+pub struct UdpSource {
+    name: String,
+    port: u16,
+    interface: String,
+    framing: Framing,
+    clock: SharedClock,
+    server_task: Option<ServerTask>,
+    running: bool,
+}
+
+impl UdpSource {
+    /// Create a new UDP source
+    pub fn new(name: String, port: u16, interface: String, framing: Framing, clock: SharedClock) -> Self {
+        Self {
+            name,
+            port,
+            interface,
+            framing,
+            clock,
+            server_task: None,
+            running: false,
+        }
+    }
+}
+
+#[async_trait]
+impl LogSource for UdpSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.running {
+            return Err(anyhow!("Source already running"));
+        }
+
+        let address = format!("{}:{}", self.interface, self.port);
+        let socket = UdpSocket::bind(&address)
+            .await
+            .map_err(|e| anyhow!("UDP source {}: failed to bind {}: {}", self.name, address, e))?;
+        let (framing, name, clock) = (self.framing, self.name.clone(), self.clock.clone());
+        self.server_task = Some(ServerTask::spawn(move |shutdown| receive_socket_udp(socket, framing, name, sender, clock, shutdown)));
+        self.running = true;
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if !self.running {
+            return Err(anyhow!("Source not running"));
+        }
+
+        self.running = false;
+
+        // Wait for the listener to close so a restart can bind the port again
+        if let Some(task) = self.server_task.take() {
+            task.stop(SHUTDOWN_GRACE).await;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Receive datagrams, emitting every record each one holds
+async fn receive_socket_udp(
+    socket: UdpSocket,
+    framing: Framing,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("UDP source {} listening on {:?}", source_name, socket.local_addr().ok());
+
+    // Large enough for any UDP payload
+    let mut buffer = vec![0u8; 65_536];
+
+    loop {
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buffer) => received,
+            _ = shutdown.changed() => return,
+        };
+        let (length, peer) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                tracing::warn!("UDP source {}: failed to receive datagram: {}", source_name, e);
+                continue;
+            }
+        };
+
+        let mut framer = Framer::new(framing, SOCKET_MAX_RECORD_BYTES);
+        let mut records = match framer.push(&buffer[..length]) {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::warn!("UDP source {}: dropping datagram from {}: {}", source_name, peer, e);
+                continue;
+            }
+        };
+        records.extend(framer.finish());
+
+        for record in records {
+            if sender.send(socket_entry(record, peer, &source_name, clock.as_ref())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Longest wait honoured from a polled API's `Retry-After`
+const HTTP_POLL_MAX_RETRY_AFTER: Duration = Duration::from_secs(3600);
+
+/// Source polling a REST API for records
+///
+/// Every interval, pages are fetched until the API stops handing back a
+/// next cursor. With a checkpointer, the last cursor and the latest record
+/// timestamp are kept in it: polling resumes from that
+/// cursor after a restart, and records no newer than the latest timestamp
+/// already emitted are skipped rather than collected twice.
+pub struct HttpPollSource {
+    name: String,
+    poller: Arc<HttpPoller>,
+    interval: Duration,
+    checkpoints: Option<SharedCheckpointer>,
+    clock: SharedClock,
+    task: Option<JoinHandle<()>>,
+}
+
+impl HttpPollSource {
+    /// Create a source polling with `poller` every `interval`
+    pub fn new(
+        name: String,
+        poller: HttpPoller,
+        interval: Duration,
+        checkpoints: Option<SharedCheckpointer>,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            name,
+            poller: Arc::new(poller),
+            interval,
+            checkpoints,
+            clock,
+            task: None,
+        }
+    }
+
+    /// Metadata key holding this source's next cursor
+    fn cursor_key(source_name: &str) -> String {
+        format!("http_poll_cursor:{}", source_name)
+    }
+
+    /// Metadata key holding the latest timestamp this source emitted
+    fn timestamp_key(source_name: &str) -> String {
+        format!("http_poll_timestamp:{}", source_name)
+    }
+}
+
+#[async_trait]
+impl LogSource for HttpPollSource {
+    async fn start(&mut self, sender: LogSender) -> Result<()> {
+        if self.task.is_some() {
+            return Err(anyhow!("Source already running"));
+        }
+
+        self.task = Some(tokio::spawn(poll_http(
+            self.poller.clone(),
+            self.interval,
+            self.checkpoints.clone(),
+            self.name.clone(),
+            sender,
+            self.clock.clone(),
+        )));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        match self.task.take() {
+            Some(task) => {
+                task.abort();
+                Ok(())
+            },
+            None => Err(anyhow!("Source not running")),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Poll every `interval`, reading pages until the last, until the channel closes
+async fn poll_http(
+    poller: Arc<HttpPoller>,
+    interval: Duration,
+    state: Option<SharedCheckpointer>,
+    source_name: String,
+    sender: LogSender,
+    clock: SharedClock,
+) {
+    let cursor_key = HttpPollSource::cursor_key(&source_name);
+    let timestamp_key = HttpPollSource::timestamp_key(&source_name);
+    let load = |key: &str| state.as_ref().and_then(|state| state.load(key).ok().flatten());
+    let save = |key: &str, value: &str| {
+        if let Some(Err(e)) = state.as_ref().map(|state| state.store(key, value)) {
+            tracing::warn!("HTTP source {}: failed to save poll state: {}", source_name, e);
+        }
+    };
+
+    let mut cursor = load(&cursor_key);
+    let mut latest = load(&timestamp_key)
+        .and_then(|stamp| DateTime::parse_from_rfc3339(&stamp).ok())
+        .map(|stamp| stamp.with_timezone(&Utc));
+
+    loop {
+        // Compare against what earlier polls emitted, as pages may run newest first
+        let newest = latest;
+
+        loop {
+            let page = match poller.fetch(cursor.as_deref()).await {
+                Ok(Fetch::Page(page)) => page,
+                Ok(Fetch::RateLimited(retry_after)) => {
+                    let delay = retry_after.unwrap_or(interval).min(HTTP_POLL_MAX_RETRY_AFTER);
+                    tracing::warn!("HTTP source {}: rate limited, retrying in {:?}", source_name, delay);
+                    tokio::time::sleep(delay).await;
+                    continue;
+                },
+                Err(e) => {
+                    tracing::warn!("HTTP source {}: poll failed: {}", source_name, e);
+                    break;
+                },
+            };
+
+            let before = latest;
+            for record in page.records {
+                // Records already emitted by an earlier poll come back when a page is re-read
+                if poller.has_timestamps() {
+                    match (record.timestamp, newest) {
+                        (Some(stamp), Some(newest)) if stamp <= newest => continue,
+                        (Some(stamp), _) => latest = latest.max(Some(stamp)),
+                        (None, _) => {},
+                    }
+                }
+                if sender.send(record.into_log_entry(&source_name, clock.now())).await.is_err() {
+                    return;
+                }
+            }
+            if latest != before {
+                if let Some(latest) = latest {
+                    save(&timestamp_key, &latest.to_rfc3339());
+                }
+            }
+
+            match page.next_cursor {
+                Some(next) if cursor.as_ref() != Some(&next) => {
+                    save(&cursor_key, &next);
+                    cursor = Some(next);
+                },
+                _ => break,
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::checkpoint::MemoryCheckpointer;
+    use crate::collector::clock::{system_clock, MockClock};
+    use crate::collector::config::{HttpCursorConfig, HttpMethod, HttpPollAuth, TlsMode};
+    use std::io::Write;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tokio::time::timeout;
+    use tokio_rustls::rustls;
+
+    fn file_options(start_at: StartAt, delimiter: &str) -> FileSourceOptions {
+        FileSourceOptions {
+            start_at,
+            delimiter: delimiter.to_string(),
+            poll_interval: Duration::from_millis(20),
+            multiline: None,
+            max_line_bytes: 1024 * 1024,
+            long_lines: LongLineAction::Truncate,
+            claims: None,
+        }
+    }
+
+    fn file_source(include: &Path, options: FileSourceOptions) -> Result<FileSource> {
+        FileSource::new(
+            "app".to_string(),
+            vec![include.to_string_lossy().to_string()],
+            None,
+            options,
+            None,
+            system_clock(),
+        )
+    }
+
+    fn append(path: &Path, content: &str) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Receive the next message, failing the test if none arrives
+    async fn next_message(receiver: &mut mpsc::Receiver<LogEntry>) -> String {
+        timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("timed out waiting for a record")
+            .expect("log channel closed")
+            .message
+    }
+
+    /// Assert that nothing more is emitted for a few polls
+    async fn assert_quiet(receiver: &mut mpsc::Receiver<LogEntry>) {
+        assert!(timeout(Duration::from_millis(200), receiver.recv()).await.is_err());
+    }
+
+    #[test]
+    fn test_splitter_handles_crlf_across_chunks() {
+        let mut splitter = RecordSplitter::new("\n");
+
+        assert_eq!(splitter.push(b"one\r\ntwo\r\nthr"), vec!["one", "two"]);
+        assert_eq!(splitter.push(b"ee\r"), Vec::<String>::new());
+        assert_eq!(splitter.push(b"\n\r\n"), vec!["three"]);
+        assert_eq!(splitter.finish(), None);
+    }
+
+    #[test]
+    fn test_splitter_handles_nul_delimiter() {
+        let mut splitter = RecordSplitter::new("\0");
+
+        assert_eq!(splitter.push(b"first\0second\0\0thi"), vec!["first", "second"]);
+        assert_eq!(splitter.push(b"rd\r\n"), Vec::<String>::new());
+        // CR is only stripped for newline-delimited records
+        // The record starts after "first\0second\0\0"
+        assert_eq!(splitter.finish(), Some(Record { start: 14, ..Record::from("third\r\n") }));
+    }
+
+    #[test]
+    fn test_splitter_truncates_long_records_without_buffering_them() {
+        let mut splitter = RecordSplitter::with_limit("\n", Some(8), LongLineAction::Truncate);
+
+        assert_eq!(splitter.push(b"short\nabcdefghij"), vec!["short", "abcdefgh"]);
+        // The rest of the long record is skipped rather than held
+        for _ in 0..1000 {
+            assert!(splitter.push(&[b'x'; 1024]).is_empty());
+            assert_eq!(splitter.pending_len(), 0);
+        }
+        assert_eq!(splitter.push(b"tail\nnext\n"), vec!["next"]);
+
+        // Complete records arriving in one chunk are truncated too
+        let records = splitter.push(b"0123456789\n");
+        assert_eq!(records, vec!["01234567"]);
+        assert_eq!(records[0].cut, Some(LongLineAction::Truncate));
+        assert_eq!(splitter.finish(), None);
+    }
+
+    #[test]
+    fn test_splitter_splits_long_records() {
+        let mut splitter = RecordSplitter::with_limit("\n", Some(4), LongLineAction::Split);
+
+        let records = splitter.push(b"abcdefghij");
+        assert_eq!(records, vec!["abcd", "efgh"]);
+        assert!(records.iter().all(|record| record.cut == Some(LongLineAction::Split)));
+        assert_eq!(splitter.pending_len(), 2);
+
+        assert_eq!(splitter.push(b"\nok\n"), vec!["ij", "ok"]);
+    }
+
+    #[test]
+    fn test_splitter_cuts_on_character_boundaries() {
+        let mut splitter = RecordSplitter::with_limit("\n", Some(4), LongLineAction::Split);
+
+        // "é" is two bytes; cutting after four would split the second one
+        assert_eq!(splitter.push("aéébc\n".as_bytes()), vec!["aé", "ébc"]);
+    }
+
+    #[tokio::test]
+    async fn test_file_source_truncates_huge_line() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("huge.log");
+
+        let mut content = vec![b'x'; 8 * 1024 * 1024];
+        content.extend_from_slice(b"\nafter\n");
+        std::fs::write(&path, &content)?;
+
+        let mut options = file_options(StartAt::Beginning, "\n");
+        options.max_line_bytes = 1024;
+
+        let mut source = file_source(&path, options)?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let truncated = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(truncated.message.len(), 1024);
+        assert_eq!(truncated.attributes.get("record.truncated").map(String::as_str), Some("true"));
+
+        let after = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(after.message, "after");
+        assert!(!after.attributes.contains_key("record.truncated"));
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_reads_crlf_file() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(b"started\r\nrequest handled\r\n")?;
+
+        let mut source = FileSource::new(
+            "app".to_string(),
+            vec![path.to_string_lossy().to_string()],
+            None,
+            file_options(StartAt::Beginning, "\n"),
+            None,
+            system_clock(),
+        )?;
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let first = receiver.recv().await.unwrap();
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(first.message, "started");
+        assert_eq!(second.message, "request handled");
+        assert_eq!(first.attributes.get("file.path"), Some(&path.display().to_string()));
+
+        // No empty trailing record follows
+        assert_quiet(&mut receiver).await;
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_reads_nul_delimited_file() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("records.bin");
+        std::fs::write(&path, b"alpha\0beta\0")?;
+
+        let mut source = FileSource::new(
+            "records".to_string(),
+            vec![path.to_string_lossy().to_string()],
+            None,
+            file_options(StartAt::Beginning, "\0"),
+            None,
+            system_clock(),
+        )?;
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        assert_eq!(receiver.recv().await.unwrap().message, "alpha");
+        assert_eq!(receiver.recv().await.unwrap().message, "beta");
+        assert_quiet(&mut receiver).await;
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_tails_appended_records() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "old entry\n")?;
+
+        let mut source = file_source(&path, file_options(StartAt::End, "\n"))?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        append(&path, "first\nsec")?;
+        assert_eq!(next_message(&mut receiver).await, "first");
+        // The partial record waits for its delimiter
+        assert_quiet(&mut receiver).await;
+
+        append(&path, "ond\n")?;
+        assert_eq!(next_message(&mut receiver).await, "second");
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_follows_rotation() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "before\n")?;
+
+        let mut source = file_source(&path, file_options(StartAt::Beginning, "\n"))?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+        assert_eq!(next_message(&mut receiver).await, "before");
+
+        // Rotate: the tail of the old file is still read, then the new file from its start
+        append(&path, "last of old")?;
+        std::fs::rename(&path, dir.path().join("app.log.1"))?;
+        std::fs::write(&path, "first of new\n")?;
+
+        assert_eq!(next_message(&mut receiver).await, "last of old");
+        assert_eq!(next_message(&mut receiver).await, "first of new");
+
+        // Delete and re-create under the same name
+        std::fs::remove_file(&path)?;
+        std::fs::write(&path, "recreated\n")?;
+        assert_eq!(next_message(&mut receiver).await, "recreated");
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_reads_gzipped_rotations_at_startup() -> Result<()> {
+        use flate2::write::GzEncoder;
+
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "live\n")?;
+
+        // Written newest first, so only the modification times give the order
+        let now = std::time::SystemTime::now();
+        for (name, content, age) in [("app.log.1.gz", "second\nthird\n", 60), ("app.log.2.gz", "first\n", 120)] {
+            let mut encoder = GzEncoder::new(std::fs::File::create(dir.path().join(name))?, flate2::Compression::default());
+            encoder.write_all(content.as_bytes())?;
+            encoder.finish()?.set_modified(now - Duration::from_secs(age))?;
+        }
+
+        let mut source = file_source(&dir.path().join("*.log"), file_options(StartAt::Beginning, "\n"))?;
+        let (sender, mut receiver) = mpsc::channel(1);
+        source.start(sender).await?;
+
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "first");
+        assert_eq!(log.attributes.get("file.path"), Some(&dir.path().join("app.log.2.gz").display().to_string()));
+        for expected in ["second", "third", "live"] {
+            assert_eq!(next_message(&mut receiver).await, expected);
+        }
+
+        // The live file is still followed afterwards
+        append(&path, "appended\n")?;
+        assert_eq!(next_message(&mut receiver).await, "appended");
+        assert_quiet(&mut receiver).await;
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_rereads_truncated_file() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "a long first line\n")?;
+
+        let mut source = file_source(&path, file_options(StartAt::Beginning, "\n"))?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+        assert_eq!(next_message(&mut receiver).await, "a long first line");
+
+        std::fs::write(&path, "short\n")?;
+        assert_eq!(next_message(&mut receiver).await, "short");
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_picks_up_files_matching_glob_later() -> Result<()> {
+        let dir = tempdir()?;
+        std::fs::write(dir.path().join("skipped.txt"), "not a log\n")?;
+
+        let mut source = file_source(&dir.path().join("*.log"), file_options(StartAt::End, "\n"))?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        // Files appearing after startup are read in full, whatever `start_at` says
+        let path = dir.path().join("late.log");
+        std::fs::write(&path, "hello from a new file\n")?;
+
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "hello from a new file");
+        assert_eq!(log.attributes.get("file.path"), Some(&path.display().to_string()));
+        assert_quiet(&mut receiver).await;
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_file_sources_read_shared_files_once() -> Result<()> {
+        let dir = tempdir()?;
+        let shared = dir.path().join("app.log");
+        let other = dir.path().join("other.log");
+        std::fs::write(&shared, "shared line\n")?;
+        std::fs::write(&other, "other line\n")?;
+
+        let claims = FileClaims::default();
+        let options = FileSourceOptions { claims: Some(claims.clone()), ..file_options(StartAt::Beginning, "\n") };
+        let mut app = FileSource::new(
+            "app".to_string(),
+            vec![shared.to_string_lossy().to_string()],
+            None,
+            options.clone(),
+            None,
+            system_clock(),
+        )?;
+        let mut all = FileSource::new(
+            "all".to_string(),
+            vec![dir.path().join("*.log").to_string_lossy().to_string()],
+            None,
+            options,
+            None,
+            system_clock(),
+        )?;
+
+        let (app_sender, mut app_receiver) = mpsc::channel(10);
+        let (all_sender, mut all_receiver) = mpsc::channel(10);
+        app.start(app_sender).await?;
+        all.start(all_sender).await?;
+
+        // The first source to open the shared file keeps it; the other reads only its own files
+        assert_eq!(next_message(&mut app_receiver).await, "shared line");
+        assert_eq!(next_message(&mut all_receiver).await, "other line");
+        append(&shared, "appended\n")?;
+        assert_eq!(next_message(&mut app_receiver).await, "appended");
+        assert_quiet(&mut all_receiver).await;
+
+        // Stopping a source releases its files
+        app.stop().await?;
+        assert!(claims.claim(&shared, "all"));
+
+        all.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_resumes_from_stored_offset() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "one\ntwo\n")?;
+
+        let options = file_options(StartAt::Beginning, "\n");
+        let checkpoints: SharedCheckpointer = Arc::new(MemoryCheckpointer::default());
+        let offsets_source = |options: FileSourceOptions| FileSource::new(
+            "app".to_string(),
+            vec![path.to_string_lossy().to_string()],
+            None,
+            options,
+            Some(checkpoints.clone()),
+            system_clock(),
+        );
+
+        let mut source = offsets_source(options.clone())?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+        assert_eq!(next_message(&mut receiver).await, "one");
+        assert_eq!(next_message(&mut receiver).await, "two");
+
+        // Give the tailer a poll to persist its offset before stopping
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        source.stop().await?;
+
+        append(&path, "three\n")?;
+
+        let mut restarted = offsets_source(options)?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        restarted.start(sender).await?;
+        assert_eq!(next_message(&mut receiver).await, "three");
+        assert_quiet(&mut receiver).await;
+
+        restarted.stop().await?;
+        Ok(())
+    }
+
+    const STACK_TRACE_LOG: &str = "\
+2024-03-09 07:45:00 ERROR Request failed
+java.lang.IllegalStateException: connection closed
+\tat com.example.db.Pool.acquire(Pool.java:42)
+\tat com.example.api.Handler.handle(Handler.java:17)
+2024-03-09 07:45:01 INFO Retrying request
+";
+
+    fn multiline(max_lines: usize, flush_timeout_ms: u64) -> MultilineConfig {
+        MultilineConfig {
+            start_pattern: r"^\d{4}-\d{2}-\d{2} ".to_string(),
+            max_lines,
+            flush_timeout_ms,
+        }
+    }
+
+    #[test]
+    fn test_joiner_joins_stack_trace_onto_first_line() -> Result<()> {
+        let mut joiner = MultilineJoiner::new(&multiline(500, 1000))?;
+        let first_line_at: DateTime<Utc> = "2024-03-09T07:45:00Z".parse()?;
+        let mut completed = Vec::new();
+
+        for (i, line) in STACK_TRACE_LOG.lines().enumerate() {
+            let now = first_line_at + chrono::Duration::milliseconds(i as i64);
+            completed.extend(joiner.push(line.into(), now));
+        }
+
+        assert_eq!(completed, vec![(
+            first_line_at,
+            Record::from(STACK_TRACE_LOG.lines().take(4).collect::<Vec<_>>().join("\n").as_str()),
+        )]);
+
+        // The last entry waits for more lines until the flush timeout passes
+        let last_line_at = first_line_at + chrono::Duration::milliseconds(4);
+        assert!(joiner.flush_expired(last_line_at + chrono::Duration::milliseconds(999)).is_none());
+        let (timestamp, message) = joiner.flush_expired(last_line_at + chrono::Duration::seconds(1)).unwrap();
+        assert_eq!(timestamp, last_line_at);
+        assert_eq!(message.text, "2024-03-09 07:45:01 INFO Retrying request");
+        assert_eq!(joiner.pending_start(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_joiner_caps_entries_at_max_lines() -> Result<()> {
+        let mut joiner = MultilineJoiner::new(&multiline(2, 1000))?;
+        let now = Utc::now();
+
+        assert!(joiner.push("2024-03-09 07:45:00 ERROR boom".into(), now).is_empty());
+        let completed = joiner.push("\tat one".into(), now);
+        assert_eq!(completed[0].1, "2024-03-09 07:45:00 ERROR boom\n\tat one");
+
+        // Continuation lines past the cap start a new entry
+        assert!(joiner.push("\tat two".into(), now).is_empty());
+        assert_eq!(joiner.finish().unwrap().1, "\tat two");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_joins_multiline_entries() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, STACK_TRACE_LOG)?;
+
+        let mut options = file_options(StartAt::Beginning, "\n");
+        options.multiline = Some(multiline(500, 100));
+
+        let mut source = file_source(&path, options)?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let first = next_message(&mut receiver).await;
+        assert!(first.starts_with("2024-03-09 07:45:00 ERROR Request failed\njava.lang.IllegalStateException"));
+        assert!(first.ends_with("\tat com.example.api.Handler.handle(Handler.java:17)"));
+
+        // The dangling last entry is emitted once the flush timeout passes
+        assert_eq!(next_message(&mut receiver).await, "2024-03-09 07:45:01 INFO Retrying request");
+        assert_quiet(&mut receiver).await;
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_checkpoints_at_pending_entry() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, STACK_TRACE_LOG)?;
+
+        let checkpoints: SharedCheckpointer = Arc::new(MemoryCheckpointer::default());
+        let multiline_source = |flush_timeout_ms: u64| {
+            let mut options = file_options(StartAt::Beginning, "\n");
+            options.multiline = Some(multiline(500, flush_timeout_ms));
+            FileSource::new(
+                "app".to_string(),
+                vec![path.to_string_lossy().to_string()],
+                None,
+                options,
+                Some(checkpoints.clone()),
+                system_clock(),
+            )
+        };
+
+        // The last entry is still pending when the source stops
+        let mut source = multiline_source(60_000)?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+        assert!(next_message(&mut receiver).await.starts_with("2024-03-09 07:45:00 ERROR Request failed"));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        source.stop().await?;
+
+        // The completed entry is not read again, the pending one is read whole
+        let mut restarted = multiline_source(100)?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        restarted.start(sender).await?;
+        assert_eq!(next_message(&mut receiver).await, "2024-03-09 07:45:01 INFO Retrying request");
+        assert_quiet(&mut receiver).await;
+
+        restarted.stop().await?;
+        Ok(())
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    /// Post a payload to the OTLP handler, returning the response and the entries it produced
+    async fn post_otlp(body: Vec<u8>, content_type: &str) -> Result<(Response<Body>, Vec<LogEntry>)> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/v1/logs")
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))?;
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        let response = handle_otlp_request(request, "otlp".to_string(), MissingTimestamp::Observed, sender, system_clock()).await?;
+
+        let mut entries = Vec::new();
+        while let Ok(log) = receiver.try_recv() {
+            entries.push(log);
+        }
+
+        Ok((response, entries))
+    }
+
+    /// Check the entries decoded from the `otlp_logs` fixtures
+    fn assert_otlp_fixture_entries(entries: &[LogEntry]) -> Result<()> {
+        assert_eq!(entries.len(), 2);
+
+        let declined = &entries[0];
+        assert_eq!(declined.source, "otlp");
+        assert_eq!(declined.timestamp, "2024-03-09T07:45:00Z".parse::<DateTime<Utc>>()?);
+        assert_eq!(declined.level.as_deref(), Some("ERROR"));
+        assert_eq!(declined.severity_number, Some(17));
+        assert_eq!(declined.message, "payment declined");
+
+        let expected: HashMap<String, String> = [
+            ("service.name", "checkout"),
+            ("host.name", "web-1"),
+            ("otel.scope.name", "com.example.checkout"),
+            ("otel.scope.version", "1.4.0"),
+            ("order.id", "A-1001"),
+            ("retry", "2"),
+            ("http.method", "POST"),
+            ("http.status_code", "402"),
+            ("trace_id", "5b8efff798038103d269b633813fc60c"),
+            ("span_id", "eee19b7ec3c1b174"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(declined.attributes, expected);
+
+        // No event time: the observed time is used; record attributes override resource ones
+        let viewed = &entries[1];
+        assert_eq!(viewed.timestamp, "2024-03-09T07:45:01Z".parse::<DateTime<Utc>>()?);
+        assert_eq!(viewed.level.as_deref(), Some("INFO"));
+        assert_eq!(viewed.message, r#"{"event":"cart_viewed","items":3}"#);
+        assert_eq!(viewed.attributes.get("service.name").map(String::as_str), Some("checkout-worker"));
+        assert_eq!(viewed.attributes.get(otlp::TIMESTAMP_SOURCE_ATTRIBUTE).map(String::as_str), Some("observed"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_handler_decodes_protobuf_fixture() -> Result<()> {
+        let (response, entries) = post_otlp(std::fs::read(fixture("otlp_logs.pb"))?, otlp::PROTOBUF_CONTENT_TYPE).await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], otlp::PROTOBUF_CONTENT_TYPE);
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        assert_eq!(body.as_ref(), otlp::encode_success(Encoding::Protobuf).as_slice());
+
+        assert_otlp_fixture_entries(&entries)
+    }
+
+    #[tokio::test]
+    async fn test_otlp_handler_decodes_json_fixture() -> Result<()> {
+        let (response, entries) = post_otlp(std::fs::read(fixture("otlp_logs.json"))?, "application/json; charset=utf-8").await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[hyper::header::CONTENT_TYPE], otlp::JSON_CONTENT_TYPE);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await?.as_ref(), b"{}");
+
+        assert_otlp_fixture_entries(&entries)
+    }
+
+    #[tokio::test]
+    async fn test_otlp_records_without_timestamps_are_tagged() -> Result<()> {
+        let payload = br#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [
+            {"timeUnixNano": "1709970300000000000", "body": {"stringValue": "timed"}},
+            {"timeUnixNano": "0", "observedTimeUnixNano": "1709970301000000000", "body": {"stringValue": "observed"}},
+            {"body": {"stringValue": "untimed"}}
+        ]}]}]}"#;
+        let ingested = "2024-03-09T08:00:00Z".parse::<DateTime<Utc>>()?;
+        let clock = MockClock::new(ingested);
+
+        let decode = |missing_timestamp| -> Result<Vec<LogEntry>> {
+            let request = otlp::decode_request(payload, Encoding::Json)?;
+            Ok(otlp::to_log_entries(request, "otlp", missing_timestamp, &clock))
+        };
+        let tag = |log: &LogEntry| log.attributes.get(otlp::TIMESTAMP_SOURCE_ATTRIBUTE).cloned();
+
+        let entries = decode(MissingTimestamp::Observed)?;
+        assert_eq!(entries[0].timestamp, "2024-03-09T07:45:00Z".parse::<DateTime<Utc>>()?);
+        assert_eq!(tag(&entries[0]), None);
+        assert_eq!(entries[1].timestamp, "2024-03-09T07:45:01Z".parse::<DateTime<Utc>>()?);
+        assert_eq!(tag(&entries[1]), Some("observed".to_string()));
+        assert_eq!(entries[2].timestamp, ingested);
+        assert_eq!(tag(&entries[2]), Some("ingestion".to_string()));
+
+        // Ingestion time ignores the observed time as well
+        let entries = decode(MissingTimestamp::Ingestion)?;
+        assert_eq!(tag(&entries[0]), None);
+        assert_eq!(entries[1].timestamp, ingested);
+        assert_eq!(tag(&entries[1]), Some("ingestion".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_handler_rejects_malformed_payload() -> Result<()> {
+        // A resource_logs field claiming more bytes than follow
+        let (response, entries) = post_otlp(vec![0x0a, 0x05, 0x01], otlp::PROTOBUF_CONTENT_TYPE).await?;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(entries.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_reports_taken_port() -> Result<()> {
+        let taken = TcpListener::bind("127.0.0.1:0").await?;
+        let port = taken.local_addr()?.port();
+
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Http,
+            None,
+            HttpServerConfig::default(),
+            None,
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, _receiver) = mpsc::channel(10);
+
+        let error = source.start(sender).await.unwrap_err().to_string();
+        assert!(error.contains("OTLP source otlp"));
+        assert!(error.contains("already in use"));
+
+        // A failed start leaves the source stopped rather than half-running
+        assert!(source.stop().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_retries_transient_bind_failure() -> Result<()> {
+        let taken = TcpListener::bind("127.0.0.1:0").await?;
+        let port = taken.local_addr()?.port();
+
+        // Release the port while the source is backing off
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(taken);
+        });
+
+        let retry = BindRetryConfig {
+            max_attempts: 20,
+            initial_backoff_ms: 25,
+            max_backoff_ms: 50,
+        };
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Http,
+            Some(retry),
+            HttpServerConfig::default(),
+            None,
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{}/v1/logs", port))
+            .header("content-type", otlp::PROTOBUF_CONTENT_TYPE)
+            .body(std::fs::read(fixture("otlp_logs.pb"))?)
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+        assert_eq!(receiver.recv().await.unwrap().message, "payment declined");
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_accepts_http2_on_one_connection() -> Result<()> {
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Http,
+            None,
+            HttpServerConfig::default(),
+            None,
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let client = reqwest::Client::builder().http2_prior_knowledge().build()?;
+        for _ in 0..2 {
+            let response = client
+                .post(format!("http://127.0.0.1:{}/v1/logs", port))
+                .header("content-type", otlp::PROTOBUF_CONTENT_TYPE)
+                .body(std::fs::read(fixture("otlp_logs.pb"))?)
+                .send()
+                .await?;
+            assert_eq!(response.version(), reqwest::Version::HTTP_2);
+            assert!(response.status().is_success());
+            assert_eq!(next_message(&mut receiver).await, "payment declined");
+            assert_eq!(next_message(&mut receiver).await, r#"{"event":"cart_viewed","items":3}"#);
+        }
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_closes_idle_connections() -> Result<()> {
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let server = HttpServerConfig { idle_timeout_seconds: 1, ..HttpServerConfig::default() };
+        let mut source = OtlpSource::new("otlp".to_string(), port, "127.0.0.1".to_string(), OtlpProtocol::Http, None, server, None, MissingTimestamp::Observed, system_clock())?;
+        let (sender, _receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        // A connection that never sends a request is closed by the server
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        let mut buffer = [0u8; 16];
+        let read = timeout(Duration::from_secs(5), stream.read(&mut buffer)).await?;
+        assert_eq!(read?, 0);
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_releases_its_port_on_stop() -> Result<()> {
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Http,
+            None,
+            HttpServerConfig::default(),
+            None,
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        // A kept-alive connection is closed rather than holding up the stop
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://127.0.0.1:{}/v1/logs", port))
+            .header("content-type", otlp::PROTOBUF_CONTENT_TYPE)
+            .body(std::fs::read(fixture("otlp_logs.pb"))?)
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+        assert_eq!(receiver.recv().await.unwrap().message, "payment declined");
+
+        timeout(Duration::from_secs(5), source.stop()).await??;
+        drop(TcpListener::bind(("127.0.0.1", port)).await?);
+
+        // The same source starts again on the freed port
+        let (sender, _receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_grpc_export_emits_entries() -> Result<()> {
+        use tonic_health::pb::{health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest};
+
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Grpc,
+            None,
+            HttpServerConfig::default(),
+            None,
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://127.0.0.1:{}", port))?
+            .connect()
+            .await?;
+
+        let health = HealthClient::new(channel.clone())
+            .check(HealthCheckRequest { service: otlp::LOGS_SERVICE_NAME.to_string() })
+            .await?;
+        assert_eq!(health.into_inner().status, ServingStatus::Serving as i32);
+
+        // The same payload the HTTP tests post, sent as the Export RPC
+        let request = otlp::decode_request(&std::fs::read(fixture("otlp_logs.pb"))?, Encoding::Protobuf)?;
+        let mut client = tonic::client::Grpc::new(channel);
+        client.ready().await?;
+        let response: tonic::Response<otlp::ExportLogsServiceResponse> = client
+            .unary(
+                tonic::Request::new(request),
+                tonic::codegen::http::uri::PathAndQuery::from_static(otlp::GRPC_EXPORT_PATH),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await?;
+        assert_eq!(response.into_inner().partial_success, None);
+
+        let mut entries = Vec::new();
+        for _ in 0..2 {
+            entries.push(timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap());
+        }
+        assert_otlp_fixture_entries(&entries)?;
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_syslog_source_receives_udp_and_tcp() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let udp_port = std::net::UdpSocket::bind("127.0.0.1:0")?.local_addr()?.port();
+        let mut udp = SyslogSource::new("syslog".to_string(), udp_port, "127.0.0.1".to_string(), SyslogProtocol::Udp, None, 1, system_clock())?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        udp.start(sender).await?;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await?;
+        client.send_to(b"<34>Mar  1 12:30:45 web-1 sshd[812]: Failed password", ("127.0.0.1", udp_port)).await?;
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "Failed password");
+        assert_eq!(log.level, Some("FATAL".to_string()));
+        assert_eq!(log.attributes.get("host.name"), Some(&"web-1".to_string()));
+        assert_eq!(log.attributes.get("net.peer.ip"), Some(&"127.0.0.1".to_string()));
+        udp.stop().await?;
+
+        let tcp_port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut tcp = SyslogSource::new("syslog".to_string(), tcp_port, "127.0.0.1".to_string(), SyslogProtocol::Tcp, None, 1, system_clock())?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        tcp.start(sender).await?;
+
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", tcp_port)).await?;
+        stream.write_all(b"28 <11>1 - app1 - - - - counted\n<13>not a header\n").await?;
+        assert_eq!(next_message(&mut receiver).await, "counted");
+        assert_eq!(next_message(&mut receiver).await, "not a header");
+
+        // With one connection allowed, a second client is read once the first leaves
+        let mut second = tokio::net::TcpStream::connect(("127.0.0.1", tcp_port)).await?;
+        second.write_all(b"<13>waited\n").await?;
+        assert!(timeout(Duration::from_millis(200), receiver.recv()).await.is_err());
+        drop(stream);
+        assert_eq!(next_message(&mut receiver).await, "waited");
+        tcp.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tcp_source_reassembles_lines_per_connection() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut source = TcpSource::new("raw".to_string(), port, "127.0.0.1".to_string(), Framing::Newline, 8, system_clock());
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        // Two clients interleave writes that split lines at arbitrary points
+        let mut clients = vec![
+            tokio::net::TcpStream::connect(("127.0.0.1", port)).await?,
+            tokio::net::TcpStream::connect(("127.0.0.1", port)).await?,
+        ];
+        for (client, chunk) in [
+            (0, &b"job sta"[..]),
+            (1, &b"cache "[..]),
+            (0, &b"rted\njob fin"[..]),
+            (1, &b"warmed\r\n"[..]),
+            (0, &b"ished\n"[..]),
+        ] {
+            clients[client].write_all(chunk).await?;
+            clients[client].flush().await?;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0..3 {
+            entries.push(timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap());
+        }
+        let mut messages: Vec<&str> = entries.iter().map(|log| log.message.as_str()).collect();
+        messages.sort();
+        assert_eq!(messages, vec!["cache warmed", "job finished", "job started"]);
+        assert!(entries.iter().all(|log| log.source == "raw" && log.level.is_none()));
+        assert_eq!(entries[0].attributes.get("net.peer.ip"), Some(&"127.0.0.1".to_string()));
+        assert!(entries[0].attributes.contains_key("net.peer.port"));
+
+        // A final line without a newline is emitted when the client closes
+        let mut first = clients.remove(0);
+        first.write_all(b"last words").await?;
+        drop(first);
+        assert_eq!(next_message(&mut receiver).await, "last words");
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tcp_source_reads_length_prefixed_records() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let mut source = TcpSource::new("raw".to_string(), port, "127.0.0.1".to_string(), Framing::LengthPrefixed, 8, system_clock());
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        let record = b"multi\nline trace";
+        stream.write_all(&(record.len() as u32).to_be_bytes()[..3]).await?;
+        stream.flush().await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        stream.write_all(&(record.len() as u32).to_be_bytes()[3..]).await?;
+        stream.write_all(record).await?;
+
+        assert_eq!(next_message(&mut receiver).await, "multi\nline trace");
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_udp_source_emits_each_line_of_a_datagram() -> Result<()> {
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")?.local_addr()?.port();
+        let mut source = UdpSource::new("raw".to_string(), port, "127.0.0.1".to_string(), Framing::Newline, system_clock());
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await?;
+        client.send_to(b"first\nsecond", ("127.0.0.1", port)).await?;
+        assert_eq!(next_message(&mut receiver).await, "first");
+        assert_eq!(next_message(&mut receiver).await, "second");
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_poll_source_reads_pages_and_resumes() -> Result<()> {
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let first_page = server.mock("GET", "/events")
+            .match_query(Matcher::Missing)
+            .match_header("authorization", "Bearer secret")
+            .with_body(r#"{"events": [
+                {"text": "deploy started", "at": "2024-03-01T12:00:00Z"},
+                {"text": "deploy finished", "at": "2024-03-01T12:01:00Z"}
+            ], "next": "page-2"}"#)
+            .create_async()
+            .await;
+        server.mock("GET", "/events")
+            .match_query(Matcher::UrlEncoded("cursor".to_string(), "page-2".to_string()))
+            .with_body(r#"{"events": [{"text": "rollback", "at": "2024-03-01T12:02:00Z"}], "next": null}"#)
+            .create_async()
+            .await;
+
+        let state: SharedCheckpointer = Arc::new(MemoryCheckpointer::default());
+        let poller = HttpPoller::new(
+            format!("{}/events", server.url()),
+            HttpMethod::Get,
+            HashMap::new(),
+            Some(HttpPollAuth { username: None, password: None, bearer_token: Some("secret".to_string()) }),
+            "/events".to_string(),
+            "/text".to_string(),
+            Some("/at".to_string()),
+            Some(HttpCursorConfig { param: "cursor".to_string(), pointer: "/next".to_string() }),
+        )?;
+        let mut source = HttpPollSource::new("saas".to_string(), poller, Duration::from_secs(1), Some(state.clone()), system_clock());
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        assert_eq!(next_message(&mut receiver).await, "deploy started");
+        assert_eq!(next_message(&mut receiver).await, "deploy finished");
+        let last = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(last.message, "rollback");
+        assert_eq!(last.timestamp, DateTime::parse_from_rfc3339("2024-03-01T12:02:00Z")?);
+        first_page.assert_async().await;
+
+        // The next poll re-reads the last page, whose record was already emitted
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(receiver.try_recv().is_err());
+        source.stop().await?;
+
+        assert_eq!(state.load("http_poll_cursor:saas")?, Some("page-2".to_string()));
+        assert_eq!(state.load("http_poll_timestamp:saas")?, Some("2024-03-01T12:02:00+00:00".to_string()));
+
+        Ok(())
+    }
+
+    /// Certificates for TLS tests: a CA, and a server and a client certificate it signed
+    struct TestPki {
+        _dir: tempfile::TempDir,
+        cert_path: String,
+        key_path: String,
+        ca_path: String,
+        ca: rustls::Certificate,
+        client_cert: rustls::Certificate,
+        client_key: rustls::PrivateKey,
+    }
+
+    fn test_pki() -> Result<TestPki> {
+        let dir = tempdir()?;
+
+        let mut ca_params = rcgen::CertificateParams::new(Vec::new());
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(rcgen::DnType::CommonName, "Test CA");
+        let ca = rcgen::Certificate::from_params(ca_params)?;
+
+        let server = rcgen::Certificate::from_params(rcgen::CertificateParams::new(vec!["localhost".to_string()]))?;
+
+        let mut client_params = rcgen::CertificateParams::new(Vec::new());
+        client_params.distinguished_name = rcgen::DistinguishedName::new();
+        client_params.distinguished_name.push(rcgen::DnType::CommonName, "web-1");
+        let client = rcgen::Certificate::from_params(client_params)?;
+
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        let (cert_path, key_path, ca_path) = (path("server.pem"), path("server.key"), path("ca.pem"));
+        std::fs::write(&cert_path, server.serialize_pem_with_signer(&ca)?)?;
+        std::fs::write(&key_path, server.serialize_private_key_pem())?;
+        std::fs::write(&ca_path, ca.serialize_pem()?)?;
+
+        Ok(TestPki {
+            cert_path,
+            key_path,
+            ca_path,
+            ca: rustls::Certificate(ca.serialize_der()?),
+            client_cert: rustls::Certificate(client.serialize_der_with_signer(&ca)?),
+            client_key: rustls::PrivateKey(client.serialize_private_key_der()),
+            _dir: dir,
+        })
+    }
+
+    /// Start a TCP syslog source with TLS in `mode`, returning its port
+    async fn tls_syslog_source(pki: &TestPki, mode: TlsMode) -> Result<(SyslogSource, u16, mpsc::Receiver<LogEntry>)> {
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let tls = TlsConfig {
+            cert_path: pki.cert_path.clone(),
+            key_path: pki.key_path.clone(),
+            client_ca_path: Some(pki.ca_path.clone()),
+            require_client_cert: false,
+            mode,
+        };
+        let mut source = SyslogSource::new(
+            "syslog".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            SyslogProtocol::Tcp,
+            Some(&tls),
+            4,
+            system_clock(),
+        )?;
+        let (sender, receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        Ok((source, port, receiver))
+    }
+
+    /// Connect over TLS, trusting the test CA and presenting the client certificate if asked
+    async fn tls_client(
+        port: u16,
+        pki: &TestPki,
+        present_cert: bool,
+    ) -> Result<tokio_rustls::client::TlsStream<tokio::net::TcpStream>> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(&pki.ca)?;
+        let builder = rustls::ClientConfig::builder().with_safe_defaults().with_root_certificates(roots);
+        let config = if present_cert {
+            builder.with_client_auth_cert(vec![pki.client_cert.clone()], pki.client_key.clone())?
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let stream = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+        Ok(connector.connect(rustls::ServerName::try_from("localhost")?, stream).await?)
+    }
+
+    #[tokio::test]
+    async fn test_syslog_tls_required_refuses_plaintext() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let pki = test_pki()?;
+        let (mut source, port, mut receiver) = tls_syslog_source(&pki, TlsMode::Required).await?;
+
+        let mut client = tls_client(port, &pki, true).await?;
+        client.write_all(b"<13>1 - app1 - - - - over tls\n").await?;
+        client.flush().await?;
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "over tls");
+        assert_eq!(log.attributes.get("tls"), Some(&"true".to_string()));
+        assert_eq!(log.attributes.get("tls.peer.subject"), Some(&"CN=web-1".to_string()));
+        assert_eq!(log.attributes.get("tls.peer.issuer"), Some(&"CN=Test CA".to_string()));
+
+        // A plaintext client is disconnected without anything being read
+        let mut plain = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        plain.write_all(b"<13>1 - app1 - - - - in the clear\n").await?;
+        let mut rest = Vec::new();
+        // Closing with the line unread may reach the client as a reset rather than EOF
+        let closed = timeout(Duration::from_secs(5), plain.read_to_end(&mut rest)).await?;
+        assert!(closed.map_or(true, |read| read == 0));
+        assert_quiet(&mut receiver).await;
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_syslog_tls_drops_clients_that_stall_the_handshake() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let pki = test_pki()?;
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let tls = TlsConfig {
+            cert_path: pki.cert_path.clone(),
+            key_path: pki.key_path.clone(),
+            client_ca_path: Some(pki.ca_path.clone()),
+            require_client_cert: false,
+            mode: TlsMode::Required,
+        };
+        let mut source = SyslogSource::new(
+            "syslog".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            SyslogProtocol::Tcp,
+            Some(&tls),
+            2,
+            system_clock(),
+        )?;
+        source.tls = source.tls.take().map(|tls| tls.with_handshake_timeout(Duration::from_millis(200)));
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        // One client sends nothing, the other only the first byte of a handshake
+        let silent = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        let mut stalled = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        stalled.write_all(&[0x16]).await?;
+
+        // Both are disconnected once the handshake timeout passes
+        for mut client in [silent, stalled] {
+            let mut rest = Vec::new();
+            let closed = timeout(Duration::from_secs(5), client.read_to_end(&mut rest)).await?;
+            assert!(closed.map_or(true, |read| read == 0));
+        }
+
+        // Their connection slots are free again
+        let mut client = tls_client(port, &pki, true).await?;
+        client.write_all(b"<13>1 - app1 - - - - after the stall\n").await?;
+        client.flush().await?;
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "after the stall");
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_syslog_tls_optional_serves_both_on_one_port() -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let pki = test_pki()?;
+        let (mut source, port, mut receiver) = tls_syslog_source(&pki, TlsMode::Optional).await?;
+
+        // Client certificates stay optional
+        let mut client = tls_client(port, &pki, false).await?;
+        client.write_all(b"<13>1 - app1 - - - - over tls\n").await?;
+        client.flush().await?;
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "over tls");
+        assert_eq!(log.attributes.get("tls"), Some(&"true".to_string()));
+        assert_eq!(log.attributes.get("tls.peer.subject"), None);
+
+        let mut plain = tokio::net::TcpStream::connect(("127.0.0.1", port)).await?;
+        plain.write_all(b"<13>1 - app1 - - - - in the clear\n").await?;
+        let log = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(log.message, "in the clear");
+        assert_eq!(log.attributes.get("tls"), Some(&"false".to_string()));
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    /// POST the OTLP fixture over TLS, presenting the client certificate if asked
+    async fn post_otlp_over_tls(port: u16, pki: &TestPki, present_cert: bool) -> Result<StatusCode> {
+        let stream = tls_client(port, pki, present_cert).await?;
+        let (mut requests, connection) = hyper::client::conn::handshake(stream).await?;
+        tokio::spawn(connection);
+
+        let request = Request::post("/v1/logs")
+            .header("host", "localhost")
+            .header("content-type", otlp::PROTOBUF_CONTENT_TYPE)
+            .body(Body::from(std::fs::read(fixture("otlp_logs.pb"))?))?;
+        Ok(requests.send_request(request).await?.status())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_source_serves_https_with_required_client_cert() -> Result<()> {
+        let pki = test_pki()?;
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        let tls = TlsConfig {
+            cert_path: pki.cert_path.clone(),
+            key_path: pki.key_path.clone(),
+            client_ca_path: Some(pki.ca_path.clone()),
+            require_client_cert: true,
+            mode: TlsMode::Required,
+        };
+        let mut source = OtlpSource::new(
+            "otlp".to_string(),
+            port,
+            "127.0.0.1".to_string(),
+            OtlpProtocol::Http,
+            None,
+            HttpServerConfig::default(),
+            Some(&tls),
+            MissingTimestamp::Observed,
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        assert!(post_otlp_over_tls(port, &pki, true).await?.is_success());
+        assert_eq!(next_message(&mut receiver).await, "payment declined");
+        assert_eq!(next_message(&mut receiver).await, r#"{"event":"cart_viewed","items":3}"#);
+
+        // Without a certificate the handshake is refused and nothing is read
+        assert!(post_otlp_over_tls(port, &pki, false).await.is_err());
+        assert_quiet(&mut receiver).await;
+
+        source.stop().await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_tls_listener_requires_client_ca_for_client_certs() {
+        let pki = test_pki().unwrap();
+        let tls = TlsConfig {
+            cert_path: pki.cert_path.clone(),
+            key_path: pki.key_path.clone(),
+            client_ca_path: None,
+            require_client_cert: true,
+            mode: TlsMode::Required,
+        };
+        assert!(TlsListener::new(&tls).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_source_uses_injected_clock() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "first\nsecond\n")?;
+
+        let pinned: DateTime<Utc> = "2024-03-09T07:45:00Z".parse()?;
+        let clock = Arc::new(MockClock::new(pinned));
+
+        let mut source = FileSource::new(
+            "app".to_string(),
+            vec![path.to_string_lossy().to_string()],
+            None,
+            file_options(StartAt::Beginning, "\n"),
+            None,
+            clock.clone(),
+        )?;
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        assert_eq!(receiver.recv().await.unwrap().timestamp, pinned);
+        assert_eq!(receiver.recv().await.unwrap().timestamp, pinned);
+
+        Ok(())
+    }
+
+    fn docker_frame(stream: u8, payload: &str) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload.as_bytes());
+        frame
+    }
+
+    /// Serve canned Docker Engine API responses on a unix socket, recording each request path
+    #[cfg(unix)]
+    fn fake_docker_daemon(socket_path: &Path, logs: Vec<u8>) -> Arc<Mutex<Vec<String>>> {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::UnixListener::bind(socket_path).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf).await.unwrap() {
+                        0 => break,
+                        n => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+                recorded.lock().unwrap().push(path.clone());
+
+                let body = if path == "/containers/json" {
+                    br#"[{"Id":"4f1c2a9b8e7d","Names":["/web"]},{"Id":"9a8b7c6d5e4f","Names":["/db"]}]"#.to_vec()
+                } else if path.ends_with("/json") {
+                    br#"{"Config":{"Tty":false}}"#.to_vec()
+                } else {
+                    logs.clone()
+                };
+
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len(),
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+                stream.shutdown().await.ok();
+            }
+        });
+
+        requests
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_docker_source_follows_selected_containers() -> Result<()> {
+        use chrono::TimeZone;
+
+        let dir = tempdir()?;
+        let socket_path = dir.path().join("docker.sock");
+
+        let mut logs = docker_frame(1, "2024-03-01T12:30:45.000000001Z GET /health 200\n");
+        logs.extend(docker_frame(2, "2024-03-01T12:30:46Z upstream timed out\n"));
+        let requests = fake_docker_daemon(&socket_path, logs);
+
+        let mut source = DockerSource::new(
+            "containers".to_string(),
+            vec!["web".to_string()],
+            false,
+            socket_path.to_string_lossy().to_string(),
+            Duration::from_secs(1),
+            system_clock(),
+        )?;
+        let (sender, mut receiver) = mpsc::channel(10);
+        source.start(sender).await?;
+
+        let stdout = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(stdout.source, "web");
+        assert_eq!(stdout.message, "GET /health 200");
+        assert_eq!(stdout.timestamp, Utc.timestamp_nanos(1_709_296_245_000_000_001));
+        assert_eq!(stdout.attributes.get("stream"), Some(&"stdout".to_string()));
+        assert_eq!(stdout.attributes.get("container.id"), Some(&"4f1c2a9b8e7d".to_string()));
+
+        let stderr = timeout(Duration::from_secs(5), receiver.recv()).await?.unwrap();
+        assert_eq!(stderr.message, "upstream timed out");
+        assert_eq!(stderr.attributes.get("stream"), Some(&"stderr".to_string()));
+
+        // The stream ends as if the container stopped; once re-listed it is
+        // followed again from the last line, which is not repeated
+        let logs_requests = || -> Vec<String> {
+            requests.lock().unwrap().iter().filter(|path| path.contains("/logs?")).cloned().collect()
+        };
+        timeout(Duration::from_secs(5), async {
+            while logs_requests().len() < 2 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }).await?;
+        assert_quiet(&mut receiver).await;
+
+        let logs_requests = logs_requests();
+        assert!(logs_requests[0].starts_with("/containers/4f1c2a9b8e7d/logs?follow=true&stdout=true&stderr=true"));
+        assert!(logs_requests[1].ends_with("since=1709296246.000000000"));
+        assert!(requests.lock().unwrap().iter().all(|path| !path.contains("9a8b7c6d5e4f")));
+
+        source.stop().await?;
+
+        Ok(())
+    }
+
+    fn mixed_sources() -> Vec<SourceConfig> {
+        vec![
+            SourceConfig::Journald {
+                name: "journal".to_string(),
+                directory: None,
+                units: vec!["sshd".to_string()],
+                cursor_path: None,
+                pipeline: None,
+            },
+            SourceConfig::Docker {
+                name: "containers".to_string(),
+                containers: Vec::new(),
+                all_containers: true,
+                socket_path: "/var/run/docker.sock".to_string(),
+                refresh_interval_seconds: 10,
+                pipeline: None,
+            },
+        ]
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "journald")))]
+    #[test]
+    fn test_journald_source_without_journal_support() -> Result<()> {
+        let configs = mixed_sources();
+
+        let supported = supported_sources(&configs, UnsupportedSourcePolicy::Skip)?;
+        assert_eq!(supported.len(), 1);
+        assert_eq!(supported[0].name(), "containers");
+
+        let error = supported_sources(&configs, UnsupportedSourcePolicy::Error).unwrap_err();
+        assert!(error.to_string().contains("journal"));
+
+        Ok(())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "journald"))]
+    #[test]
+    fn test_journald_source_with_journal_support() -> Result<()> {
+        let configs = mixed_sources();
+
+        let supported = supported_sources(&configs, UnsupportedSourcePolicy::Error)?;
+        assert_eq!(supported.len(), 2);
+
+        Ok(())
+    }
+}
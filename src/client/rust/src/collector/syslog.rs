@@ -0,0 +1,521 @@
+//! Syslog message parsing and TCP framing for the syslog source
+//!
+//! Messages are parsed as RFC 5424 when they carry the version after the
+//! priority, and as RFC 3164 (BSD syslog) otherwise. Anything that fits
+//! neither is kept as raw text rather than dropped. Over TCP, messages are
+//! framed either by an octet count (RFC 6587 section 3.4.1) or by a trailing
+//! newline, decided per message by [`TcpFramer`].
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashMap;
+
+use crate::collector::severity;
+use crate::collector::sources::LogEntry;
+
+/// Facility names by code, as used by rsyslog and syslog-ng
+const FACILITY_NAMES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news",
+    "uucp", "cron", "authpriv", "ftp", "ntp", "security", "console", "solaris-cron",
+    "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+
+/// Longest digit prefix accepted as an RFC 6587 octet count
+const MAX_OCTET_COUNT_DIGITS: usize = 10;
+
+/// Which syslog format a message was parsed as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFormat {
+    Rfc5424,
+    Rfc3164,
+    /// No recognisable header; the whole text is the message
+    Raw,
+}
+
+impl SyslogFormat {
+    /// Name used in the `syslog.format` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyslogFormat::Rfc5424 => "rfc5424",
+            SyslogFormat::Rfc3164 => "rfc3164",
+            SyslogFormat::Raw => "raw",
+        }
+    }
+}
+
+/// A parsed syslog message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyslogMessage {
+    pub format: SyslogFormat,
+    pub facility: Option<u8>,
+    pub severity: Option<u8>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub hostname: Option<String>,
+    pub app_name: Option<String>,
+    pub proc_id: Option<String>,
+    pub msg_id: Option<String>,
+    pub structured_data: Option<String>,
+    pub message: String,
+}
+
+impl SyslogMessage {
+    /// A message with no recognisable header
+    fn raw(message: &str) -> Self {
+        Self {
+            format: SyslogFormat::Raw,
+            facility: None,
+            severity: None,
+            timestamp: None,
+            hostname: None,
+            app_name: None,
+            proc_id: None,
+            msg_id: None,
+            structured_data: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Convert to a log entry, using `received` when the message has no timestamp
+    ///
+    /// The severity becomes the entry's level. Facility, hostname and the
+    /// other header fields become attributes.
+    pub fn into_log_entry(self, source: &str, received: DateTime<Utc>) -> LogEntry {
+        let mut attributes = HashMap::new();
+        attributes.insert("syslog.format".to_string(), self.format.as_str().to_string());
+
+        if let Some(facility) = self.facility {
+            attributes.insert("syslog.facility".to_string(), facility_name(facility).to_string());
+        }
+        let fields = [
+            ("host.name", self.hostname),
+            ("syslog.identifier", self.app_name),
+            ("process.pid", self.proc_id),
+            ("syslog.msgid", self.msg_id),
+            ("syslog.structured_data", self.structured_data),
+        ];
+        for (attribute, value) in fields {
+            if let Some(value) = value {
+                attributes.insert(attribute.to_string(), value);
+            }
+        }
+
+        LogEntry {
+            timestamp: self.timestamp.unwrap_or(received),
+            source: source.to_string(),
+            level: self.severity.and_then(severity::syslog_level).map(|level| level.to_string()),
+            severity_number: None,
+            message: self.message,
+            attributes,
+        }
+    }
+}
+
+/// Name of a facility code, e.g. `auth` for 4
+pub fn facility_name(facility: u8) -> &'static str {
+    FACILITY_NAMES.get(facility as usize).copied().unwrap_or("unknown")
+}
+
+/// Parse one syslog message
+///
+/// `now` supplies the year for RFC 3164 timestamps, which lack one; a
+/// timestamp that would land more than a day in the future is taken to be
+/// from the previous year. RFC 3164 timestamps carry no zone and are read as
+/// UTC. A message whose priority parses but whose header does not keeps the
+/// facility and severity, with everything after the priority as the message.
+pub fn parse(line: &str, now: DateTime<Utc>) -> SyslogMessage {
+    let line = line.trim_end_matches(['\n', '\r', '\0']);
+
+    let (priority, rest) = match split_priority(line) {
+        Some(split) => split,
+        None => return SyslogMessage::raw(line),
+    };
+
+    let mut message = parse_rfc5424(rest)
+        .or_else(|| parse_rfc3164(rest, now))
+        .unwrap_or_else(|| SyslogMessage::raw(rest));
+    message.facility = Some(priority >> 3);
+    message.severity = Some(priority & 0x07);
+
+    message
+}
+
+/// Split `<PRI>` off the front of a message
+fn split_priority(line: &str) -> Option<(u8, &str)> {
+    let rest = line.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    let digits = &rest[..end];
+
+    if digits.is_empty() || digits.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // A leading zero is only valid for priority 0
+    if digits.len() > 1 && digits.starts_with('0') {
+        return None;
+    }
+
+    let priority: u8 = digits.parse().ok()?;
+    if priority > 191 {
+        return None;
+    }
+
+    Some((priority, &rest[end + 1..]))
+}
+
+/// `-` is the RFC 5424 nil value
+fn nil_or(value: &str) -> Option<String> {
+    if value == "-" || value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Parse everything after the priority as `VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID SD [MSG]`
+fn parse_rfc5424(rest: &str) -> Option<SyslogMessage> {
+    let rest = rest.strip_prefix("1 ")?;
+    let mut fields = rest.splitn(6, ' ');
+    let timestamp = fields.next()?;
+    let hostname = fields.next()?;
+    let app_name = fields.next()?;
+    let proc_id = fields.next()?;
+    let msg_id = fields.next()?;
+    let remainder = fields.next()?;
+
+    let timestamp = match timestamp {
+        "-" => None,
+        stamp => Some(DateTime::parse_from_rfc3339(stamp).ok()?.with_timezone(&Utc)),
+    };
+    let (structured_data, message) = split_structured_data(remainder)?;
+
+    Some(SyslogMessage {
+        format: SyslogFormat::Rfc5424,
+        facility: None,
+        severity: None,
+        timestamp,
+        hostname: nil_or(hostname),
+        app_name: nil_or(app_name),
+        proc_id: nil_or(proc_id),
+        msg_id: nil_or(msg_id),
+        structured_data: structured_data.map(|data| data.to_string()),
+        message: message.trim_start_matches('\u{feff}').to_string(),
+    })
+}
+
+/// Split RFC 5424 structured data from the message that follows it
+///
+/// Structured data is `-` or one or more `[id param="value" ...]` elements,
+/// where values may contain `\"`, `\\` and `\]` escapes.
+fn split_structured_data(remainder: &str) -> Option<(Option<&str>, &str)> {
+    let (data, rest) = if let Some(rest) = remainder.strip_prefix('-') {
+        (None, rest)
+    } else {
+        let mut end = None;
+        let mut in_element = false;
+        let mut in_value = false;
+        let mut escaped = false;
+
+        for (index, c) in remainder.char_indices() {
+            if !in_element {
+                if c != '[' {
+                    end = Some(index);
+                    break;
+                }
+                in_element = true;
+            } else if in_value {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_value = false,
+                    _ => {},
+                }
+            } else {
+                match c {
+                    '"' => in_value = true,
+                    ']' => in_element = false,
+                    _ => {},
+                }
+            }
+        }
+
+        // An element left open means this is not structured data
+        if in_element {
+            return None;
+        }
+        let end = end.unwrap_or(remainder.len());
+        if end == 0 {
+            return None;
+        }
+        (Some(&remainder[..end]), &remainder[end..])
+    };
+
+    if rest.is_empty() {
+        Some((data, ""))
+    } else {
+        rest.strip_prefix(' ').map(|message| (data, message))
+    }
+}
+
+/// Parse everything after the priority as `Mmm dd hh:mm:ss [HOSTNAME] TAG: MSG`
+fn parse_rfc3164(rest: &str, now: DateTime<Utc>) -> Option<SyslogMessage> {
+    let stamp = rest.get(..15)?;
+    let after = rest.get(15..)?.strip_prefix(' ')?;
+    let timestamp = parse_bsd_timestamp(stamp, now)?;
+
+    // Some senders leave out the hostname and go straight to the tag
+    let (first, remainder) = after.split_once(' ').unwrap_or((after, ""));
+    let (hostname, tagged) = if first.ends_with(':') || first.contains('[') {
+        (None, after)
+    } else {
+        (Some(first.to_string()), remainder)
+    };
+    let (app_name, proc_id, message) = split_tag(tagged);
+
+    Some(SyslogMessage {
+        format: SyslogFormat::Rfc3164,
+        facility: None,
+        severity: None,
+        timestamp: Some(timestamp),
+        hostname,
+        app_name,
+        proc_id,
+        msg_id: None,
+        structured_data: None,
+        message: message.to_string(),
+    })
+}
+
+/// Parse `Mmm dd hh:mm:ss` in the year of `now`
+fn parse_bsd_timestamp(stamp: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    // Days below 10 are space padded, as in `Mar  1`
+    let stamp = stamp.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let at_year = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{} {}", year, stamp), "%Y %b %d %H:%M:%S")
+            .ok()
+            .map(|naive| Utc.from_utc_datetime(&naive))
+    };
+
+    let timestamp = at_year(now.year())?;
+    if timestamp > now + Duration::days(1) {
+        return at_year(now.year() - 1);
+    }
+
+    Some(timestamp)
+}
+
+/// Split `app[pid]: message` into its parts
+///
+/// Text without a tag is returned whole as the message.
+fn split_tag(text: &str) -> (Option<String>, Option<String>, &str) {
+    let (tag, message) = text.split_once(' ').unwrap_or((text, ""));
+
+    let tag = match tag.strip_suffix(':') {
+        Some(tag) if !tag.is_empty() => tag,
+        _ => return (None, None, text),
+    };
+
+    match tag.split_once('[') {
+        Some((app, pid)) => (
+            Some(app.to_string()).filter(|app| !app.is_empty()),
+            pid.strip_suffix(']').map(|pid| pid.to_string()),
+            message,
+        ),
+        None => (Some(tag.to_string()), None, message),
+    }
+}
+
+/// Splits a TCP syslog stream into messages
+///
+/// A message that starts with a digit is octet counted: `LEN SP MSG`. Any
+/// other message runs to the next newline. Senders may use either, so the
+/// choice is made per message. A newline-framed message longer than
+/// `max_message_bytes` is emitted in pieces of that size.
+pub struct TcpFramer {
+    buffer: Vec<u8>,
+    max_message_bytes: usize,
+}
+
+impl TcpFramer {
+    /// Create a framer accepting messages up to `max_message_bytes`
+    pub fn new(max_message_bytes: usize) -> Self {
+        Self { buffer: Vec::new(), max_message_bytes }
+    }
+
+    /// Feed a chunk of the stream, returning every message it completes
+    ///
+    /// Fails on an octet count that is malformed or over the limit, after
+    /// which the stream cannot be resynchronised.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut messages = Vec::new();
+        let mut consumed = 0;
+
+        loop {
+            // Skip separators left between messages
+            while consumed < self.buffer.len() && matches!(self.buffer[consumed], b'\n' | b'\r') {
+                consumed += 1;
+            }
+            let pending = &self.buffer[consumed..];
+            if pending.is_empty() {
+                break;
+            }
+
+            if pending[0].is_ascii_digit() {
+                let space = match pending.iter().take(MAX_OCTET_COUNT_DIGITS + 1).position(|&b| b == b' ') {
+                    Some(space) => space,
+                    None if pending.len() <= MAX_OCTET_COUNT_DIGITS => break,
+                    None => return Err(anyhow!("Invalid syslog octet count")),
+                };
+                let length: usize = std::str::from_utf8(&pending[..space])
+                    .ok()
+                    .and_then(|digits| digits.parse().ok())
+                    .ok_or_else(|| anyhow!("Invalid syslog octet count"))?;
+                if length > self.max_message_bytes {
+                    return Err(anyhow!(
+                        "Syslog message of {} bytes exceeds the {} byte limit",
+                        length, self.max_message_bytes,
+                    ));
+                }
+
+                let start = space + 1;
+                if pending.len() - start < length {
+                    break;
+                }
+                messages.push(String::from_utf8_lossy(&pending[start..start + length]).into_owned());
+                consumed += start + length;
+            } else {
+                match pending.iter().position(|&b| b == b'\n') {
+                    Some(end) if end <= self.max_message_bytes => {
+                        messages.push(String::from_utf8_lossy(&pending[..end]).trim_end_matches('\r').to_string());
+                        consumed += end + 1;
+                    },
+                    _ if pending.len() >= self.max_message_bytes => {
+                        messages.push(String::from_utf8_lossy(&pending[..self.max_message_bytes]).into_owned());
+                        consumed += self.max_message_bytes;
+                    },
+                    _ => break,
+                }
+            }
+        }
+
+        self.buffer.drain(..consumed);
+        Ok(messages)
+    }
+
+    /// Take a final newline-framed message the sender closed without terminating
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&self.buffer).trim().to_string();
+        self.buffer.clear();
+        Some(rest).filter(|rest| !rest.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 13, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rfc5424_message() {
+        let line = r#"<165>1 2024-03-01T12:30:45.123Z web-1 nginx 812 ACCESS [meta env="prod" note="a \] b"] GET /health 200"#;
+        let message = parse(line, now());
+
+        assert_eq!(message.format, SyslogFormat::Rfc5424);
+        assert_eq!(message.facility, Some(20));
+        assert_eq!(message.severity, Some(5));
+        assert_eq!(message.timestamp, Some(Utc.timestamp_millis_opt(1_709_296_245_123).unwrap()));
+        assert_eq!(message.hostname.as_deref(), Some("web-1"));
+        assert_eq!(message.app_name.as_deref(), Some("nginx"));
+        assert_eq!(message.proc_id.as_deref(), Some("812"));
+        assert_eq!(message.msg_id.as_deref(), Some("ACCESS"));
+        assert_eq!(message.structured_data.as_deref(), Some(r#"[meta env="prod" note="a \] b"]"#));
+        assert_eq!(message.message, "GET /health 200");
+
+        let log = message.into_log_entry("syslog", now());
+        assert_eq!(log.level, Some("INFO".to_string()));
+        assert_eq!(log.attributes.get("syslog.facility"), Some(&"local4".to_string()));
+        assert_eq!(log.attributes.get("host.name"), Some(&"web-1".to_string()));
+
+        // Nil fields and no structured data
+        let message = parse("<11>1 - - - - - - disk full", now());
+        assert_eq!(message.format, SyslogFormat::Rfc5424);
+        assert_eq!((message.timestamp, message.hostname, message.structured_data), (None, None, None));
+        assert_eq!(message.message, "disk full");
+    }
+
+    #[test]
+    fn test_rfc3164_message() {
+        let message = parse("<34>Mar  1 12:30:45 web-1 sshd[812]: Failed password for root\n", now());
+
+        assert_eq!(message.format, SyslogFormat::Rfc3164);
+        assert_eq!(message.facility, Some(4));
+        assert_eq!(message.severity, Some(2));
+        assert_eq!(message.timestamp, Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 45).unwrap()));
+        assert_eq!(message.hostname.as_deref(), Some("web-1"));
+        assert_eq!(message.app_name.as_deref(), Some("sshd"));
+        assert_eq!(message.proc_id.as_deref(), Some("812"));
+        assert_eq!(message.message, "Failed password for root");
+
+        let log = message.into_log_entry("syslog", now());
+        assert_eq!(log.level, Some("FATAL".to_string()));
+        assert_eq!(log.attributes.get("syslog.facility"), Some(&"auth".to_string()));
+
+        // Without a hostname, and a December timestamp seen in March is last year's
+        let message = parse("<13>Dec 31 23:59:59 cron: job finished", now());
+        assert_eq!(message.hostname, None);
+        assert_eq!(message.app_name.as_deref(), Some("cron"));
+        assert_eq!(message.timestamp, Some(Utc.with_ymd_and_hms(2023, 12, 31, 23, 59, 59).unwrap()));
+    }
+
+    #[test]
+    fn test_malformed_messages_pass_through_as_raw_text() {
+        let received = now();
+
+        for line in ["plain text without a header", "<999>1 bad priority", "<abc>nope", ""] {
+            let message = parse(line, received);
+            assert_eq!(message.format, SyslogFormat::Raw, "line {:?}", line);
+            assert_eq!(message.message, line);
+            assert_eq!(message.severity, None);
+
+            let log = message.into_log_entry("syslog", received);
+            assert_eq!(log.timestamp, received);
+            assert_eq!(log.level, None);
+        }
+
+        // A valid priority with an unparseable header keeps facility and severity
+        let message = parse("<14>garbled header", received);
+        assert_eq!(message.format, SyslogFormat::Raw);
+        assert_eq!((message.facility, message.severity), (Some(1), Some(6)));
+        assert_eq!(message.message, "garbled header");
+
+        // An unterminated structured data element is not RFC 5424
+        let message = parse("<14>1 - host app - - [open element", received);
+        assert_eq!(message.format, SyslogFormat::Raw);
+    }
+
+    #[test]
+    fn test_tcp_framing_mixes_octet_counting_and_newlines() -> Result<()> {
+        let stream = b"11 <14>1 - - -21 <14>first\nsecond line\r\n<14>third";
+        let mut framer = TcpFramer::new(1024);
+        let mut messages = Vec::new();
+        for chunk in stream.chunks(4) {
+            messages.extend(framer.push(chunk)?);
+        }
+        messages.extend(framer.finish());
+
+        assert_eq!(messages, vec!["<14>1 - - -", "<14>first\nsecond line", "<14>third"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_framing_rejects_oversized_octet_counts() {
+        let mut framer = TcpFramer::new(16);
+        assert!(framer.push(b"17 ").is_err());
+        assert!(TcpFramer::new(16).push(b"12345678901 <14>x").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Prometheus metrics and probe endpoints for the collector
+//!
+//! The collector records its metrics through the `metrics` facade, which
+//! discards them until a recorder is installed. With a `telemetry` section
+//! configured, [`start_metrics_server`] installs the Prometheus recorder and
+//! serves what it has gathered over HTTP:
+//!
+//! - `collector_logs_received_total{source}`: entries read by each source
+//! - `collector_processor_dropped_total{processor}`: entries a processor dropped
+//! - `collector_logs_exported_total{exporter}` / `collector_logs_export_failed_total{exporter}`:
+//!   entries each exporter took or failed on
+//! - `collector_export_duration_seconds{exporter}` and `collector_export_batch_size{exporter}`
+//! - `collector_upload_duration_seconds{exporter,outcome}`: requests of network exporters
+//! - `collector_exporter_breaker_state{exporter}`: circuit breaker of exporters with a
+//!   `resilience` policy (0 closed, 1 half-open, 2 open)
+//!
+//! With a `probes` section, [`start_probe_server`] answers liveness checks on
+//! `/healthz` and readiness checks on `/readyz` from a [`ReadinessTracker`].
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::collector::config::{ProbesConfig, TelemetryConfig};
+use crate::collector::health::ReadinessTracker;
+
+/// Handle of the installed Prometheus recorder; `metrics` takes only one per process
+static RECORDER: Mutex<Option<PrometheusHandle>> = Mutex::new(None);
+
+/// Install the Prometheus recorder, or return the one already installed
+pub fn install_recorder() -> Result<PrometheusHandle> {
+    let mut installed = RECORDER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(handle) = installed.as_ref() {
+        return Ok(handle.clone());
+    }
+
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let handle = recorder.handle();
+    metrics::set_boxed_recorder(Box::new(recorder))
+        .map_err(|e| anyhow!("Failed to install the metrics recorder: {}", e))?;
+
+    *installed = Some(handle.clone());
+    Ok(handle)
+}
+
+/// Serve the collector's metrics at `config.path`, returning the bound address
+pub async fn start_metrics_server(config: &TelemetryConfig) -> Result<(SocketAddr, JoinHandle<()>)> {
+    let handle = install_recorder()?;
+    let path = config.path.clone();
+
+    serve("metrics", &config.listen_address, move |request| {
+        if request.uri().path() != path {
+            return respond(StatusCode::NOT_FOUND, None, String::new());
+        }
+        if request.method() != Method::GET {
+            return respond(StatusCode::METHOD_NOT_ALLOWED, None, String::new());
+        }
+        respond(StatusCode::OK, Some("text/plain; version=0.0.4"), handle.render())
+    })
+    .await
+}
+
+/// Serve `/healthz` and `/readyz`, returning the bound address
+///
+/// `/healthz` answers 200 as long as the process serves requests. `/readyz`
+/// answers 200 when `readiness` finds nothing wrong, and otherwise 503 with
+/// the components that are not ready.
+pub async fn start_probe_server(
+    config: &ProbesConfig,
+    readiness: Arc<ReadinessTracker>,
+) -> Result<(SocketAddr, JoinHandle<()>)> {
+    serve("probe", &config.listen_address, move |request| {
+        if request.method() != Method::GET {
+            return respond(StatusCode::METHOD_NOT_ALLOWED, None, String::new());
+        }
+
+        match request.uri().path() {
+            "/healthz" => respond(StatusCode::OK, Some("application/json"), r#"{"status":"ok"}"#.to_string()),
+            "/readyz" => {
+                let unready = readiness.check(Utc::now());
+                let status = if unready.is_empty() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+                let body = serde_json::json!({ "ready": unready.is_empty(), "unready": unready });
+                respond(status, Some("application/json"), body.to_string())
+            },
+            _ => respond(StatusCode::NOT_FOUND, None, String::new()),
+        }
+    })
+    .await
+}
+
+/// Bind `address` and answer every request on it with `handler`
+async fn serve<F>(what: &'static str, address: &str, handler: F) -> Result<(SocketAddr, JoinHandle<()>)>
+where
+    F: Fn(&Request<Body>) -> Response<Body> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| anyhow!("Failed to bind {} endpoint {}: {}", what, address, e))?;
+    let address = listener.local_addr()?;
+    let handler = Arc::new(handler);
+
+    tracing::info!("Serving {} endpoint on http://{}", what, address);
+
+    let task = tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Failed to accept {} connection: {}", what, e);
+                    continue;
+                },
+            };
+
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let response = handler(&request);
+                    async move { Ok::<_, Infallible>(response) }
+                });
+                if let Err(e) = Http::new().serve_connection(stream, service).await {
+                    tracing::debug!("{} connection from {} closed: {}", what, peer, e);
+                }
+            });
+        }
+    });
+
+    Ok((address, task))
+}
+
+/// Build a response with an optional content type
+fn respond(status: StatusCode, content_type: Option<&'static str>, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    if let Some(content_type) = content_type {
+        response.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(content_type));
+    }
+    response
+}
+
+/// Record one request a network exporter made, and how it ended
+pub(crate) fn record_upload(exporter: &str, outcome: &'static str, elapsed: Duration) {
+    metrics::histogram!(
+        "collector_upload_duration_seconds",
+        elapsed.as_secs_f64(),
+        "exporter" => exporter.to_string(),
+        "outcome" => outcome,
+    );
+}
+
+/// Record the state of an exporter's circuit breaker
+pub(crate) fn record_breaker_state(exporter: &str, state: f64) {
+    metrics::gauge!("collector_exporter_breaker_state", state, "exporter" => exporter.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_readyz_lists_unready_components() -> Result<()> {
+        let readiness = Arc::new(ReadinessTracker::new(60));
+        let config = ProbesConfig { listen_address: "127.0.0.1:0".to_string(), max_flush_age_seconds: 60 };
+        let (address, server) = start_probe_server(&config, readiness.clone()).await?;
+        let get = |path: &str| reqwest::get(format!("http://{}{}", address, path));
+
+        // Alive from the start, but not ready
+        assert_eq!(get("/healthz").await?.status(), reqwest::StatusCode::OK);
+        let response = get("/readyz").await?;
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = response.json().await?;
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["unready"][0]["component"], "sources");
+        assert_eq!(body["unready"][1]["component"], "exporters");
+
+        // An exporter whose last flush is too old keeps it unready
+        readiness.set_sources_started(true);
+        readiness.set_exporters([("cloud", true)], Utc::now() - chrono::Duration::seconds(120));
+        let body: serde_json::Value = get("/readyz").await?.json().await?;
+        assert_eq!(body["unready"], serde_json::json!([{ "component": "exporter:cloud", "reason": "no successful flush for 120s" }]));
+
+        readiness.record_flush("cloud", Utc::now());
+        let response = get("/readyz").await?;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.json::<serde_json::Value>().await?, serde_json::json!({ "ready": true, "unready": [] }));
+
+        server.abort();
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! TLS for TCP sources, with plaintext detection on a shared port
+//!
+//! Every TLS connection opens with a handshake record, whose first byte is
+//! 0x16, while plaintext syslog starts with `<` or an octet count and
+//! plaintext HTTP with a method name. Peeking at that byte lets one listener
+//! serve both kinds of client, or turn plaintext away when TLS is required.
+//!
+//! The client side, for exporters talking to HTTPS servers, is configured on
+//! a `reqwest` client builder.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::collector::config::{ClientTlsConfig, TlsConfig, TlsMode};
+
+/// Content type of a TLS handshake record, the first byte a TLS client sends
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// Time a client gets to send its first byte and finish the handshake, unless set otherwise
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sorts accepted connections into TLS and plaintext per the configured mode
+#[derive(Clone)]
+pub struct TlsListener {
+    acceptor: TlsAcceptor,
+    mode: TlsMode,
+    handshake_timeout: Duration,
+}
+
+/// An accepted connection, after any TLS handshake
+pub enum Connection {
+    Tls(Box<TlsStream<TcpStream>>),
+    Plain(TcpStream),
+}
+
+impl Connection {
+    /// Attributes describing the connection
+    ///
+    /// `tls` is `true` or `false`; a client that authenticated with a
+    /// certificate adds its `tls.peer.subject` and `tls.peer.issuer`.
+    pub fn attributes(&self) -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+
+        match self {
+            Connection::Plain(_) => {
+                attributes.insert("tls".to_string(), "false".to_string());
+            },
+            Connection::Tls(stream) => {
+                attributes.insert("tls".to_string(), "true".to_string());
+
+                let peer = stream.get_ref().1.peer_certificates().and_then(|certs| certs.first());
+                if let Some(Ok((_, cert))) = peer.map(|cert| x509_parser::parse_x509_certificate(&cert.0)) {
+                    attributes.insert("tls.peer.subject".to_string(), cert.subject().to_string());
+                    attributes.insert("tls.peer.issuer".to_string(), cert.issuer().to_string());
+                }
+            },
+        }
+
+        attributes
+    }
+}
+
+impl TlsListener {
+    /// Load the certificate, key and optional client CA named in `config`
+    ///
+    /// With `require_client_cert`, the handshake fails for a client without
+    /// a certificate the CA signed; otherwise such a client is served anonymously.
+    pub fn new(config: &TlsConfig) -> Result<Self> {
+        let certs = load_certs(&config.cert_path)?;
+        let key = load_key(&config.key_path)?;
+
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match &config.client_ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots.add(&cert).map_err(|e| anyhow!("Invalid CA certificate in {}: {}", path, e))?;
+                }
+                if config.require_client_cert {
+                    builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+                } else {
+                    builder.with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+                }
+            },
+            None if config.require_client_cert => {
+                return Err(anyhow!("require_client_cert needs a client_ca_path to verify certificates against"));
+            },
+            None => builder.with_no_client_auth(),
+        };
+        let server_config = builder
+            .with_single_cert(certs, key)
+            .map_err(|e| anyhow!("Invalid certificate {} or key {}: {}", config.cert_path, config.key_path, e))?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(server_config)),
+            mode: config.mode,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        })
+    }
+
+    /// Give clients `timeout` to send their first byte and finish the handshake
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Complete the handshake of a TLS client, or pass a plaintext one through when allowed
+    ///
+    /// Returns `None` for a connection closed before it sent anything. A
+    /// client that is silent or stalls mid-handshake for longer than the
+    /// handshake timeout is refused, so it cannot hold the connection open.
+    pub async fn accept(&self, stream: TcpStream) -> Result<Option<Connection>> {
+        tokio::time::timeout(self.handshake_timeout, self.sort(stream))
+            .await
+            .map_err(|_| anyhow!("TLS handshake timed out after {:?}", self.handshake_timeout))?
+    }
+
+    /// Peek at the first byte to tell TLS from plaintext, and handshake with a TLS client
+    async fn sort(&self, stream: TcpStream) -> Result<Option<Connection>> {
+        let mut first = [0u8; 1];
+        if stream.peek(&mut first).await? == 0 {
+            return Ok(None);
+        }
+
+        if first[0] == TLS_HANDSHAKE {
+            let stream = self.acceptor.accept(stream).await?;
+            return Ok(Some(Connection::Tls(Box::new(stream))));
+        }
+
+        match self.mode {
+            TlsMode::Optional => Ok(Some(Connection::Plain(stream))),
+            TlsMode::Required => Err(anyhow!("plaintext connection refused, TLS is required")),
+        }
+    }
+}
+
+/// Read every certificate from a PEM file
+fn load_certs(path: &str) -> Result<Vec<Certificate>> {
+    let file = File::open(path).map_err(|e| anyhow!("Failed to open certificate file {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .map_err(|e| anyhow!("Invalid certificate file {}: {}", path, e))?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path));
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Read the first PKCS#8, PKCS#1 or SEC1 private key from a PEM file
+fn load_key(path: &str) -> Result<PrivateKey> {
+    let file = File::open(path).map_err(|e| anyhow!("Failed to open key file {}: {}", path, e))?;
+    let items = rustls_pemfile::read_all(&mut BufReader::new(file))
+        .map_err(|e| anyhow!("Invalid key file {}: {}", path, e))?;
+
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No private key found in {}", path))
+}
+
+/// Apply `config` to an HTTP client: server verification, extra trusted CAs and a client certificate
+///
+/// The client uses rustls, which is the only backend that reads a PEM identity.
+pub fn configure_client(builder: reqwest::ClientBuilder, config: &ClientTlsConfig) -> Result<reqwest::ClientBuilder> {
+    let mut builder = builder
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(!config.verify_certs);
+
+    if let Some(path) = &config.ca_cert_path {
+        for cert in load_certs(path)? {
+            let cert = reqwest::Certificate::from_der(&cert.0)
+                .map_err(|e| anyhow!("Invalid CA certificate in {}: {}", path, e))?;
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&config.client_cert_path, &config.client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            // reqwest reads the identity from one PEM holding both the chain and the key
+            let mut pem = std::fs::read(cert_path).map_err(|e| anyhow!("Failed to open certificate file {}: {}", cert_path, e))?;
+            pem.push(b'\n');
+            pem.extend(std::fs::read(key_path).map_err(|e| anyhow!("Failed to open key file {}: {}", key_path, e))?);
+            let identity = reqwest::Identity::from_pem(&pem)
+                .map_err(|e| anyhow!("Invalid client certificate {} or key {}: {}", cert_path, key_path, e))?;
+            builder = builder.identity(identity);
+        },
+        (None, None) => {},
+        _ => return Err(anyhow!("client_cert_path and client_key_path must be set together")),
+    }
+
+    Ok(builder)
+}
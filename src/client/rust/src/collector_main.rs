@@ -0,0 +1,371 @@
+//! LogNarrator Log Collector
+//!
+//! This binary runs the log collection pipeline described by a collector
+//! configuration file, and checks such files before they are deployed.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+use lognarrator_client::collector::config::{self, CollectorConfig, DEFAULT_PIPELINE};
+use lognarrator_client::collector::pipeline::ProcessorChain;
+use lognarrator_client::collector::sources::LogEntry;
+use lognarrator_client::collector::{admin, processors, LogCollector};
+use lognarrator_client::crypto::{self, KeyType};
+
+/// Command-line arguments for the log collector
+#[derive(Parser, Debug)]
+#[clap(author, version, about)]
+struct Args {
+    /// Path to the configuration file
+    #[clap(short, long, default_value = "/app/config/collector.yaml")]
+    config: String,
+
+    /// Enable verbose logging
+    #[clap(short, long)]
+    verbose: bool,
+
+    /// Check the configuration and exit, without binding sockets or starting the pipeline
+    #[clap(long, visible_alias = "validate")]
+    check_config: bool,
+
+    /// Run logs read from stdin, one per line, through a pipeline's processors and print the results
+    #[clap(long, value_name = "PIPELINE", num_args = 0..=1, default_missing_value = DEFAULT_PIPELINE)]
+    test_pipeline: Option<String>,
+
+    /// Print the type and fingerprint of a `.private` or `.public` key file, and check it against its counterpart
+    #[clap(long, value_name = "PATH")]
+    key_info: Option<PathBuf>,
+
+    /// Generate a keypair into `<PREFIX>.private` and `<PREFIX>.public`, readable by the owner only
+    #[clap(long, value_name = "PREFIX")]
+    generate_keys: Option<PathBuf>,
+
+    /// Type of keypair `--generate-keys` creates: `box` for the exporter's key_path, or `signing`
+    #[clap(long, default_value = "box", value_parser = ["box", "signing"])]
+    key_type: String,
+
+    /// Send a command, such as `pipeline`, to the running collector's admin socket and print its answer
+    #[clap(long, value_name = "COMMAND")]
+    admin: Option<String>,
+}
+
+/// A log line given to `--test-pipeline` as JSON
+///
+/// Only `message` is required; `source` defaults to `stdin` and
+/// `timestamp` to the time the line is read.
+#[derive(Deserialize, Debug)]
+struct SampleLog {
+    message: String,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    attributes: HashMap<String, String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    let args = Args::parse();
+
+    if args.check_config {
+        return Ok(check_config(&args.config));
+    }
+
+    if let Some(path) = &args.key_info {
+        return key_info(path);
+    }
+
+    if let Some(prefix) = &args.generate_keys {
+        let key_type = if args.key_type == "signing" { KeyType::Signing } else { KeyType::Box };
+        crypto::init()?;
+        let key = crypto::generate_key_files(prefix, key_type)?;
+        let (private_path, public_path) = crypto::key_file_paths(prefix);
+        println!("Wrote {} and {}", private_path.display(), public_path.display());
+        println!("Public key:  {}", key.public_key_base64());
+        println!("Fingerprint: {}", key.fingerprint());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(command) = &args.admin {
+        return admin_command(&args.config, command).await;
+    }
+
+    if let Some(pipeline) = &args.test_pipeline {
+        let config = config::read_config(&args.config)
+            .with_context(|| format!("Failed to load configuration from {}", args.config))?;
+        test_pipeline(&config, pipeline).await?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    init_logging(args.verbose)?;
+
+    let config = config::load_config(&args.config)
+        .with_context(|| format!("Failed to load configuration from {}", args.config))?;
+    let mut collector = LogCollector::new(config)?;
+
+    // Listen before starting, so an early SIGHUP cannot end the collector
+    let mut reloads = reload_requests();
+
+    tracing::info!("Starting LogNarrator log collector");
+    collector.start().await?;
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            Some(()) = reloads.recv() => reload_exporters(&mut collector, &args.config).await,
+        }
+    }
+
+    tracing::info!("Shutting down LogNarrator log collector");
+    collector.stop().await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Load and check the configuration at `path`, printing a summary
+///
+/// Besides the checks run at startup, every processor is built, so each
+/// pattern is compiled as the running collector would. Nothing is bound,
+/// opened for writing or started.
+fn check_config(path: &str) -> ExitCode {
+    match load_and_check(path) {
+        Ok(config) => {
+            println!(
+                "OK: {} ({} sources, {} processors, {} exporters)",
+                path,
+                config.sources.len(),
+                config.processors.len(),
+                config.exporters.len(),
+            );
+            ExitCode::SUCCESS
+        },
+        Err(e) => {
+            println!("FAILED: {}", path);
+            println!("{:#}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn load_and_check(path: &str) -> Result<CollectorConfig> {
+    let config = config::load_config(path)?;
+    for processor in &config.processors {
+        processors::create_processor(processor)
+            .with_context(|| format!("processor '{}'", processor.name()))?;
+    }
+    Ok(config)
+}
+
+/// Describe the key file at `path`, failing when its counterpart holds a different key
+///
+/// Fingerprints of box keys are the key IDs the exporter reports, so they can
+/// be compared with the server's record of the client's key.
+fn key_info(path: &Path) -> Result<ExitCode> {
+    crypto::init()?;
+    let key = crypto::read_key_file(path)?;
+
+    let key_type = match key.key_type {
+        Some(KeyType::Signing) => "signing (Ed25519)",
+        Some(KeyType::Box) => "box (X25519)",
+        None => "signing or box, unknown without its private key",
+    };
+    println!("File:        {}", path.display());
+    println!("Type:        {} {}", if key.secret { "private" } else { "public" }, key_type);
+    println!("Public key:  {}", key.public_key_base64());
+    println!("Fingerprint: {}", key.fingerprint());
+
+    let counterpart = match crypto::counterpart_path(path) {
+        Some(counterpart) if counterpart.is_file() => counterpart,
+        Some(counterpart) => {
+            println!("Counterpart: {} (missing)", counterpart.display());
+            return Ok(ExitCode::SUCCESS);
+        },
+        None => return Ok(ExitCode::SUCCESS),
+    };
+
+    let other = crypto::read_key_file(&counterpart)?;
+    if other.public_key == key.public_key {
+        println!("Counterpart: {} (matches)", counterpart.display());
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("Counterpart: {} (DIFFERENT key, fingerprint {})", counterpart.display(), other.fingerprint());
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Send `command` to the admin socket named in the configuration at `path`
+///
+/// The answer is printed as it comes; an `error` answer fails the command.
+async fn admin_command(path: &str, command: &str) -> Result<ExitCode> {
+    let config = config::read_config(path)
+        .with_context(|| format!("Failed to load configuration from {}", path))?;
+    let admin = config.admin
+        .ok_or_else(|| anyhow::anyhow!("{} has no admin section, so the collector has no admin socket", path))?;
+
+    let response = admin::send_command(Path::new(&admin.socket_path), command).await?;
+    println!("{}", response);
+
+    let failed = serde_json::from_str::<serde_json::Value>(&response).map_or(true, |answer| answer.get("error").is_some());
+    Ok(if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+/// Feed logs from stdin through the processors of `pipeline`, printing what comes out
+///
+/// Each line is a JSON [`SampleLog`] when it starts with `{`, or else a
+/// plain message. Every surviving entry is printed as one line of JSON, and
+/// a line that yields nothing as `dropped` (or `held or dropped`, when a
+/// processor such as `batch` holds entries until the input ends).
+async fn test_pipeline(config: &CollectorConfig, pipeline: &str) -> Result<()> {
+    let chain = ProcessorChain::new(config, pipeline)?;
+    let nothing = if chain.holds_entries() { "held or dropped" } else { "dropped" };
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut line_number = 0;
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let log = sample_entry(&line).with_context(|| format!("Line {}", line_number))?;
+        let processed = chain.process(log).await;
+        if processed.is_empty() {
+            println!("{}", nothing);
+        }
+        for log in processed {
+            println!("{}", serde_json::to_string(&log)?);
+        }
+    }
+
+    for log in chain.finish().await {
+        println!("{}", serde_json::to_string(&log)?);
+    }
+
+    Ok(())
+}
+
+/// Turn a line given to `--test-pipeline` into a log entry
+fn sample_entry(line: &str) -> Result<LogEntry> {
+    if !line.trim_start().starts_with('{') {
+        return Ok(LogEntry {
+            timestamp: Utc::now(),
+            source: "stdin".to_string(),
+            level: None,
+            severity_number: None,
+            message: line.to_string(),
+            attributes: HashMap::new(),
+        });
+    }
+
+    let sample: SampleLog = serde_json::from_str(line).context("Invalid JSON log")?;
+    Ok(LogEntry {
+        timestamp: sample.timestamp.unwrap_or_else(Utc::now),
+        source: sample.source.unwrap_or_else(|| "stdin".to_string()),
+        level: sample.level,
+        severity_number: None,
+        message: sample.message,
+        attributes: sample.attributes,
+    })
+}
+
+/// Re-read the configuration at `path` and replace the exporters with its `exporters` section
+///
+/// Sources, processors and pipelines keep running as loaded; changes to them
+/// need a restart. A configuration that fails to load or validate, or an
+/// exporter that fails to start, leaves the running exporters in place.
+async fn reload_exporters(collector: &mut LogCollector, path: &str) {
+    tracing::info!("Reloading exporters from {}", path);
+
+    let config = match config::load_config(path) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Not reloading exporters, failed to load {}: {:#}", path, e);
+            return;
+        },
+    };
+
+    if let Err(e) = collector.reload_exporters(config.exporters).await {
+        tracing::error!("Failed to reload exporters, keeping the current ones: {:#}", e);
+    }
+}
+
+/// Receive one message per SIGHUP, each asking for the exporters to be reloaded
+///
+/// Nothing is ever received where SIGHUP cannot be listened for.
+fn reload_requests() -> tokio::sync::mpsc::Receiver<()> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+
+    #[cfg(unix)]
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(mut hangup) => {
+            tokio::spawn(async move {
+                while hangup.recv().await.is_some() {
+                    tracing::info!("Received SIGHUP");
+                    // A reload already pending will read the latest configuration
+                    let _ = sender.try_send(());
+                }
+            });
+        },
+        Err(e) => tracing::warn!("Cannot listen for SIGHUP, exporters will not be reloaded: {}", e),
+    }
+
+    #[cfg(not(unix))]
+    drop(sender);
+
+    receiver
+}
+
+/// Complete on SIGTERM, or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM, stopping on Ctrl-C only: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            },
+        };
+        tokio::select! {
+            _ = terminate.recv() => tracing::info!("Received SIGTERM"),
+            _ = tokio::signal::ctrl_c() => tracing::info!("Received Ctrl-C"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Initialize the logging system based on verbosity level
+fn init_logging(verbose: bool) -> Result<()> {
+    let filter = if verbose {
+        "debug".to_string()
+    } else {
+        std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string())
+    };
+
+    // In verbose mode, report each span's busy/idle time as it closes
+    let span_events = if verbose { FmtSpan::CLOSE } else { FmtSpan::NONE };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(true)
+        .with_span_events(span_events)
+        .init();
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 //! Configuration handling for the MCP client
 
 use anyhow::Result;
-use config::{Config, ConfigError, File};
+use config::{Config, File};
 use serde::Deserialize;
 use std::path::Path;
 
@@ -42,6 +42,9 @@ pub struct DatabaseConfig {
     /// Path to the SQLite database file
     pub db_path: String,
     /// Maximum number of cached log entries
+    ///
+    /// Ignored: the MCP client stores only its action history, no logs. It is
+    /// still read so existing configurations load.
     pub max_cache_entries: usize,
 }
 
@@ -56,6 +59,30 @@ pub struct ActionsConfig {
     pub require_confirmation: bool,
     /// Maximum time to wait for action execution in seconds
     pub execution_timeout: u64,
+    /// How operators are asked to confirm high-risk actions
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+}
+
+/// Channel operators confirm actions through
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalChannelKind {
+    /// Prompt on the client's terminal
+    #[default]
+    Tty,
+    /// Ask an approval webhook
+    Webhook,
+}
+
+/// Configuration of action confirmation
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ApprovalConfig {
+    /// Channel to request confirmation through
+    #[serde(default)]
+    pub channel: ApprovalChannelKind,
+    /// URL of the approval webhook, for the webhook channel
+    pub webhook_url: Option<String>,
 }
 
 /// Load the configuration from a file
@@ -104,9 +131,10 @@ mod tests {
 
         assert_eq!(config.server.api_url, "https://api.lognarrator.com");
         assert_eq!(config.server.timeout_seconds, 30);
-        assert_eq!(config.security.verify_certs, true);
+        assert!(config.security.verify_certs);
         assert_eq!(config.database.max_cache_entries, 10000);
-        assert_eq!(config.actions.require_confirmation, true);
+        assert!(config.actions.require_confirmation);
+        assert_eq!(config.actions.approval.channel, ApprovalChannelKind::Tty);
 
         Ok(())
     }
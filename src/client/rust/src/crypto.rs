@@ -1,22 +1,21 @@
 //! Cryptography module for the MCP client
 //!
 //! This module handles encryption and decryption of data using libsodium.
-//! It implements the XChaCha20-Poly1305 and X25519 algorithms for secure
-//! communication with the LogNarrator cloud.
+//! It uses libsodium's `crypto_box` (X25519 key agreement with
+//! XSalsa20-Poly1305) for secure communication with the LogNarrator cloud,
+//! and Ed25519 signing keys.
 
 use anyhow::{Context, Result};
 use sodium_oxide::crypto::box_;
-use sodium_oxide::crypto::secretbox;
-use std::fs::File;
+use sodium_oxide::crypto::sign;
+use std::collections::HashMap;
+use std::fs::{self, File};
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Initialize the sodium library
 pub fn init() -> Result<()> {
-    if !sodium_oxide::init() {
-        anyhow::bail!("Failed to initialize sodium library");
-    }
-    Ok(())
+    sodium_oxide::init().map_err(|_| anyhow::anyhow!("Failed to initialize sodium library"))
 }
 
 /// Keypair for asymmetric encryption
@@ -40,13 +39,25 @@ pub fn load_keypair<P: AsRef<Path>>(private_key_path: P) -> Result<KeyPair> {
     let secret_key = box_::SecretKey::from_slice(&key_data)
         .context("Invalid private key format")?;
 
-    // Derive the public key from the secret key
-    let public_key = box_::PublicKey::from_slice(&box_::keypair_from_secretkey(&secret_key).0)
-        .context("Failed to derive public key")?;
+    // Derive the public key from the secret key (scalar multiplication of the base point)
+    let public_key = secret_key.public_key();
 
     Ok(KeyPair { public_key, secret_key })
 }
 
+/// Load a public key, such as the LogNarrator server's, from a file
+pub fn load_public_key<P: AsRef<Path>>(public_key_path: P) -> Result<box_::PublicKey> {
+    let mut file = File::open(&public_key_path)
+        .context("Failed to open public key file")?;
+
+    let mut key_data = Vec::new();
+    file.read_to_end(&mut key_data)
+        .context("Failed to read public key file")?;
+
+    box_::PublicKey::from_slice(&key_data)
+        .context("Invalid public key format")
+}
+
 /// Encrypt data with the recipient's public key
 pub fn encrypt(data: &[u8], recipient_pk: &box_::PublicKey, sender_sk: &box_::SecretKey) -> Result<Vec<u8>> {
     // Generate a random nonce
@@ -62,17 +73,24 @@ pub fn encrypt(data: &[u8], recipient_pk: &box_::PublicKey, sender_sk: &box_::Se
 
     Ok(result)
 }
-//! Cryptography utilities for the LogNarrator client
 
-use anyhow::Result;
-use sodium_oxide::crypto::sign;
-use std::fs;
-use std::path::Path;
+/// Decrypt data with the recipient's secret key
+pub fn decrypt(data: &[u8], sender_pk: &box_::PublicKey, recipient_sk: &box_::SecretKey) -> Result<Vec<u8>> {
+    // Split nonce and ciphertext
+    if data.len() < box_::NONCEBYTES {
+        anyhow::bail!("Data too short to contain nonce");
+    }
 
-/// Initialize the cryptography subsystem
-pub fn init() -> Result<()> {
-    sodium_oxide::init();
-    Ok(())
+    let nonce = box_::Nonce::from_slice(&data[..box_::NONCEBYTES])
+        .context("Invalid nonce")?;
+
+    let ciphertext = &data[box_::NONCEBYTES..];
+
+    // Decrypt the data
+    let plaintext = box_::open(ciphertext, &nonce, sender_pk, recipient_sk)
+        .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
+
+    Ok(plaintext)
 }
 
 /// Generate a new key pair
@@ -87,7 +105,7 @@ pub fn sign(data: &[u8], secret_key: &sign::SecretKey) -> Vec<u8> {
 
 /// Verify a signature
 pub fn verify(signed_data: &[u8], public_key: &sign::PublicKey) -> Option<Vec<u8>> {
-    sign::verify(signed_data, public_key)
+    sign::verify(signed_data, public_key).ok()
 }
 
 /// Read a secret key from a file
@@ -102,23 +120,268 @@ pub fn read_public_key<P: AsRef<Path>>(path: P) -> Result<sign::PublicKey> {
     sign::PublicKey::from_slice(&key_data).ok_or_else(|| anyhow::anyhow!("Invalid public key"))
 }
 
-/// Write a secret key to a file
+/// Write a secret key to a file only its owner can read
 pub fn write_secret_key<P: AsRef<Path>>(path: P, key: &sign::SecretKey) -> Result<()> {
-    fs::write(path, key.as_ref())?;
-    Ok(())
+    write_key(path.as_ref(), key.as_ref())
 }
 
-/// Write a public key to a file
+/// Write a public key to a file only its owner can read
 pub fn write_public_key<P: AsRef<Path>>(path: P, key: &sign::PublicKey) -> Result<()> {
-    fs::write(path, key.as_ref())?;
+    write_key(path.as_ref(), key.as_ref())
+}
+
+/// Write key material with mode 0600 on Unix, replacing any earlier file
+pub fn write_key(path: &Path, key: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = key_file_options().create(true).truncate(true).open(path)?;
+    // The mode only applies to a new file, so restrict an existing one as well
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(key)?;
     Ok(())
 }
 
-/// Compute SHA-256 hash of data
+/// Options opening a key file for writing, created with mode 0600 on Unix
+fn key_file_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Compute the SHA-256 hash of data, as lowercase hex
 pub fn hash_sha256(data: &str) -> String {
-    use sodium_oxide::crypto::hash;
-    let hash = hash::hash(data.as_bytes());
-    hex::encode(hash)
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Parse `age` X25519 recipients (`age1...`)
+#[cfg(feature = "age")]
+pub fn parse_age_recipients(recipients: &[String]) -> Result<Vec<age::x25519::Recipient>> {
+    recipients
+        .iter()
+        .map(|recipient| {
+            recipient
+                .parse::<age::x25519::Recipient>()
+                .map_err(|e| anyhow::anyhow!("Invalid age recipient {}: {}", recipient, e))
+        })
+        .collect()
+}
+
+/// Encrypt data to every one of `recipients`, in the binary `age` format
+///
+/// Any one recipient's identity decrypts the result, with `age -d` or the
+/// `age` crate's `Decryptor`.
+#[cfg(feature = "age")]
+pub fn age_encrypt(data: &[u8], recipients: &[age::x25519::Recipient]) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let recipients = recipients.iter().map(|recipient| recipient as &dyn age::Recipient);
+    let encryptor = age::Encryptor::with_recipients(recipients).context("No age recipients to encrypt to")?;
+
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+
+    Ok(encrypted)
+}
+
+/// Identifier of a public key: the first 8 bytes of its SHA-256, in hex
+pub fn key_id(public_key: &box_::PublicKey) -> String {
+    fingerprint(public_key.as_ref())
+}
+
+/// Short fingerprint of any public key's bytes, as [`key_id`] computes it
+pub fn fingerprint(public_key: &[u8]) -> String {
+    use sodium_oxide::crypto::hash::sha256;
+
+    sha256::hash(public_key).as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Algorithm a key file belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// Ed25519 signing key
+    Signing,
+    /// X25519 `crypto_box` encryption key
+    Box,
+}
+
+/// What a `.private` or `.public` key file holds
+#[derive(Debug, Clone)]
+pub struct KeyFile {
+    /// Algorithm of the key, unknown for a public key read on its own
+    pub key_type: Option<KeyType>,
+    /// Whether the file holds a secret key
+    pub secret: bool,
+    /// The public key, read or derived from the secret key
+    pub public_key: Vec<u8>,
+}
+
+impl KeyFile {
+    /// Short SHA-256 fingerprint of the public key; for a box key, its [`key_id`]
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public_key)
+    }
+
+    /// The public key, in standard base64
+    pub fn public_key_base64(&self) -> String {
+        use base64::Engine;
+
+        base64::engine::general_purpose::STANDARD.encode(&self.public_key)
+    }
+}
+
+/// Read a key file written by the key generator, telling its type apart by length
+///
+/// A signing secret key is 64 bytes and a box secret key 32. Public keys of
+/// both types are 32 bytes, so a `.public` file takes its type from its
+/// `.private` counterpart, when there is one.
+pub fn read_key_file<P: AsRef<Path>>(path: P) -> Result<KeyFile> {
+    let path = path.as_ref();
+    let key_data = std::fs::read(path)
+        .with_context(|| format!("Failed to read key file {}", path.display()))?;
+    let is_public = path.extension().is_some_and(|extension| extension == "public");
+
+    match (key_data.len(), is_public) {
+        (sign::SECRETKEYBYTES, _) => {
+            let secret_key = sign::SecretKey::from_slice(&key_data).context("Invalid signing secret key")?;
+            Ok(KeyFile { key_type: Some(KeyType::Signing), secret: true, public_key: secret_key.public_key().as_ref().to_vec() })
+        },
+        (box_::SECRETKEYBYTES, false) => {
+            let secret_key = box_::SecretKey::from_slice(&key_data).context("Invalid box secret key")?;
+            Ok(KeyFile { key_type: Some(KeyType::Box), secret: true, public_key: secret_key.public_key().as_ref().to_vec() })
+        },
+        (box_::PUBLICKEYBYTES, true) => {
+            let key_type = counterpart_path(path)
+                .filter(|private_path| private_path.is_file())
+                .and_then(|private_path| read_key_file(private_path).ok())
+                .and_then(|private| private.key_type);
+            Ok(KeyFile { key_type, secret: false, public_key: key_data })
+        },
+        (length, _) => anyhow::bail!("{} holds {} bytes, which is no known key length", path.display(), length),
+    }
+}
+
+/// The `<prefix>.private` and `<prefix>.public` paths of a keypair
+///
+/// The suffixes are appended, so a prefix such as `collector.v2` keeps its dot.
+pub fn key_file_paths<P: AsRef<Path>>(prefix: P) -> (PathBuf, PathBuf) {
+    let with_suffix = |suffix: &str| {
+        let mut path = prefix.as_ref().as_os_str().to_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    (with_suffix(".private"), with_suffix(".public"))
+}
+
+/// Generate a keypair of `key_type` into `<prefix>.private` and `<prefix>.public`
+///
+/// A box keypair is what the exporter's `key_path` takes. Existing key files
+/// are never overwritten: both are created anew, with mode 0600, and when
+/// either already exists neither is written.
+pub fn generate_key_files<P: AsRef<Path>>(prefix: P, key_type: KeyType) -> Result<KeyFile> {
+    use std::io::{ErrorKind, Write};
+
+    let (private_path, public_path) = key_file_paths(prefix);
+    let create = |path: &Path| {
+        key_file_options().create_new(true).open(path).map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => anyhow::anyhow!("{} already exists, not overwriting it", path.display()),
+            _ => anyhow::anyhow!("Failed to create {}: {}", path.display(), e),
+        })
+    };
+    let mut private_file = create(&private_path)?;
+    let mut public_file = match create(&public_path) {
+        Ok(file) => file,
+        Err(e) => {
+            drop(private_file);
+            let _ = fs::remove_file(&private_path);
+            return Err(e);
+        },
+    };
+
+    let (secret_key, public_key) = match key_type {
+        KeyType::Signing => {
+            let (public_key, secret_key) = sign::gen_keypair();
+            (secret_key.as_ref().to_vec(), public_key.as_ref().to_vec())
+        },
+        KeyType::Box => {
+            let (public_key, secret_key) = box_::gen_keypair();
+            (secret_key.as_ref().to_vec(), public_key.as_ref().to_vec())
+        },
+    };
+
+    private_file.write_all(&secret_key)?;
+    public_file.write_all(&public_key)?;
+
+    Ok(KeyFile { key_type: Some(key_type), secret: true, public_key })
+}
+
+/// The `.public` file for a `.private` one, or the other way round
+pub fn counterpart_path<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let path = path.as_ref();
+    match path.extension()?.to_str()? {
+        "private" => Some(path.with_extension("public")),
+        "public" => Some(path.with_extension("private")),
+        _ => None,
+    }
+}
+
+/// Keypairs by key ID, one of them active
+///
+/// Rotating to a new keypair keeps the earlier ones, so data sealed under
+/// a retired key can still be matched to it.
+#[derive(Debug, Clone)]
+pub struct KeyStore {
+    keys: HashMap<String, KeyPair>,
+    active: String,
+}
+
+impl KeyStore {
+    /// Create a store whose active key is `keypair`
+    pub fn new(keypair: KeyPair) -> Self {
+        let active = key_id(&keypair.public_key);
+        Self { keys: HashMap::from([(active.clone(), keypair)]), active }
+    }
+
+    /// Make `keypair` the active key, returning whether that changed the active key
+    pub fn rotate(&mut self, keypair: KeyPair) -> bool {
+        let id = key_id(&keypair.public_key);
+        if id == self.active {
+            return false;
+        }
+
+        self.keys.entry(id.clone()).or_insert(keypair);
+        self.active = id;
+        true
+    }
+
+    /// ID of the active key
+    pub fn active_id(&self) -> &str {
+        &self.active
+    }
+
+    /// The active keypair
+    pub fn active(&self) -> &KeyPair {
+        &self.keys[&self.active]
+    }
+
+    /// Keypair with the given ID, active or retired
+    pub fn get(&self, id: &str) -> Option<&KeyPair> {
+        self.keys.get(id)
+    }
 }
 
 #[cfg(test)]
@@ -126,6 +389,196 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    fn keypair() -> KeyPair {
+        let (public_key, secret_key) = box_::gen_keypair();
+        KeyPair { public_key, secret_key }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() -> Result<()> {
+        // Initialize sodium
+        init()?;
+
+        // Generate keypairs
+        let sender = box_::gen_keypair();
+        let recipient = box_::gen_keypair();
+
+        // Test data
+        let data = b"This is a test message";
+
+        // Encrypt
+        let encrypted = encrypt(data, &recipient.0, &sender.1)?;
+
+        // Decrypt
+        let decrypted = decrypt(&encrypted, &sender.0, &recipient.1)?;
+
+        assert_eq!(decrypted, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loaded_keypair_round_trips() -> Result<()> {
+        init()?;
+
+        let dir = tempfile::tempdir()?;
+        let key_path = dir.path().join("private.key");
+        let (public_key, secret_key) = box_::gen_keypair();
+        std::fs::write(&key_path, secret_key.as_ref())?;
+
+        let keypair = load_keypair(&key_path)?;
+        assert_eq!(keypair.public_key, public_key);
+
+        // Encrypt to the loaded public key, decrypt with the loaded secret key
+        let sender = box_::gen_keypair();
+        let data = b"This is a test message";
+        let encrypted = encrypt(data, &keypair.public_key, &sender.1)?;
+        let decrypted = decrypt(&encrypted, &sender.0, &keypair.secret_key)?;
+
+        assert_eq!(decrypted, data);
+
+        Ok(())
+    }
+    #[test]
+    fn test_key_store_rotation_keeps_retired_keys() -> Result<()> {
+        init()?;
+
+        let first = keypair();
+        let second = keypair();
+        let first_id = key_id(&first.public_key);
+        let second_id = key_id(&second.public_key);
+        assert_eq!(first_id.len(), 16);
+        assert_ne!(first_id, second_id);
+
+        let mut store = KeyStore::new(first.clone());
+        assert_eq!(store.active_id(), first_id);
+        assert!(!store.rotate(first.clone()));
+
+        assert!(store.rotate(second.clone()));
+        assert_eq!(store.active_id(), second_id);
+        assert_eq!(store.active().public_key, second.public_key);
+        assert_eq!(store.get(&first_id).map(|keys| keys.public_key), Some(first.public_key));
+
+        // Rotating back reactivates the retired key
+        assert!(store.rotate(first));
+        assert_eq!(store.active_id(), first_id);
+
+        Ok(())
+    }
+    #[test]
+    fn test_key_files_report_stable_fingerprints() -> Result<()> {
+        init()?;
+
+        // SHA-256 of 32 zero bytes starts 66687aadf862bd77
+        assert_eq!(fingerprint(&[0; 32]), "66687aadf862bd77");
+
+        let dir = tempfile::tempdir()?;
+        let private_path = dir.path().join("collector.private");
+        let public_path = dir.path().join("collector.public");
+        let (public_key, secret_key) = sign::gen_keypair();
+        std::fs::write(&private_path, secret_key.as_ref())?;
+        std::fs::write(&public_path, public_key.as_ref())?;
+
+        let private = read_key_file(&private_path)?;
+        let public = read_key_file(&public_path)?;
+        assert_eq!((private.key_type, private.secret), (Some(KeyType::Signing), true));
+        assert_eq!((public.key_type, public.secret), (Some(KeyType::Signing), false));
+        assert_eq!(private.public_key, public_key.as_ref());
+        assert_eq!(private.fingerprint(), public.fingerprint());
+        assert_eq!(private.fingerprint(), read_key_file(&private_path)?.fingerprint());
+        assert_eq!(counterpart_path(&private_path), Some(public_path.clone()));
+
+        // A box key's fingerprint is its key ID
+        let box_path = dir.path().join("box.private");
+        let keys = keypair();
+        std::fs::write(&box_path, keys.secret_key.as_ref())?;
+        let boxed = read_key_file(&box_path)?;
+        assert_eq!(boxed.key_type, Some(KeyType::Box));
+        assert_eq!(boxed.fingerprint(), key_id(&keys.public_key));
+        assert_eq!(boxed.public_key_base64().len(), 44);
+
+        // Without its private counterpart, a public key's type is unknown
+        std::fs::remove_file(&private_path)?;
+        assert_eq!(read_key_file(&public_path)?.key_type, None);
+        assert!(read_key_file(dir.path().join("missing.private")).is_err());
+
+        Ok(())
+    }
+    #[test]
+    fn test_generated_box_keys_load_and_are_owner_only() -> Result<()> {
+        init()?;
+
+        let dir = tempfile::tempdir()?;
+        let prefix = dir.path().join("collector");
+        let generated = generate_key_files(&prefix, KeyType::Box)?;
+        let (private_path, public_path) = key_file_paths(&prefix);
+
+        let keypair = load_keypair(private_path.as_path())?;
+        assert_eq!(generated.public_key, keypair.public_key.as_ref());
+        assert_eq!(generated.fingerprint(), key_id(&keypair.public_key));
+        assert_eq!(load_public_key(public_path.as_path())?, keypair.public_key);
+        assert_eq!(read_key_file(public_path.as_path())?.key_type, Some(KeyType::Box));
+
+        #[cfg(unix)]
+        for path in [&private_path, &public_path] {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", path.display());
+        }
+
+        // Generating again would replace the deployed key
+        assert!(generate_key_files(&prefix, KeyType::Box).is_err());
+        assert_eq!(load_keypair(private_path.as_path())?.public_key, keypair.public_key);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_files_keep_dots_in_the_prefix() -> Result<()> {
+        init()?;
+
+        let dir = tempfile::tempdir()?;
+        let (private_path, public_path) = key_file_paths(dir.path().join("collector.v2"));
+        assert_eq!(private_path, dir.path().join("collector.v2.private"));
+        assert_eq!(public_path, dir.path().join("collector.v2.public"));
+
+        generate_key_files(dir.path().join("collector.v2"), KeyType::Signing)?;
+        generate_key_files(dir.path().join("collector.v3"), KeyType::Signing)?;
+        assert_eq!(counterpart_path(&private_path), Some(public_path.clone()));
+
+        // A leftover public file stops generation before a private file is written
+        std::fs::write(dir.path().join("collector.v4.public"), b"leftover")?;
+        assert!(generate_key_files(dir.path().join("collector.v4"), KeyType::Box).is_err());
+        assert!(!dir.path().join("collector.v4.private").exists());
+        assert_eq!(std::fs::read(dir.path().join("collector.v4.public"))?, b"leftover");
+
+        Ok(())
+    }
+    #[cfg(feature = "age")]
+    #[test]
+    fn test_age_encryption_opens_with_any_recipient() -> Result<()> {
+        use std::io::Read;
+
+        let first = age::x25519::Identity::generate();
+        let second = age::x25519::Identity::generate();
+        let recipients = parse_age_recipients(&[first.to_public().to_string(), second.to_public().to_string()])?;
+        assert!(parse_age_recipients(&["age1nope".to_string()]).is_err());
+
+        let data = b"This is a test message";
+        let encrypted = age_encrypt(data, &recipients)?;
+
+        for identity in [&first, &second] {
+            let decryptor = age::Decryptor::new(&encrypted[..])?;
+            assert!(!decryptor.is_scrypt(), "expected a recipients-encrypted file");
+            let mut decrypted = Vec::new();
+            decryptor.decrypt(std::iter::once(identity as &dyn age::Identity))?.read_to_end(&mut decrypted)?;
+            assert_eq!(decrypted, data);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_keypair_generation() {
         init().unwrap();
@@ -167,50 +620,15 @@ mod tests {
 
         Ok(())
     }
-}
-/// Decrypt data with the recipient's secret key
-pub fn decrypt(data: &[u8], sender_pk: &box_::PublicKey, recipient_sk: &box_::SecretKey) -> Result<Vec<u8>> {
-    // Split nonce and ciphertext
-    if data.len() < box_::NONCEBYTES {
-        anyhow::bail!("Data too short to contain nonce");
-    }
-
-    let nonce = box_::Nonce::from_slice(&data[..box_::NONCEBYTES])
-        .context("Invalid nonce")?;
-
-    let ciphertext = &data[box_::NONCEBYTES..];
-
-    // Decrypt the data
-    let plaintext = box_::open(ciphertext, &nonce, sender_pk, recipient_sk)
-        .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
-
-    Ok(plaintext)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn test_encrypt_decrypt() -> Result<()> {
-        // Initialize sodium
-        init()?;
-
-        // Generate keypairs
-        let sender = box_::gen_keypair();
-        let recipient = box_::gen_keypair();
-
-        // Test data
-        let data = b"This is a test message";
-
-        // Encrypt
-        let encrypted = encrypt(data, &recipient.0, &sender.1)?;
-
-        // Decrypt
-        let decrypted = decrypt(&encrypted, &sender.0, &recipient.1)?;
-
-        assert_eq!(decrypted, data);
-
-        Ok(())
+    fn test_hash_sha256_matches_published_vectors() {
+        // FIPS 180-2 test vectors
+        assert_eq!(hash_sha256(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hash_sha256("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            hash_sha256("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        );
     }
 }
@@ -1,25 +1,64 @@
-//! Database module for the MCP client
+//! Local SQLite storage
 //!
-//! This module handles local storage using SQLite for caching logs
-//! and storing action execution history.
-
-use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+//! One database file holds the collector's log cache, the MCP client's
+//! action history and the `metadata` key-value table both use for state
+//! such as read offsets. The schema version is kept in SQLite's
+//! `user_version`, and opening a file applies the [`MIGRATIONS`] it has not
+//! had yet, so older files are upgraded in place.
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::types::Type;
+use rusqlite::{params, params_from_iter, Connection, Row, Transaction, TransactionBehavior};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Schema migrations, in order
+///
+/// `MIGRATIONS[n]` brings a database from schema version `n` to `n + 1`.
+/// Released migrations must never change; a schema change adds a new one.
+const MIGRATIONS: &[fn(&Connection) -> Result<()>] = &[migrate_v1, migrate_v2];
+
+/// Current schema version
+const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Log IDs bound per `UPDATE` when marking logs exported, well under
+/// SQLite's limit on bound parameters
+const MARK_CHUNK_SIZE: usize = 500;
+
+/// How long a statement waits for another connection's write lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set up a new connection for concurrent use
+///
+/// WAL journaling lets readers proceed while another connection writes, and
+/// the busy timeout makes writers wait for each other instead of failing
+/// with "database is locked".
+fn configure(conn: &Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    Ok(())
+}
 
+/// Database connection for the LogNarrator client
 pub struct Database {
     conn: Connection,
 }
 
-/// Log entry structure
-pub struct LogEntry {
-    pub id: Option<i64>,
-    pub timestamp: i64,
+/// Log entry stored by the collector
+///
+/// Serializes to the JSON form of the log, without its row id.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredLog {
+    #[serde(skip_serializing)]
+    pub id: i64,
+    pub timestamp: String,
     pub source: String,
-    pub content: String,
-    pub encrypted: bool,
-    pub sent: bool,
+    pub level: Option<String>,
+    pub severity_number: Option<i32>,
+    pub message: String,
+    pub attributes: serde_json::Value,
 }
 
 /// Action execution record
@@ -32,124 +71,214 @@ pub struct ActionRecord {
     pub result: String,
 }
 
+/// Pool of connections to one database file
+pub type DatabasePool = r2d2::Pool<DatabaseManager>;
+
+/// Opens pooled connections to a database file
+pub struct DatabaseManager {
+    path: PathBuf,
+}
+
+/// Failure to open a pooled connection
+#[derive(Debug)]
+pub struct ConnectError(anyhow::Error);
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
+impl r2d2::ManageConnection for DatabaseManager {
+    type Connection = Database;
+    type Error = ConnectError;
+
+    fn connect(&self) -> Result<Database, ConnectError> {
+        Database::open(&self.path).map_err(ConnectError)
+    }
+
+    fn is_valid(&self, db: &mut Database) -> Result<(), ConnectError> {
+        db.conn.execute_batch("").map_err(|e| ConnectError(e.into()))
+    }
+
+    fn has_broken(&self, _db: &mut Database) -> bool {
+        false
+    }
+}
+
 impl Database {
-    /// Open or create a database connection
+    /// Open or create a database, upgrading an older schema
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
         let conn = Connection::open(path)
-            .context("Failed to open database")?;
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
+        configure(&conn).context("Failed to configure database")?;
 
         let db = Self { conn };
-        db.initialize()?;
+        db.initialize()
+            .with_context(|| format!("Failed to initialize database {}", path.display()))?;
 
         Ok(db)
     }
 
-    /// Initialize the database schema
-    fn initialize(&self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS logs (
-                id INTEGER PRIMARY KEY,
-                timestamp INTEGER NOT NULL,
-                source TEXT NOT NULL,
-                content TEXT NOT NULL,
-                encrypted BOOLEAN NOT NULL,
-                sent BOOLEAN NOT NULL
-            )",
-            [],
-        )?;
+    /// Open a pool of up to `max_size` connections to the database at `path`
+    pub fn pool<P: AsRef<Path>>(path: P, max_size: u32) -> Result<DatabasePool> {
+        let manager = DatabaseManager { path: path.as_ref().to_path_buf() };
+        Ok(r2d2::Pool::builder().max_size(max_size).build(manager)?)
+    }
 
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS actions (
-                id INTEGER PRIMARY KEY,
-                timestamp INTEGER NOT NULL,
-                action_id TEXT NOT NULL,
-                parameters TEXT NOT NULL,
-                status TEXT NOT NULL,
-                result TEXT NOT NULL
-            )",
-            [],
-        )?;
+    /// Create the schema, or upgrade it to the current version
+    fn initialize(&self) -> Result<()> {
+        // Take the write lock up front, so pooled connections opening
+        // together upgrade the schema one at a time
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+
+        let version: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Database schema v{} is newer than this client supports (v{})",
+                version,
+                SCHEMA_VERSION,
+            ));
+        }
 
-        // Create indices for better performance
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_logs_sent ON logs (sent)",
-            [],
-        )?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            tracing::debug!("Migrating database to schema v{}", index + 1);
+            migration(&tx).with_context(|| format!("Failed to migrate database to schema v{}", index + 1))?;
+        }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_actions_timestamp ON actions (timestamp)",
-            [],
-        )?;
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        tx.commit()?;
 
         Ok(())
     }
 
     /// Store a log entry
-    pub fn store_log(&self, entry: &LogEntry) -> Result<i64> {
-        let timestamp = entry.timestamp;
-
-        let id = self.conn.execute(
-            "INSERT INTO logs (timestamp, source, content, encrypted, sent)
-             VALUES (?, ?, ?, ?, ?)",
-            params![timestamp, entry.source, entry.content, entry.encrypted, entry.sent],
+    pub fn store_log(
+        &self,
+        timestamp: &str,
+        source: &str,
+        level: Option<&str>,
+        severity_number: Option<i32>,
+        message: &str,
+        attributes: &str,
+    ) -> Result<i64> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO logs (timestamp, source, level, severity_number, message, attributes)
+             VALUES (?, ?, ?, ?, ?, ?)",
         )?;
 
-        Ok(id as i64)
+        stmt.execute(params![timestamp, source, level, severity_number, message, attributes])?;
+
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Run `op` in a transaction, committed if it succeeds and rolled back otherwise
+    pub fn transaction<T>(&self, op: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        let tx = self.conn.unchecked_transaction()?;
+        let value = op(self)?;
+        tx.commit()?;
+        Ok(value)
     }
 
-    /// Get unsent logs
-    pub fn get_unsent_logs(&self, limit: usize) -> Result<Vec<LogEntry>> {
+    /// Mark log entries as exported
+    ///
+    /// IDs are bound in chunks of [`MARK_CHUNK_SIZE`], so any number of them
+    /// stays within SQLite's limits; all chunks are marked or none.
+    pub fn mark_exported(&self, ids: &[i64]) -> Result<usize> {
+        self.transaction(|db| {
+            let mut count = 0;
+            for chunk in ids.chunks(MARK_CHUNK_SIZE) {
+                let placeholders = vec!["?"; chunk.len()].join(", ");
+                let mut stmt = db.conn.prepare_cached(&format!(
+                    "UPDATE logs SET exported = 1 WHERE id IN ({})",
+                    placeholders
+                ))?;
+                count += stmt.execute(params_from_iter(chunk))?;
+            }
+            Ok(count)
+        })
+    }
+
+    /// Get unexported log entries, oldest first
+    pub fn get_unexported_logs(&self, limit: usize) -> Result<Vec<StoredLog>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, timestamp, source, content, encrypted, sent
+            "SELECT id, timestamp, source, level, severity_number, message, attributes
              FROM logs
-             WHERE sent = 0
-             ORDER BY timestamp
+             WHERE exported = 0
+             ORDER BY id
              LIMIT ?",
         )?;
 
-        let log_iter = stmt.query_map([limit as i64], |row| {
-            Ok(LogEntry {
-                id: Some(row.get(0)?),
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            let attributes = match row.get::<_, Option<String>>(6)? {
+                Some(attributes) => serde_json::from_str(&attributes)
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, Type::Text, Box::new(e)))?,
+                None => serde_json::Value::Object(Default::default()),
+            };
+
+            Ok(StoredLog {
+                id: row.get(0)?,
                 timestamp: row.get(1)?,
                 source: row.get(2)?,
-                content: row.get(3)?,
-                encrypted: row.get(4)?,
-                sent: row.get(5)?,
+                level: row.get(3)?,
+                severity_number: row.get(4)?,
+                message: row.get(5)?,
+                attributes,
             })
         })?;
 
-        let logs: Result<Vec<_>, _> = log_iter.collect();
+        let logs: Result<Vec<_>, _> = rows.collect();
         Ok(logs?)
     }
 
-    /// Mark logs as sent
-    pub fn mark_logs_sent(&self, ids: &[i64]) -> Result<()> {
-        let id_list = ids.iter()
-            .map(|id| id.to_string())
-            .collect::<Vec<_>>()
-            .join(",");
-
-        if !id_list.is_empty() {
-            let query = format!(
-                "UPDATE logs SET sent = 1 WHERE id IN ({})",
-                id_list
-            );
+    /// Set a metadata value
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES (?, ?)",
+            params![key, value],
+        )?;
+
+        Ok(())
+    }
 
-            self.conn.execute(&query, [])?;
+    /// Get a metadata value
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT value FROM metadata WHERE key = ?",
+        )?;
+
+        let mut rows = stmt.query(params![key])?;
+
+        if let Some(row) = rows.next()? {
+            let value: String = row.get(0)?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
         }
+    }
 
-        Ok(())
+    /// Delete old log entries
+    pub fn delete_old_logs(&self, days_to_keep: u32) -> Result<usize> {
+        let sql = format!(
+            "DELETE FROM logs WHERE datetime(timestamp) < datetime('now', '-{} days')",
+            days_to_keep
+        );
+
+        let count = self.conn.execute(&sql, [])?;
+
+        Ok(count)
     }
 
     /// Record an action execution
     pub fn record_action(&self, record: &ActionRecord) -> Result<i64> {
-        let timestamp = record.timestamp;
-
-        let id = self.conn.execute(
+        self.conn.execute(
             "INSERT INTO actions (timestamp, action_id, parameters, status, result)
              VALUES (?, ?, ?, ?, ?)",
             params![
-                timestamp,
+                record.timestamp,
                 record.action_id,
                 record.parameters,
                 record.status,
@@ -157,7 +286,7 @@ impl Database {
             ],
         )?;
 
-        Ok(id as i64)
+        Ok(self.conn.last_insert_rowid())
     }
 
     /// Get recent action executions
@@ -169,40 +298,163 @@ impl Database {
              LIMIT ?",
         )?;
 
-        let action_iter = stmt.query_map([limit as i64], |row| {
-            Ok(ActionRecord {
-                id: Some(row.get(0)?),
-                timestamp: row.get(1)?,
-                action_id: row.get(2)?,
-                parameters: row.get(3)?,
-                status: row.get(4)?,
-                result: row.get(5)?,
-            })
-        })?;
+        let action_iter = stmt.query_map([limit as i64], action_from_row)?;
 
         let actions: Result<Vec<_>, _> = action_iter.collect();
         Ok(actions?)
     }
 
-    /// Clean up old logs
-    pub fn cleanup_old_logs(&self, max_age_days: u64) -> Result<usize> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
-        let max_age_secs = max_age_days * 24 * 60 * 60;
-        let cutoff = now - (max_age_secs as i64);
+    /// Query action executions, newest first
+    ///
+    /// Each filter that is given narrows the result: `action_id` and `status`
+    /// must match exactly, and the timestamp must fall in `[from, to)`.
+    pub fn query_actions(
+        &self,
+        action_id: Option<&str>,
+        status: Option<&str>,
+        from: Option<i64>,
+        to: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ActionRecord>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(action_id) = action_id {
+            conditions.push("action_id = ?");
+            values.push(action_id.to_string().into());
+        }
+        if let Some(status) = status {
+            conditions.push("status = ?");
+            values.push(status.to_string().into());
+        }
+        if let Some(from) = from {
+            conditions.push("timestamp >= ?");
+            values.push(from.into());
+        }
+        if let Some(to) = to {
+            conditions.push("timestamp < ?");
+            values.push(to.into());
+        }
+        values.push((limit as i64).into());
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let query = format!(
+            "SELECT id, timestamp, action_id, parameters, status, result
+             FROM actions
+             {}
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?",
+            where_clause
+        );
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let action_iter = stmt.query_map(params_from_iter(values), action_from_row)?;
 
-        let rows = self.conn.execute(
-            "DELETE FROM logs WHERE timestamp < ? AND sent = 1",
-            params![cutoff],
-        )?;
+        let actions: Result<Vec<_>, _> = action_iter.collect();
+        Ok(actions?)
+    }
+}
 
-        Ok(rows)
+/// Schema v1: logs, action history and metadata
+///
+/// Files from before schema versioning already have some of these tables.
+/// The MCP client stored logs as `content` with integer timestamps and
+/// `sent` flags; those rows are copied into the v1 layout. The collector's
+/// logs table only lacks the `encrypted` column.
+fn migrate_v1(conn: &Connection) -> Result<()> {
+    let columns = conn.prepare("SELECT name FROM pragma_table_info('logs')")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let has = |column: &str| columns.iter().any(|name| name == column);
+
+    if has("content") {
+        tracing::info!("Migrating MCP client logs table to schema v1");
+        conn.execute_batch(
+            "ALTER TABLE logs RENAME TO logs_unversioned;
+             DROP INDEX IF EXISTS idx_logs_sent;
+             CREATE TABLE logs (
+                 id INTEGER PRIMARY KEY,
+                 timestamp TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 level TEXT,
+                 message TEXT NOT NULL,
+                 attributes TEXT,
+                 encrypted INTEGER NOT NULL DEFAULT 0,
+                 exported INTEGER NOT NULL DEFAULT 0
+             );
+             INSERT INTO logs (id, timestamp, source, message, attributes, encrypted, exported)
+                 SELECT id, strftime('%Y-%m-%dT%H:%M:%SZ', timestamp, 'unixepoch'), source, content, '{}', encrypted, sent
+                 FROM logs_unversioned;
+             DROP TABLE logs_unversioned;",
+        )?;
+    } else if has("message") && !has("encrypted") {
+        tracing::info!("Migrating collector logs table to schema v1");
+        conn.execute_batch("ALTER TABLE logs ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0")?;
     }
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS logs (
+            id INTEGER PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            source TEXT NOT NULL,
+            level TEXT,
+            message TEXT NOT NULL,
+            attributes TEXT,
+            encrypted INTEGER NOT NULL DEFAULT 0,
+            exported INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_logs_exported ON logs (exported);
+
+        CREATE TABLE IF NOT EXISTS actions (
+            id INTEGER PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            action_id TEXT NOT NULL,
+            parameters TEXT NOT NULL,
+            status TEXT NOT NULL,
+            result TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_actions_timestamp ON actions (timestamp);
+
+        -- Serve history queries filtered by action or status, newest first
+        CREATE INDEX IF NOT EXISTS idx_actions_action_id ON actions (action_id, timestamp);
+        CREATE INDEX IF NOT EXISTS idx_actions_status ON actions (status, timestamp);
+
+        CREATE TABLE IF NOT EXISTS metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Schema v2: keep the numeric severity of logs alongside their level
+fn migrate_v2(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE logs ADD COLUMN severity_number INTEGER")?;
+    Ok(())
+}
+
+/// Build an action record from a row of `id, timestamp, action_id, parameters, status, result`
+fn action_from_row(row: &Row) -> rusqlite::Result<ActionRecord> {
+    Ok(ActionRecord {
+        id: Some(row.get(0)?),
+        timestamp: row.get(1)?,
+        action_id: row.get(2)?,
+        parameters: row.get(3)?,
+        status: row.get(4)?,
+        result: row.get(5)?,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::SystemTime;
     use tempfile::tempdir;
 
     #[test]
@@ -210,38 +462,42 @@ mod tests {
         let dir = tempdir()?;
         let db_path = dir.path().join("test.db");
 
-        let db = Database::open(db_path)?;
-
-        // Test storing logs
-        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let db = Database::open(&db_path)?;
 
-        let log = LogEntry {
-            id: None,
-            timestamp,
-            source: "test".to_string(),
-            content: "test log".to_string(),
-            encrypted: false,
-            sent: false,
-        };
+        // Test storing a log entry
+        let id = db.store_log(
+            "2023-01-01T12:00:00Z",
+            "test-source",
+            Some("INFO"),
+            Some(9),
+            "Test message",
+            "{\"attr1\":\"value1\"}",
+        )?;
 
-        let id = db.store_log(&log)?;
         assert!(id > 0);
 
-        // Test retrieving unsent logs
-        let unsent = db.get_unsent_logs(10)?;
-        assert_eq!(unsent.len(), 1);
-        assert_eq!(unsent[0].content, "test log");
+        // Test getting unexported logs
+        let logs = db.get_unexported_logs(10)?;
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].id, id);
+
+        // Test marking logs as exported
+        let count = db.mark_exported(&[id])?;
+        assert_eq!(count, 1);
 
-        // Test marking logs as sent
-        db.mark_logs_sent(&[id])?;
+        // Test that the log is no longer unexported
+        let logs = db.get_unexported_logs(10)?;
+        assert_eq!(logs.len(), 0);
 
-        let unsent_after = db.get_unsent_logs(10)?;
-        assert_eq!(unsent_after.len(), 0);
+        // Test metadata operations
+        db.set_metadata("test-key", "test-value")?;
+        let value = db.get_metadata("test-key")?;
+        assert_eq!(value, Some("test-value".to_string()));
 
         // Test action recording
         let action = ActionRecord {
             id: None,
-            timestamp,
+            timestamp: 1_672_574_400,
             action_id: "test.action".to_string(),
             parameters: "{\"param\": \"value\"}".to_string(),
             status: "success".to_string(),
@@ -254,217 +510,306 @@ mod tests {
         // Test retrieving recent actions
         let recent = db.get_recent_actions(10)?;
         assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].id, Some(action_id));
         assert_eq!(recent[0].action_id, "test.action");
-//! Database utilities for the LogNarrator client
-
-use anyhow::Result;
-use rusqlite::{Connection, params};
-use std::path::Path;
 
-/// Database connection for the LogNarrator client
-pub struct Database {
-    conn: Connection,
-}
-
-impl Database {
-    /// Open a database connection
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let conn = Connection::open(path)?;
-        let db = Self { conn };
-        db.initialize()?;
-        Ok(db)
+        Ok(())
     }
 
-    /// Initialize the database schema
-    fn initialize(&self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS logs (
-                id INTEGER PRIMARY KEY,
-                timestamp TEXT NOT NULL,
-                source TEXT NOT NULL,
-                level TEXT,
-                message TEXT NOT NULL,
-                attributes TEXT,
-                exported INTEGER DEFAULT 0
-            )",
-            [],
-        )?;
+    #[test]
+    fn test_unexported_logs_serialize_to_valid_json() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::open(dir.path().join("test.db"))?;
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_logs_exported ON logs(exported)",
-            [],
+        let message = "query \"SELECT 1\" failed\n\tat C:\\app\\db.rs";
+        db.store_log(
+            "2023-01-01T12:00:00Z",
+            "app \"primary\"",
+            Some("ERR\"OR"),
+            None,
+            message,
+            r#"{"path":"C:\\logs","note":"say \"hi\""}"#,
         )?;
 
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS metadata (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
+        let logs = db.get_unexported_logs(10)?;
+        assert_eq!(logs[0].message, message);
+        assert_eq!(logs[0].attributes["note"], "say \"hi\"");
+
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&logs[0])?)?;
+        assert_eq!(json["message"], message);
+        assert_eq!(json["source"], "app \"primary\"");
+        assert_eq!(json["level"], "ERR\"OR");
+        assert_eq!(json["attributes"]["path"], "C:\\logs");
+        assert!(json.get("id").is_none());
 
         Ok(())
     }
 
-    /// Store a log entry
-    pub fn store_log(
-        &self,
-        timestamp: &str,
-        source: &str,
-        level: Option<&str>,
-        message: &str,
-        attributes: &str,
-    ) -> Result<i64> {
-        let mut stmt = self.conn.prepare(
-            "INSERT INTO logs (timestamp, source, level, message, attributes)
-             VALUES (?, ?, ?, ?, ?)",
-        )?;
+    #[test]
+    fn test_mark_exported_in_chunks() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::open(dir.path().join("test.db"))?;
+
+        let ids = db.transaction(|db| {
+            (0..2000)
+                .map(|i| db.store_log("2023-01-01T12:00:00Z", "test-source", None, None, &format!("log {}", i), "{}"))
+                .collect::<Result<Vec<_>>>()
+        })?;
 
-        stmt.execute(params![timestamp, source, level, message, attributes])?;
+        assert_eq!(db.mark_exported(&ids)?, 2000);
+        assert!(db.get_unexported_logs(10)?.is_empty());
+        assert_eq!(db.mark_exported(&[])?, 0);
 
-        Ok(self.conn.last_insert_rowid())
+        Ok(())
     }
 
-    /// Mark log entries as exported
-    pub fn mark_exported(&self, ids: &[i64]) -> Result<usize> {
-        let id_list = ids
-            .iter()
-            .map(|id| id.to_string())
-            .collect::<Vec<String>>()
-            .join(", ");
+    #[test]
+    fn test_failed_transaction_stores_nothing() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::open(dir.path().join("test.db"))?;
+        db.conn.execute_batch(
+            "CREATE TRIGGER reject_poison BEFORE INSERT ON logs WHEN NEW.message = 'poison'
+             BEGIN SELECT RAISE(ABORT, 'constraint failed: poison'); END",
+        )?;
 
-        let sql = format!(
-            "UPDATE logs SET exported = 1 WHERE id IN ({})",
-            id_list
-        );
+        let result = db.transaction(|db| {
+            for message in ["first", "second", "poison", "fourth"] {
+                db.store_log("2023-01-01T12:00:00Z", "test-source", Some("INFO"), None, message, "{}")?;
+            }
+            Ok(())
+        });
+        assert!(result.unwrap_err().to_string().contains("constraint failed: poison"));
+        assert!(db.get_unexported_logs(10)?.is_empty());
 
-        let count = self.conn.execute(&sql, [])?;
+        // The connection is usable again once the batch rolled back
+        db.transaction(|db| db.store_log("2023-01-01T12:00:00Z", "test-source", None, None, "retried", "{}"))?;
+        assert_eq!(db.get_unexported_logs(10)?.len(), 1);
 
-        Ok(count)
+        Ok(())
     }
 
-    /// Get unexported log entries
-    pub fn get_unexported_logs(&self, limit: usize) -> Result<Vec<(i64, String)>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, timestamp, source, level, message, attributes
-             FROM logs
-             WHERE exported = 0
-             ORDER BY id
-             LIMIT ?",
-        )?;
+    #[test]
+    fn test_pooled_batch_inserts_alongside_reads() -> Result<()> {
+        let dir = tempdir()?;
+        let pool = Database::pool(dir.path().join("test.db"), 4)?;
+
+        // Four writers insert 10k logs in batches while a reader polls
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let pool = pool.clone();
+                std::thread::spawn(move || -> Result<()> {
+                    for batch in 0..25 {
+                        pool.get()?.transaction(|db| {
+                            for i in 0..100 {
+                                let message = format!("writer {} batch {} log {}", writer, batch, i);
+                                db.store_log("2023-01-01T12:00:00Z", "bench", Some("INFO"), Some(9), &message, "{}")?;
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        let reader = {
+            let pool = pool.clone();
+            std::thread::spawn(move || -> Result<()> {
+                for _ in 0..50 {
+                    pool.get()?.get_unexported_logs(100)?;
+                }
+                Ok(())
+            })
+        };
 
-        let rows = stmt.query_map(params![limit as i64], |row| {
-            let id: i64 = row.get(0)?;
-            let timestamp: String = row.get(1)?;
-            let source: String = row.get(2)?;
-            let level: Option<String> = row.get(3)?;
-            let message: String = row.get(4)?;
-            let attributes: String = row.get(5)?;
-
-            // Construct a JSON representation of the log entry
-            let log_json = format!(
-                "{{\"timestamp\":\"{}\",\"source\":\"{}\",\"level\":{},\"message\":\"{}\",\"attributes\":{}}}",
-                timestamp,
-                source,
-                level.map_or("null".to_string(), |l| format!("\"{}\"", l)),
-                message.replace('"', "\\\""),
-                attributes
-            );
+        for writer in writers {
+            writer.join().unwrap()?;
+        }
+        reader.join().unwrap()?;
 
-            Ok((id, log_json))
-        })?;
+        assert_eq!(pool.get()?.get_unexported_logs(20_000)?.len(), 10_000);
+
+        Ok(())
+    }
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
+    /// A database holding a week of `restart.service` and `clear.cache` runs
+    fn action_history() -> Result<(tempfile::TempDir, Database)> {
+        let dir = tempdir()?;
+        let db = Database::open(dir.path().join("actions.db"))?;
+
+        let runs = [
+            (100, "restart.service", "failed"),
+            (200, "restart.service", "success"),
+            (300, "clear.cache", "failed"),
+            (400, "restart.service", "failed"),
+            (500, "clear.cache", "success"),
+            (600, "restart.service", "failed"),
+        ];
+        for (timestamp, action_id, status) in runs {
+            db.record_action(&ActionRecord {
+                id: None,
+                timestamp,
+                action_id: action_id.to_string(),
+                parameters: "{}".to_string(),
+                status: status.to_string(),
+                result: String::new(),
+            })?;
         }
 
-        Ok(result)
+        Ok((dir, db))
     }
 
-    /// Set a metadata value
-    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO metadata (key, value) VALUES (?, ?)",
-            params![key, value],
-        )?;
-
-        Ok(())
+    fn timestamps(actions: &[ActionRecord]) -> Vec<i64> {
+        actions.iter().map(|action| action.timestamp).collect()
     }
 
-    /// Get a metadata value
-    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT value FROM metadata WHERE key = ?",
-        )?;
+    #[test]
+    fn test_query_actions_by_each_filter() -> Result<()> {
+        let (_dir, db) = action_history()?;
 
-        let mut rows = stmt.query(params![key])?;
+        assert_eq!(timestamps(&db.query_actions(None, None, None, None, 10)?), vec![600, 500, 400, 300, 200, 100]);
+        assert_eq!(timestamps(&db.query_actions(Some("clear.cache"), None, None, None, 10)?), vec![500, 300]);
+        assert_eq!(timestamps(&db.query_actions(None, Some("success"), None, None, 10)?), vec![500, 200]);
+        assert_eq!(timestamps(&db.query_actions(None, None, Some(400), None, 10)?), vec![600, 500, 400]);
+        assert_eq!(timestamps(&db.query_actions(None, None, None, Some(300), 10)?), vec![200, 100]);
+        assert_eq!(timestamps(&db.query_actions(None, None, None, None, 2)?), vec![600, 500]);
+        assert!(db.query_actions(Some("reboot.host"), None, None, None, 10)?.is_empty());
 
-        if let Some(row) = rows.next()? {
-            let value: String = row.get(0)?;
-            Ok(Some(value))
-        } else {
-            Ok(None)
-        }
+        Ok(())
     }
 
-    /// Delete old log entries
-    pub fn delete_old_logs(&self, days_to_keep: u32) -> Result<usize> {
-        let sql = format!(
-            "DELETE FROM logs WHERE datetime(timestamp) < datetime('now', '-{} days')",
-            days_to_keep
-        );
+    #[test]
+    fn test_query_actions_combines_filters() -> Result<()> {
+        let (_dir, db) = action_history()?;
 
-        let count = self.conn.execute(&sql, [])?;
+        // Failed restarts in [200, 600)
+        let failed = db.query_actions(Some("restart.service"), Some("failed"), Some(200), Some(600), 10)?;
+        assert_eq!(timestamps(&failed), vec![400]);
+        assert!(failed.iter().all(|action| action.action_id == "restart.service" && action.status == "failed"));
 
-        Ok(count)
-    }
-}
+        let failed = db.query_actions(Some("restart.service"), Some("failed"), Some(100), None, 2)?;
+        assert_eq!(timestamps(&failed), vec![600, 400]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+        Ok(())
+    }
 
     #[test]
-    fn test_database_operations() -> Result<()> {
+    fn test_migrates_mcp_client_database() -> Result<()> {
         let dir = tempdir()?;
-        let db_path = dir.path().join("test.db");
-
-        let db = Database::open(&db_path)?;
-
-        // Test storing a log entry
-        let id = db.store_log(
-            "2023-01-01T12:00:00Z",
-            "test-source",
-            Some("INFO"),
-            "Test message",
-            "{\"attr1\":\"value1\"}",
+        let path = dir.path().join("mcp.db");
+        Connection::open(&path)?.execute_batch(
+            "CREATE TABLE logs (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                content TEXT NOT NULL,
+                encrypted BOOLEAN NOT NULL,
+                sent BOOLEAN NOT NULL
+            );
+            CREATE INDEX idx_logs_sent ON logs (sent);
+            CREATE TABLE actions (
+                id INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                action_id TEXT NOT NULL,
+                parameters TEXT NOT NULL,
+                status TEXT NOT NULL,
+                result TEXT NOT NULL
+            );
+            INSERT INTO logs VALUES (1, 1672574400, 'app', 'disk full', 0, 0);
+            INSERT INTO logs VALUES (2, 1672574401, 'app', 'sent already', 0, 1);
+            INSERT INTO actions VALUES (1, 1672574402, 'disk.cleanup', '{}', 'success', 'OK');",
         )?;
 
-        assert!(id > 0);
-
-        // Test getting unexported logs
+        let db = Database::open(&path)?;
         let logs = db.get_unexported_logs(10)?;
         assert_eq!(logs.len(), 1);
-        assert_eq!(logs[0].0, id);
+        assert_eq!(logs[0].id, 1);
+        assert_eq!(logs[0].timestamp, "2023-01-01T12:00:00Z");
+        assert_eq!(logs[0].message, "disk full");
+        assert_eq!(db.get_recent_actions(10)?[0].action_id, "disk.cleanup");
+
+        // The collector's metadata table is added, and reopening changes nothing
+        db.set_metadata("offset", "42")?;
+        drop(db);
+        let db = Database::open(&path)?;
+        assert_eq!(db.get_metadata("offset")?, Some("42".to_string()));
+        assert_eq!(db.get_unexported_logs(10)?.len(), 1);
 
-        // Test marking logs as exported
-        let count = db.mark_exported(&[id])?;
-        assert_eq!(count, 1);
+        Ok(())
+    }
 
-        // Test that the log is no longer unexported
-        let logs = db.get_unexported_logs(10)?;
-        assert_eq!(logs.len(), 0);
+    #[test]
+    fn test_migrates_collector_database() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("logs.db");
+        Connection::open(&path)?.execute_batch(
+            "CREATE TABLE logs (
+                id INTEGER PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                source TEXT NOT NULL,
+                level TEXT,
+                message TEXT NOT NULL,
+                attributes TEXT,
+                exported INTEGER DEFAULT 0
+            );
+            CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO logs (timestamp, source, level, message, attributes)
+                VALUES ('2023-01-01T12:00:00Z', 'app', 'INFO', 'started', '{}');
+            INSERT INTO metadata VALUES ('offset:/var/log/app.log', '1024');",
+        )?;
 
-        // Test metadata operations
-        db.set_metadata("test-key", "test-value")?;
-        let value = db.get_metadata("test-key")?;
-        assert_eq!(value, Some("test-value".to_string()));
+        let db = Database::open(&path)?;
+        assert_eq!(db.get_unexported_logs(10)?.len(), 1);
+        assert_eq!(db.get_metadata("offset:/var/log/app.log")?, Some("1024".to_string()));
+        // The MCP client's action history is added
+        assert!(db.get_recent_actions(10)?.is_empty());
 
         Ok(())
     }
-}
+
+    #[test]
+    fn test_upgrades_v1_database() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("v1.db");
+        let conn = Connection::open(&path)?;
+        migrate_v1(&conn)?;
+        conn.pragma_update(None, "user_version", 1)?;
+        conn.execute_batch(
+            "INSERT INTO logs (timestamp, source, level, message, attributes)
+                 VALUES ('2023-01-01T12:00:00Z', 'app', 'WARN', 'disk 90% full', '{}');
+             INSERT INTO metadata VALUES ('offset:/var/log/app.log', '2048');",
+        )?;
+        drop(conn);
+
+        let db = Database::open(&path)?;
+        let version: i64 = db.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(version, SCHEMA_VERSION);
+        let columns: Vec<String> = db.conn.prepare("SELECT name FROM pragma_table_info('logs')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        assert!(columns.contains(&"severity_number".to_string()));
+
+        // Old rows are kept, and new ones use the v2 column
+        db.store_log("2023-01-01T12:00:01Z", "app", Some("ERROR"), Some(17), "disk full", "{}")?;
+        let logs = db.get_unexported_logs(10)?;
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].level.as_deref(), Some("WARN"));
+        assert_eq!(logs[0].severity_number, None);
+        assert_eq!(logs[0].message, "disk 90% full");
+        assert_eq!(logs[1].severity_number, Some(17));
+        assert_eq!(db.get_metadata("offset:/var/log/app.log")?, Some("2048".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("future.db");
+        Connection::open(&path)?.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+
+        let error = Database::open(&path).err().unwrap();
+        assert!(format!("{:#}", error).contains("is newer than this client supports"));
+
         Ok(())
     }
 }
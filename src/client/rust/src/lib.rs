@@ -0,0 +1,14 @@
+//! LogNarrator client library
+//!
+//! The modules behind the `mcp_client` and `log_collector` binaries. The
+//! collector and the MCP client share the key handling in [`crypto`] and the
+//! local store in [`db`].
+
+pub mod actions;
+pub mod approval;
+pub mod collector;
+pub mod config;
+pub mod crypto;
+pub mod db;
+pub mod mcp;
+pub mod permissions;
@@ -5,11 +5,8 @@
 
 use anyhow::{Context, Result};
 use clap::Parser;
-
-mod config;
-mod crypto;
-mod db;
-mod mcp;
+use lognarrator_client::{config, mcp};
+use tracing_subscriber::fmt::format::FmtSpan;
 
 /// Command-line arguments for the MCP client
 #[derive(Parser, Debug)]
@@ -51,14 +48,18 @@ async fn main() -> Result<()> {
 /// Initialize the logging system based on verbosity level
 fn init_logging(verbose: bool) -> Result<()> {
     let filter = if verbose {
-        "debug"
+        "debug".to_string()
     } else {
         std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string())
     };
 
+    // In verbose mode, report each span's busy/idle time as it closes
+    let span_events = if verbose { FmtSpan::CLOSE } else { FmtSpan::NONE };
+
     tracing_subscriber::fmt()
         .with_env_filter(filter)
         .with_target(true)
+        .with_span_events(span_events)
         .init();
 
     Ok(())
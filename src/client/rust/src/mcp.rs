@@ -3,14 +3,36 @@
 //! This module implements the Multi-Command Protocol client for executing
 //! authorized actions on target systems based on LogNarrator analysis.
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sodium_oxide::crypto::sign;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::future::Future;
 use tokio::time::{self, Duration};
 
+use crate::actions::{ActionDefinition, ActionRegistry};
+use crate::approval::{self, Approval, ApprovalChannel, ApprovalRequest};
 use crate::config::McpConfig;
+use crate::crypto;
 use crate::db::{ActionRecord, Database};
+use crate::permissions::{Decision, PermissionEngine};
+
+/// How long the server may hold a command poll open before answering with no commands
+const LONG_POLL_WAIT: Duration = Duration::from_secs(30);
+
+/// First delay before polling again after a failed poll; doubled on each failure
+const POLL_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between polls while the server keeps failing
+const POLL_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Most output kept from each stream of an action's command
+const COMMAND_OUTPUT_LIMIT: usize = 64 * 1024;
 
 /// Action permission level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -79,279 +101,589 @@ pub enum ActionStatus {
     NotPermitted,
     /// Action was not found
     NotFound,
+    /// Action was held until an operator confirms it
+    AwaitingConfirmation,
+    /// Action parameters did not match its definition
+    InvalidParameters,
+    /// An operator approved the action; recorded before it runs
+    Approved,
+    /// No operator decided on the action within the execution timeout
+    ApprovalTimeout,
 }
 
-/// MCP client state
-pub struct McpClient {
-    config: McpConfig,
-    db: Database,
+impl ActionStatus {
+    /// Status as recorded in the `actions` table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionStatus::Success => "success",
+            ActionStatus::Failure => "failed",
+            ActionStatus::Timeout => "timeout",
+            ActionStatus::NotPermitted => "denied",
+            ActionStatus::NotFound => "not_found",
+            ActionStatus::AwaitingConfirmation => "awaiting_confirmation",
+            ActionStatus::InvalidParameters => "invalid_parameters",
+            ActionStatus::Approved => "approved",
+            ActionStatus::ApprovalTimeout => "timed_out",
+        }
+    }
 }
 
-impl McpClient {
-    /// Create a new MCP client
-    pub fn new(config: McpConfig, db: Database) -> Self {
-        Self { config, db }
-    }
+/// Runs one kind of action on this system
+///
+/// Executors are registered on [`McpClient`] under the action identifier
+/// they handle. Returning an error records the action as failed.
+#[async_trait]
+pub trait ActionExecutor: Send + Sync {
+    /// Execute an action that passed the local permission check
+    ///
+    /// `params` have been validated against `action`, with defaults filled in.
+    async fn execute(&self, action: &ActionDefinition, params: &HashMap<String, String>) -> Result<ActionResult>;
+}
 
-    /// Process an MCP message from the server
-    pub async fn process_message(&self, message: McpMessage) -> Result<Vec<ActionResult>> {
-        tracing::info!("Processing MCP message: {}", message.id);
+/// Runs an action's command, as rendered from its definition
+///
+/// The program is started directly with the rendered arguments, never through
+/// a shell. A zero exit status is a success and any other a failure; both
+/// carry the exit code and the captured stdout and stderr, each cut to
+/// [`COMMAND_OUTPUT_LIMIT`] bytes. The process is killed if the action times out.
+pub struct CommandExecutor;
 
-        let mut results = Vec::new();
+#[async_trait]
+impl ActionExecutor for CommandExecutor {
+    async fn execute(&self, action: &ActionDefinition, params: &HashMap<String, String>) -> Result<ActionResult> {
+        let argv = action.render(params);
+        let (program, args) = argv.split_first().ok_or_else(|| anyhow!("Action {} has an empty command", action.id))?;
 
-        // Process each recommended action
-        for recommendation in message.actions {
-            let action_id = recommendation.action_id.clone();
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", program))?;
 
-            tracing::debug!("Processing action recommendation: {}", action_id);
+        let (status, message) = if output.status.success() {
+            (ActionStatus::Success, format!("{} exited successfully", program))
+        } else {
+            (ActionStatus::Failure, format!("{} exited with {}", program, output.status))
+        };
 
-            // Check if the action is permitted
-            let permitted = self.check_permission(&recommendation)
-                .context(format!("Failed to check permission for action {}", action_id))?;
+        Ok(ActionResult {
+            action_id: action.id.clone(),
+            status,
+            message,
+            data: Some(serde_json::json!({
+                "exit_code": output.status.code(),
+                "stdout": truncated_output(&output.stdout),
+                "stderr": truncated_output(&output.stderr),
+            })),
+        })
+    }
+}
 
-            if !permitted {
-                tracing::warn!("Action not permitted: {}", action_id);
+/// Command output as text, cut to [`COMMAND_OUTPUT_LIMIT`] bytes
+fn truncated_output(output: &[u8]) -> String {
+    String::from_utf8_lossy(&output[..output.len().min(COMMAND_OUTPUT_LIMIT)]).into_owned()
+}
 
-                results.push(ActionResult {
-                    action_id: action_id.clone(),
-                    status: ActionStatus::NotPermitted,
-                    message: "Action not permitted by local policy".to_string(),
-                    data: None,
-                });
+/// Source of action commands, and sink for their results
+#[async_trait]
+pub trait CommandTransport: Send + Sync {
+    /// Wait for the next commands; an empty list means none arrived in time
+    async fn poll(&self) -> Result<Vec<McpMessage>>;
 
-                continue;
-            }
+    /// Report the results of the actions of a message
+    async fn report(&self, message_id: &str, results: &[ActionResult]) -> Result<()>;
+}
 
-            // Execute the action
-            let result = self.execute_action(&recommendation).await
-                .context(format!("Failed to execute action {}", action_id))?;
+/// Long-poll HTTP transport to the LogNarrator server
+///
+/// Each request carries the client ID, a timestamp and a signature made with
+/// the client's signing key, so the server can authenticate the client:
+/// `X-Client-Id`, `X-Timestamp` and `X-Signature`. The signature is the base64
+/// signed message of the client ID, the timestamp, the method, the path with
+/// its query and the hex SHA-256 of the body, joined by newlines, so captured
+/// headers cannot be replayed on another request.
+pub struct HttpTransport {
+    api_url: String,
+    client_id: String,
+    signing_key: sign::SecretKey,
+    http_client: reqwest::Client,
+    timeout: Duration,
+}
 
-            results.push(result);
-//! Management Control Plane (MCP) client implementation
+/// Commands returned by a poll
+#[derive(Debug, Deserialize)]
+struct PollResponse {
+    messages: Vec<McpMessage>,
+}
 
-use anyhow::{anyhow, Result};
-use reqwest::{Client, StatusCode};
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use std::time::Duration;
+impl HttpTransport {
+    /// Create a transport from the server and security configuration
+    pub fn new(config: &McpConfig) -> Result<Self> {
+        crypto::init()?;
+        let signing_key = crypto::read_secret_key(&config.security.private_key_path)
+            .with_context(|| format!("Failed to read signing key {}", config.security.private_key_path))?;
 
-use crate::config::McpConfig;
-use crate::crypto;
+        let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(!config.security.verify_certs);
+        if let Some(ca_cert_path) = &config.security.ca_cert_path {
+            let pem = std::fs::read(ca_cert_path)
+                .with_context(|| format!("Failed to read CA certificate {}", ca_cert_path))?;
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
 
-/// Client for interacting with the LogNarrator Management Control Plane
-pub struct McpClient {
-    config: McpConfig,
-    http_client: Client,
-}
+        Ok(Self {
+            api_url: config.server.api_url.trim_end_matches('/').to_string(),
+            client_id: config.server.client_id.clone(),
+            signing_key,
+            http_client: builder.build()?,
+            timeout: Duration::from_secs(config.server.timeout_seconds),
+        })
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HeartbeatRequest {
-    client_id: String,
-    timestamp: String,
-    status: String,
-    metrics: serde_json::Value,
-    signature: String,
+    /// Add the authentication headers to a request and send it
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut request = request.build()?;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let url = request.url();
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+        let message = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            self.client_id, timestamp, request.method(), path, hex::encode(Sha256::digest(body)),
+        );
+        let signed = crypto::sign(message.as_bytes(), &self.signing_key);
+
+        let headers = request.headers_mut();
+        headers.insert("X-Client-Id", self.client_id.parse()?);
+        headers.insert("X-Timestamp", timestamp.parse()?);
+        headers.insert("X-Signature", BASE64.encode(signed).parse()?);
+
+        Ok(self.http_client.execute(request).await?)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct HeartbeatResponse {
-    status: String,
-    actions: Vec<RemoteAction>,
+#[async_trait]
+impl CommandTransport for HttpTransport {
+    async fn poll(&self) -> Result<Vec<McpMessage>> {
+        let request = self.http_client
+            .get(format!("{}/v1/commands", self.api_url))
+            .query(&[("wait", LONG_POLL_WAIT.as_secs())])
+            .timeout(LONG_POLL_WAIT + self.timeout);
+        let response = self.send(request).await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<PollResponse>().await?.messages),
+            StatusCode::NO_CONTENT => Ok(Vec::new()),
+            status => Err(anyhow!("Command poll failed with status {}", status)),
+        }
+    }
+
+    async fn report(&self, message_id: &str, results: &[ActionResult]) -> Result<()> {
+        let request = self.http_client
+            .post(format!("{}/v1/commands/{}/results", self.api_url, message_id))
+            .json(&serde_json::json!({ "results": results }))
+            .timeout(self.timeout);
+        let response = self.send(request).await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(anyhow!("Reporting results of {} failed with status {}", message_id, response.status()))
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct RemoteAction {
-    id: String,
-    action_type: String,
-    parameters: serde_json::Value,
-    priority: String,
-    requires_confirmation: bool,
+/// MCP client state
+pub struct McpClient {
+    config: McpConfig,
+    db: Database,
+    permissions: PermissionEngine,
+    actions: ActionRegistry,
+    executors: HashMap<String, Box<dyn ActionExecutor>>,
+    approval: Option<Box<dyn ApprovalChannel>>,
 }
 
 impl McpClient {
-    /// Create a new MCP client
-    pub async fn new(config: McpConfig) -> Result<Self> {
-        // Initialize crypto subsystem
-        crypto::init()?;
+    /// Create a new MCP client authorizing actions with `permissions` and
+    /// running those defined in `actions`
+    pub fn new(config: McpConfig, db: Database, permissions: PermissionEngine, actions: ActionRegistry) -> Self {
+        Self { config, db, permissions, actions, executors: HashMap::new(), approval: None }
+    }
 
-        // Build HTTP client with appropriate timeouts
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(config.server.timeout_seconds))
-            .build()?;
+    /// Handle actions with the identifier `action_id` with `executor`
+    pub fn register_executor(&mut self, action_id: impl Into<String>, executor: Box<dyn ActionExecutor>) {
+        self.executors.insert(action_id.into(), executor);
+    }
 
-        Ok(Self {
-            config,
-            http_client,
-        })
+    /// Ask operators through `channel` to confirm actions needing confirmation
+    ///
+    /// Without a channel, such actions are held and never run.
+    pub fn set_approval_channel(&mut self, channel: Box<dyn ApprovalChannel>) {
+        self.approval = Some(channel);
     }
 
-    /// Send a heartbeat to the MCP server
-    pub async fn send_heartbeat(&self, metrics: serde_json::Value) -> Result<HeartbeatResponse> {
-        // Load the private key
-        let private_key = crypto::read_secret_key(&self.config.security.private_key_path)?;
+    /// Process an MCP message from the server
+    ///
+    /// Every recommended action gets a result, which is recorded and returned
+    /// for reporting; an action that fails to be checked or run becomes a
+    /// failure rather than stopping the rest of the message.
+    pub async fn process_message(&self, message: McpMessage) -> Vec<ActionResult> {
+        tracing::info!("Processing MCP message: {}", message.id);
 
-        // Create the heartbeat timestamp
-        let timestamp = chrono::Utc::now().to_rfc3339();
+        let mut results = Vec::new();
 
-        // Create the signature data
-        let signature_data = format!("{}{}{}", 
-            self.config.server.client_id,
-            timestamp,
-            metrics.to_string()
-        );
+        // Process each recommended action
+        for recommendation in &message.actions {
+            let action_id = recommendation.action_id.clone();
 
-        // Sign the data
-        let signature = hex::encode(crypto::sign(signature_data.as_bytes(), &private_key));
+            tracing::debug!("Processing action recommendation: {}", action_id);
 
-        // Create the heartbeat request
-        let request = HeartbeatRequest {
-            client_id: self.config.server.client_id.clone(),
-            timestamp,
-            status: "healthy".to_string(),
-            metrics,
-            signature,
-        };
+            let result = match self.process_action(&message, recommendation).await {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::error!("Action {} failed: {:#}", action_id, e);
 
-        // Send the request
-        let response = self.http_client
-            .post(format!("{}/v1/heartbeat", self.config.server.api_url))
-            .json(&request)
-            .send()
-            .await?;
+                    ActionResult {
+                        action_id: action_id.clone(),
+                        status: ActionStatus::Failure,
+                        message: format!("{:#}", e),
+                        data: None,
+                    }
+                },
+            };
 
-        // Check the response status
-        match response.status() {
-            StatusCode::OK => {
-                let heartbeat_response = response.json::<HeartbeatResponse>().await?;
-                Ok(heartbeat_response)
-            },
-            status => {
-                Err(anyhow!("Unexpected status code: {}", status))
-            }
+            // Record the action execution
+            self.record(recommendation, &result);
+
+            results.push(result);
         }
+
+        results
     }
 
-    /// Execute a remote action
-    pub async fn execute_action(&self, action: RemoteAction) -> Result<()> {
-        // Check if the action requires confirmation
-        if action.requires_confirmation && !self.config.actions.require_confirmation {
-            return Err(anyhow!("Action requires confirmation but confirmation is disabled"));
-        }
+    /// Check one recommended action against the permissions policy, and run
+    /// it if allowed or confirmed
+    async fn process_action(&self, message: &McpMessage, recommendation: &ActionRecommendation) -> Result<ActionResult> {
+        let action_id = recommendation.action_id.clone();
 
-        // Handle different action types
-        match action.action_type.as_str() {
-            "update_config" => {
-                // Update configuration
-                tracing::info!("Executing update_config action: {}", action.id);
-                // Implementation would go here
-            },
-            "restart" => {
-                // Restart the client
-                tracing::info!("Executing restart action: {}", action.id);
-                // Implementation would go here
-            },
-            "collect_logs" => {
-                // Collect specific logs
-                tracing::info!("Executing collect_logs action: {}", action.id);
-                // Implementation would go here
+        // Check the action against the permissions policy; only allowed actions run
+        let decision = self.check_permission(recommendation)
+            .context(format!("Failed to check permission for action {}", action_id))?;
+
+        Ok(match decision {
+            Decision::Allow => self.execute_action(recommendation).await
+                .context(format!("Failed to execute action {}", action_id))?,
+            Decision::Confirm => match &self.approval {
+                Some(channel) => self.confirm_action(channel.as_ref(), &message.id, &message.narrative, recommendation).await
+                    .context(format!("Failed to confirm action {}", action_id))?,
+                None => {
+                    tracing::warn!("Action awaits confirmation: {}", action_id);
+
+                    ActionResult {
+                        action_id,
+                        status: ActionStatus::AwaitingConfirmation,
+                        message: "Action held until an operator confirms it".to_string(),
+                        data: None,
+                    }
+                },
             },
-            _ => {
-                return Err(anyhow!("Unknown action type: {}", action.action_type));
-            }
-        }
+            Decision::Deny(reason) => {
+                tracing::warn!("Action not permitted: {}: {}", action_id, reason);
 
-        Ok(())
+                ActionResult {
+                    action_id,
+                    status: ActionStatus::NotPermitted,
+                    message: reason,
+                    data: None,
+                }
+            },
+        })
     }
-}
-            // Record the action execution
-            let parameters = serde_json::to_string(&recommendation.parameters)?;
-            let result_str = serde_json::to_string(&result)?;
 
+    /// Record an action outcome in the `actions` table
+    ///
+    /// A failure to record is logged; the outcome is still reported.
+    fn record(&self, recommendation: &ActionRecommendation, result: &ActionResult) {
+        let record = || -> Result<i64> {
             let record = ActionRecord {
                 id: None,
                 timestamp: chrono::Utc::now().timestamp(),
-                action_id,
-                parameters,
-                status: format!("{:?}", result.status),
-                result: result_str,
+                action_id: recommendation.action_id.clone(),
+                parameters: serde_json::to_string(&recommendation.parameters)?,
+                status: result.status.as_str().to_string(),
+                result: serde_json::to_string(result)?,
             };
-
             self.db.record_action(&record)
-                .context("Failed to record action execution")?;
+        };
+
+        if let Err(e) = record() {
+            tracing::error!("Failed to record action {}: {:#}", recommendation.action_id, e);
         }
+    }
+
+    /// Ask an operator to approve an action, and run it once approved
+    ///
+    /// Waits up to the execution timeout for a decision. The approval is
+    /// recorded before the action runs; a denial, a failed request and a
+    /// timeout leave the action unrun.
+    async fn confirm_action(
+        &self,
+        channel: &dyn ApprovalChannel,
+        message_id: &str,
+        narrative: &str,
+        recommendation: &ActionRecommendation,
+    ) -> Result<ActionResult> {
+        let action_id = recommendation.action_id.clone();
+        let outcome = |status, message: String| ActionResult { action_id: action_id.clone(), status, message, data: None };
+        let request = ApprovalRequest {
+            client_id: self.config.server.client_id.clone(),
+            message_id: message_id.to_string(),
+            narrative: narrative.to_string(),
+            action_id: action_id.clone(),
+            description: recommendation.description.clone(),
+            defined_as: self.actions.get(&action_id).map(|action| action.description.clone()).unwrap_or_default(),
+            parameters: recommendation.parameters.clone(),
+        };
 
-        Ok(results)
+        tracing::info!("Requesting approval of action {}", action_id);
+        let timeout = Duration::from_secs(self.config.actions.execution_timeout);
+        match time::timeout(timeout, channel.request(&request)).await {
+            Ok(Ok(Approval::Approved)) => {
+                tracing::info!("Action approved by operator: {}", action_id);
+                self.record(recommendation, &outcome(ActionStatus::Approved, "Approved by operator".to_string()));
+                self.execute_action(recommendation).await
+            },
+            Ok(Ok(Approval::Denied(reason))) => {
+                tracing::warn!("Action denied by operator: {}: {}", action_id, reason);
+                Ok(outcome(ActionStatus::NotPermitted, reason))
+            },
+            Ok(Err(e)) => {
+                tracing::warn!("Approval request for action {} failed: {:#}", action_id, e);
+                Ok(outcome(ActionStatus::NotPermitted, format!("Approval request failed: {}", e)))
+            },
+            Err(_) => {
+                tracing::warn!("No decision on action {} within {}s", action_id, timeout.as_secs());
+                Ok(outcome(ActionStatus::ApprovalTimeout, format!("No approval within {}s", timeout.as_secs())))
+            },
+        }
     }
 
-    /// Check if an action is permitted by local policy
-    fn check_permission(&self, recommendation: &ActionRecommendation) -> Result<bool> {
-        // TODO: Implement actual permission checking
-        // For now, just allow everything except HighRisk actions
-        let permitted = recommendation.permission_level != PermissionLevel::HighRisk;
+    /// Check an action against the permissions policy
+    ///
+    /// High-risk actions the policy allows still need confirmation, whether the
+    /// server or the local action definition says they are high-risk. Any
+    /// action needing confirmation is denied when confirmation is disabled.
+    fn check_permission(&self, recommendation: &ActionRecommendation) -> Result<Decision> {
+        let mut decision = self.permissions.check(
+            &self.config.server.client_id,
+            &recommendation.action_id,
+            &recommendation.parameters,
+        )?;
+
+        let high_risk = recommendation.permission_level == PermissionLevel::HighRisk
+            || self.actions
+                .get(&recommendation.action_id)
+                .is_some_and(|action| action.risk_level == PermissionLevel::HighRisk);
+        if decision == Decision::Allow && high_risk {
+            decision = Decision::Confirm;
+        }
+        if decision == Decision::Confirm && !self.config.actions.require_confirmation {
+            decision = Decision::Deny("Action requires confirmation, which is disabled".to_string());
+        }
 
-        Ok(permitted)
+        Ok(decision)
     }
 
-    /// Execute an action
+    /// Validate an action's parameters and run it with its registered
+    /// executor, within the execution timeout
+    ///
+    /// Undefined actions, invalid parameters, executor errors and timeouts
+    /// become results rather than errors, so they are recorded and reported
+    /// like any other outcome.
     async fn execute_action(&self, recommendation: &ActionRecommendation) -> Result<ActionResult> {
         let action_id = recommendation.action_id.clone();
+        let failed = |status, message: String| ActionResult { action_id: action_id.clone(), status, message, data: None };
 
-        // TODO: Implement actual action execution
-        // For now, just simulate success for all actions
+        let (action, executor) = match (self.actions.get(&action_id), self.executors.get(&action_id)) {
+            (Some(action), Some(executor)) => (action, executor),
+            (None, _) => return Ok(failed(ActionStatus::NotFound, format!("No definition for action {}", action_id))),
+            (Some(_), None) => return Ok(failed(ActionStatus::NotFound, format!("No executor for action {}", action_id))),
+        };
 
-        // Simulate some execution time
-        time::sleep(Duration::from_millis(500)).await;
+        let params = match action.validate(&recommendation.parameters) {
+            Ok(params) => params,
+            Err(e) => return Ok(failed(ActionStatus::InvalidParameters, e.to_string())),
+        };
 
-        Ok(ActionResult {
-            action_id,
-            status: ActionStatus::Success,
-            message: "Action executed successfully (simulated)".to_string(),
-            data: Some(serde_json::json!({"executed": true})),
+        let timeout = Duration::from_secs(self.config.actions.execution_timeout);
+        Ok(match time::timeout(timeout, executor.execute(action, &params)).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => failed(ActionStatus::Failure, e.to_string()),
+            Err(_) => failed(ActionStatus::Timeout, format!("Action did not finish within {}s", timeout.as_secs())),
         })
     }
 }
 
 /// Start the MCP service
+///
+/// Polls the server for commands until SIGTERM or Ctrl-C.
 pub async fn start_service(config: McpConfig) -> Result<()> {
     // Open the database
     let db = Database::open(&config.database.db_path)
         .context("Failed to open database")?;
 
-    // Create the MCP client
-    let client = McpClient::new(config.clone(), db);
+    let transport = HttpTransport::new(&config)?;
+    let permissions = PermissionEngine::load(&config.actions.permissions_path)?;
+    let actions = ActionRegistry::load(&config.actions.actions_dir)?;
 
-    // Create a channel for incoming messages
-    let (tx, mut rx) = mpsc::channel(100);
+    let approval = if config.actions.require_confirmation { Some(approval::from_config(&config)?) } else { None };
 
-    // Spawn a task to poll for messages
-    tokio::spawn(async move {
-        loop {
-            // TODO: Implement actual message polling from API
-            time::sleep(Duration::from_secs(10)).await;
-        }
-    });
+    // Every defined action runs its command
+    let action_ids: Vec<String> = actions.ids().map(str::to_string).collect();
+    let mut client = McpClient::new(config, db, permissions, actions);
+    for action_id in action_ids {
+        client.register_executor(action_id, Box::new(CommandExecutor));
+    }
+    if let Some(channel) = approval {
+        client.set_approval_channel(channel);
+    }
 
-    // Main processing loop
-    while let Some(message) = rx.recv().await {
-        match client.process_message(message).await {
-            Ok(results) => {
-                tracing::info!("Processed message with {} action results", results.len());
-            }
-            Err(err) => {
-                tracing::error!("Error processing message: {}", err);
+    run_service(&client, &transport, shutdown_signal()).await
+}
+
+/// Poll `transport` for commands and execute them until `shutdown` completes
+///
+/// A message being processed when `shutdown` completes is finished and
+/// reported first. Failed polls are retried with a doubling delay.
+pub async fn run_service<T, F>(client: &McpClient, transport: &T, shutdown: F) -> Result<()>
+where
+    T: CommandTransport,
+    F: Future<Output = ()>,
+{
+    tokio::pin!(shutdown);
+    let mut retry_delay = POLL_RETRY_INITIAL_DELAY;
+
+    tracing::info!("MCP service started");
+
+    loop {
+        let polled = tokio::select! {
+            _ = &mut shutdown => break,
+            polled = transport.poll() => polled,
+        };
+
+        let messages = match polled {
+            Ok(messages) => {
+                retry_delay = POLL_RETRY_INITIAL_DELAY;
+                messages
+            },
+            Err(e) => {
+                tracing::warn!("Failed to poll for commands, retrying in {}s: {}", retry_delay.as_secs(), e);
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = time::sleep(retry_delay) => {},
+                }
+                retry_delay = (retry_delay * 2).min(POLL_RETRY_MAX_DELAY);
+                continue;
+            },
+        };
+
+        for message in messages {
+            let message_id = message.id.clone();
+            let results = client.process_message(message).await;
+            tracing::info!("Processed message {} with {} action results", message_id, results.len());
+            if let Err(e) = transport.report(&message_id, &results).await {
+                tracing::error!("Error reporting results of message {}: {}", message_id, e);
             }
         }
     }
 
+    tracing::info!("MCP service stopped");
     Ok(())
 }
 
+/// Complete on SIGTERM, or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM, stopping on Ctrl-C only: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            },
+        };
+        tokio::select! {
+            _ = terminate.recv() => tracing::info!("Received SIGTERM"),
+            _ = tokio::signal::ctrl_c() => tracing::info!("Received Ctrl-C"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use std::path::Path;
     use tempfile::tempdir;
 
+    /// Executor succeeding with the parameters it was given
+    struct EchoExecutor;
+
+    #[async_trait]
+    impl ActionExecutor for EchoExecutor {
+        async fn execute(&self, action: &ActionDefinition, params: &HashMap<String, String>) -> Result<ActionResult> {
+            Ok(ActionResult {
+                action_id: action.id.clone(),
+                status: ActionStatus::Success,
+                message: "done".to_string(),
+                data: Some(serde_json::to_value(params)?),
+            })
+        }
+    }
+
+    fn registry(definitions: &str) -> Result<ActionRegistry> {
+        ActionRegistry::from_definitions(serde_yaml::from_str::<Vec<ActionDefinition>>(definitions)?)
+    }
+
+    fn allow_all() -> Result<PermissionEngine> {
+        PermissionEngine::from_yaml("{ clients: { test-client: [all] }, roles: { all: [{ action: '*' }] } }")
+    }
+
+    fn test_config(api_url: &str, db_path: &Path, key_path: &Path) -> McpConfig {
+        McpConfig {
+            server: crate::config::ServerConfig {
+                api_url: api_url.to_string(),
+                timeout_seconds: 5,
+                client_id: "test-client".to_string(),
+            },
+            security: crate::config::SecurityConfig {
+                private_key_path: key_path.to_string_lossy().to_string(),
+                verify_certs: true,
+                ca_cert_path: None,
+            },
+            database: crate::config::DatabaseConfig {
+                db_path: db_path.to_string_lossy().to_string(),
+                max_cache_entries: 1000,
+            },
+            actions: crate::config::ActionsConfig {
+                actions_dir: "/tmp/actions".to_string(),
+                permissions_path: "/tmp/permissions.yaml".to_string(),
+                require_confirmation: false,
+                execution_timeout: 60,
+                approval: Default::default(),
+            },
+        }
+    }
+
     #[tokio::test]
     async fn test_process_message() -> Result<()> {
         // Create a temporary database
@@ -382,11 +714,14 @@ mod tests {
                 permissions_path: "/tmp/permissions.yaml".to_string(),
                 require_confirmation: false,
                 execution_timeout: 60,
+                approval: Default::default(),
             },
         };
 
         // Create the MCP client
-        let client = McpClient::new(config, db);
+        let actions = registry("[{ id: test.action, risk_level: Standard, command: [true] }]")?;
+        let mut client = McpClient::new(config, db, allow_all()?, actions);
+        client.register_executor("test.action", Box::new(EchoExecutor));
 
         // Create a test message
         let message = McpMessage {
@@ -405,7 +740,7 @@ mod tests {
         };
 
         // Process the message
-        let results = client.process_message(message).await?;
+        let results = client.process_message(message).await;
 
         // Check the results
         assert_eq!(results.len(), 1);
@@ -414,4 +749,456 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_service_executes_polled_commands() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let key_path = dir.path().join("signing.key");
+        let (_, secret_key) = crypto::generate_keypair();
+        crypto::write_secret_key(&key_path, &secret_key)?;
+
+        let message = serde_json::json!({
+            "messages": [{
+                "id": "msg-1",
+                "timestamp": 1_700_000_000,
+                "severity": "warning",
+                "narrative": "Disk filling up",
+                "actions": [
+                    {
+                        "action_id": "disk.cleanup",
+                        "description": "Remove rotated logs",
+                        "parameters": { "path": "/var/log" },
+                        "permission_level": "Standard",
+                    },
+                    {
+                        "action_id": "disk.format",
+                        "description": "Unregistered action",
+                        "parameters": {},
+                        "permission_level": "Standard",
+                    },
+                ],
+            }],
+        });
+
+        let mut server = mockito::Server::new_async().await;
+        let poll = server.mock("GET", "/v1/commands")
+            .match_query(mockito::Matcher::UrlEncoded("wait".to_string(), "30".to_string()))
+            .match_header("x-client-id", "test-client")
+            .match_header("x-signature", mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(message.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        // Later polls time out without commands
+        server.mock("GET", "/v1/commands")
+            .match_query(mockito::Matcher::Any)
+            .with_status(204)
+            .create_async()
+            .await;
+        let report = server.mock("POST", "/v1/commands/msg-1/results")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "results": [
+                    { "action_id": "disk.cleanup", "status": "Success", "data": { "path": "/var/log" } },
+                    { "action_id": "disk.format", "status": "NotFound" },
+                ],
+            })))
+            .with_status(204)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let config = test_config(&server.url(), &db_path, &key_path);
+        let transport = HttpTransport::new(&config)?;
+        let actions = registry("[{ id: disk.cleanup, risk_level: Standard, command: [rm, -r, '{path}'], parameters: { path: { type: path } } }]")?;
+        let mut client = McpClient::new(config, Database::open(&db_path)?, allow_all()?, actions);
+        client.register_executor("disk.cleanup", Box::new(EchoExecutor));
+
+        // Stop once the results of the first message are reported
+        let reported = async {
+            while !report.matched_async().await {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        time::timeout(Duration::from_secs(10), run_service(&client, &transport, reported)).await??;
+        poll.assert_async().await;
+
+        let mut actions = Database::open(&db_path)?.get_recent_actions(10)?;
+        actions.sort_by(|a, b| a.action_id.cmp(&b.action_id));
+        assert_eq!(actions[0].action_id, "disk.cleanup");
+        assert_eq!(actions[0].status, "success");
+        assert_eq!(actions[0].parameters, r#"{"path":"/var/log"}"#);
+        assert_eq!(actions[1].action_id, "disk.format");
+        assert_eq!(actions[1].status, "not_found");
+
+        Ok(())
+    }
+
+    /// Executor that must never run
+    struct UnreachableExecutor;
+
+    #[async_trait]
+    impl ActionExecutor for UnreachableExecutor {
+        async fn execute(&self, action: &ActionDefinition, _params: &HashMap<String, String>) -> Result<ActionResult> {
+            panic!("{} ran without being allowed", action.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_actions_are_recorded_and_never_run() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let permissions = PermissionEngine::from_yaml(r#"
+            clients: { test-client: [operator] }
+            roles:
+              operator:
+                - { action: disk.cleanup }
+                - { action: service.restart, require_confirmation: true }
+        "#)?;
+
+        let recommendation = |action_id: &str, permission_level| ActionRecommendation {
+            action_id: action_id.to_string(),
+            description: action_id.to_string(),
+            parameters: HashMap::new(),
+            permission_level,
+        };
+        let message = McpMessage {
+            id: "msg-1".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            severity: "critical".to_string(),
+            narrative: "Test narrative".to_string(),
+            actions: vec![
+                recommendation("disk.format", PermissionLevel::Standard),
+                recommendation("service.restart", PermissionLevel::Standard),
+                recommendation("disk.cleanup", PermissionLevel::HighRisk),
+            ],
+        };
+
+        let mut config = test_config("https://test.lognarrator.com", &db_path, &dir.path().join("key"));
+        config.actions.require_confirmation = true;
+        let mut client = McpClient::new(config.clone(), Database::open(&db_path)?, permissions, ActionRegistry::default());
+        for action_id in ["disk.format", "service.restart", "disk.cleanup"] {
+            client.register_executor(action_id, Box::new(UnreachableExecutor));
+        }
+
+        let results = client.process_message(message.clone()).await;
+        let statuses: Vec<&ActionStatus> = results.iter().map(|result| &result.status).collect();
+        assert_eq!(statuses, [&ActionStatus::NotPermitted, &ActionStatus::AwaitingConfirmation, &ActionStatus::AwaitingConfirmation]);
+        assert_eq!(results[0].message, "no role of client test-client allows disk.format");
+
+        let mut recorded: Vec<(String, String)> = client.db.get_recent_actions(10)?
+            .into_iter()
+            .map(|action| (action.action_id, action.status))
+            .collect();
+        recorded.sort();
+        assert_eq!(recorded, [
+            ("disk.cleanup".to_string(), "awaiting_confirmation".to_string()),
+            ("disk.format".to_string(), "denied".to_string()),
+            ("service.restart".to_string(), "awaiting_confirmation".to_string()),
+        ]);
+
+        // Without the confirmation flow, actions needing confirmation are denied
+        config.actions.require_confirmation = false;
+        client.config = config;
+        let results = client.process_message(message).await;
+        assert!(results.iter().all(|result| result.status == ActionStatus::NotPermitted));
+        assert_eq!(results[1].message, "Action requires confirmation, which is disabled");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_action_definitions_gate_execution() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let actions = registry(r#"
+            - id: disk.cleanup
+              risk_level: Standard
+              command: [find, '{path}', -delete]
+              parameters: { path: { type: path } }
+            - id: service.restart
+              risk_level: HighRisk
+              command: [systemctl, restart, '{unit}']
+              parameters: { unit: { type: string } }
+        "#)?;
+
+        let mut config = test_config("https://test.lognarrator.com", &db_path, &dir.path().join("key"));
+        config.actions.require_confirmation = true;
+        let mut client = McpClient::new(config, Database::open(&db_path)?, allow_all()?, actions);
+        client.register_executor("disk.cleanup", Box::new(UnreachableExecutor));
+        client.register_executor("service.restart", Box::new(UnreachableExecutor));
+
+        let recommendation = |action_id: &str, parameters: &[(&str, &str)]| ActionRecommendation {
+            action_id: action_id.to_string(),
+            description: action_id.to_string(),
+            parameters: parameters.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            permission_level: PermissionLevel::Standard,
+        };
+        let message = McpMessage {
+            id: "msg-1".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            severity: "warning".to_string(),
+            narrative: "Test narrative".to_string(),
+            actions: vec![
+                recommendation("disk.cleanup", &[("path", "../etc"), ("force", "true")]),
+                recommendation("service.restart", &[("unit", "nginx")]),
+            ],
+        };
+
+        let results = client.process_message(message).await;
+        assert_eq!(results[0].status, ActionStatus::InvalidParameters);
+        assert_eq!(
+            results[0].message,
+            "Invalid parameters for disk.cleanup: unknown parameter 'force'; \
+             parameter 'path': '../etc' is not an absolute path without '..'",
+        );
+        // The definition marks the action high-risk even though the server did not
+        assert_eq!(results[1].status, ActionStatus::AwaitingConfirmation);
+
+        let mut statuses: Vec<String> = client.db.get_recent_actions(10)?.into_iter().map(|action| action.status).collect();
+        statuses.sort();
+        assert_eq!(statuses, ["awaiting_confirmation", "invalid_parameters"]);
+
+        Ok(())
+    }
+
+    /// Approval channel answering from a script, and never for unscripted actions
+    struct ScriptedApproval(HashMap<&'static str, Approval>);
+
+    #[async_trait]
+    impl ApprovalChannel for ScriptedApproval {
+        async fn request(&self, request: &ApprovalRequest) -> Result<Approval> {
+            match self.0.get(request.action_id.as_str()) {
+                Some(approval) => Ok(approval.clone()),
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_through_approval_channel() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let actions = registry(r#"
+            - { id: disk.cleanup, risk_level: HighRisk, command: [true] }
+            - { id: service.restart, risk_level: HighRisk, command: [true] }
+            - { id: host.reboot, risk_level: HighRisk, command: [true] }
+        "#)?;
+
+        let mut config = test_config("https://test.lognarrator.com", &db_path, &dir.path().join("key"));
+        config.actions.require_confirmation = true;
+        config.actions.execution_timeout = 1;
+        let mut client = McpClient::new(config, Database::open(&db_path)?, allow_all()?, actions);
+        client.register_executor("disk.cleanup", Box::new(EchoExecutor));
+        client.register_executor("service.restart", Box::new(UnreachableExecutor));
+        client.register_executor("host.reboot", Box::new(UnreachableExecutor));
+        client.set_approval_channel(Box::new(ScriptedApproval(HashMap::from([
+            ("disk.cleanup", Approval::Approved),
+            ("service.restart", Approval::Denied("Not during business hours".to_string())),
+        ]))));
+
+        let recommendation = |action_id: &str| ActionRecommendation {
+            action_id: action_id.to_string(),
+            description: action_id.to_string(),
+            parameters: HashMap::new(),
+            permission_level: PermissionLevel::Standard,
+        };
+        let message = McpMessage {
+            id: "msg-1".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            severity: "critical".to_string(),
+            narrative: "Test narrative".to_string(),
+            actions: vec![recommendation("disk.cleanup"), recommendation("service.restart"), recommendation("host.reboot")],
+        };
+
+        let results = client.process_message(message).await;
+        let statuses: Vec<&ActionStatus> = results.iter().map(|result| &result.status).collect();
+        assert_eq!(statuses, [&ActionStatus::Success, &ActionStatus::NotPermitted, &ActionStatus::ApprovalTimeout]);
+        assert_eq!(results[1].message, "Not during business hours");
+
+        let mut recorded: Vec<(String, String)> = client.db.get_recent_actions(10)?
+            .into_iter()
+            .map(|action| (action.action_id, action.status))
+            .collect();
+        recorded.sort();
+        assert_eq!(recorded, [
+            ("disk.cleanup".to_string(), "approved".to_string()),
+            ("disk.cleanup".to_string(), "success".to_string()),
+            ("host.reboot".to_string(), "timed_out".to_string()),
+            ("service.restart".to_string(), "denied".to_string()),
+        ]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_executor_runs_the_rendered_command() -> Result<()> {
+        let actions = registry(r#"
+            - id: text.echo
+              risk_level: Standard
+              command: [echo, '{text}']
+              parameters: { text: { type: string } }
+            - { id: path.list, risk_level: Standard, command: [ls, /nonexistent-lognarrator-path] }
+        "#)?;
+
+        // The value reaches the program as one argument, never through a shell
+        let params = HashMap::from([("text".to_string(), "$(id); echo two".to_string())]);
+        let result = CommandExecutor.execute(actions.get("text.echo").unwrap(), &params).await?;
+        assert_eq!(result.status, ActionStatus::Success);
+        let data = result.data.unwrap();
+        assert_eq!(data["exit_code"], 0);
+        assert_eq!(data["stdout"], "$(id); echo two\n");
+        assert_eq!(data["stderr"], "");
+
+        let result = CommandExecutor.execute(actions.get("path.list").unwrap(), &HashMap::new()).await?;
+        assert_eq!(result.status, ActionStatus::Failure);
+        let data = result.data.unwrap();
+        assert_ne!(data["exit_code"], 0);
+        assert!(data["stderr"].as_str().unwrap().contains("nonexistent-lognarrator-path"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_executor_reports_programs_that_cannot_start() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let actions = registry("[{ id: tool.run, risk_level: Standard, command: [/nonexistent/lognarrator-tool] }]")?;
+
+        let config = test_config("https://test.lognarrator.com", &db_path, &dir.path().join("key"));
+        let mut client = McpClient::new(config, Database::open(&db_path)?, allow_all()?, actions);
+        client.register_executor("tool.run", Box::new(CommandExecutor));
+
+        let message = McpMessage {
+            id: "msg-1".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            severity: "warning".to_string(),
+            narrative: "Test narrative".to_string(),
+            actions: vec![ActionRecommendation {
+                action_id: "tool.run".to_string(),
+                description: "Run the tool".to_string(),
+                parameters: HashMap::new(),
+                permission_level: PermissionLevel::Standard,
+            }],
+        };
+
+        let results = client.process_message(message).await;
+        assert_eq!(results[0].status, ActionStatus::Failure);
+        assert!(results[0].message.contains("Failed to run /nonexistent/lognarrator-tool"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_sign_method_path_and_body() -> Result<()> {
+        let dir = tempdir()?;
+        let key_path = dir.path().join("signing.key");
+        let (public_key, secret_key) = crypto::generate_keypair();
+        crypto::write_secret_key(&key_path, &secret_key)?;
+
+        // What the server received: headers, method, path and body
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let capture = received.clone();
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/v1/commands/msg-1/results")
+            .with_status(204)
+            .with_body_from_request(move |request| {
+                let header = |name: &str| request.header(name)[0].to_str().unwrap().to_string();
+                capture.lock().unwrap().push((
+                    header("x-client-id"),
+                    header("x-timestamp"),
+                    header("x-signature"),
+                    format!("{}\n{}\n{}", request.method(), request.path_and_query(), hex::encode(Sha256::digest(request.body().unwrap()))),
+                ));
+                Vec::new()
+            })
+            .create_async()
+            .await;
+
+        let config = test_config(&server.url(), &dir.path().join("mcp.db"), &key_path);
+        let transport = HttpTransport::new(&config)?;
+        let result = ActionResult {
+            action_id: "disk.cleanup".to_string(),
+            status: ActionStatus::Success,
+            message: "done".to_string(),
+            data: None,
+        };
+        transport.report("msg-1", &[result]).await?;
+
+        let (client_id, timestamp, signature, request) = received.lock().unwrap().pop().unwrap();
+        let signed = crypto::verify(&BASE64.decode(signature)?, &public_key).expect("valid signature");
+        assert_eq!(String::from_utf8(signed)?, format!("{}\n{}\n{}", client_id, timestamp, request));
+        assert!(request.starts_with("POST\n/v1/commands/msg-1/results\n"));
+        // An empty body would hash to e3b0c442...
+        assert!(!request.ends_with("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_results_are_reported_when_recording_fails() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("mcp.db");
+        let key_path = dir.path().join("signing.key");
+        let (_, secret_key) = crypto::generate_keypair();
+        crypto::write_secret_key(&key_path, &secret_key)?;
+
+        let message = serde_json::json!({
+            "messages": [{
+                "id": "msg-1",
+                "timestamp": 1_700_000_000,
+                "severity": "warning",
+                "narrative": "Disk filling up",
+                "actions": [
+                    { "action_id": "disk.cleanup", "description": "Remove rotated logs", "parameters": {}, "permission_level": "Standard" },
+                    { "action_id": "disk.trim", "description": "Trim the disk", "parameters": {}, "permission_level": "Standard" },
+                ],
+            }],
+        });
+
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/v1/commands")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(message.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        server.mock("GET", "/v1/commands")
+            .match_query(mockito::Matcher::Any)
+            .with_status(204)
+            .create_async()
+            .await;
+        let report = server.mock("POST", "/v1/commands/msg-1/results")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "results": [
+                    { "action_id": "disk.cleanup", "status": "Success" },
+                    { "action_id": "disk.trim", "status": "Success" },
+                ],
+            })))
+            .with_status(204)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let config = test_config(&server.url(), &db_path, &key_path);
+        let transport = HttpTransport::new(&config)?;
+        let actions = registry("[{ id: disk.cleanup, risk_level: Standard, command: [true] }, { id: disk.trim, risk_level: Standard, command: [true] }]")?;
+        let mut client = McpClient::new(config, Database::open(&db_path)?, allow_all()?, actions);
+        client.register_executor("disk.cleanup", Box::new(EchoExecutor));
+        client.register_executor("disk.trim", Box::new(EchoExecutor));
+
+        // Every record fails from here on
+        rusqlite::Connection::open(&db_path)?.execute("DROP TABLE actions", [])?;
+
+        let reported = async {
+            while !report.matched_async().await {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        time::timeout(Duration::from_secs(10), run_service(&client, &transport, reported)).await??;
+
+        Ok(())
+    }
 }
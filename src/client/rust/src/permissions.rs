@@ -0,0 +1,266 @@
+//! Authorization of MCP actions
+//!
+//! The policy file named by `actions.permissions_path` grants roles to
+//! clients, and lets each role run actions, optionally only on some targets:
+//!
+//! ```yaml
+//! clients:
+//!   12345678-1234-1234-1234-123456789012: [operator]
+//! roles:
+//!   operator:
+//!     - action: disk.cleanup
+//!       targets: ["/var/log/*"]
+//!     - action: service.*
+//!       targets: [nginx, postgres]
+//!       require_confirmation: true
+//! ```
+//!
+//! Actions and targets are glob patterns. An action's target is its `target`
+//! parameter; a rule without `targets` applies to any target, including none.
+//! In targets `*` and `?` never match `/`, so `/var/log/*` covers the files
+//! directly in `/var/log` only, and a target with a `..` component is always
+//! denied. Anything no rule allows is denied.
+
+use anyhow::{anyhow, Context, Result};
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Parameter naming what an action operates on
+pub const TARGET_PARAMETER: &str = "target";
+
+/// Target patterns match one path segment per wildcard
+const TARGET_MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Outcome of checking an action against the policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// The action may run
+    Allow,
+    /// The action may run once an operator confirms it
+    Confirm,
+    /// The action must not run, and why
+    Deny(String),
+}
+
+/// Policy file layout
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    /// Roles granted to each client ID
+    #[serde(default)]
+    clients: HashMap<String, Vec<String>>,
+    /// Rules of each role
+    #[serde(default)]
+    roles: HashMap<String, Vec<RuleConfig>>,
+}
+
+/// One permission of a role
+#[derive(Debug, Deserialize)]
+struct RuleConfig {
+    /// Action ID pattern
+    action: String,
+    /// Target patterns; any target when empty
+    #[serde(default)]
+    targets: Vec<String>,
+    /// Hold matching actions for confirmation
+    #[serde(default)]
+    require_confirmation: bool,
+}
+
+struct Rule {
+    action: Pattern,
+    targets: Vec<Pattern>,
+    require_confirmation: bool,
+}
+
+impl Rule {
+    fn matches(&self, action_id: &str, target: Option<&str>) -> bool {
+        if !self.action.matches(action_id) {
+            return false;
+        }
+        if self.targets.is_empty() {
+            return true;
+        }
+        target.is_some_and(|target| self.targets.iter().any(|pattern| pattern.matches_with(target, TARGET_MATCH)))
+    }
+}
+
+/// Decides whether a client may run an action
+pub struct PermissionEngine {
+    clients: HashMap<String, Vec<String>>,
+    roles: HashMap<String, Vec<Rule>>,
+}
+
+impl PermissionEngine {
+    /// Load the policy file at `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read permissions policy {}", path.display()))?;
+        Self::from_yaml(&content).with_context(|| format!("Invalid permissions policy {}", path.display()))
+    }
+
+    /// Parse a policy from YAML
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let policy: PolicyFile = serde_yaml::from_str(content)?;
+
+        for (client_id, roles) in &policy.clients {
+            if let Some(role) = roles.iter().find(|role| !policy.roles.contains_key(*role)) {
+                return Err(anyhow!("client '{}' has unknown role '{}'", client_id, role));
+            }
+        }
+
+        let compile = |pattern: &str| Pattern::new(pattern).map_err(|e| anyhow!("invalid pattern '{}': {}", pattern, e));
+        let mut roles = HashMap::new();
+        for (role, rules) in policy.roles {
+            let rules = rules
+                .into_iter()
+                .map(|rule| {
+                    Ok(Rule {
+                        action: compile(&rule.action)?,
+                        targets: rule.targets.iter().map(|target| compile(target)).collect::<Result<_>>()?,
+                        require_confirmation: rule.require_confirmation,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("role '{}'", role))?;
+            roles.insert(role, rules);
+        }
+
+        Ok(Self { clients: policy.clients, roles })
+    }
+
+    /// Decide whether `client_id` may run `action_id` with `params`
+    ///
+    /// When several rules match, one that needs no confirmation wins.
+    pub fn check(&self, client_id: &str, action_id: &str, params: &HashMap<String, String>) -> Result<Decision> {
+        let roles = match self.clients.get(client_id) {
+            Some(roles) => roles,
+            None => return Ok(Decision::Deny(format!("client {} has no roles", client_id))),
+        };
+
+        let target = params.get(TARGET_PARAMETER).map(String::as_str);
+        // Patterns are matched lexically, so a target must not climb out of what they name
+        if let Some(target) = target.filter(|target| target.split('/').any(|segment| segment == "..")) {
+            return Ok(Decision::Deny(format!("target {} contains '..'", target)));
+        }
+
+        let matching: Vec<&Rule> = roles
+            .iter()
+            .filter_map(|role| self.roles.get(role))
+            .flatten()
+            .filter(|rule| rule.matches(action_id, target))
+            .collect();
+
+        Ok(if matching.is_empty() {
+            let target = target.map(|target| format!(" on {}", target)).unwrap_or_default();
+            Decision::Deny(format!("no role of client {} allows {}{}", client_id, action_id, target))
+        } else if matching.iter().any(|rule| !rule.require_confirmation) {
+            Decision::Allow
+        } else {
+            Decision::Confirm
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+        clients:
+          web-1: [operator]
+          db-1: [auditor]
+        roles:
+          operator:
+            - action: disk.cleanup
+              targets: ["/var/log/*"]
+            - action: service.*
+              targets: [nginx]
+              require_confirmation: true
+          auditor:
+            - action: logs.collect
+    "#;
+
+    fn params(target: Option<&str>) -> HashMap<String, String> {
+        target.map(|target| (TARGET_PARAMETER.to_string(), target.to_string())).into_iter().collect()
+    }
+
+    #[test]
+    fn test_allows_granted_actions_on_granted_targets() -> Result<()> {
+        let engine = PermissionEngine::from_yaml(POLICY)?;
+
+        assert_eq!(engine.check("web-1", "disk.cleanup", &params(Some("/var/log/nginx")))?, Decision::Allow);
+        // A rule without targets applies with or without one
+        assert_eq!(engine.check("db-1", "logs.collect", &params(None))?, Decision::Allow);
+        assert_eq!(engine.check("db-1", "logs.collect", &params(Some("/etc")))?, Decision::Allow);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_denies_everything_not_granted() -> Result<()> {
+        let engine = PermissionEngine::from_yaml(POLICY)?;
+
+        assert_eq!(
+            engine.check("web-1", "disk.cleanup", &params(Some("/etc")))?,
+            Decision::Deny("no role of client web-1 allows disk.cleanup on /etc".to_string()),
+        );
+        assert!(matches!(engine.check("web-1", "disk.cleanup", &params(None))?, Decision::Deny(_)));
+        assert!(matches!(engine.check("db-1", "disk.cleanup", &params(Some("/var/log/app")))?, Decision::Deny(_)));
+        assert_eq!(
+            engine.check("unknown", "logs.collect", &params(None))?,
+            Decision::Deny("client unknown has no roles".to_string()),
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_wildcards_stay_within_one_directory() -> Result<()> {
+        let engine = PermissionEngine::from_yaml(POLICY)?;
+
+        assert!(matches!(engine.check("web-1", "disk.cleanup", &params(Some("/var/log/a/b/c")))?, Decision::Deny(_)));
+        assert_eq!(
+            engine.check("web-1", "disk.cleanup", &params(Some("/var/log/../../etc/shadow")))?,
+            Decision::Deny("target /var/log/../../etc/shadow contains '..'".to_string()),
+        );
+        assert!(matches!(engine.check("web-1", "disk.cleanup", &params(Some("/var/log/..")))?, Decision::Deny(_)));
+        // Even a rule allowing any target refuses one that climbs out
+        assert!(matches!(engine.check("db-1", "logs.collect", &params(Some("../secrets")))?, Decision::Deny(_)));
+        // Dots that are not a whole segment are ordinary characters
+        assert_eq!(engine.check("web-1", "disk.cleanup", &params(Some("/var/log/app..1")))?, Decision::Allow);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_confirmation_rules() -> Result<()> {
+        let engine = PermissionEngine::from_yaml(POLICY)?;
+        assert_eq!(engine.check("web-1", "service.restart", &params(Some("nginx")))?, Decision::Confirm);
+        assert!(matches!(engine.check("web-1", "service.restart", &params(Some("sshd")))?, Decision::Deny(_)));
+
+        // A rule needing no confirmation takes precedence
+        let policy = POLICY.replace("web-1: [operator]", "web-1: [operator, auditor]")
+            .replace("- action: logs.collect", "- action: logs.collect\n            - action: service.reload");
+        let engine = PermissionEngine::from_yaml(&policy)?;
+        assert_eq!(engine.check("web-1", "service.reload", &params(Some("nginx")))?, Decision::Allow);
+        assert_eq!(engine.check("web-1", "service.restart", &params(Some("nginx")))?, Decision::Confirm);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_unknown_roles_and_bad_patterns() {
+        let error = PermissionEngine::from_yaml("clients: { web-1: [admin] }").err().unwrap();
+        assert!(error.to_string().contains("client 'web-1' has unknown role 'admin'"));
+
+        let error = PermissionEngine::from_yaml("roles: { operator: [{ action: 'disk.[' }] }").err().unwrap();
+        assert!(format!("{:#}", error).contains("invalid pattern 'disk.['"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Queries a running `log_collector` through its admin socket
+#![cfg(unix)]
+
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+/// Kills the collector when the test ends, however it ends
+struct Running(Child);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn admin(config_path: &Path, command: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_log_collector"))
+        .arg("--config")
+        .arg(config_path)
+        .arg("--admin")
+        .arg(command)
+        .output()
+        .expect("run log_collector")
+}
+
+#[test]
+fn test_pipeline_command_dumps_the_loaded_topology() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("collector.yaml");
+    let socket_path = dir.path().join("admin.sock");
+    std::fs::write(dir.path().join("app.log"), "").unwrap();
+    std::fs::write(dir.path().join("auth.log"), "").unwrap();
+
+    std::fs::write(&config_path, format!(r#"
+        sources:
+          - source_type: file
+            name: app
+            include: ["{dir}/app.log"]
+          - source_type: file
+            name: auth
+            include: ["{dir}/auth.log"]
+            pipeline: audit
+        processors:
+          - processor_type: normalizelevel
+            name: levels
+        exporters:
+          - exporter_type: stdout
+            name: console
+          - exporter_type: stdout
+            name: audit-console
+            format: logfmt
+        pipelines:
+          audit:
+            processors: [levels]
+            exporters: [audit-console]
+        admin:
+          socket_path: {socket}
+    "#, dir = dir.path().display(), socket = socket_path.display())).unwrap();
+
+    let _collector = Running(
+        Command::new(env!("CARGO_BIN_EXE_log_collector"))
+            .arg("--config")
+            .arg(&config_path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("run log_collector"),
+    );
+
+    let started = Instant::now();
+    while !socket_path.exists() {
+        assert!(started.elapsed() < Duration::from_secs(10), "admin socket never appeared");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let output = admin(&config_path, "pipeline");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+
+    let topology: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(topology, serde_json::json!({
+        "sources": [
+            { "name": "app", "pipelines": ["default"] },
+            { "name": "auth", "pipelines": ["audit"] },
+        ],
+        "routes": [
+            { "name": "default", "processors": ["levels"], "exporters": ["console", "audit-console"] },
+            { "name": "audit", "processors": ["levels"], "exporters": ["audit-console"] },
+        ],
+        "exporters": ["console", "audit-console"],
+    }));
+
+    // Neither source has emitted yet, and neither has been silent long enough to be stale
+    let output = admin(&config_path, "health");
+    assert!(output.status.success());
+    let health: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut sources: Vec<(String, bool)> = health.as_array().unwrap()
+        .iter()
+        .map(|source| (source["source"].as_str().unwrap().to_string(), source["healthy"].as_bool().unwrap()))
+        .collect();
+    sources.sort();
+    assert_eq!(sources, [("app".to_string(), true), ("auth".to_string(), true)]);
+    assert!(health.as_array().unwrap().iter().all(|source| source["last_event"].is_null()));
+
+    // Unknown commands are answered with an error, and fail
+    let output = admin(&config_path, "shutdown");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unknown command 'shutdown'"));
+}
+
+#[test]
+fn test_sighup_reloads_the_exporters() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("collector.yaml");
+    let socket_path = dir.path().join("admin.sock");
+    std::fs::write(dir.path().join("app.log"), "").unwrap();
+
+    let config = |exporters: &str| format!(r#"
+        sources:
+          - source_type: file
+            name: app
+            include: ["{dir}/app.log"]
+        processors: []
+        exporters:
+{exporters}
+        admin:
+          socket_path: {socket}
+    "#, dir = dir.path().display(), socket = socket_path.display());
+    std::fs::write(&config_path, config("          - { exporter_type: stdout, name: console }")).unwrap();
+
+    let collector = Running(
+        Command::new(env!("CARGO_BIN_EXE_log_collector"))
+            .arg("--config")
+            .arg(&config_path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("run log_collector"),
+    );
+
+    let started = Instant::now();
+    while !socket_path.exists() {
+        assert!(started.elapsed() < Duration::from_secs(10), "admin socket never appeared");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let exporters = || {
+        let output = admin(&config_path, "pipeline");
+        let topology: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        topology["exporters"].clone()
+    };
+    assert_eq!(exporters(), serde_json::json!(["console"]));
+
+    std::fs::write(&config_path, config("
+          - { exporter_type: stdout, name: console }
+          - { exporter_type: stdout, name: audit-console, format: logfmt }
+    ")).unwrap();
+    let status = Command::new("kill").arg("-HUP").arg(collector.0.id().to_string()).status().unwrap();
+    assert!(status.success());
+
+    let started = Instant::now();
+    while exporters() != serde_json::json!(["console", "audit-console"]) {
+        assert!(started.elapsed() < Duration::from_secs(10), "exporters were never reloaded");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    // A configuration that does not load leaves the exporters in place
+    std::fs::write(&config_path, "exporters: [").unwrap();
+    Command::new("kill").arg("-HUP").arg(collector.0.id().to_string()).status().unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    std::fs::write(&config_path, config("          - { exporter_type: stdout, name: console }")).unwrap();
+    assert_eq!(exporters(), serde_json::json!(["console", "audit-console"]));
+}
@@ -0,0 +1,115 @@
+//! Runs `log_collector --check-config` against good and broken configurations
+
+use std::net::UdpSocket;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::tempdir;
+
+fn check_config(path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_log_collector"))
+        .arg("--check-config")
+        .arg("--config")
+        .arg(path)
+        .output()
+        .expect("run log_collector")
+}
+
+#[test]
+fn test_check_config_accepts_a_valid_configuration() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("collector.yaml");
+
+    // A free port, bound again by nothing while the configuration is checked
+    let port = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    std::fs::write(&config_path, format!(r#"
+        sources:
+          - source_type: syslog
+            name: syslog-udp
+            interface: 127.0.0.1
+            port: {port}
+            protocol: udp
+        processors:
+          - processor_type: filter
+            name: errors-only
+            logs:
+              include:
+                match_type: regexp
+                regexp: ['(?i)error']
+        exporters:
+          - exporter_type: stdout
+            name: console
+    "#)).unwrap();
+
+    let output = check_config(&config_path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("OK:"));
+    assert!(stdout.contains("1 sources, 1 processors, 1 exporters"));
+
+    // The check did not leave the source's port bound
+    UdpSocket::bind(("127.0.0.1", port)).unwrap();
+}
+
+#[test]
+fn test_check_config_lists_every_problem_and_fails() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("collector.yaml");
+    let key_path = dir.path().join("missing.key");
+
+    std::fs::write(&config_path, format!(r#"
+        sources:
+          - source_type: syslog
+            name: syslog-udp
+            port: 5514
+            protocol: udp
+        processors:
+          - processor_type: filter
+            name: errors-only
+            logs:
+              include:
+                match_type: regexp
+                regexp: ['(unclosed']
+        exporters:
+          - exporter_type: lognarrator
+            name: cloud-export
+            endpoint: https://api.lognarrator.com
+            client_id: test-client
+            key_path: {key}
+    "#, key = key_path.display())).unwrap();
+
+    let output = check_config(&config_path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("FAILED:"));
+    assert!(stdout.contains("processor 'errors-only': invalid regex '(unclosed'"));
+    assert!(stdout.contains(&format!("exporter 'cloud-export': key_path '{}' does not exist", key_path.display())));
+
+    // A file that is not there at all fails too
+    let output = check_config(&dir.path().join("absent.yaml"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_config_accepts_the_sample_configuration() {
+    let dir = tempdir().unwrap();
+    let key_path = dir.path().join("private.key");
+    let server_key_path = dir.path().join("server.pub");
+    std::fs::write(&key_path, "").unwrap();
+    std::fs::write(&server_key_path, "").unwrap();
+
+    let sample = Path::new(env!("CARGO_MANIFEST_DIR")).join("../config/collector.yaml");
+    let output = Command::new(env!("CARGO_BIN_EXE_log_collector"))
+        .arg("--check-config")
+        .arg("--config")
+        .arg(&sample)
+        .env("LOGNARRATOR_KEY_PATH", &key_path)
+        .env("LOGNARRATOR_SERVER_KEY_PATH", &server_key_path)
+        .output()
+        .expect("run log_collector");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("OK:"));
+}
@@ -0,0 +1,63 @@
+//! Pipes sample logs through `log_collector --test-pipeline`
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::tempdir;
+
+#[test]
+fn test_pipeline_prints_survivors_and_drops() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("collector.yaml");
+
+    // Only the processors matter: no sources or exporters are needed
+    std::fs::write(&config_path, r#"
+        sources: []
+        processors:
+          - processor_type: filter
+            name: no-debug
+            logs:
+              exclude:
+                match_type: regexp
+                regexp: ['(?i)^debug']
+        exporters: []
+    "#).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_log_collector"))
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--test-pipeline")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run log_collector");
+
+    child.stdin.take().unwrap().write_all(concat!(
+        "user alice logged in\n",
+        "DEBUG cache miss for key 42\n",
+        "\n",
+        r#"{"message": "disk full", "level": "ERROR", "attributes": {"host": "db-1"}}"#, "\n",
+        r#"{"message": "debug: retrying", "source": "worker"}"#, "\n",
+    ).as_bytes()).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}\nstderr: {}", stdout, String::from_utf8_lossy(&output.stderr));
+
+    // One line of output per non-empty line of input, in order
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", stdout);
+
+    let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(first["message"], "user alice logged in");
+    assert_eq!(first["source"], "stdin");
+
+    assert_eq!(lines[1], "dropped");
+
+    let third: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+    assert_eq!(third["message"], "disk full");
+    assert_eq!(third["level"], "ERROR");
+    assert_eq!(third["attributes"]["host"], "db-1");
+
+    assert_eq!(lines[3], "dropped");
+}